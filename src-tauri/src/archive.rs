@@ -0,0 +1,259 @@
+// src-tauri/src/archive.rs
+
+use crate::extractor::{decode_text, looks_binary, normalize_text, ExtractorError, DetectedLanguage, SUPPORTED_CODE_EXTENSIONS};
+use log::{debug, info, warn};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use whatlang::{detect, Lang};
+
+/// Separates the archive path from the member path in a composite path,
+/// e.g. `/home/me/reports.zip!2023/summary.txt`
+pub const ARCHIVE_PATH_SEPARATOR: char = '!';
+
+/// Maximum total bytes of member text read from a single archive
+pub const MAX_ARCHIVE_EXTRACTED_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Members larger than this are skipped rather than read into memory
+pub const MAX_ARCHIVE_MEMBER_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Archive extensions the indexer reads text members from, as `archive_kind` recognises them
+pub const SUPPORTED_ARCHIVE_EXTENSIONS: &[&str] = &["zip", "tar", "tar.gz", "tgz"];
+
+/// Member extensions read as plain text
+const ARCHIVE_TEXT_EXTENSIONS: &[&str] = &["txt", "md"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+/// A text-like file read from inside an archive
+#[derive(Debug, Clone)]
+pub struct ArchiveMember {
+    /// `archive_path!member_path`
+    pub composite_path: String,
+    pub text: String,
+    pub language: DetectedLanguage,
+}
+
+fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else if name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else if name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else {
+        None
+    }
+}
+
+/// Whether the indexer can look inside this file
+pub fn is_supported_archive(path: &Path) -> bool {
+    archive_kind(path).is_some()
+}
+
+/// Builds the composite path used to store an archive member in the index
+pub fn composite_path(archive_path: &Path, member_path: &str) -> String {
+    format!("{}{}{}", archive_path.display(), ARCHIVE_PATH_SEPARATOR, member_path)
+}
+
+/// Splits a composite path into `(archive_path, member_path)`.
+/// Returns `None` for regular paths or when the archive part isn't a supported archive.
+pub fn split_composite_path(path: &str) -> Option<(&str, &str)> {
+    let (archive, member) = path.split_once(ARCHIVE_PATH_SEPARATOR)?;
+    if member.is_empty() || !is_supported_archive(Path::new(archive)) {
+        return None;
+    }
+    Some((archive, member))
+}
+
+fn is_text_member(member_path: &str) -> bool {
+    Path::new(member_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .is_some_and(|ext| {
+            ARCHIVE_TEXT_EXTENSIONS.contains(&ext.as_str()) || SUPPORTED_CODE_EXTENSIONS.contains(&ext.as_str())
+        })
+}
+
+/// Tracks the extraction budget for one archive and turns raw member bytes into members
+struct MemberCollector<'a> {
+    archive_path: &'a Path,
+    extracted_bytes: u64,
+    members: Vec<ArchiveMember>,
+}
+
+impl MemberCollector<'_> {
+    /// Returns false once the archive's size budget is used up
+    fn wants(&self, member_path: &str, size: u64) -> bool {
+        if !is_text_member(member_path) {
+            return false;
+        }
+        if size > MAX_ARCHIVE_MEMBER_BYTES {
+            debug!("Skipping large archive member {} ({} bytes)", member_path, size);
+            return false;
+        }
+        self.extracted_bytes + size <= MAX_ARCHIVE_EXTRACTED_BYTES
+    }
+
+    fn budget_exhausted(&self) -> bool {
+        self.extracted_bytes >= MAX_ARCHIVE_EXTRACTED_BYTES
+    }
+
+    fn add(&mut self, member_path: &str, mut reader: impl Read) {
+        let mut bytes = Vec::new();
+        if let Err(e) = reader.by_ref().take(MAX_ARCHIVE_MEMBER_BYTES).read_to_end(&mut bytes) {
+            warn!("Failed to read {} in {}: {}", member_path, self.archive_path.display(), e);
+            return;
+        }
+        self.extracted_bytes += bytes.len() as u64;
+        if looks_binary(&bytes) {
+            debug!("Skipping binary archive member {}", member_path);
+            return;
+        }
+        let (text, _) = decode_text(&bytes);
+        let text = normalize_text(&text);
+        if text.is_empty() {
+            return;
+        }
+        let language = match detect(&text) {
+            Some(info) if info.lang() == Lang::Eng => DetectedLanguage::English,
+            Some(info) if info.lang() == Lang::Amh => DetectedLanguage::Amharic,
+            _ => DetectedLanguage::Other,
+        };
+        self.members.push(ArchiveMember {
+            composite_path: composite_path(self.archive_path, member_path),
+            text,
+            language,
+        });
+    }
+}
+
+/// Reads the text-like members of a ZIP or TAR archive in memory.
+///
+/// Stops once `MAX_ARCHIVE_EXTRACTED_BYTES` have been read, and skips
+/// encrypted, oversized or binary members instead of failing the archive.
+pub fn extract_archive_texts(archive_path: &Path) -> Result<Vec<ArchiveMember>, ExtractorError> {
+    let kind = archive_kind(archive_path).ok_or_else(|| {
+        ExtractorError::UnsupportedFileType(archive_path.display().to_string())
+    })?;
+    let io_err = |e: std::io::Error| ExtractorError::IoError(archive_path.display().to_string(), e);
+    let file = File::open(archive_path).map_err(io_err)?;
+
+    let mut collector = MemberCollector {
+        archive_path,
+        extracted_bytes: 0,
+        members: Vec::new(),
+    };
+
+    match kind {
+        ArchiveKind::Zip => {
+            let mut zip = zip::ZipArchive::new(file).map_err(|e| {
+                ExtractorError::ArchiveExtractionFailed(archive_path.display().to_string(), e.to_string())
+            })?;
+            for i in 0..zip.len() {
+                if collector.budget_exhausted() {
+                    break;
+                }
+                // Encrypted members fail here; skip them and keep going
+                let member = match zip.by_index(i) {
+                    Ok(member) => member,
+                    Err(e) => {
+                        debug!("Skipping unreadable member #{} in {}: {}", i, archive_path.display(), e);
+                        continue;
+                    }
+                };
+                if member.is_dir() || member.encrypted() {
+                    continue;
+                }
+                let name = member.name().to_string();
+                if collector.wants(&name, member.size()) {
+                    collector.add(&name, member);
+                }
+            }
+        }
+        ArchiveKind::Tar | ArchiveKind::TarGz => {
+            let reader: Box<dyn Read> = if kind == ArchiveKind::TarGz {
+                Box::new(flate2::read::GzDecoder::new(file))
+            } else {
+                Box::new(file)
+            };
+            let mut tar = tar::Archive::new(reader);
+            let entries = tar.entries().map_err(io_err)?;
+            for entry in entries {
+                if collector.budget_exhausted() {
+                    break;
+                }
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        warn!("Stopping at corrupt entry in {}: {}", archive_path.display(), e);
+                        break;
+                    }
+                };
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let Ok(name) = entry.path().map(|p| p.to_string_lossy().to_string()) else {
+                    continue;
+                };
+                if collector.wants(&name, entry.size()) {
+                    collector.add(&name, entry);
+                }
+            }
+        }
+    }
+
+    info!(
+        "Extracted {} text members ({} bytes) from archive {}",
+        collector.members.len(),
+        collector.extracted_bytes,
+        archive_path.display()
+    );
+    Ok(collector.members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_split_composite_path() {
+        assert_eq!(
+            split_composite_path("/docs/reports.zip!2023/summary.txt"),
+            Some(("/docs/reports.zip", "2023/summary.txt"))
+        );
+        assert_eq!(split_composite_path("/docs/wow!.txt"), None);
+        assert_eq!(split_composite_path("/docs/plain.txt"), None);
+    }
+
+    #[test]
+    fn test_extract_zip_members() {
+        let dir = tempdir().unwrap();
+        let archive_path = dir.path().join("bundle.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("notes/readme.txt", options).unwrap();
+        writer.write_all(b"Meeting notes about the product launch.").unwrap();
+        // Windows-1252 text, as older tools wrote it
+        writer.start_file("notes/menu.txt", options).unwrap();
+        writer.write_all(b"Caf\xe9 menu for the spring opening.").unwrap();
+        writer.start_file("photo.jpg", options).unwrap();
+        writer.write_all(&[0xFF, 0xD8, 0xFF]).unwrap();
+        writer.finish().unwrap();
+
+        let members = extract_archive_texts(&archive_path).expect("Extraction failed");
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].composite_path, composite_path(&archive_path, "notes/readme.txt"));
+        assert_eq!(members[0].text, "Meeting notes about the product launch.");
+        assert_eq!(members[1].text, "Caf\u{e9} menu for the spring opening.");
+    }
+}
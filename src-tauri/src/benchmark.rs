@@ -1,8 +1,20 @@
-use crate::extractor::extract_text;
+use crate::db::{bump_index_version, connect_db_with_path, create_text_schema, open_or_create_text_table, TEXT_EMBEDDING_DIM};
+use crate::embedder::{embed_text, DEFAULT_MODEL_NAME};
+use crate::extractor::{extract_text, DetectedLanguage};
+use crate::image_embedder::{embed_image, MODEL_NAME as IMAGE_MODEL_NAME};
+use crate::search::{multimodal_search_with_options, SearchContentType, SearchOptions};
+use arrow_array::builder::Float32Builder;
+use arrow_array::{FixedSizeListArray, Int32Array, RecordBatch, RecordBatchIterator, StringArray, TimestampSecondArray};
 use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use lance_arrow::FixedSizeListArrayExt;
+use lancedb::index::Index;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
 use log::{info, warn, error};
 use std::path::{Path, PathBuf};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 use std::collections::HashMap;
 use std::fs;
@@ -20,6 +32,9 @@ pub enum BenchmarkError {
     
     #[error("Extraction error: {0}")]
     ExtractionError(String),
+
+    #[error("Search benchmark failed: {0}")]
+    SearchError(String),
 }
 
 #[derive(Debug, Clone)]
@@ -216,6 +231,358 @@ pub fn run_model_comparison(
     results
 }
 
+/// Default index sizes used by the search latency benchmark
+pub const DEFAULT_SEARCH_BENCHMARK_ROWS: &[usize] = &[1_000, 10_000, 100_000];
+
+/// Default number of timed queries per index size
+pub const DEFAULT_SEARCH_BENCHMARK_QUERIES: usize = 20;
+
+/// Rows are inserted in batches of this size when populating the benchmark index
+const SYNTHETIC_INSERT_BATCH: usize = 5_000;
+
+/// Queries cycled through during the search latency benchmark
+const BENCHMARK_QUERIES: &[&str] = &[
+    "quarterly financial report",
+    "machine learning research notes",
+    "meeting agenda for next week",
+    "travel itinerary and bookings",
+    "recipe with chicken and rice",
+];
+
+/// Latency measurements for one index size
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchLatencyResult {
+    /// Number of synthetic rows in the text table
+    pub row_count: usize,
+    /// Whether a vector index was built before querying
+    pub vector_index: bool,
+    /// Number of timed queries
+    pub queries_run: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub mean_ms: f64,
+}
+
+/// Returns the value at the given percentile (0.0..=100.0) of an ascending slice
+fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (pct / 100.0) * (sorted.len() - 1) as f64;
+    sorted[rank.round() as usize]
+}
+
+/// Deterministic pseudo-random unit vector so runs are comparable
+fn synthetic_embedding(seed: u64) -> Vec<f32> {
+    let mut state = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    let mut vector: Vec<f32> = (0..TEXT_EMBEDDING_DIM)
+        .map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) as f32 / u32::MAX as f32) - 0.25
+        })
+        .collect();
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+    vector
+}
+
+/// Appends `count` synthetic documents (one chunk each) starting at `offset`
+async fn insert_synthetic_documents(
+    table: &lancedb::Table,
+    offset: usize,
+    count: usize,
+) -> Result<(), BenchmarkError> {
+    let schema = create_text_schema();
+    let now_ts = chrono::Utc::now().timestamp();
+
+    let paths: Vec<String> = (offset..offset + count)
+        .map(|i| format!("/benchmark/doc_{}.txt", i))
+        .collect();
+    let hashes: Vec<String> = (offset..offset + count).map(|i| format!("hash_{}", i)).collect();
+
+    let mut embedding_builder = Float32Builder::new();
+    for i in offset..offset + count {
+        embedding_builder.append_slice(&synthetic_embedding(i as u64));
+    }
+    let values_array = Arc::new(embedding_builder.finish()) as Arc<dyn arrow_array::Array>;
+    let embedding_array = FixedSizeListArray::try_new_from_values(values_array, TEXT_EMBEDDING_DIM)
+        .map_err(|e| BenchmarkError::SearchError(e.to_string()))?;
+
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(paths)),
+            Arc::new(StringArray::from(hashes)),
+            Arc::new(Int32Array::from(vec![0; count])),
+            Arc::new(embedding_array),
+            Arc::new(TimestampSecondArray::from(vec![now_ts; count])),
+            Arc::new(StringArray::from(vec![None::<&str>; count])),
+        ],
+    )
+    .map_err(|e| BenchmarkError::SearchError(e.to_string()))?;
+
+    let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+    table
+        .add(Box::new(reader))
+        .execute()
+        .await
+        .map_err(|e| BenchmarkError::SearchError(e.to_string()))?;
+    Ok(())
+}
+
+/// Text-only search with no score cutoff, bypassing the result cache so
+/// every repeated query is actually run
+fn benchmark_search_options() -> SearchOptions {
+    SearchOptions {
+        min_score: Some(0.0),
+        content_type: Some(SearchContentType::TextOnly),
+        use_cache: false,
+        ..Default::default()
+    }
+}
+
+/// Times `queries` searches against the connection and summarizes the latencies
+async fn time_searches(
+    conn: &lancedb::Connection,
+    row_count: usize,
+    vector_index: bool,
+    queries: usize,
+) -> Result<SearchLatencyResult, BenchmarkError> {
+    let mut latencies_ms = Vec::with_capacity(queries);
+    for i in 0..queries {
+        let query = BENCHMARK_QUERIES[i % BENCHMARK_QUERIES.len()];
+        let start = Instant::now();
+        multimodal_search_with_options(conn, query, &benchmark_search_options())
+            .await
+            .map_err(|e| BenchmarkError::SearchError(e.to_string()))?;
+        latencies_ms.push(start.elapsed().as_secs_f64() * 1000.0);
+    }
+
+    latencies_ms.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mean_ms = if latencies_ms.is_empty() {
+        0.0
+    } else {
+        latencies_ms.iter().sum::<f64>() / latencies_ms.len() as f64
+    };
+
+    Ok(SearchLatencyResult {
+        row_count,
+        vector_index,
+        queries_run: latencies_ms.len(),
+        p50_ms: percentile(&latencies_ms, 50.0),
+        p95_ms: percentile(&latencies_ms, 95.0),
+        mean_ms,
+    })
+}
+
+/// Benchmark `multimodal_search` latency over temporary indexes of increasing size.
+///
+/// For each row count a fresh temporary database is filled with synthetic
+/// embeddings and queried with and without a vector index. A warmup query is
+/// issued first so model initialization does not skew the numbers.
+/// Index sizes too small for the vector index to train on only report the
+/// brute-force numbers.
+pub async fn benchmark_search_latency(
+    row_counts: &[usize],
+    queries_per_size: usize,
+) -> Result<Vec<SearchLatencyResult>, BenchmarkError> {
+    info!("Starting search latency benchmark for sizes {:?}", row_counts);
+    let mut results = Vec::new();
+
+    for &row_count in row_counts {
+        let temp_dir = TempDir::new()?;
+        let db_path = temp_dir.path().to_string_lossy().to_string();
+        let conn = connect_db_with_path(&db_path)
+            .await
+            .map_err(|e| BenchmarkError::SearchError(e.to_string()))?;
+        let table = open_or_create_text_table(&conn)
+            .await
+            .map_err(|e| BenchmarkError::SearchError(e.to_string()))?;
+
+        let mut inserted = 0;
+        while inserted < row_count {
+            let batch = SYNTHETIC_INSERT_BATCH.min(row_count - inserted);
+            insert_synthetic_documents(&table, inserted, batch).await?;
+            inserted += batch;
+        }
+        info!("Populated benchmark index with {} rows", row_count);
+
+        // Warmup: loads the embedding model and opens the tables
+        multimodal_search_with_options(&conn, BENCHMARK_QUERIES[0], &benchmark_search_options())
+            .await
+            .map_err(|e| BenchmarkError::SearchError(e.to_string()))?;
+
+        results.push(time_searches(&conn, row_count, false, queries_per_size).await?);
+
+        match table.create_index(&["embedding"], Index::Auto).execute().await {
+            Ok(_) => {
+                bump_index_version();
+                info!("Built vector index for {} rows", row_count);
+                results.push(time_searches(&conn, row_count, true, queries_per_size).await?);
+            }
+            Err(e) => {
+                warn!("Skipping indexed run for {} rows, vector index build failed: {}", row_count, e);
+            }
+        }
+    }
+
+    info!("Search latency benchmark completed with {} measurements", results.len());
+    Ok(results)
+}
+
+/// Rounds over the sample strings when the caller doesn't say
+pub const DEFAULT_THROUGHPUT_ITERATIONS: usize = 5;
+
+/// Short strings embedded by the throughput benchmark, alongside a long one built from them
+const THROUGHPUT_SAMPLES: &[&str] = &[
+    "invoice",
+    "notes from the design review",
+    "The quarterly report shows revenue growth in every region except the north.",
+    "Install the dependencies, run the migrations and restart the service before testing the new endpoint.",
+];
+
+/// Paragraph repetitions making up the long sample, roughly a page of text
+const LONG_SAMPLE_REPEATS: usize = 40;
+
+/// Sizes of the generated images the throughput benchmark embeds, from an
+/// icon to a full HD photo
+const THROUGHPUT_IMAGE_SIZES: &[(u32, u32)] = &[(64, 64), (640, 480), (1920, 1080)];
+
+/// Throughput of one embedding model on this machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelThroughput {
+    pub model_name: String,
+    pub dimension: usize,
+    /// First embedding, including loading the model if it wasn't loaded yet
+    pub warmup_ms: u64,
+    pub documents_embedded: usize,
+    pub documents_per_second: f64,
+    pub average_latency_ms: f64,
+}
+
+/// Result of [`benchmark_embedding_throughput`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingThroughputReport {
+    /// The model used for indexing English and other text
+    pub text: Option<ModelThroughput>,
+    /// The model used for indexing images
+    pub image: Option<ModelThroughput>,
+    pub iterations: usize,
+    /// Unix timestamp (seconds)
+    pub measured_at: u64,
+    /// Models that couldn't be measured and why
+    pub errors: Vec<String>,
+}
+
+static LAST_THROUGHPUT: Lazy<Mutex<Option<EmbeddingThroughputReport>>> = Lazy::new(|| Mutex::new(None));
+
+/// The last throughput measurement taken since startup
+pub fn last_embedding_throughput() -> Option<EmbeddingThroughputReport> {
+    LAST_THROUGHPUT.lock().unwrap().clone()
+}
+
+fn throughput_samples() -> Vec<String> {
+    let mut samples: Vec<String> = THROUGHPUT_SAMPLES.iter().map(|s| s.to_string()).collect();
+    samples.push(THROUGHPUT_SAMPLES.join(" ").repeat(LONG_SAMPLE_REPEATS));
+    samples
+}
+
+/// Writes a gradient PNG of each of `THROUGHPUT_IMAGE_SIZES` into `dir` and
+/// returns their paths
+fn write_throughput_images(dir: &Path) -> Result<Vec<String>, String> {
+    THROUGHPUT_IMAGE_SIZES
+        .iter()
+        .map(|&(width, height)| {
+            let image = image::RgbImage::from_fn(width, height, |x, y| {
+                image::Rgb([(x * 255 / width) as u8, (y * 255 / height) as u8, 128])
+            });
+            let path = dir.join(format!("sample_{}x{}.png", width, height));
+            image
+                .save(&path)
+                .map_err(|e| format!("Failed to write sample image {}: {}", path.display(), e))?;
+            Ok(path.to_string_lossy().into_owned())
+        })
+        .collect()
+}
+
+/// Embeds every sample `iterations` times with `embed`, which returns the
+/// embedding dimension. The first call is timed separately as warmup.
+fn measure_throughput(
+    model_name: String,
+    samples: &[String],
+    iterations: usize,
+    mut embed: impl FnMut(&str) -> Result<usize, String>,
+) -> Result<ModelThroughput, String> {
+    let warmup = Instant::now();
+    let dimension = embed(&samples[0])?;
+    let warmup_ms = warmup.elapsed().as_millis() as u64;
+
+    let mut latencies_ms = Vec::with_capacity(samples.len() * iterations);
+    for _ in 0..iterations {
+        for sample in samples {
+            let start = Instant::now();
+            embed(sample)?;
+            latencies_ms.push(start.elapsed().as_secs_f64() * 1000.0);
+        }
+    }
+    Ok(throughput_from_latencies(model_name, dimension, warmup_ms, &latencies_ms))
+}
+
+fn throughput_from_latencies(model_name: String, dimension: usize, warmup_ms: u64, latencies_ms: &[f64]) -> ModelThroughput {
+    let total_ms: f64 = latencies_ms.iter().sum();
+    let documents = latencies_ms.len();
+    ModelThroughput {
+        model_name,
+        dimension,
+        warmup_ms,
+        documents_embedded: documents,
+        documents_per_second: if total_ms > 0.0 { documents as f64 * 1000.0 / total_ms } else { 0.0 },
+        average_latency_ms: if documents > 0 { total_ms / documents as f64 } else { 0.0 },
+    }
+}
+
+/// Measures how fast the text model embeds a fixed set of short and long
+/// strings, and the image model a set of generated images, on this machine.
+/// Keeps the result for indexing estimates.
+pub fn benchmark_embedding_throughput(iterations: usize) -> EmbeddingThroughputReport {
+    let iterations = iterations.max(1);
+    let samples = throughput_samples();
+    let mut errors = Vec::new();
+
+    let text = measure_throughput(format!("{:?}", DEFAULT_MODEL_NAME), &samples, iterations, |sample| {
+        embed_text(&[sample.to_string()], &DetectedLanguage::English, false)
+            .map_err(|e| e.to_string())?
+            .first()
+            .map(Vec::len)
+            .ok_or_else(|| "No embedding returned".to_string())
+    })
+    .map_err(|e| errors.push(format!("Text model: {}", e)))
+    .ok();
+    let image = TempDir::new()
+        .map_err(|e| format!("Failed to create a folder for sample images: {}", e))
+        .and_then(|dir| {
+            let images = write_throughput_images(dir.path())?;
+            measure_throughput(format!("{:?}", IMAGE_MODEL_NAME), &images, iterations, |path| {
+                embed_image(path).map(|embedding| embedding.len()).map_err(|e| e.to_string())
+            })
+        })
+        .map_err(|e| errors.push(format!("Image model: {}", e)))
+        .ok();
+
+    let report = EmbeddingThroughputReport {
+        text,
+        image,
+        iterations,
+        measured_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        errors,
+    };
+    info!("Embedding throughput benchmark finished: {:?}", report);
+    *LAST_THROUGHPUT.lock().unwrap() = Some(report.clone());
+    report
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -309,4 +676,53 @@ mod tests {
         let all_mini_result = results.get("AllMiniLML6V2").unwrap();
         assert_eq!(all_mini_result.embedding_dimension, 384);
     }
+
+    #[test]
+    fn test_percentile() {
+        let latencies = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0];
+        assert_eq!(percentile(&latencies, 50.0), 6.0);
+        assert_eq!(percentile(&latencies, 95.0), 10.0);
+        assert_eq!(percentile(&latencies, 0.0), 1.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+    }
+
+    #[test]
+    fn test_synthetic_embedding_is_deterministic_and_normalized() {
+        let a = synthetic_embedding(42);
+        let b = synthetic_embedding(42);
+        let c = synthetic_embedding(43);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a.len(), TEXT_EMBEDDING_DIM as usize);
+        let norm = a.iter().map(|v| v * v).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_measure_throughput_counts_every_sample() {
+        let samples = throughput_samples();
+        assert!(samples.last().unwrap().len() > samples[0].len() * 100);
+
+        let mut calls = 0;
+        let result = measure_throughput("mock".to_string(), &samples, 3, |_| {
+            calls += 1;
+            Ok(384)
+        })
+        .unwrap();
+        // One warmup call plus every sample per iteration
+        assert_eq!(calls, 1 + samples.len() * 3);
+        assert_eq!(result.documents_embedded, samples.len() * 3);
+        assert_eq!(result.dimension, 384);
+
+        let dir = tempdir().unwrap();
+        let images = write_throughput_images(dir.path()).unwrap();
+        assert_eq!(images.len(), THROUGHPUT_IMAGE_SIZES.len());
+        let (width, height) = THROUGHPUT_IMAGE_SIZES[2];
+        let largest = image::open(&images[2]).unwrap();
+        assert_eq!((largest.width(), largest.height()), (width, height));
+
+        let timed = throughput_from_latencies("mock".to_string(), 384, 0, &[10.0, 30.0]);
+        assert_eq!(timed.average_latency_ms, 20.0);
+        assert_eq!(timed.documents_per_second, 50.0);
+    }
 }
@@ -0,0 +1,86 @@
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::collections::HashMap;
+
+/// Images are shrunk to this many pixels per side before counting colors
+const SAMPLE_SIZE: u32 = 32;
+
+/// Bits kept per channel when bucketing pixels; 3 bits gives 512 buckets
+const BUCKET_BITS: u8 = 3;
+
+/// Largest possible distance between two RGB colors
+const MAX_RGB_DISTANCE: f32 = 441.672_96; // sqrt(3 * 255^2)
+
+/// Parses `#rrggbb` or `rrggbb` (case-insensitive)
+pub fn parse_hex_color(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Formats a color as lowercase `#rrggbb`
+pub fn to_hex_color(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+/// Similarity of two colors from 0 (black vs white) to 1 (identical)
+pub fn color_similarity(a: [u8; 3], b: [u8; 3]) -> f32 {
+    let distance = a
+        .iter()
+        .zip(b.iter())
+        .map(|(x, y)| (*x as f32 - *y as f32).powi(2))
+        .sum::<f32>()
+        .sqrt();
+    1.0 - distance / MAX_RGB_DISTANCE
+}
+
+/// The average color of the most common coarse color bucket in the pixels
+fn dominant_color_of_pixels(pixels: impl Iterator<Item = [u8; 3]>) -> Option<[u8; 3]> {
+    let shift = 8 - BUCKET_BITS;
+    let mut buckets: HashMap<(u8, u8, u8), (u32, [u64; 3])> = HashMap::new();
+    for [r, g, b] in pixels {
+        let bucket = buckets.entry((r >> shift, g >> shift, b >> shift)).or_default();
+        bucket.0 += 1;
+        bucket.1[0] += r as u64;
+        bucket.1[1] += g as u64;
+        bucket.1[2] += b as u64;
+    }
+    let (count, sums) = buckets.into_values().max_by_key(|(count, _)| *count)?;
+    let count = count as u64;
+    Some([(sums[0] / count) as u8, (sums[1] / count) as u8, (sums[2] / count) as u8])
+}
+
+/// Dominant color of an already decoded image
+pub fn dominant_color_of_image(image: &DynamicImage) -> Option<[u8; 3]> {
+    let sample = image.resize_exact(SAMPLE_SIZE, SAMPLE_SIZE, FilterType::Triangle).to_rgb8();
+    dominant_color_of_pixels(sample.pixels().map(|p| p.0))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_round_trip_and_similarity() {
+        assert_eq!(parse_hex_color("#FF8000"), Some([255, 128, 0]));
+        assert_eq!(parse_hex_color("00ff00"), Some([0, 255, 0]));
+        assert_eq!(parse_hex_color("#12345"), None);
+        assert_eq!(to_hex_color([255, 128, 0]), "#ff8000");
+
+        assert_eq!(color_similarity([10, 20, 30], [10, 20, 30]), 1.0);
+        assert!(color_similarity([0, 0, 0], [255, 255, 255]).abs() < 1e-4);
+        assert!(color_similarity([200, 0, 0], [180, 20, 0]) > color_similarity([200, 0, 0], [0, 0, 200]));
+    }
+
+    #[test]
+    fn test_dominant_color_picks_majority() {
+        let red = std::iter::repeat([250, 10, 10]).take(70);
+        let blue = std::iter::repeat([10, 10, 250]).take(30);
+        assert_eq!(dominant_color_of_pixels(red.chain(blue)), Some([250, 10, 10]));
+        assert_eq!(dominant_color_of_pixels(std::iter::empty()), None);
+    }
+}
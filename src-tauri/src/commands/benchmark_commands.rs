@@ -1,7 +1,17 @@
 use std::path::Path;
 use serde::{Deserialize, Serialize};
 use log::info;
-use crate::benchmark::{run_model_comparison, BenchmarkResult};
+use crate::embedder::{cosine_similarity, embed_text, l2_norm};
+use crate::core::indexer::{check_content, is_text_too_short, should_stream, ContentCheck};
+use crate::extractor::{
+    calculate_file_hash, calculate_hash, detect_language, extract_text, get_content_type, stream_text_segments,
+    ContentType, DetectedLanguage,
+};
+use crate::benchmark::{
+    benchmark_embedding_throughput, benchmark_search_latency, run_model_comparison, BenchmarkResult,
+    EmbeddingThroughputReport, SearchLatencyResult, DEFAULT_SEARCH_BENCHMARK_QUERIES, DEFAULT_SEARCH_BENCHMARK_ROWS,
+    DEFAULT_THROUGHPUT_ITERATIONS,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BenchmarkRequest {
@@ -108,3 +118,290 @@ pub async fn run_benchmarks(request: BenchmarkRequest) -> Result<BenchmarkRespon
         messages,
     })
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchBenchmarkRequest {
+    /// Index sizes to benchmark (defaults to 1k/10k/100k rows)
+    pub row_counts: Option<Vec<usize>>,
+    /// Number of timed queries per index size
+    pub queries_per_size: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchBenchmarkResponse {
+    /// One entry per index size and vector-index setting
+    pub results: Vec<SearchLatencyResult>,
+}
+
+/// Measure search latency across synthetic index sizes
+#[tauri::command]
+pub async fn run_search_benchmark(request: SearchBenchmarkRequest) -> Result<SearchBenchmarkResponse, String> {
+    let row_counts = request
+        .row_counts
+        .unwrap_or_else(|| DEFAULT_SEARCH_BENCHMARK_ROWS.to_vec());
+    let queries = request.queries_per_size.unwrap_or(DEFAULT_SEARCH_BENCHMARK_QUERIES);
+    info!("Starting search benchmark for sizes {:?} ({} queries each)", row_counts, queries);
+
+    if queries == 0 {
+        return Err("queries_per_size must be greater than zero".to_string());
+    }
+
+    let results = benchmark_search_latency(&row_counts, queries)
+        .await
+        .map_err(|e| format!("Search benchmark failed: {}", e))?;
+
+    Ok(SearchBenchmarkResponse { results })
+}
+
+/// One embedded piece of the previewed text
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChunkEmbedding {
+    pub embedding: Vec<f32>,
+    pub norm: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddingPreview {
+    /// Picks the model, exactly as during indexing and search
+    pub language: DetectedLanguage,
+    pub dimension: usize,
+    /// One entry for a query; one per chunk for a passage
+    pub chunks: Vec<ChunkEmbedding>,
+}
+
+/// Embeds `text` off the async runtime, the way search (`is_query`) or indexing would
+async fn embed_for_preview(text: String, is_query: bool) -> Result<(DetectedLanguage, Vec<Vec<f32>>), String> {
+    if text.trim().is_empty() {
+        return Err("Text is empty".to_string());
+    }
+    tokio::task::spawn_blocking(move || {
+        let language = detect_language(&text);
+        embed_text(&[text], &language, is_query)
+            .map(|embeddings| (language, embeddings))
+            .map_err(|e| format!("Embedding failed: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Embedding task failed: {}", e))?
+}
+
+/// Developer command returning the embedding(s) `embed_text` produces for
+/// `text`, with their L2 norms. Passages are chunked like indexed documents.
+#[tauri::command]
+pub async fn embed_preview_command(text: String, is_query: bool) -> Result<EmbeddingPreview, String> {
+    info!("Embedding preview for {} chars (query: {})", text.len(), is_query);
+    let (language, embeddings) = embed_for_preview(text, is_query).await?;
+    Ok(EmbeddingPreview {
+        language,
+        dimension: embeddings.first().map_or(0, Vec::len),
+        chunks: embeddings
+            .into_iter()
+            .map(|embedding| ChunkEmbedding {
+                norm: l2_norm(&embedding),
+                embedding,
+            })
+            .collect(),
+    })
+}
+
+/// Developer command embedding two strings as queries and returning their
+/// cosine similarity. Strings in different languages may go to models with
+/// different dimensions, which can't be compared.
+#[tauri::command]
+pub async fn cosine_between_command(a: String, b: String) -> Result<f32, String> {
+    let (language_a, embeddings_a) = embed_for_preview(a, true).await?;
+    let (language_b, embeddings_b) = embed_for_preview(b, true).await?;
+    let (Some(vector_a), Some(vector_b)) = (embeddings_a.first(), embeddings_b.first()) else {
+        return Err("No embedding was produced".to_string());
+    };
+    cosine_similarity(vector_a, vector_b).ok_or_else(|| {
+        format!(
+            "Embeddings can't be compared ({:?}: {} dimensions, {:?}: {} dimensions)",
+            language_a,
+            vector_a.len(),
+            language_b,
+            vector_b.len()
+        )
+    })
+}
+
+/// Characters of extracted text returned by `extract_preview_command`
+const EXTRACT_PREVIEW_CHARS: usize = 2000;
+
+/// What indexing would make of one file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtractionPreview {
+    pub content_type: ContentType,
+    /// Text files only
+    pub language: Option<DetectedLanguage>,
+    /// Characters of extracted text, after normalization
+    pub text_length: usize,
+    /// The first `EXTRACT_PREVIEW_CHARS` characters of the extracted text
+    pub text_preview: String,
+    /// Hash stored with the file's rows: of the text, or of the bytes for
+    /// images and streamed files
+    pub content_hash: Option<String>,
+    /// Read in segments, as indexing does for text files above `streaming_threshold_bytes`
+    pub streamed: bool,
+    /// False when the text is empty or shorter than `min_text_chars`, so indexing skips the file
+    pub indexable: bool,
+    pub image_width: Option<u32>,
+    pub image_height: Option<u32>,
+    /// Why extraction failed, if it did
+    pub error: Option<String>,
+}
+
+fn preview_extraction(path: &Path, min_text_chars: usize, streaming_threshold_bytes: u64) -> ExtractionPreview {
+    let content_type = get_content_type(path);
+    let mut preview = ExtractionPreview {
+        content_type: content_type.clone(),
+        language: None,
+        text_length: 0,
+        text_preview: String::new(),
+        content_hash: None,
+        streamed: false,
+        indexable: false,
+        image_width: None,
+        image_height: None,
+        error: None,
+    };
+    if content_type == ContentType::Image {
+        match image::image_dimensions(path) {
+            Ok((width, height)) => {
+                preview.image_width = Some(width);
+                preview.image_height = Some(height);
+            }
+            Err(e) => preview.error = Some(format!("Failed to read image dimensions: {}", e)),
+        }
+        match calculate_file_hash(path) {
+            Ok(hash) => {
+                preview.content_hash = Some(hash);
+                preview.indexable = true;
+            }
+            Err(e) => preview.error = Some(e.to_string()),
+        }
+        return preview;
+    }
+    if should_stream(path, streaming_threshold_bytes) {
+        preview.streamed = true;
+        preview_streamed_text(path, min_text_chars, &mut preview);
+        return preview;
+    }
+
+    match extract_text(path) {
+        Ok(extraction) => {
+            preview.language = Some(extraction.language);
+            preview.text_length = extraction.text.chars().count();
+            preview.text_preview = extraction.text.chars().take(EXTRACT_PREVIEW_CHARS).collect();
+            preview.content_hash = Some(calculate_hash(&extraction.text));
+            preview.indexable = check_content(&extraction.text, min_text_chars) == ContentCheck::Meaningful;
+        }
+        Err(e) => preview.error = Some(e.to_string()),
+    }
+    preview
+}
+
+/// Fills `preview` the way indexing reads a large text file: the hash is of
+/// the raw bytes, the text is read a segment at a time and the language is
+/// detected once there is enough of it
+fn preview_streamed_text(path: &Path, min_text_chars: usize, preview: &mut ExtractionPreview) {
+    match calculate_file_hash(path) {
+        Ok(hash) => preview.content_hash = Some(hash),
+        Err(e) => {
+            preview.error = Some(e.to_string());
+            return;
+        }
+    }
+    let segments = match stream_text_segments(path) {
+        Ok(segments) => segments,
+        Err(e) => {
+            preview.error = Some(e.to_string());
+            return;
+        }
+    };
+    let mut leading_text = String::new();
+    for segment in segments {
+        let segment = match segment {
+            Ok(segment) => segment,
+            Err(e) => {
+                preview.error = Some(e.to_string());
+                return;
+            }
+        };
+        preview.text_length += segment.chars().count();
+        let room = EXTRACT_PREVIEW_CHARS.saturating_sub(preview.text_preview.chars().count());
+        preview.text_preview.extend(segment.chars().take(room));
+        if preview.language.is_none() {
+            leading_text.push_str(&segment);
+            if !is_text_too_short(&leading_text, min_text_chars) {
+                preview.language = Some(detect_language(&leading_text));
+                preview.indexable = true;
+                leading_text.clear();
+            }
+        }
+    }
+}
+
+/// Developer command showing what indexing extracts from `path`: content
+/// type, language, text and hash for documents, dimensions for images.
+/// Extraction failures are reported in the preview rather than as errors.
+#[tauri::command]
+pub async fn extract_preview_command(path: String) -> Result<ExtractionPreview, String> {
+    let file_path = Path::new(&path).to_path_buf();
+    if !file_path.is_file() {
+        return Err(format!("Not a file: {}", path));
+    }
+    info!("Extraction preview for {}", path);
+    let settings = crate::core::settings::current_settings();
+    let (min_text_chars, streaming_threshold_bytes) = (settings.min_text_chars, settings.streaming_threshold_bytes);
+    tokio::task::spawn_blocking(move || preview_extraction(&file_path, min_text_chars, streaming_threshold_bytes))
+        .await
+        .map_err(|e| format!("Extraction task failed: {}", e))
+}
+
+/// Measures embedding throughput of the text and image models on this
+/// machine. The result is kept and used by indexing time estimates.
+#[tauri::command]
+pub async fn benchmark_embedding_command(iterations: Option<usize>) -> Result<EmbeddingThroughputReport, String> {
+    let iterations = iterations.unwrap_or(DEFAULT_THROUGHPUT_ITERATIONS);
+    info!("Benchmarking embedding throughput ({} iterations)", iterations);
+    tokio::task::spawn_blocking(move || benchmark_embedding_throughput(iterations))
+        .await
+        .map_err(|e| format!("Embedding benchmark failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_preview_extraction_reports_text_and_images() {
+        let dir = tempdir().unwrap();
+        let text_path = dir.path().join("notes.txt");
+        std::fs::write(&text_path, "The quarterly report covers revenue and hiring plans.").unwrap();
+        let preview = preview_extraction(&text_path, 10, u64::MAX);
+        assert_eq!(preview.content_type, ContentType::Text);
+        assert_eq!(preview.language, Some(DetectedLanguage::English));
+        assert_eq!(preview.text_length, preview.text_preview.chars().count());
+        assert_eq!(preview.content_hash, Some(calculate_hash(&preview.text_preview)));
+        assert!(preview.indexable);
+        assert!(!preview.streamed);
+        assert!(!preview_extraction(&text_path, 1000, u64::MAX).indexable);
+
+        // Above the streaming threshold the hash is of the file, as indexing stores it
+        let streamed = preview_extraction(&text_path, 10, 8);
+        assert!(streamed.streamed);
+        assert_eq!(streamed.content_hash, Some(calculate_file_hash(&text_path).unwrap()));
+        assert_eq!(streamed.text_preview, preview.text_preview);
+        assert_eq!(streamed.text_length, preview.text_length);
+        assert_eq!(streamed.language, Some(DetectedLanguage::English));
+        assert!(streamed.indexable);
+
+        let image_path = dir.path().join("pixel.png");
+        image::RgbImage::new(3, 2).save(&image_path).unwrap();
+        let preview = preview_extraction(&image_path, 10, u64::MAX);
+        assert_eq!(preview.content_type, ContentType::Image);
+        assert_eq!((preview.image_width, preview.image_height), (Some(3), Some(2)));
+        assert_eq!(preview.content_hash, Some(calculate_file_hash(&image_path).unwrap()));
+    }
+}
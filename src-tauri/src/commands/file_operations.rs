@@ -1,8 +1,16 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use serde::{Serialize, Deserialize};
 use tokio::fs;
+use tokio::io::AsyncReadExt;
 use std::fs::metadata;
-use tracing::{info, error};
+use tracing::{info, error, warn};
+use crate::core::cancellation::{cancel_operation, register_operation, CancellationToken};
+use crate::core::compare::{compare_directories, DiffEntry, DiffMode};
+use crate::core::storage::{storage_by_category, StorageBreakdown};
+use crate::core::tombstones::{tombstone_folder, tombstone_path};
+use crate::core::virtual_content::{is_virtual_path, remove_virtual_content};
+use crate::repair_db::update_moved_paths;
+use tauri::{AppHandle, Emitter};
 
 #[derive(Debug, Serialize, Deserialize, thiserror::Error)]
 pub enum FileOperationError {
@@ -47,8 +55,8 @@ fn is_parent_of(source: &Path, destination: &Path) -> bool {
     }
 }
 
-/// Helper function to copy a single file
-async fn copy_file(src: &Path, dst: &Path) -> Result<(), FileOperationError> {
+/// Helper function to copy a single file. Returns the bytes copied.
+async fn copy_file(src: &Path, dst: &Path) -> Result<u64, FileOperationError> {
     // Make sure parent directory exists
     if let Some(parent) = dst.parent() {
         fs::create_dir_all(parent).await
@@ -57,27 +65,113 @@ async fn copy_file(src: &Path, dst: &Path) -> Result<(), FileOperationError> {
     
     // Copy file
     fs::copy(src, dst).await
-        .map_err(|e| io_to_error(e, dst.to_str().unwrap_or("")))?;
-    
-    Ok(())
+        .map_err(|e| io_to_error(e, dst.to_str().unwrap_or("")))
+}
+
+/// How far a copy or move got
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransferSummary {
+    pub files_copied: u64,
+    pub bytes_copied: u64,
+    /// Stopped by `cancel_operation_command` before every file was copied
+    pub cancelled: bool,
+    /// The partial copy was removed after cancelling
+    pub rolled_back: bool,
+}
+
+/// What a directory copy created, so a cancelled copy can be undone
+#[derive(Default)]
+struct CopiedPaths {
+    /// Only files that didn't exist before; overwritten ones are left alone
+    files: Vec<PathBuf>,
+    /// Only directories that didn't exist before, parents first
+    dirs: Vec<PathBuf>,
+}
+
+impl CopiedPaths {
+    /// Creates `dir` and any missing parents, recording each one created
+    async fn create_dir(&mut self, dir: &Path) -> Result<(), FileOperationError> {
+        let mut missing = Vec::new();
+        for ancestor in dir.ancestors() {
+            if ancestor.as_os_str().is_empty() || fs::metadata(ancestor).await.is_ok() {
+                break;
+            }
+            missing.push(ancestor.to_path_buf());
+        }
+        if missing.is_empty() {
+            return Ok(());
+        }
+        fs::create_dir_all(dir).await
+            .map_err(|e| io_to_error(e, dir.to_str().unwrap_or("")))?;
+        self.dirs.extend(missing.into_iter().rev());
+        Ok(())
+    }
+
+    /// Copies `src` to `dst`, recording `dst` if the copy created it
+    async fn copy_file(&mut self, src: &Path, dst: &Path) -> Result<u64, FileOperationError> {
+        let existed = fs::symlink_metadata(dst).await.is_ok();
+        let bytes = copy_file(src, dst).await?;
+        if !existed {
+            self.files.push(dst.to_path_buf());
+        }
+        Ok(bytes)
+    }
+
+    /// Removes the files and directories the copy created. Files it
+    /// overwrote keep their new contents rather than being deleted.
+    async fn roll_back(&self) {
+        for file in &self.files {
+            if let Err(e) = fs::remove_file(file).await {
+                warn!("Could not roll back copied file '{}': {}", file.display(), e);
+            }
+        }
+        for dir in self.dirs.iter().rev() {
+            if let Err(e) = fs::remove_dir(dir).await {
+                warn!("Could not roll back created folder '{}': {}", dir.display(), e);
+            }
+        }
+    }
 }
 
-/// Helper function to copy a directory without recursion
-async fn copy_directory(src_dir: &Path, dst_dir: &Path) -> Result<(), FileOperationError> {
+/// Helper function to copy a directory without recursion. `token` is checked
+/// between files; a cancelled copy stops there, and is undone when `rollback`
+/// is set.
+async fn copy_directory(
+    src_dir: &Path,
+    dst_dir: &Path,
+    token: &CancellationToken,
+    rollback: bool,
+) -> Result<TransferSummary, FileOperationError> {
+    copy_directory_until(src_dir, dst_dir, || token.is_cancelled(), rollback).await
+}
+
+/// `copy_directory` stopping once `should_stop` returns true
+async fn copy_directory_until(
+    src_dir: &Path,
+    dst_dir: &Path,
+    mut should_stop: impl FnMut() -> bool,
+    rollback: bool,
+) -> Result<TransferSummary, FileOperationError> {
+    let mut summary = TransferSummary::default();
+    let mut copied = CopiedPaths::default();
+
     // Create the target directory
-    fs::create_dir_all(dst_dir).await
-        .map_err(|e| io_to_error(e, dst_dir.to_str().unwrap_or("")))?;
+    copied.create_dir(dst_dir).await?;
     
     // Collect all files and directories first to avoid recursion issues
     let mut dirs_to_process = vec![(src_dir.to_path_buf(), dst_dir.to_path_buf())];
     
     // Process each directory and its contents
-    while let Some((src, dst)) = dirs_to_process.pop() {
+    'copy: while let Some((src, dst)) = dirs_to_process.pop() {
         // Read directory entries
         let mut read_dir = fs::read_dir(&src).await
             .map_err(|e| io_to_error(e, src.to_str().unwrap_or("")))?;
         
         while let Ok(Some(entry)) = read_dir.next_entry().await {
+            if should_stop() {
+                summary.cancelled = true;
+                break 'copy;
+            }
             let entry_path = entry.path();
             let file_name = entry.file_name();
             let dst_path = dst.join(file_name);
@@ -85,24 +179,56 @@ async fn copy_directory(src_dir: &Path, dst_dir: &Path) -> Result<(), FileOperat
             // Handle directory or file
             if entry_path.is_dir() {
                 // Create the directory
-                fs::create_dir_all(&dst_path).await
-                    .map_err(|e| io_to_error(e, dst_path.to_str().unwrap_or("")))?;
+                copied.create_dir(&dst_path).await?;
                 
                 // Add to processing queue
                 dirs_to_process.push((entry_path, dst_path));
             } else {
                 // Copy the file
-                copy_file(&entry_path, &dst_path).await?;
+                summary.bytes_copied += copied.copy_file(&entry_path, &dst_path).await?;
+                summary.files_copied += 1;
             }
         }
     }
-    
-    Ok(())
+
+    if summary.cancelled {
+        info!(
+            "Copy of '{}' cancelled after {} files ({} bytes)",
+            src_dir.display(),
+            summary.files_copied,
+            summary.bytes_copied
+        );
+        if rollback {
+            copied.roll_back().await;
+            summary.rolled_back = true;
+        }
+    }
+    Ok(summary)
+}
+
+/// Copies a single file, counting it in a summary
+async fn copy_single_file(src: &Path, dst: &Path) -> Result<TransferSummary, FileOperationError> {
+    let bytes_copied = copy_file(src, dst).await?;
+    Ok(TransferSummary {
+        files_copied: 1,
+        bytes_copied,
+        ..Default::default()
+    })
 }
 
-/// Copy a file or directory to a new location
+/// Copy a file or directory to a new location.
+///
+/// Pass `operation_id` to be able to stop a folder copy with
+/// `cancel_operation_command`. It stops between files and reports what was
+/// copied; with `rollback_on_cancel` the files and folders it created are
+/// removed again. Files it overwrote keep their new contents.
 #[tauri::command]
-pub async fn copy_item(source: String, destination: String) -> Result<(), FileOperationError> {
+pub async fn copy_item(
+    source: String,
+    destination: String,
+    operation_id: Option<String>,
+    rollback_on_cancel: Option<bool>,
+) -> Result<TransferSummary, FileOperationError> {
     info!("Copying from '{}' to '{}'", source, destination);
     
     let source_path = Path::new(&source);
@@ -124,18 +250,26 @@ pub async fn copy_item(source: String, destination: String) -> Result<(), FileOp
     
     // Perform the copy based on whether it's a file or directory
     if source_path.is_dir() {
-        copy_directory(source_path, destination_path).await?
+        let token = register_operation(operation_id.as_deref());
+        copy_directory(source_path, destination_path, &token, rollback_on_cancel.unwrap_or(false)).await
     } else {
-        copy_file(source_path, destination_path).await?
+        copy_single_file(source_path, destination_path).await
     }
-    
-    Ok(())
 }
 
 
-/// Move a file or directory to a new location
+/// Move a file or directory to a new location.
+///
+/// Only a move to another filesystem copies files, and only then can
+/// `operation_id` stop it (see `copy_item`). A cancelled move leaves the source
+/// in place.
 #[tauri::command]
-pub async fn move_item(source: String, destination: String) -> Result<(), FileOperationError> {
+pub async fn move_item(
+    source: String,
+    destination: String,
+    operation_id: Option<String>,
+    rollback_on_cancel: Option<bool>,
+) -> Result<TransferSummary, FileOperationError> {
     info!("Moving from '{}' to '{}'", source, destination);
     
     let source_path = Path::new(&source);
@@ -161,17 +295,135 @@ pub async fn move_item(source: String, destination: String) -> Result<(), FileOp
             .map_err(|e| io_to_error(e, parent.to_str().unwrap_or("")))?;
     }
     
-    // Perform the move operation
-    fs::rename(source_path, destination_path).await
-        .map_err(|e| io_to_error(e, &destination))?;
-    
-    Ok(())
+    // Perform the move operation, falling back to copy + delete across filesystems
+    let summary = match fs::rename(source_path, destination_path).await {
+        Ok(()) => TransferSummary::default(),
+        Err(e) if is_cross_device_error(&e) => {
+            info!("'{}' is on another device, moving by copy and delete", source);
+            if source_path.is_dir() {
+                let token = register_operation(operation_id.as_deref());
+                let summary =
+                    copy_directory(source_path, destination_path, &token, rollback_on_cancel.unwrap_or(false)).await?;
+                if summary.cancelled {
+                    return Ok(summary);
+                }
+                fs::remove_dir_all(source_path).await
+                    .map_err(|e| io_to_error(e, &source))?;
+                summary
+            } else {
+                let summary = copy_single_file(source_path, destination_path).await?;
+                fs::remove_file(source_path).await
+                    .map_err(|e| io_to_error(e, &source))?;
+                summary
+            }
+        }
+        Err(e) => return Err(io_to_error(e, &destination)),
+    };
+
+    update_index_paths(source_path, destination_path).await;
+    Ok(summary)
 }
 
-/// Delete a file or directory
+/// Points index entries at a renamed or moved item's new path. The item has
+/// already moved, so failing here only leaves stale entries for the next scan.
+async fn update_index_paths(old: &Path, new: &Path) {
+    let updated = match crate::db::connect_db().await {
+        Ok(conn) => update_moved_paths(&conn, old, new).await,
+        Err(e) => Err(format!("Database connection failed: {}", e)),
+    };
+    if let Err(e) = updated {
+        warn!("Moved '{}' but could not update its index entries: {}", old.display(), e);
+    }
+}
+
+/// Whether a rename failed because source and destination are on different filesystems
+fn is_cross_device_error(error: &std::io::Error) -> bool {
+    // The standard library maps both EXDEV and ERROR_NOT_SAME_DEVICE to this kind
+    error.kind() == std::io::ErrorKind::CrossesDevices
+}
+
+/// Outcome of moving one source in a batch move
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MoveResult {
+    pub source: String,
+    /// Where the item ended up, when the move succeeded
+    pub destination: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Create `parent_dir/folder_name` and move every source into it.
+///
+/// Fails up front if the folder already exists, unless `reuse_existing` is set.
+/// After that each source is moved independently and reported in the results.
+#[tauri::command]
+pub async fn move_to_new_folder_command(
+    sources: Vec<String>,
+    parent_dir: String,
+    folder_name: String,
+    reuse_existing: Option<bool>,
+) -> Result<Vec<MoveResult>, FileOperationError> {
+    let name = folder_name.trim();
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(FileOperationError::InvalidPath(format!("Invalid folder name: {}", folder_name)));
+    }
+    let parent = Path::new(&parent_dir);
+    if !parent.is_dir() {
+        return Err(FileOperationError::NotFound(parent_dir));
+    }
+
+    let folder = parent.join(name);
+    let folder_display = folder.to_string_lossy().to_string();
+    if folder.exists() {
+        if !(reuse_existing.unwrap_or(false) && folder.is_dir()) {
+            return Err(FileOperationError::AlreadyExists(folder_display));
+        }
+    } else {
+        fs::create_dir(&folder).await
+            .map_err(|e| io_to_error(e, &folder_display))?;
+    }
+    info!("Moving {} items into '{}'", sources.len(), folder_display);
+
+    let mut results = Vec::with_capacity(sources.len());
+    for source in sources {
+        let Some(file_name) = Path::new(&source).file_name() else {
+            results.push(MoveResult {
+                error: Some(FileOperationError::InvalidPath(source.clone()).to_string()),
+                source,
+                destination: None,
+            });
+            continue;
+        };
+        let destination = folder.join(file_name).to_string_lossy().to_string();
+        let outcome = if Path::new(&destination).exists() {
+            Err(FileOperationError::AlreadyExists(destination.clone()))
+        } else {
+            move_item(source.clone(), destination.clone(), None, None).await
+        };
+        match outcome {
+            Ok(_) => results.push(MoveResult { source, destination: Some(destination), error: None }),
+            Err(e) => {
+                error!("Failed to move '{}' into '{}': {}", source, folder_display, e);
+                results.push(MoveResult { source, destination: None, error: Some(e.to_string()) });
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// Delete a file or directory. Its index entries are replaced by tombstones,
+/// see `list_tombstones_command`. Virtual paths from `index_content_command`
+/// have no file, so deleting one removes it from the index instead.
 #[tauri::command]
 pub async fn delete_item(path: String) -> Result<(), FileOperationError> {
     info!("Deleting '{}'", path);
+
+    if is_virtual_path(&path) {
+        let conn = crate::db::connect_db()
+            .await
+            .map_err(|e| FileOperationError::IoError(format!("Database connection failed: {}", e)))?;
+        return remove_virtual_content(&conn, &path).await.map_err(FileOperationError::InvalidPath);
+    }
     
     let path_obj = Path::new(&path);
     
@@ -181,14 +433,25 @@ pub async fn delete_item(path: String) -> Result<(), FileOperationError> {
     }
     
     // Perform the delete operation
-    if path_obj.is_dir() {
+    let is_dir = path_obj.is_dir();
+    if is_dir {
         fs::remove_dir_all(path_obj).await
             .map_err(|e| io_to_error(e, &path))?;
     } else {
         fs::remove_file(path_obj).await
             .map_err(|e| io_to_error(e, &path))?;
     }
-    
+
+    // The file is gone either way; a stale index entry is cleaned up by the integrity check
+    let tombstoned = match crate::db::connect_db().await {
+        Ok(conn) if is_dir => tombstone_folder(&conn, path_obj).await,
+        Ok(conn) => tombstone_path(&conn, &path).await,
+        Err(e) => Err(format!("Database connection failed: {}", e)),
+    };
+    if let Err(e) = tombstoned {
+        warn!("Deleted '{}' but could not tombstone its index entries: {}", path, e);
+    }
+
     Ok(())
 }
 
@@ -219,7 +482,8 @@ pub async fn rename_item(path: String, new_name: String) -> Result<(), FileOpera
     // Perform the rename operation
     fs::rename(path_obj, &new_path).await
         .map_err(|e| io_to_error(e, &path))?;
-    
+
+    update_index_paths(path_obj, &new_path).await;
     Ok(())
 }
 
@@ -266,3 +530,464 @@ pub fn get_item_info(path: String) -> Result<serde_json::Value, FileOperationErr
     
     Ok(info)
 }
+
+/// Upper bound on `max_bytes` so a preview can never pull a whole large file into memory
+const MAX_PREVIEW_BYTES: usize = 1024 * 1024;
+
+/// How many leading bytes of a binary file are shown as hex
+const HEX_PREVIEW_BYTES: usize = 256;
+
+/// Extensions whose raw bytes aren't readable and need text extraction instead
+const EXTRACTED_PREVIEW_EXTENSIONS: &[&str] = &["pdf", "docx"];
+
+/// How a preview was produced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewKind {
+    /// Leading bytes decoded as (lossy) UTF-8
+    Text,
+    /// Leading text extracted from a document format such as PDF or DOCX
+    Extracted,
+    /// Hex dump of the leading bytes of a binary file
+    Binary,
+}
+
+/// Preview of the start of a file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContentPreview {
+    pub kind: PreviewKind,
+    pub content: String,
+    pub file_size: u64,
+    /// True when the file holds more than what the preview shows
+    pub truncated: bool,
+}
+
+/// Heuristic used by most editors: NUL bytes or a high share of control characters mean binary
+fn looks_binary(bytes: &[u8]) -> bool {
+    if bytes.contains(&0) {
+        return true;
+    }
+    let control = bytes
+        .iter()
+        .filter(|b| b.is_ascii_control() && !matches!(b, b'\n' | b'\r' | b'\t' | 0x0c))
+        .count();
+    !bytes.is_empty() && control * 10 > bytes.len()
+}
+
+/// Decodes bytes as UTF-8, dropping a multi-byte character cut off by the read limit
+fn decode_preview(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(e) if e.error_len().is_none() => String::from_utf8_lossy(&bytes[..e.valid_up_to()]).into_owned(),
+        Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Formats bytes as `offset  hex bytes  |ascii|` lines of 16
+fn hex_dump(bytes: &[u8]) -> String {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(line, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:08x}  {:<47}  |{}|", line * 16, hex.join(" "), ascii)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns a preview of the first `max_bytes` of a file without reading all of it
+#[tauri::command]
+pub async fn get_content_preview_command(path: String, max_bytes: usize) -> Result<ContentPreview, FileOperationError> {
+    let path_obj = Path::new(&path);
+    let file_size = metadata(&path).map_err(|e| io_to_error(e, &path))?.len();
+    if !path_obj.is_file() {
+        return Err(FileOperationError::InvalidPath(format!("{} is not a file", path)));
+    }
+    let max_bytes = max_bytes.clamp(1, MAX_PREVIEW_BYTES);
+
+    let extension = path_obj
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+    if EXTRACTED_PREVIEW_EXTENSIONS.contains(&extension.as_str()) {
+        let owned_path = path_obj.to_path_buf();
+        let extracted = tokio::task::spawn_blocking(move || crate::extractor::extract_text(&owned_path))
+            .await
+            .map_err(|e| FileOperationError::IoError(e.to_string()))?
+            .map_err(|e| FileOperationError::IoError(e.to_string()))?;
+        let mut end = extracted.text.len().min(max_bytes);
+        while !extracted.text.is_char_boundary(end) {
+            end -= 1;
+        }
+        return Ok(ContentPreview {
+            kind: PreviewKind::Extracted,
+            content: extracted.text[..end].to_string(),
+            file_size,
+            truncated: end < extracted.text.len(),
+        });
+    }
+
+    let file = fs::File::open(&path).await.map_err(|e| io_to_error(e, &path))?;
+    let mut bytes = Vec::with_capacity(max_bytes.min(file_size as usize));
+    file.take(max_bytes as u64)
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| io_to_error(e, &path))?;
+
+    // UTF-16 text is full of NUL bytes, so check the encoding before the binary heuristic
+    let (encoding, _) = crate::extractor::detect_encoding(&bytes);
+    let is_utf16 = encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE;
+    let preview = if !is_utf16 && looks_binary(&bytes) {
+        let shown = bytes.len().min(HEX_PREVIEW_BYTES);
+        ContentPreview {
+            kind: PreviewKind::Binary,
+            content: hex_dump(&bytes[..shown]),
+            file_size,
+            truncated: (shown as u64) < file_size,
+        }
+    } else {
+        ContentPreview {
+            kind: PreviewKind::Text,
+            content: if encoding == encoding_rs::UTF_8 {
+                decode_preview(&bytes)
+            } else {
+                crate::extractor::decode_text(&bytes).0
+            },
+            file_size,
+            truncated: (bytes.len() as u64) < file_size,
+        }
+    };
+    info!("Previewed {} as {:?} ({} bytes read)", path, preview.kind, bytes.len());
+    Ok(preview)
+}
+
+/// Largest window one range read may return; players request media in chunks
+const MAX_RANGE_READ_BYTES: usize = 16 * 1024 * 1024;
+
+/// Reads `length` bytes starting at `offset`, stopping early at end of file.
+/// Only the requested window is ever read, so files larger than memory work.
+fn read_range(path: &str, offset: u64, length: usize) -> Result<Vec<u8>, FileOperationError> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = std::fs::File::open(path).map_err(|e| io_to_error(e, path))?;
+    let file_metadata = file.metadata().map_err(|e| io_to_error(e, path))?;
+    let file_size = file_metadata.len();
+    if !file_metadata.is_file() {
+        return Err(FileOperationError::InvalidPath(format!("{} is not a file", path)));
+    }
+    if offset > file_size {
+        return Err(FileOperationError::InvalidPath(format!(
+            "Offset {} is past the end of {} ({} bytes)",
+            offset, path, file_size
+        )));
+    }
+
+    let window = (length as u64).min(file_size - offset) as usize;
+    file.seek(SeekFrom::Start(offset)).map_err(|e| io_to_error(e, path))?;
+    let mut bytes = Vec::with_capacity(window);
+    file.take(window as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|e| io_to_error(e, path))?;
+    Ok(bytes)
+}
+
+/// Returns the raw bytes of `path` in `[offset, offset + length)`, truncated at end of file,
+/// for streaming media previews. `length` may not exceed 16 MiB.
+#[tauri::command]
+pub async fn read_file_range_command(path: String, offset: u64, length: usize) -> Result<tauri::ipc::Response, FileOperationError> {
+    if length > MAX_RANGE_READ_BYTES {
+        return Err(FileOperationError::InvalidPath(format!(
+            "Requested {} bytes; at most {} can be read at once",
+            length, MAX_RANGE_READ_BYTES
+        )));
+    }
+    let owned_path = path.clone();
+    let bytes = tokio::task::spawn_blocking(move || read_range(&owned_path, offset, length))
+        .await
+        .map_err(|e| FileOperationError::IoError(e.to_string()))??;
+    info!("Read {} bytes of {} at offset {}", bytes.len(), path, offset);
+    Ok(tauri::ipc::Response::new(bytes))
+}
+
+/// Returns the heading structure of a Markdown, PDF or DOCX file as a nested outline
+#[tauri::command]
+pub async fn document_outline_command(path: String) -> Result<Vec<crate::outline::OutlineEntry>, FileOperationError> {
+    let owned_path = Path::new(&path).to_path_buf();
+    if !owned_path.is_file() {
+        return Err(FileOperationError::InvalidPath(format!("{} is not a file", path)));
+    }
+    let outline = tokio::task::spawn_blocking(move || crate::outline::document_outline(&owned_path))
+        .await
+        .map_err(|e| FileOperationError::IoError(e.to_string()))?
+        .map_err(|e| FileOperationError::IoError(e.to_string()))?;
+    info!("Read outline of '{}' with {} top-level headings", path, outline.len());
+    Ok(outline)
+}
+
+/// Encoding detected for a text file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EncodingReport {
+    /// WHATWG encoding name, e.g. "UTF-8", "UTF-16LE" or "windows-1252"
+    pub encoding: String,
+    /// Whether the file starts with a byte order mark
+    pub has_bom: bool,
+}
+
+/// Detects the text encoding of a file from its leading bytes
+#[tauri::command]
+pub async fn detect_encoding_command(path: String) -> Result<EncodingReport, FileOperationError> {
+    let file = fs::File::open(&path).await.map_err(|e| io_to_error(e, &path))?;
+    let mut bytes = Vec::new();
+    file.take(64 * 1024)
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| io_to_error(e, &path))?;
+
+    let (encoding, has_bom) = crate::extractor::detect_encoding(&bytes);
+    info!("Detected encoding {} for '{}'", encoding.name(), path);
+    Ok(EncodingReport {
+        encoding: encoding.name().to_string(),
+        has_bom,
+    })
+}
+
+/// One page of a directory comparison
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DirectoryDiffPage {
+    pub entries: Vec<DiffEntry>,
+    /// Number of differences in the whole comparison
+    pub total: usize,
+    pub offset: usize,
+    pub files_compared: usize,
+    pub cancelled: bool,
+}
+
+/// Compare two folders by name, size or content hash.
+///
+/// Pass `operation_id` to be able to stop the comparison with
+/// `cancel_operation_command`; a cancelled comparison returns what it found so far.
+#[tauri::command]
+pub async fn compare_directories_command(
+    a: String,
+    b: String,
+    mode: DiffMode,
+    operation_id: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<DirectoryDiffPage, FileOperationError> {
+    for dir in [&a, &b] {
+        if !Path::new(dir).is_dir() {
+            return Err(FileOperationError::InvalidPath(format!("{} is not a directory", dir)));
+        }
+    }
+    info!("Comparing '{}' and '{}' by {:?}", a, b, mode);
+
+    let token = register_operation(operation_id.as_deref());
+    let diff = tokio::task::spawn_blocking(move || compare_directories(Path::new(&a), Path::new(&b), mode, &token))
+        .await
+        .map_err(|e| FileOperationError::IoError(e.to_string()))?;
+
+    let total = diff.entries.len();
+    let offset = offset.unwrap_or(0).min(total);
+    let limit = limit.unwrap_or(total);
+    Ok(DirectoryDiffPage {
+        entries: diff.entries.into_iter().skip(offset).take(limit).collect(),
+        total,
+        offset,
+        files_compared: diff.files_compared,
+        cancelled: diff.cancelled,
+    })
+}
+
+/// Event emitted while `storage_by_category_command` walks a tree
+pub const STORAGE_SCAN_PROGRESS_EVENT: &str = "storage-scan-progress";
+
+#[derive(Debug, Clone, Serialize)]
+struct StorageScanProgress {
+    root: String,
+    operation_id: Option<String>,
+    files_scanned: usize,
+    bytes_scanned: u64,
+}
+
+/// Sums disk usage under `root` per file category, emitting
+/// `storage-scan-progress` events; cancel it through `operation_id`.
+#[tauri::command]
+pub async fn storage_by_category_command(
+    app_handle: AppHandle,
+    root: String,
+    operation_id: Option<String>,
+) -> Result<StorageBreakdown, FileOperationError> {
+    if !Path::new(&root).is_dir() {
+        return Err(FileOperationError::InvalidPath(format!("{} is not a directory", root)));
+    }
+    info!("Measuring storage by category under '{}'", root);
+
+    let token = register_operation(operation_id.as_deref());
+    tokio::task::spawn_blocking(move || {
+        storage_by_category(Path::new(&root), &token, |files_scanned, bytes_scanned| {
+            let progress = StorageScanProgress {
+                root: root.clone(),
+                operation_id: operation_id.clone(),
+                files_scanned,
+                bytes_scanned,
+            };
+            if let Err(e) = app_handle.emit(STORAGE_SCAN_PROGRESS_EVENT, progress) {
+                error!("Failed to emit storage scan progress: {}", e);
+            }
+        })
+    })
+    .await
+    .map_err(|e| FileOperationError::IoError(e.to_string()))
+}
+
+/// Ask a running cancellable operation to stop. Returns false if it isn't running.
+#[tauri::command]
+pub fn cancel_operation_command(operation_id: String) -> Result<bool, FileOperationError> {
+    Ok(cancel_operation(&operation_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_cancelled_directory_copy_rolls_back() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("photos");
+        std::fs::create_dir_all(source.join("2024")).unwrap();
+        std::fs::write(source.join("a.jpg"), b"abc").unwrap();
+        std::fs::write(source.join("2024/b.jpg"), b"defg").unwrap();
+
+        let copy = dir.path().join("copy");
+        let summary = copy_directory(&source, &copy, &CancellationToken::none(), true).await.unwrap();
+        assert_eq!((summary.files_copied, summary.bytes_copied, summary.cancelled), (2, 7, false));
+        assert!(copy.join("2024/b.jpg").exists());
+
+        let token = register_operation(Some("copy-test"));
+        assert!(cancel_operation("copy-test"));
+        let cancelled = dir.path().join("cancelled");
+        let summary = copy_directory(&source, &cancelled, &token, true).await.unwrap();
+        assert!(summary.cancelled && summary.rolled_back);
+        assert_eq!(summary.files_copied, 0);
+        assert!(!cancelled.exists(), "The folder the copy created is removed again");
+
+        // Stop halfway through the subfolder, into a destination whose parent is missing too
+        std::fs::write(source.join("2024/c.jpg"), b"hi").unwrap();
+        let backups = dir.path().join("backups");
+        let partial = backups.join("photos");
+        let subfolder = partial.join("2024");
+        let summary = copy_directory_until(
+            &source,
+            &partial,
+            || std::fs::read_dir(&subfolder).is_ok_and(|mut entries| entries.next().is_some()),
+            true,
+        )
+        .await
+        .unwrap();
+        assert!(summary.cancelled && summary.rolled_back);
+        assert_eq!(summary.files_copied, 2, "a.jpg and one file of 2024/ were copied first");
+        assert!(!backups.exists(), "Copied files, subfolders and created parents are all removed");
+        assert!(source.join("2024/b.jpg").exists());
+    }
+
+    #[tokio::test]
+    async fn test_content_preview_kinds() {
+        let dir = tempdir().unwrap();
+        let text_path = dir.path().join("notes.txt");
+        std::fs::write(&text_path, "caf\u{e9} menu").unwrap();
+        let preview = get_content_preview_command(text_path.to_string_lossy().to_string(), 4).await.unwrap();
+        assert_eq!(preview.kind, PreviewKind::Text);
+        assert_eq!(preview.content, "caf", "The é cut in half by the limit is dropped");
+        assert!(preview.truncated);
+        assert_eq!(preview.file_size, 10);
+
+        let binary_path = dir.path().join("blob.bin");
+        std::fs::write(&binary_path, [0u8, b'A', 0xff]).unwrap();
+        let preview = get_content_preview_command(binary_path.to_string_lossy().to_string(), 1024).await.unwrap();
+        assert_eq!(preview.kind, PreviewKind::Binary);
+        assert_eq!(preview.content, format!("00000000  {:<47}  |.A.|", "00 41 ff"));
+        assert!(!preview.truncated);
+
+        let missing = dir.path().join("missing.txt").to_string_lossy().to_string();
+        assert!(get_content_preview_command(missing, 16).await.is_err());
+    }
+
+    #[test]
+    fn test_read_range_windows() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("clip.bin");
+        std::fs::write(&path, b"0123456789").unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        assert_eq!(read_range(&path, 2, 3).unwrap(), b"234");
+        assert_eq!(read_range(&path, 8, 100).unwrap(), b"89", "Reads stop at end of file");
+        assert!(read_range(&path, 10, 4).unwrap().is_empty());
+        assert!(read_range(&path, 11, 1).is_err());
+        assert!(read_range(&dir.path().to_string_lossy(), 0, 1).is_err());
+    }
+
+    #[test]
+    fn test_cross_device_error_detection() {
+        assert!(is_cross_device_error(&std::io::Error::from(std::io::ErrorKind::CrossesDevices)));
+        assert!(!is_cross_device_error(&std::io::Error::from(std::io::ErrorKind::AlreadyExists)));
+        assert!(!is_cross_device_error(&std::io::Error::from(std::io::ErrorKind::PermissionDenied)));
+    }
+
+    #[tokio::test]
+    async fn test_move_to_new_folder() {
+        let dir = tempdir().unwrap();
+        let parent = dir.path().to_string_lossy().to_string();
+        let report = dir.path().join("report.txt");
+        std::fs::write(&report, b"q3").unwrap();
+        let missing = dir.path().join("missing.txt").to_string_lossy().to_string();
+
+        let results = move_to_new_folder_command(
+            vec![report.to_string_lossy().to_string(), missing.clone()],
+            parent.clone(),
+            "Archive".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+        let moved = dir.path().join("Archive").join("report.txt");
+        assert_eq!(results[0].destination.as_deref(), Some(moved.to_string_lossy().as_ref()));
+        assert!(moved.exists() && !report.exists());
+        assert_eq!(results[1].source, missing);
+        assert!(results[1].destination.is_none() && results[1].error.is_some());
+
+        let again = move_to_new_folder_command(Vec::new(), parent.clone(), "Archive".to_string(), None).await;
+        assert!(matches!(again, Err(FileOperationError::AlreadyExists(_))));
+        assert!(move_to_new_folder_command(Vec::new(), parent.clone(), "Archive".to_string(), Some(true)).await.is_ok());
+        let invalid = move_to_new_folder_command(Vec::new(), parent, "a/b".to_string(), None).await;
+        assert!(matches!(invalid, Err(FileOperationError::InvalidPath(_))));
+    }
+
+    #[tokio::test]
+    async fn test_roll_back_keeps_overwritten_files() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source");
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::write(source.join("new.txt"), b"new").unwrap();
+        std::fs::write(source.join("shared.txt"), b"copied").unwrap();
+        let destination = dir.path().join("destination");
+        std::fs::create_dir_all(&destination).unwrap();
+        std::fs::write(destination.join("shared.txt"), b"existing").unwrap();
+
+        let mut copied = CopiedPaths::default();
+        copied.copy_file(&source.join("new.txt"), &destination.join("new.txt")).await.unwrap();
+        copied.copy_file(&source.join("shared.txt"), &destination.join("shared.txt")).await.unwrap();
+        assert_eq!(copied.files, vec![destination.join("new.txt")]);
+
+        copied.roll_back().await;
+        assert!(!destination.join("new.txt").exists());
+        assert!(destination.join("shared.txt").exists(), "A file the copy overwrote is not deleted");
+    }
+}
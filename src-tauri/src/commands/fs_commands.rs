@@ -1,7 +1,20 @@
 use crate::core::{
+    cancellation::register_operation,
+    capabilities::{capabilities, Capabilities},
     error::FileSystemError,      // The error type
-    file_system::list_directory, // Your core function
+    favorites::{self, FavoriteEntry},
+    file_system::{get_file_type, list_directory}, // Your core function
+    folder_preview::{compose_mosaic, pick_mosaic_sources},
+    listing_cache::{cache_listing, quick_filter, DirectoryListing},
     models::FileInfo,            // The return data structure
+    query::{query_files, FileFilter, FileQuerySummary},
+    recents::{record_opened, recently_opened, RecentEntry},
+    settings::DEFAULT_THUMBNAIL_CONCURRENCY,
+    thumbnail_cache::{
+        hash_path_and_mtime, prune_thumbnail_cache, record_thumbnail_source, thumbnail_cache_stats,
+        ThumbnailCacheStats, ThumbnailPruneReport, THUMBNAIL_IMAGE_EXTENSIONS, THUMBNAIL_VIDEO_EXTENSIONS,
+    },
+    tree::{walk_tree, TreeEntry, TreeWalkSummary},
 };
 use directories_next::UserDirs;
 use hostname;
@@ -12,11 +25,11 @@ use serde::{Serialize, Deserialize};
 use tauri::Manager; // Needed for AppHandle
 use tokio::fs::{read_to_string, write};
 use tokio::io::ErrorKind;
-use tauri::AppHandle;
-use sha2::{Sha256, Digest};
-use std::time::SystemTime;
+use tauri::{AppHandle, Emitter};
+use futures::stream::{self, StreamExt};
 use lazy_static::lazy_static;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
 use std::collections::HashSet;
 use image::{imageops::FilterType, DynamicImage, ImageFormat, ImageReader};
 use ffmpeg_next as ffmpeg;
@@ -25,9 +38,28 @@ use ffmpeg_next::Rescale;
 use std::fs::File as StdFile; // Use std::fs::File for png crate decoder
 use png;
 
+/// Thumbnails waiting or being generated beyond which new requests are dropped.
+/// Dropped files get another chance the next time their folder is listed.
+const MAX_PENDING_THUMBNAILS: usize = 1024;
+
 // Keep track of paths currently being processed to avoid duplicate generation tasks
 lazy_static! {
     static ref PROCESSING_THUMBNAILS: Mutex<HashSet<PathBuf>> = Mutex::new(HashSet::new());
+    /// Permits for thumbnail generation and the concurrency they were sized for
+    static ref THUMBNAIL_PERMITS: Mutex<(usize, Arc<Semaphore>)> =
+        Mutex::new((DEFAULT_THUMBNAIL_CONCURRENCY, Arc::new(Semaphore::new(DEFAULT_THUMBNAIL_CONCURRENCY))));
+}
+
+/// Permits bounding concurrent thumbnail generation. Replaced when the
+/// `thumbnail_concurrency` setting changes; generations already holding a
+/// permit from the old semaphore finish normally.
+fn thumbnail_permits() -> Arc<Semaphore> {
+    let limit = crate::core::settings::current_settings().thumbnail_concurrency.max(1);
+    let mut permits = THUMBNAIL_PERMITS.lock().unwrap();
+    if permits.0 != limit {
+        *permits = (limit, Arc::new(Semaphore::new(limit)));
+    }
+    permits.1.clone()
 }
 
 #[derive(Debug, serde::Serialize, thiserror::Error)]
@@ -65,7 +97,7 @@ pub enum LocationStorageError {
 // --- Helper Functions ---
 
 // Gets the path to the storage file (e.g., app_data_dir/custom_locations.json)
-async fn get_locations_file_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, LocationStorageError> {
+pub(crate) async fn get_locations_file_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, LocationStorageError> {
     app_handle
         .path()
         .app_data_dir()
@@ -85,24 +117,24 @@ pub(crate) fn get_thumbnail_cache_dir(app_handle: &AppHandle) -> Result<PathBuf,
         .map(|p| p.join("thumbnails"))
 }
 
-// Creates a hash string from path and modified time
-pub(crate) fn hash_path_and_mtime(path: &Path, modified: Option<SystemTime>) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(path.to_string_lossy().as_bytes());
-    if let Some(mtime) = modified {
-        if let Ok(duration) = mtime.duration_since(SystemTime::UNIX_EPOCH) {
-            hasher.update(duration.as_secs().to_le_bytes());
-        }
-    }
-    format!("{:x}", hasher.finalize())
-}
-
 // Checks if a file type is potentially eligible for thumbnail generation
 pub(crate) fn is_thumbnailable(file_type: &str) -> bool {
-    matches!(file_type.to_lowercase().as_str(), 
-        "image" | "png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "bmp" |
-        "video" | "mp4" | "mov" | "avi" | "mkv" | "webm"
-    )
+    let file_type = file_type.to_lowercase();
+    matches!(file_type.as_str(), "image" | "video" | "text" | "code")
+        || THUMBNAIL_IMAGE_EXTENSIONS.contains(&file_type.as_str())
+        || THUMBNAIL_VIDEO_EXTENSIONS.contains(&file_type.as_str())
+}
+
+// Checks if a file extension can be rendered as a text preview thumbnail
+pub(crate) fn is_text_previewable(ext: &str) -> bool {
+    // Source files that mime_guess reports as `application/*` still read fine as text
+    const CODE_EXTENSIONS: &[&str] = &["js", "json", "xml", "sql", "sh", "toml", "yaml", "yml"];
+    if CODE_EXTENSIONS.contains(&ext) {
+        return true;
+    }
+    mime_guess::from_ext(ext)
+        .first()
+        .map_or(false, |mime| mime.type_() == mime_guess::mime::TEXT)
 }
 
 // --- Commands --- 
@@ -190,10 +222,47 @@ pub async fn list_directory_command(
     }
 }
 
+/// Lists a folder like `list_directory_command` and keeps the listing so
+/// `quick_filter_command` can filter it per keystroke without a disk call.
+/// Listing the same folder again refreshes it under the same token.
+#[tauri::command]
+pub async fn list_directory_with_token_command(
+    path: String,
+    app_handle: AppHandle,
+) -> Result<DirectoryListing, FileSystemError> {
+    let items = list_directory(Path::new(&path), app_handle).await?;
+    Ok(cache_listing(&path, items))
+}
+
+/// Filters a listing from `list_directory_with_token_command` by name,
+/// ignoring case and accents. Prefix matches come first, then matches at the
+/// start of a word, then matches anywhere in the name.
+#[tauri::command]
+pub fn quick_filter_command(token: String, query: String) -> Result<Vec<FileInfo>, String> {
+    quick_filter(&token, &query).ok_or_else(|| format!("Listing {} has expired; list the folder again", token))
+}
+
 #[derive(Debug, serde::Serialize, thiserror::Error)]
 pub enum OpenError {
     #[error("Failed to open path '{path}': {message}")]
     IoError { path: String, message: String },
+    #[error("Refusing to open {requested} paths at once (limit is {limit})")]
+    TooManyPaths { requested: usize, limit: usize },
+}
+
+/// Default cap on how many paths `open_paths_command` opens in a single call
+pub const DEFAULT_MAX_OPEN_PATHS: usize = 20;
+
+/// How many recently opened paths are returned when the caller doesn't say
+const DEFAULT_RECENTS_LIMIT: usize = 20;
+
+/// Outcome of opening one path in a batch
+#[derive(Debug, serde::Serialize)]
+pub struct OpenPathResult {
+    pub path: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 /// Attempts to open the given path (file or directory) using the system's default application.
@@ -206,7 +275,69 @@ pub async fn open_path_command(path: String) -> Result<(), OpenError> {
             path: path.clone(),
             message: e.to_string(),
         }
-    })
+    })?;
+    record_opened(&path);
+    Ok(())
+}
+
+/// Opens several paths with their default applications, reporting each outcome.
+/// Refuses batches larger than `max_paths` (default `DEFAULT_MAX_OPEN_PATHS`)
+/// so a stray selection can't launch hundreds of apps.
+#[tauri::command]
+pub async fn open_paths_command(
+    paths: Vec<String>,
+    max_paths: Option<usize>,
+) -> Result<Vec<OpenPathResult>, OpenError> {
+    let limit = max_paths.unwrap_or(DEFAULT_MAX_OPEN_PATHS);
+    if paths.len() > limit {
+        tracing::warn!("Refusing to open {} paths (limit {})", paths.len(), limit);
+        return Err(OpenError::TooManyPaths {
+            requested: paths.len(),
+            limit,
+        });
+    }
+
+    tracing::info!("Attempting to open {} paths", paths.len());
+    let results = paths
+        .into_iter()
+        .map(|path| match opener::open(&path) {
+            Ok(()) => {
+                record_opened(&path);
+                OpenPathResult { path, success: true, error: None }
+            }
+            Err(e) => {
+                tracing::error!("Failed to open path '{}': {}", path, e);
+                OpenPathResult { path, success: false, error: Some(e.to_string()) }
+            }
+        })
+        .collect();
+    Ok(results)
+}
+
+/// Returns up to `limit` paths opened through the explorer, most recent first
+#[tauri::command]
+pub async fn recently_opened_command(limit: Option<usize>) -> Result<Vec<RecentEntry>, String> {
+    Ok(recently_opened(limit.unwrap_or(DEFAULT_RECENTS_LIMIT)))
+}
+
+/// Stars a file or folder. Returns false when it already was a favorite.
+#[tauri::command]
+pub async fn add_favorite(path: String) -> Result<bool, String> {
+    tracing::info!("Adding favorite: {}", path);
+    favorites::add_favorite(&path)
+}
+
+/// Unstars a file or folder. Returns false when it wasn't a favorite.
+#[tauri::command]
+pub async fn remove_favorite(path: String) -> Result<bool, String> {
+    tracing::info!("Removing favorite: {}", path);
+    favorites::remove_favorite(&path)
+}
+
+/// Returns every favorite, most recently starred first
+#[tauri::command]
+pub async fn list_favorites() -> Result<Vec<FavoriteEntry>, String> {
+    Ok(favorites::list_favorites())
 }
 
 // Helper function to get a specific user directory path as String
@@ -238,6 +369,13 @@ pub async fn get_movies_dir() -> Result<String, ConfigError> {
     get_user_dir_path(|dirs| dirs.video_dir()) // Often video_dir corresponds to Movies
 }
 
+/// What this build can index and thumbnail, and which optional features it
+/// has, so the frontend doesn't hardcode its own lists
+#[tauri::command]
+pub fn capabilities_command() -> Result<Capabilities, String> {
+    Ok(capabilities())
+}
+
 #[tauri::command]
 pub async fn get_hostname_command() -> Result<String, HostnameError> {
     hostname::get()
@@ -252,6 +390,11 @@ pub async fn get_hostname_command() -> Result<String, HostnameError> {
 // --- Thumbnail Generation Task Implementation ---
 
 const THUMBNAIL_SIZE: u32 = 128; // Target size for thumbnails (e.g., 128x128)
+const TEXT_PREVIEW_MAX_BYTES: usize = 2048; // Only the head of a text file is rendered
+const TEXT_PREVIEW_MAX_LINES: usize = 24;
+const TEXT_PREVIEW_CHAR_WIDTH: u32 = 2; // Pixels per character cell
+const TEXT_PREVIEW_LINE_HEIGHT: u32 = 5; // Pixels per line (3px glyph + 2px spacing)
+const TEXT_PREVIEW_MARGIN: u32 = 4;
 
 fn resize_and_save_image(
     img: DynamicImage,
@@ -369,6 +512,58 @@ async fn generate_image_thumbnail(original_path: &Path, cache_path: &Path) -> Re
     resize_and_save_image(img, cache_path)
 }
 
+// Renders the first lines of a text/code file as a minimap-style preview.
+// Each visible character becomes a small block, which is enough to convey
+// the shape of the content (indentation, line lengths, density) without
+// needing a font rasterizer.
+fn generate_text_thumbnail(original_path: &Path, cache_path: &Path) -> Result<(), String> {
+    use std::io::Read;
+
+    let mut file = StdFile::open(original_path)
+        .map_err(|e| format!("Failed to open text file: {}", e))?;
+    let mut buf = Vec::with_capacity(TEXT_PREVIEW_MAX_BYTES);
+    file.by_ref()
+        .take(TEXT_PREVIEW_MAX_BYTES as u64)
+        .read_to_end(&mut buf)
+        .map_err(|e| format!("Failed to read text file: {}", e))?;
+    let text = String::from_utf8_lossy(&buf);
+
+    let background = image::Rgb([250u8, 250, 250]);
+    let mut canvas = image::RgbImage::from_pixel(THUMBNAIL_SIZE, THUMBNAIL_SIZE, background);
+    let max_columns = (THUMBNAIL_SIZE - 2 * TEXT_PREVIEW_MARGIN) / TEXT_PREVIEW_CHAR_WIDTH;
+
+    for (line_index, line) in text.lines().take(TEXT_PREVIEW_MAX_LINES).enumerate() {
+        let y = TEXT_PREVIEW_MARGIN + line_index as u32 * TEXT_PREVIEW_LINE_HEIGHT;
+        if y + TEXT_PREVIEW_LINE_HEIGHT > THUMBNAIL_SIZE {
+            break;
+        }
+        let mut column: u32 = 0;
+        for ch in line.chars() {
+            if column >= max_columns {
+                break;
+            }
+            if ch == '\t' {
+                column += 4;
+                continue;
+            }
+            if !ch.is_whitespace() && !ch.is_control() {
+                // Letters/digits are darker than punctuation to give some texture
+                let shade = if ch.is_alphanumeric() { 70u8 } else { 150u8 };
+                let x = TEXT_PREVIEW_MARGIN + column * TEXT_PREVIEW_CHAR_WIDTH;
+                // Leave a 1px gap between characters
+                for dx in 0..TEXT_PREVIEW_CHAR_WIDTH - 1 {
+                    for dy in 0..3 {
+                        canvas.put_pixel(x + dx, y + dy, image::Rgb([shade, shade, shade]));
+                    }
+                }
+            }
+            column += 1;
+        }
+    }
+
+    resize_and_save_image(DynamicImage::ImageRgb8(canvas), cache_path)
+}
+
 fn generate_video_thumbnail(original_path: &Path, cache_path: &Path) -> Result<(), String> {
     // tracing::debug!("Generating video thumbnail for: {:?}", original_path);
     ffmpeg::init().map_err(|e| format!("Failed to initialize ffmpeg: {}", e))?;
@@ -448,34 +643,57 @@ fn generate_video_thumbnail(original_path: &Path, cache_path: &Path) -> Result<(
     }
 }
 
-pub(crate) async fn generate_thumbnail_task(
-    original_path: PathBuf,
-    cache_path: PathBuf,
-    _app_handle: AppHandle,
-) {
-    let added = {
-        let mut processing = PROCESSING_THUMBNAILS.lock().unwrap();
-        processing.insert(original_path.clone())
-    };
-    if !added { return; }
+// Generates the thumbnail and records which file it came from for pruning
+async fn generate_thumbnail(original_path: &Path, cache_path: &Path) -> Result<(), String> {
+    render_thumbnail(original_path, cache_path).await?;
+    record_thumbnail_source(cache_path, original_path);
+    Ok(())
+}
 
-    let result = if let Some(ext) = original_path.extension().and_then(|s| s.to_str()) {
+// Dispatches thumbnail generation based on the file extension
+async fn render_thumbnail(original_path: &Path, cache_path: &Path) -> Result<(), String> {
+    if let Some(ext) = original_path.extension().and_then(|s| s.to_str()) {
         match ext.to_lowercase().as_str() {
             // Image types - Added svg
-            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "svg" => {
+            ext if THUMBNAIL_IMAGE_EXTENSIONS.contains(&ext) => {
                 // Await the async image generation
-                generate_image_thumbnail(&original_path, &cache_path).await
+                generate_image_thumbnail(original_path, cache_path).await
             }
             // Video types
-            "mp4" | "mov" | "avi" | "mkv" | "webm" => {
+            ext if THUMBNAIL_VIDEO_EXTENSIONS.contains(&ext) => {
                 // Video generation might still be blocking depending on ffmpeg-next usage
                 // Wrap potentially blocking call in spawn_blocking if performance becomes an issue
-                generate_video_thumbnail(&original_path, &cache_path)
+                generate_video_thumbnail(original_path, cache_path)
             }
+            // Text and source files
+            ext if is_text_previewable(ext) => generate_text_thumbnail(original_path, cache_path),
             _ => Err(format!("Unsupported extension for thumbnail: {}", ext)),
         }
     } else {
         Err("File has no extension".to_string())
+    }
+}
+
+pub(crate) async fn generate_thumbnail_task(
+    original_path: PathBuf,
+    cache_path: PathBuf,
+    _app_handle: AppHandle,
+) {
+    let added = {
+        let mut processing = PROCESSING_THUMBNAILS.lock().unwrap();
+        if processing.len() >= MAX_PENDING_THUMBNAILS {
+            tracing::debug!("Thumbnail queue full, skipping {:?} for now", original_path);
+            return;
+        }
+        processing.insert(original_path.clone())
+    };
+    if !added { return; }
+
+    // Waiters are served in order, so the semaphore doubles as the queue
+    let permits = thumbnail_permits();
+    let result = match permits.acquire().await {
+        Ok(_permit) => generate_thumbnail(&original_path, &cache_path).await,
+        Err(e) => Err(format!("Thumbnail queue closed: {}", e)),
     };
 
     if let Err(e) = result {
@@ -491,4 +709,346 @@ pub(crate) async fn generate_thumbnail_task(
     }
 }
 
+/// Returns the cached thumbnail for a file, generating it first if it is missing.
+/// Unlike the listing pass, generation happens inline so the caller gets a usable path back.
+#[tauri::command]
+pub async fn get_or_generate_thumbnail_command(
+    path: String,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    let original_path = PathBuf::from(&path);
+
+    let metadata = tokio::fs::metadata(&original_path)
+        .await
+        .map_err(|e| format!("Failed to read metadata for '{}': {}", path, e))?;
+    if metadata.is_dir() {
+        return Err(format!("Cannot generate a thumbnail for a directory: {}", path));
+    }
+
+    let file_type = get_file_type(&original_path, false);
+    if !is_thumbnailable(&file_type) {
+        return Err(format!("Unsupported file type for thumbnail: {}", file_type));
+    }
+
+    let cache_dir = get_thumbnail_cache_dir(&app_handle).map_err(|e| e.to_string())?;
+    let hash = hash_path_and_mtime(&original_path, metadata.modified().ok());
+    let cache_path = cache_dir.join(format!("{}.jpg", hash));
+
+    if tokio::fs::metadata(&cache_path).await.is_err() {
+        tracing::debug!("Thumbnail not cached for {:?}, generating on demand", original_path);
+        generate_thumbnail(&original_path, &cache_path).await.map_err(|e| {
+            tracing::error!("Failed to generate thumbnail for {:?}: {}", original_path, e);
+            e
+        })?;
+    }
+
+    Ok(cache_path.to_string_lossy().to_string())
+}
+
+/// Returns a mosaic of up to four thumbnails from inside a folder, building
+/// it (and any thumbnails it needs) when the folder has none cached yet.
+/// Mosaics are cached by folder path and mtime, so adding or removing files
+/// directly in the folder gets it a new one. `None` means the folder holds no
+/// images or videos and should keep the generic folder icon.
+#[tauri::command]
+pub async fn folder_preview_command(path: String, app_handle: AppHandle) -> Result<Option<String>, String> {
+    let folder = PathBuf::from(&path);
+    let metadata = tokio::fs::metadata(&folder)
+        .await
+        .map_err(|e| format!("Failed to read metadata for '{}': {}", path, e))?;
+    if !metadata.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+
+    let cache_dir = get_thumbnail_cache_dir(&app_handle).map_err(|e| e.to_string())?;
+    let mosaic_path = cache_dir.join(format!("{}.jpg", hash_path_and_mtime(&folder, metadata.modified().ok())));
+    if tokio::fs::metadata(&mosaic_path).await.is_ok() {
+        return Ok(Some(mosaic_path.to_string_lossy().to_string()));
+    }
+
+    let follow_symlinks = crate::core::settings::current_settings().follow_symlinks;
+    let scan_root = folder.clone();
+    let sources = tokio::task::spawn_blocking(move || pick_mosaic_sources(&scan_root, follow_symlinks))
+        .await
+        .map_err(|e| format!("Folder preview scan failed: {}", e))?;
+
+    let mut thumbnails = Vec::with_capacity(sources.len());
+    for source in sources {
+        let modified = tokio::fs::metadata(&source).await.ok().and_then(|m| m.modified().ok());
+        let cache_path = cache_dir.join(format!("{}.jpg", hash_path_and_mtime(&source, modified)));
+        if tokio::fs::metadata(&cache_path).await.is_err() {
+            if let Err(e) = generate_thumbnail(&source, &cache_path).await {
+                tracing::warn!("Leaving {:?} out of the folder preview: {}", source, e);
+                continue;
+            }
+        }
+        thumbnails.push(cache_path);
+    }
+    if thumbnails.is_empty() {
+        return Ok(None);
+    }
+
+    let dest = mosaic_path.clone();
+    tokio::task::spawn_blocking(move || compose_mosaic(&thumbnails, &dest, THUMBNAIL_SIZE))
+        .await
+        .map_err(|e| format!("Folder preview failed: {}", e))??;
+    record_thumbnail_source(&mosaic_path, &folder);
+    Ok(Some(mosaic_path.to_string_lossy().to_string()))
+}
+
+/// Number and total size of the cached thumbnails
+#[tauri::command]
+pub async fn thumbnail_cache_stats_command(app_handle: AppHandle) -> Result<ThumbnailCacheStats, String> {
+    let cache_dir = get_thumbnail_cache_dir(&app_handle).map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || thumbnail_cache_stats(&cache_dir))
+        .await
+        .map_err(|e| format!("Thumbnail cache scan failed: {}", e))
+}
+
+/// Deletes cached thumbnails of files that were deleted or have changed since
+#[tauri::command]
+pub async fn prune_thumbnail_cache_command(app_handle: AppHandle) -> Result<ThumbnailPruneReport, String> {
+    let cache_dir = get_thumbnail_cache_dir(&app_handle).map_err(|e| e.to_string())?;
+    let report = tokio::task::spawn_blocking(move || prune_thumbnail_cache(&cache_dir))
+        .await
+        .map_err(|e| format!("Thumbnail cache prune failed: {}", e))?;
+    tracing::info!(
+        "Pruned {} stale and {} untraced thumbnails, freeing {} bytes",
+        report.removed, report.untraced_removed, report.freed_bytes
+    );
+    Ok(report)
+}
+
+/// Event carrying batches of matches while `query_files_command` runs
+pub const FILE_QUERY_RESULTS_EVENT: &str = "file-query-results";
+
+/// Matches returned by one file query when the caller doesn't say
+const DEFAULT_FILE_QUERY_MAX_RESULTS: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+struct FileQueryResults {
+    root: String,
+    operation_id: Option<String>,
+    files: Vec<FileInfo>,
+}
+
+/// Finds files under `root` matching every predicate in `filter`. Matches are
+/// streamed as `file-query-results` events; the returned summary arrives once
+/// the walk ends. Cancel it through `operation_id`.
+#[tauri::command]
+pub async fn query_files_command(
+    app_handle: AppHandle,
+    root: String,
+    filter: FileFilter,
+    operation_id: Option<String>,
+    max_results: Option<usize>,
+) -> Result<FileQuerySummary, String> {
+    if !Path::new(&root).is_dir() {
+        return Err(format!("Not a directory: {}", root));
+    }
+    tracing::info!("Querying files under {} with {:?}", root, filter);
+    let follow_symlinks = crate::core::settings::current_settings().follow_symlinks;
+    let max_results = max_results.unwrap_or(DEFAULT_FILE_QUERY_MAX_RESULTS);
+    let token = register_operation(operation_id.as_deref());
+
+    tokio::task::spawn_blocking(move || {
+        query_files(Path::new(&root), &filter, follow_symlinks, max_results, &token, |files| {
+            let results = FileQueryResults {
+                root: root.clone(),
+                operation_id: operation_id.clone(),
+                files,
+            };
+            if let Err(e) = app_handle.emit(FILE_QUERY_RESULTS_EVENT, results) {
+                tracing::error!("Failed to emit file query results: {}", e);
+            }
+        })
+    })
+    .await
+    .map_err(|e| format!("File query failed: {}", e))
+}
+
+/// Event carrying batches of entries while `walk_directory_tree_command` runs
+pub const DIRECTORY_TREE_ENTRIES_EVENT: &str = "directory-tree-entries";
+
+/// Entries returned by one tree walk when the caller doesn't say
+const DEFAULT_TREE_MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+struct DirectoryTreeEntries {
+    root: String,
+    operation_id: Option<String>,
+    entries: Vec<TreeEntry>,
+}
+
+/// Walks `root` down to `max_depth` levels (unlimited when not given) and
+/// streams what it finds as `directory-tree-entries` events, each entry
+/// carrying its depth and parent folder. The returned summary arrives once the
+/// walk ends. Cancel it through `operation_id`.
+#[tauri::command]
+pub async fn walk_directory_tree_command(
+    app_handle: AppHandle,
+    root: String,
+    max_depth: Option<usize>,
+    operation_id: Option<String>,
+    max_entries: Option<usize>,
+) -> Result<TreeWalkSummary, String> {
+    if !Path::new(&root).is_dir() {
+        return Err(format!("Not a directory: {}", root));
+    }
+    tracing::info!("Walking directory tree under {} (max depth {:?})", root, max_depth);
+    let follow_symlinks = crate::core::settings::current_settings().follow_symlinks;
+    let max_depth = max_depth.unwrap_or(usize::MAX);
+    let max_entries = max_entries.unwrap_or(DEFAULT_TREE_MAX_ENTRIES);
+    let token = register_operation(operation_id.as_deref());
+
+    tokio::task::spawn_blocking(move || {
+        walk_tree(Path::new(&root), max_depth, follow_symlinks, max_entries, &token, |entries| {
+            let batch = DirectoryTreeEntries {
+                root: root.clone(),
+                operation_id: operation_id.clone(),
+                entries,
+            };
+            if let Err(e) = app_handle.emit(DIRECTORY_TREE_ENTRIES_EVENT, batch) {
+                tracing::error!("Failed to emit directory tree entries: {}", e);
+            }
+        })
+    })
+    .await
+    .map_err(|e| format!("Directory tree walk failed: {}", e))
+}
+
+/// Event emitted as `regenerate_thumbnails_command` finishes each file
+pub const THUMBNAIL_REGENERATION_PROGRESS_EVENT: &str = "thumbnail-regeneration-progress";
+
+/// Thumbnails generated at the same time during bulk regeneration
+const THUMBNAIL_REGENERATION_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Clone, Serialize)]
+struct ThumbnailRegenerationProgress {
+    root: String,
+    operation_id: Option<String>,
+    processed: usize,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThumbnailRegenerationReport {
+    pub regenerated: usize,
+    pub failed: usize,
+    /// Files with no thumbnail support
+    pub skipped: usize,
+    /// True when cancelled; files not reached keep their old thumbnails
+    pub cancelled: bool,
+}
+
+/// Pairs every thumbnailable file under `root` with its cache path in
+/// `cache_dir`, and counts the files that have no thumbnail support
+fn thumbnail_regeneration_jobs(root: &Path, cache_dir: &Path) -> (Vec<(PathBuf, PathBuf)>, usize) {
+    let mut jobs = Vec::new();
+    let mut skipped = 0;
+    for entry in walkdir::WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if !is_thumbnailable(&get_file_type(entry.path(), false)) {
+            skipped += 1;
+            continue;
+        }
+        let modified = entry.metadata().ok().and_then(|m| m.modified().ok());
+        let cache_path = cache_dir.join(format!("{}.jpg", hash_path_and_mtime(entry.path(), modified)));
+        jobs.push((entry.into_path(), cache_path));
+    }
+    (jobs, skipped)
+}
+
+/// Deletes and regenerates the cached thumbnail of every thumbnailable file
+/// under `root`, emitting `thumbnail-regeneration-progress` events.
+/// Cancel it through `operation_id`.
+#[tauri::command]
+pub async fn regenerate_thumbnails_command(
+    app_handle: AppHandle,
+    root: String,
+    operation_id: Option<String>,
+) -> Result<ThumbnailRegenerationReport, String> {
+    let root_path = PathBuf::from(&root);
+    if !root_path.is_dir() {
+        return Err(format!("Not a directory: {}", root));
+    }
+    let cache_dir = get_thumbnail_cache_dir(&app_handle).map_err(|e| e.to_string())?;
+    let token = register_operation(operation_id.as_deref());
+
+    // Collect (file, cache path) pairs off the async runtime
+    let (jobs, skipped) = tokio::task::spawn_blocking(move || thumbnail_regeneration_jobs(&root_path, &cache_dir))
+        .await
+        .map_err(|e| format!("Thumbnail scan failed: {}", e))?;
+
+    let total = jobs.len();
+    tracing::info!("Regenerating {} thumbnails under {}", total, root);
+    let mut report = ThumbnailRegenerationReport {
+        skipped,
+        ..Default::default()
+    };
+    let mut results = stream::iter(jobs)
+        .take_while(|_| std::future::ready(!token.is_cancelled()))
+        .map(|(original_path, cache_path)| {
+            tokio::spawn(async move {
+                let _ = tokio::fs::remove_file(&cache_path).await;
+                generate_thumbnail(&original_path, &cache_path)
+                    .await
+                    .map_err(|e| format!("{}: {}", original_path.display(), e))
+            })
+        })
+        .buffer_unordered(THUMBNAIL_REGENERATION_CONCURRENCY);
+
+    while let Some(result) = results.next().await {
+        match result {
+            Ok(Ok(())) => report.regenerated += 1,
+            Ok(Err(e)) => {
+                tracing::warn!("Failed to regenerate thumbnail for {}", e);
+                report.failed += 1;
+            }
+            Err(e) => {
+                tracing::error!("Thumbnail task failed: {}", e);
+                report.failed += 1;
+            }
+        }
+        let progress = ThumbnailRegenerationProgress {
+            root: root.clone(),
+            operation_id: operation_id.clone(),
+            processed: report.regenerated + report.failed,
+            total,
+        };
+        if let Err(e) = app_handle.emit(THUMBNAIL_REGENERATION_PROGRESS_EVENT, progress) {
+            tracing::error!("Failed to emit thumbnail progress: {}", e);
+        }
+    }
+    report.cancelled = token.is_cancelled();
+    Ok(report)
+}
+
 // Add other file-system related commands here later if needed
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_thumbnail_regeneration_jobs() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("photos");
+        std::fs::create_dir_all(root.join("2024")).unwrap();
+        std::fs::write(root.join("2024/beach.png"), b"png").unwrap();
+        std::fs::write(root.join("notes.txt"), b"packing list").unwrap();
+        std::fs::write(root.join("backup.bin"), [0u8, 1, 2]).unwrap();
+        let cache_dir = dir.path().join("thumbnails");
+
+        let (mut jobs, skipped) = thumbnail_regeneration_jobs(&root, &cache_dir);
+        jobs.sort();
+        assert_eq!(skipped, 1, "The binary file has no thumbnail");
+        let files: Vec<PathBuf> = jobs.iter().map(|(file, _)| file.clone()).collect();
+        assert_eq!(files, vec![root.join("2024/beach.png"), root.join("notes.txt")]);
+        assert!(jobs.iter().all(|(_, cache_path)| cache_path.parent() == Some(cache_dir.as_path())));
+        assert_ne!(jobs[0].1, jobs[1].1);
+    }
+}
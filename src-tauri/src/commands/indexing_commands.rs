@@ -1,9 +1,32 @@
 // src-tauri/src/commands/indexing_commands.rs
 
-use crate::core::indexer::{index_downloads_folder, index_folder, get_last_indexing_stats};
-use crate::db::{connect_db, TABLE_NAME, clear_data};
-use log::{info, error};
+use crate::core::indexer::{
+    estimate_indexing_time, get_indexing_queue_status, index_downloads_folder, index_folder_with_options,
+    get_last_indexing_stats, IndexOptions, IndexingEstimate, IndexingQueueStatus,
+};
+use crate::core::corpus::{corpus_stats, language_breakdown, CorpusStats, LanguageBreakdown};
+use crate::core::db_location::{move_database, DatabaseMoveReport};
+use crate::core::exclusions::{exclude_path, excluded_paths, include_path, is_path_excluded};
+use crate::core::cancellation::register_operation;
+use crate::core::index_drift::{
+    compare_with_disk, indexed_files, indexed_hash, indexed_under, verify_file, verify_index, DriftOptions, FileVerification, IndexDrift,
+    IndexVerification,
+};
+use crate::core::index_size::index_size_bytes;
+use crate::core::settings::current_settings;
+use crate::core::term_frequencies::{clear_term_frequencies, rebuild_term_frequencies, TermRebuildSummary};
+use crate::core::tombstones::{
+    list_tombstones, prune_expired_tombstones, prune_tombstones, restore_tombstone, RestoredTombstone, Tombstone,
+};
+use crate::core::virtual_content::index_content;
+use crate::core::scheduler::{run_scheduled_indexing, schedule_status, ScheduleStatus, ScheduledRunSummary};
+use crate::embedder::{embedder_status, reload_embedders, EmbedderStatus};
+use crate::export::{export_embeddings, ExportFormat, ExportSummary};
+use crate::watcher::{pause_watchers, resume_watchers, watcher_status, WatcherStatus};
+use crate::db::{connect_db, list_indexed_documents, DocumentSortBy, IndexedDocument, TABLE_NAME, clear_data};
+use log::{info, error, warn};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 /// Response model for indexing operations
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,6 +40,21 @@ pub struct IndexingResponse {
     pub message: String,
     pub indexed_files: Vec<String>,
     pub failed_files: Vec<String>,
+    /// Files skipped because they were already indexed and unchanged
+    pub files_unchanged: u32,
+    /// Files skipped because their extracted text was below the minimum length
+    pub files_too_short: u32,
+    /// Zero-byte and whitespace-only files, never embedded
+    pub files_empty: u32,
+    /// Rows in each table after the run, when they could be counted
+    pub total_text_docs: Option<usize>,
+    pub total_image_docs: Option<usize>,
+    pub total_amharic_docs: Option<usize>,
+    /// Files and folders skipped because they couldn't be read
+    pub files_permission_denied: u32,
+    pub permission_denied_paths: Vec<String>,
+    /// The stats are from a run that is still going
+    pub in_progress: bool,
 }
 
 /// Generic operation response
@@ -43,11 +81,20 @@ pub async fn index_downloads_command() -> Result<IndexingResponse, String> {
                 time_taken_ms: stats.elapsed_seconds * 1000 + stats.elapsed_milliseconds as u32,
                 success: true,
                 message: format!(
-                    "Downloads folder indexed successfully. Processed: {}, Indexed: {}, Skipped: {}, Failed: {}",
-                    stats.files_processed, stats.db_inserts, stats.files_skipped, stats.files_failed
+                    "Downloads folder indexed successfully. Processed: {}, Indexed: {}, Skipped: {}, Failed: {}, Permission denied: {}",
+                    stats.files_processed, stats.db_inserts, stats.files_skipped, stats.files_failed, stats.files_permission_denied
                 ),
                 indexed_files: stats.indexed_files,
                 failed_files: stats.failed_files,
+                files_unchanged: stats.files_unchanged,
+                files_too_short: stats.files_too_short,
+                files_empty: stats.files_empty,
+                total_text_docs: stats.total_text_docs,
+                total_image_docs: stats.total_image_docs,
+                total_amharic_docs: stats.total_amharic_docs,
+                files_permission_denied: stats.files_permission_denied,
+                permission_denied_paths: stats.permission_denied_paths,
+                in_progress: stats.in_progress,
             })
         },
         Err(err) => {
@@ -63,6 +110,15 @@ pub async fn index_downloads_command() -> Result<IndexingResponse, String> {
                 message: format!("Failed to index Downloads folder: {}", err),
                 indexed_files: Vec::new(),
                 failed_files: Vec::new(),
+                files_unchanged: 0,
+                files_too_short: 0,
+                files_empty: 0,
+                total_text_docs: None,
+                total_image_docs: None,
+                total_amharic_docs: None,
+                files_permission_denied: 0,
+                permission_denied_paths: Vec::new(),
+                in_progress: false,
             })
         }
     }
@@ -70,10 +126,14 @@ pub async fn index_downloads_command() -> Result<IndexingResponse, String> {
 
 /// Tauri command to index a specific folder
 #[tauri::command]
-pub async fn index_folder_command(folder_path: String) -> Result<IndexingResponse, String> {
-    info!("Manual indexing of folder requested: {}", folder_path);
+pub async fn index_folder_command(
+    folder_path: String,
+    options: Option<IndexOptions>,
+) -> Result<IndexingResponse, String> {
+    let options = options.unwrap_or_default();
+    info!("Manual indexing of folder requested: {} ({:?})", folder_path, options);
     
-    match index_folder(&folder_path).await {
+    match index_folder_with_options(&folder_path, &options).await {
         Ok(stats) => {
             info!("Folder indexing completed successfully: {}", folder_path);
             
@@ -85,11 +145,21 @@ pub async fn index_folder_command(folder_path: String) -> Result<IndexingRespons
                 time_taken_ms: stats.elapsed_seconds * 1000 + stats.elapsed_milliseconds as u32,
                 success: true,
                 message: format!(
-                    "Folder indexed successfully. Processed: {}, Indexed: {}, Skipped: {}, Failed: {}",
-                    stats.files_processed, stats.db_inserts, stats.files_skipped, stats.files_failed
+                    "Folder indexed successfully. Processed: {}, Indexed: {}, Skipped: {}, Unchanged: {}, Failed: {}, Permission denied: {}",
+                    stats.files_processed, stats.db_inserts, stats.files_skipped, stats.files_unchanged, stats.files_failed,
+                    stats.files_permission_denied
                 ),
                 indexed_files: stats.indexed_files,
                 failed_files: stats.failed_files,
+                files_unchanged: stats.files_unchanged,
+                files_too_short: stats.files_too_short,
+                files_empty: stats.files_empty,
+                total_text_docs: stats.total_text_docs,
+                total_image_docs: stats.total_image_docs,
+                total_amharic_docs: stats.total_amharic_docs,
+                files_permission_denied: stats.files_permission_denied,
+                permission_denied_paths: stats.permission_denied_paths,
+                in_progress: stats.in_progress,
             })
         },
         Err(err) => {
@@ -105,11 +175,51 @@ pub async fn index_folder_command(folder_path: String) -> Result<IndexingRespons
                 message: format!("Failed to index folder: {}", err),
                 indexed_files: Vec::new(),
                 failed_files: Vec::new(),
+                files_unchanged: 0,
+                files_too_short: 0,
+                files_empty: 0,
+                total_text_docs: None,
+                total_image_docs: None,
+                total_amharic_docs: None,
+                files_permission_denied: 0,
+                permission_denied_paths: Vec::new(),
+                in_progress: false,
             })
         }
     }
 }
 
+/// Tauri command reporting which indexing operation is running and how many are waiting
+#[tauri::command]
+pub fn get_indexing_queue_status_command() -> Result<IndexingQueueStatus, String> {
+    Ok(get_indexing_queue_status())
+}
+
+/// Tauri command reporting the indexing schedule and when it runs next
+#[tauri::command]
+pub fn get_next_scheduled_index_command() -> Result<ScheduleStatus, String> {
+    Ok(schedule_status())
+}
+
+/// Tauri command writing one averaged embedding per indexed file to `dest`.
+/// `format` must be "ndjson".
+#[tauri::command]
+pub async fn export_embeddings_command(dest: String, format: String) -> Result<ExportSummary, String> {
+    let format = ExportFormat::parse(&format).map_err(|e| e.to_string())?;
+    let conn = connect_db().await.map_err(|e| format!("Failed to connect to database: {}", e))?;
+    info!("Exporting embeddings to {}", dest);
+    export_embeddings(&conn, Path::new(&dest), format)
+        .await
+        .map_err(|e| format!("Failed to export embeddings: {}", e))
+}
+
+/// Tauri command running the scheduled indexing pass immediately
+#[tauri::command]
+pub async fn trigger_index_now_command() -> Result<ScheduledRunSummary, String> {
+    info!("Scheduled indexing triggered manually");
+    Ok(run_scheduled_indexing().await)
+}
+
 /// Tauri command to get the last indexing statistics
 #[tauri::command]
 pub fn get_indexing_stats_command() -> Result<IndexingResponse, String> {
@@ -124,9 +234,22 @@ pub fn get_indexing_stats_command() -> Result<IndexingResponse, String> {
                 files_failed: stats.files_failed,
                 time_taken_ms: stats.elapsed_seconds * 1000 + stats.elapsed_milliseconds as u32,
                 success: true,
-                message: "Retrieved last indexing statistics".to_string(),
+                message: if stats.in_progress {
+                    "Indexing in progress".to_string()
+                } else {
+                    "Retrieved last indexing statistics".to_string()
+                },
                 indexed_files: stats.indexed_files,
                 failed_files: stats.failed_files,
+                files_unchanged: stats.files_unchanged,
+                files_too_short: stats.files_too_short,
+                files_empty: stats.files_empty,
+                total_text_docs: stats.total_text_docs,
+                total_image_docs: stats.total_image_docs,
+                total_amharic_docs: stats.total_amharic_docs,
+                files_permission_denied: stats.files_permission_denied,
+                permission_denied_paths: stats.permission_denied_paths,
+                in_progress: stats.in_progress,
             })
         },
         None => {
@@ -141,6 +264,15 @@ pub fn get_indexing_stats_command() -> Result<IndexingResponse, String> {
                 message: "No indexing has been performed yet".to_string(),
                 indexed_files: Vec::new(),
                 failed_files: Vec::new(),
+                files_unchanged: 0,
+                files_too_short: 0,
+                files_empty: 0,
+                total_text_docs: None,
+                total_image_docs: None,
+                total_amharic_docs: None,
+                files_permission_denied: 0,
+                permission_denied_paths: Vec::new(),
+                in_progress: false,
             })
         }
     }
@@ -156,6 +288,9 @@ pub async fn clear_index_command() -> Result<OperationResponse, String> {
             match clear_data(&db, TABLE_NAME).await {
                 Ok(_) => {
                     info!("Successfully cleared all indexed data");
+                    if let Err(e) = clear_term_frequencies() {
+                        warn!("Failed to clear term frequencies: {}", e);
+                    }
                     Ok(OperationResponse {
                         success: true,
                         message: "All indexed data has been cleared successfully".to_string(),
@@ -186,6 +321,10 @@ pub struct VectorDbStatsResponse {
     pub text_documents_count: usize,
     pub image_documents_count: usize,
     pub total_documents_count: usize,
+    /// Bytes the database takes on disk; `None` if it couldn't be measured
+    pub index_size_bytes: Option<u64>,
+    /// The `max_index_bytes` setting; 0 means unlimited
+    pub max_index_bytes: u64,
 }
 
 /// Tauri command to get vector database statistics 
@@ -203,10 +342,15 @@ pub async fn get_vector_db_stats_command() -> Result<VectorDbStatsResponse, Stri
                     info!("Vector database stats: {} text documents, {} image documents, {} Amharic documents, {} total", 
                           text_count, image_count, amharic_count, total_count);
                     
+                    let index_size_bytes = index_size_bytes()
+                        .map_err(|e| warn!("Failed to measure the index size: {}", e))
+                        .ok();
                     Ok(VectorDbStatsResponse {
                         text_documents_count: text_count,
                         image_documents_count: image_count,
                         total_documents_count: total_count,
+                        index_size_bytes,
+                        max_index_bytes: current_settings().max_index_bytes,
                     })
                 },
                 Err(e) => {
@@ -222,6 +366,283 @@ pub async fn get_vector_db_stats_command() -> Result<VectorDbStatsResponse, Stri
     }
 }
 
+/// Tauri command to estimate how long indexing a folder would take, without indexing it
+#[tauri::command]
+pub async fn estimate_indexing_time_command(path: String) -> Result<IndexingEstimate, String> {
+    info!("Indexing time estimate requested for: {}", path);
+    estimate_indexing_time(Path::new(&path)).map_err(|e| {
+        error!("Failed to estimate indexing time for {}: {}", path, e);
+        e
+    })
+}
+
+/// Tauri command excluding a folder or file from all future indexing.
+/// Returns false if it was already excluded.
+#[tauri::command]
+pub fn exclude_path_command(path: String) -> Result<bool, String> {
+    exclude_path(&path)
+}
+
+/// Tauri command lifting an exclusion; the path is picked up by the next
+/// indexing run. Returns false if it wasn't excluded.
+#[tauri::command]
+pub fn include_path_command(path: String) -> Result<bool, String> {
+    include_path(&path)
+}
+
+/// Tauri command telling whether a path is excluded, directly or through a parent
+#[tauri::command]
+pub fn is_path_excluded_command(path: String) -> Result<bool, String> {
+    Ok(is_path_excluded(Path::new(&path)))
+}
+
+/// Tauri command listing every excluded path
+#[tauri::command]
+pub fn list_excluded_paths_command() -> Result<Vec<String>, String> {
+    Ok(excluded_paths().iter().map(|p| p.to_string_lossy().into_owned()).collect())
+}
+
+/// Tauri command returning aggregate stats about everything in the index
+#[tauri::command]
+pub async fn corpus_stats_command() -> Result<CorpusStats, String> {
+    let conn = connect_db().await.map_err(|e| format!("Database connection error: {}", e))?;
+    corpus_stats(&conn).await.map_err(|e| {
+        error!("Failed to compute corpus stats: {}", e);
+        format!("Failed to compute corpus stats: {}", e)
+    })
+}
+
+/// Tauri command estimating which languages the indexed text is written in,
+/// to tell whether the multilingual model is worth enabling
+#[tauri::command]
+pub async fn corpus_language_breakdown_command() -> Result<LanguageBreakdown, String> {
+    let conn = connect_db().await.map_err(|e| format!("Database connection error: {}", e))?;
+    language_breakdown(&conn).await.map_err(|e| {
+        error!("Failed to compute the language breakdown: {}", e);
+        format!("Failed to compute the language breakdown: {}", e)
+    })
+}
+
+/// Hashing settings matching the ones indexing uses now
+fn drift_options() -> DriftOptions {
+    let settings = current_settings();
+    DriftOptions {
+        follow_symlinks: settings.follow_symlinks,
+        min_text_chars: settings.min_text_chars,
+        streaming_threshold_bytes: settings.streaming_threshold_bytes,
+    }
+}
+
+/// Tauri command comparing the index with the files under `root`: files not
+/// yet indexed, index entries whose file is gone, and indexed files whose
+/// content changed. Reads every indexable file; cancel it through `operation_id`.
+#[tauri::command]
+pub async fn index_drift_command(root: String, operation_id: Option<String>) -> Result<IndexDrift, String> {
+    let root_path = Path::new(&root).to_path_buf();
+    if !root_path.is_dir() {
+        return Err(format!("Not a directory: {}", root));
+    }
+    let conn = connect_db().await.map_err(|e| format!("Database connection error: {}", e))?;
+    let indexed = indexed_under(&conn, &root_path).await?;
+    let options = drift_options();
+    let token = register_operation(operation_id.as_deref());
+    info!("Comparing {} indexed files under {} with disk", indexed.len(), root);
+
+    let drift = tokio::task::spawn_blocking(move || compare_with_disk(&root_path, &indexed, &options, &token))
+        .await
+        .map_err(|e| format!("Index drift check failed: {}", e))?;
+    info!(
+        "Index drift under {}: {} not indexed, {} missing from disk, {} changed",
+        root, drift.not_indexed_count, drift.missing_from_disk_count, drift.changed_count
+    );
+    Ok(drift)
+}
+
+/// Tauri command re-hashing one indexed file and comparing it with the hash
+/// stored when it was indexed
+#[tauri::command]
+pub async fn verify_indexed_file_command(path: String) -> Result<FileVerification, String> {
+    let conn = connect_db().await.map_err(|e| format!("Database connection error: {}", e))?;
+    let stored = indexed_hash(&conn, &path).await?;
+    let options = drift_options();
+    tokio::task::spawn_blocking(move || verify_file(&path, stored.as_deref(), &options))
+        .await
+        .map_err(|e| format!("File verification failed: {}", e))
+}
+
+/// Tauri command re-hashing every indexed file and reporting those that no
+/// longer match their stored hash. Reads every file; cancel it through `operation_id`.
+#[tauri::command]
+pub async fn verify_all_command(operation_id: Option<String>) -> Result<IndexVerification, String> {
+    let conn = connect_db().await.map_err(|e| format!("Database connection error: {}", e))?;
+    let indexed = indexed_files(&conn).await?;
+    let options = drift_options();
+    let token = register_operation(operation_id.as_deref());
+    info!("Verifying {} indexed files against their stored hashes", indexed.len());
+
+    let report = tokio::task::spawn_blocking(move || verify_index(&indexed, &options, &token))
+        .await
+        .map_err(|e| format!("Index verification failed: {}", e))?;
+    info!(
+        "Verified {} indexed files: {} match, {} changed, {} missing",
+        report.checked_count, report.matching_count, report.mismatched_count, report.missing_count
+    );
+    Ok(report)
+}
+
+/// Tauri command recounting the terms behind search suggestions from every
+/// indexed text document. Reads all of them again; cancel it through `operation_id`.
+#[tauri::command]
+pub async fn rebuild_term_frequencies_command(operation_id: Option<String>) -> Result<TermRebuildSummary, String> {
+    let conn = connect_db().await.map_err(|e| format!("Database connection error: {}", e))?;
+    let token = register_operation(operation_id.as_deref());
+    info!("Rebuilding term frequencies");
+    rebuild_term_frequencies(&conn, current_settings().streaming_threshold_bytes, token).await
+}
+
+/// Tauri command retrying the text embedding models that failed to load. With
+/// `repair`, their cached files are deleted and downloaded again first.
+#[tauri::command]
+pub async fn reload_embedder_command(repair: Option<bool>) -> Result<Vec<EmbedderStatus>, String> {
+    let repair = repair.unwrap_or(false);
+    info!("Reloading failed embedding models (repair: {})", repair);
+    tokio::task::spawn_blocking(move || reload_embedders(repair))
+        .await
+        .map_err(|e| format!("Embedder reload failed: {}", e))
+}
+
+/// Tauri command reporting which text embedding models are loaded or failing
+#[tauri::command]
+pub async fn embedder_status_command() -> Result<Vec<EmbedderStatus>, String> {
+    Ok(embedder_status())
+}
+
+/// Tauri command to index text supplied by the caller, such as a web clipping,
+/// under a `virtual://` path. Search results carry that path back; deleting it
+/// with `delete_item` removes it from the index.
+#[tauri::command]
+pub async fn index_content_command(virtual_path: String, content: String, content_type: String) -> Result<(), String> {
+    info!("Request to index {} bytes of {} content as {}", content.len(), content_type, virtual_path);
+    let conn = connect_db().await.map_err(|e| format!("Database connection error: {}", e))?;
+    index_content(&conn, &virtual_path, &content, &content_type).await.map_err(|e| {
+        error!("Failed to index content for {}: {}", virtual_path, e);
+        e
+    })
+}
+
+/// Tauri command to move the database to `new_path` (an empty or new folder)
+/// and use it from there on. Fails while indexing is running.
+#[tauri::command]
+pub async fn move_database_command(new_path: String) -> Result<DatabaseMoveReport, String> {
+    info!("Request to move the database to {}", new_path);
+    let new_path = shellexpand::tilde(new_path.trim()).into_owned();
+    move_database(Path::new(&new_path)).await.map_err(|e| {
+        error!("Failed to move the database to {}: {}", new_path, e);
+        e
+    })
+}
+
+/// Tauri command listing tombstones of deleted files, newest first. Expired
+/// tombstones are pruned first.
+#[tauri::command]
+pub async fn list_tombstones_command() -> Result<Vec<Tombstone>, String> {
+    let conn = connect_db().await.map_err(|e| format!("Database connection error: {}", e))?;
+    prune_expired_tombstones(&conn, current_settings().tombstone_retention_days).await?;
+    list_tombstones(&conn).await
+}
+
+/// Tauri command re-indexing a deleted file that is back on disk
+#[tauri::command]
+pub async fn restore_tombstone_command(path: String) -> Result<RestoredTombstone, String> {
+    info!("Request to restore tombstone for {}", path);
+    let conn = connect_db().await.map_err(|e| format!("Database connection error: {}", e))?;
+    restore_tombstone(&conn, &path).await.map_err(|e| {
+        error!("Failed to restore {}: {}", path, e);
+        e
+    })
+}
+
+/// Tauri command removing tombstones older than `max_age_days`, or the
+/// `tombstone_retention_days` setting when omitted. Returns how many went.
+#[tauri::command]
+pub async fn prune_tombstones_command(max_age_days: Option<u64>) -> Result<usize, String> {
+    let conn = connect_db().await.map_err(|e| format!("Database connection error: {}", e))?;
+    match max_age_days {
+        Some(days) => prune_tombstones(&conn, days).await,
+        None => prune_expired_tombstones(&conn, current_settings().tombstone_retention_days).await,
+    }
+}
+
+/// Default page size for `list_indexed_documents_command`
+const DEFAULT_DOCUMENT_PAGE_SIZE: usize = 100;
+
+/// Response model for a page of indexed documents
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexedDocumentsPage {
+    pub documents: Vec<IndexedDocument>,
+    /// Total number of documents matching the filter, across all pages
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// Tauri command to page through everything in the index (a "library" view)
+#[tauri::command]
+pub async fn list_indexed_documents_command(
+    offset: Option<usize>,
+    limit: Option<usize>,
+    content_type_filter: Option<String>,
+    sort_by: Option<DocumentSortBy>,
+) -> Result<IndexedDocumentsPage, String> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(DEFAULT_DOCUMENT_PAGE_SIZE);
+    info!(
+        "Request to list indexed documents (offset: {}, limit: {}, filter: {:?})",
+        offset, limit, content_type_filter
+    );
+
+    let conn = connect_db().await.map_err(|e| {
+        error!("Failed to connect to database: {}", e);
+        format!("Database connection error: {}", e)
+    })?;
+    let documents = list_indexed_documents(&conn, content_type_filter.as_deref(), sort_by.unwrap_or_default())
+        .await
+        .map_err(|e| {
+            error!("Failed to list indexed documents: {}", e);
+            format!("Failed to list indexed documents: {}", e)
+        })?;
+
+    let total = documents.len();
+    Ok(IndexedDocumentsPage {
+        documents: documents.into_iter().skip(offset).take(limit).collect(),
+        total,
+        offset,
+        limit,
+    })
+}
+
+/// Tauri command to stop file watchers from acting on events, such as while
+/// a large move runs
+#[tauri::command]
+pub fn pause_watchers_command() -> Result<WatcherStatus, String> {
+    pause_watchers();
+    Ok(watcher_status())
+}
+
+/// Tauri command to let file watchers act again; paths changed while they
+/// were paused are re-synced
+#[tauri::command]
+pub fn resume_watchers_command() -> Result<WatcherStatus, String> {
+    resume_watchers();
+    Ok(watcher_status())
+}
+
+/// Tauri command reporting whether file watchers are paused
+#[tauri::command]
+pub fn watcher_status_command() -> Result<WatcherStatus, String> {
+    Ok(watcher_status())
+}
+
 /// Run Downloads folder indexing at application startup
 /// This is not exposed as a Tauri command, but called internally
 pub async fn run_startup_indexing() {
@@ -239,4 +660,12 @@ pub async fn run_startup_indexing() {
             error!("Startup indexing failed: {}", err);
         }
     }
+
+    let pruned = match connect_db().await {
+        Ok(conn) => prune_expired_tombstones(&conn, current_settings().tombstone_retention_days).await,
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = pruned {
+        error!("Failed to prune expired tombstones: {}", e);
+    }
 }
@@ -4,3 +4,4 @@ pub mod benchmark_commands;
 pub mod search_commands;
 pub mod indexing_commands;
 pub mod file_operations;
+pub mod settings_commands;
@@ -0,0 +1,95 @@
+// src-tauri/src/commands/settings_commands.rs
+
+use crate::commands::fs_commands::get_locations_file_path;
+use crate::core::config_bundle::{export_config, import_config, ConfigFile};
+use crate::core::exclusions::{exclusions_file_path, reload_exclusions};
+use crate::core::favorites::{favorites_file_path, reload_favorites};
+use crate::core::log_stream::{parse_log_level, subscribe_logs, unsubscribe_logs};
+use crate::core::recents::{recents_file_path, reload_recents};
+use crate::core::settings::{current_settings, reload_settings, settings_file_path, update_settings, AppSettings};
+use crate::db::{connect_db, validate_embedding_dimensions};
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Tauri command returning the current application settings
+#[tauri::command]
+pub fn get_settings_command() -> Result<AppSettings, String> {
+    Ok(current_settings())
+}
+
+/// Tauri command replacing the application settings and persisting them
+#[tauri::command]
+pub fn update_settings_command(settings: AppSettings) -> Result<AppSettings, String> {
+    info!("Updating settings: {:?}", settings);
+    update_settings(settings)
+}
+
+/// Where each configuration file lives on this machine
+async fn config_file_paths(app_handle: &AppHandle) -> Result<Vec<(ConfigFile, PathBuf)>, String> {
+    let custom_locations = get_locations_file_path(app_handle)
+        .await
+        .map_err(|e| format!("Failed to resolve custom locations path: {}", e))?;
+    Ok(vec![
+        (ConfigFile::Settings, settings_file_path()?),
+        (ConfigFile::CustomLocations, custom_locations),
+        (ConfigFile::RecentlyOpened, recents_file_path()?),
+        (ConfigFile::Favorites, favorites_file_path()?),
+        (ConfigFile::Exclusions, exclusions_file_path()?),
+    ])
+}
+
+/// Tauri command writing settings, custom locations, recently opened files,
+/// favorites and indexing exclusions into one bundle at `dest`. Returns the files included.
+#[tauri::command]
+pub async fn export_config_command(app_handle: AppHandle, dest: String) -> Result<Vec<String>, String> {
+    let files = config_file_paths(&app_handle).await?;
+    export_config(&files, Path::new(&dest))
+}
+
+/// Tauri command restoring a bundle written by `export_config_command`.
+/// Returns the files restored. Settings pointing `db_location` at a database
+/// whose embeddings the current models can't search are refused, and every
+/// file the import overwrote is put back.
+#[tauri::command]
+pub async fn import_config_command(app_handle: AppHandle, src: String) -> Result<Vec<String>, String> {
+    info!("Importing configuration from {}", src);
+    let files = config_file_paths(&app_handle).await?;
+    let previous_db_location = current_settings().db_location;
+    let (restored, backup) = import_config(&files, Path::new(&src))?;
+    reload_settings();
+
+    if current_settings().db_location != previous_db_location {
+        let validated = match connect_db().await {
+            Ok(conn) => validate_embedding_dimensions(&conn).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = validated {
+            warn!("Imported database location is unusable, undoing the import: {}", e);
+            let undone = backup.restore();
+            reload_settings();
+            undone?;
+            return Err(format!("The imported settings point at a database that can't be used: {}", e));
+        }
+    }
+    reload_recents();
+    reload_favorites();
+    reload_exclusions();
+    Ok(restored)
+}
+
+/// Tauri command streaming log records at `level` (default "info") or more
+/// severe to the UI as `app-log` events. Call again to change the level.
+#[tauri::command]
+pub fn subscribe_logs_command(app_handle: AppHandle, level: Option<String>) -> Result<(), String> {
+    let level = parse_log_level(level.as_deref().unwrap_or("info"))?;
+    subscribe_logs(app_handle, level);
+    info!("Streaming logs at {} and above to the UI", level);
+    Ok(())
+}
+
+/// Tauri command stopping the log stream. Returns false when none was active.
+#[tauri::command]
+pub fn unsubscribe_logs_command() -> Result<bool, String> {
+    Ok(unsubscribe_logs())
+}
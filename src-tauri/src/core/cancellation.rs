@@ -0,0 +1,88 @@
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Cancellation flags of long-running operations, keyed by a caller-chosen id
+static OPERATIONS: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Handle a long-running operation polls to see whether it should stop.
+/// Unregisters the operation when dropped.
+pub struct CancellationToken {
+    id: Option<String>,
+    flag: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// A token nobody can cancel, for callers that didn't supply an operation id
+    pub fn none() -> Self {
+        Self {
+            id: None,
+            flag: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for CancellationToken {
+    fn drop(&mut self) {
+        if let Some(id) = &self.id {
+            if let Ok(mut operations) = OPERATIONS.lock() {
+                // Only remove our own flag; the id may have been reused since
+                if operations.get(id).is_some_and(|flag| Arc::ptr_eq(flag, &self.flag)) {
+                    operations.remove(id);
+                }
+            }
+        }
+    }
+}
+
+/// Registers `id` as a running operation. Without an id the token can't be cancelled.
+pub fn register_operation(id: Option<&str>) -> CancellationToken {
+    let Some(id) = id else {
+        return CancellationToken::none();
+    };
+    let flag = Arc::new(AtomicBool::new(false));
+    if let Ok(mut operations) = OPERATIONS.lock() {
+        operations.insert(id.to_string(), flag.clone());
+    }
+    debug!("Registered cancellable operation {}", id);
+    CancellationToken {
+        id: Some(id.to_string()),
+        flag,
+    }
+}
+
+/// Requests that the operation stop. Returns false if no such operation is running.
+pub fn cancel_operation(id: &str) -> bool {
+    let flag = OPERATIONS.lock().ok().and_then(|operations| operations.get(id).cloned());
+    match flag {
+        Some(flag) => {
+            flag.store(true, Ordering::Relaxed);
+            info!("Cancellation requested for operation {}", id);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_registered_operation() {
+        let token = register_operation(Some("test-op"));
+        assert!(!token.is_cancelled());
+        assert!(cancel_operation("test-op"));
+        assert!(token.is_cancelled());
+
+        drop(token);
+        assert!(!cancel_operation("test-op"), "Finished operations are unregistered");
+        assert!(!CancellationToken::none().is_cancelled());
+    }
+}
@@ -0,0 +1,68 @@
+use crate::archive::SUPPORTED_ARCHIVE_EXTENSIONS;
+use crate::core::thumbnail_cache::{THUMBNAIL_IMAGE_EXTENSIONS, THUMBNAIL_VIDEO_EXTENSIONS};
+use crate::extractor::{SUPPORTED_CODE_EXTENSIONS, SUPPORTED_IMAGE_EXTENSIONS, SUPPORTED_TEXT_EXTENSIONS};
+use serde::{Deserialize, Serialize};
+
+/// What this build can index and preview. Extensions are lowercase, without the dot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Documents whose text is extracted and embedded
+    pub text_extensions: Vec<String>,
+    /// Source files, embedded with the code model
+    pub code_extensions: Vec<String>,
+    /// Images embedded for visual search
+    pub image_extensions: Vec<String>,
+    /// Archives whose text members are indexed
+    pub archive_extensions: Vec<String>,
+    /// Images and videos that get a rendered thumbnail
+    pub thumbnail_extensions: Vec<String>,
+    /// Text and source files get a thumbnail previewing their first lines
+    pub text_thumbnails: bool,
+    /// Optional features; none is part of any build yet, so the frontend can
+    /// hide them until one is
+    pub ocr: bool,
+    pub audio_transcription: bool,
+    pub heic: bool,
+}
+
+fn to_strings(extensions: &[&str]) -> Vec<String> {
+    extensions.iter().map(|ext| ext.to_string()).collect()
+}
+
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        text_extensions: to_strings(SUPPORTED_TEXT_EXTENSIONS),
+        code_extensions: to_strings(SUPPORTED_CODE_EXTENSIONS),
+        image_extensions: to_strings(SUPPORTED_IMAGE_EXTENSIONS),
+        archive_extensions: to_strings(SUPPORTED_ARCHIVE_EXTENSIONS),
+        thumbnail_extensions: to_strings(&[THUMBNAIL_IMAGE_EXTENSIONS, THUMBNAIL_VIDEO_EXTENSIONS].concat()),
+        text_thumbnails: true,
+        ocr: false,
+        audio_transcription: false,
+        heic: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::is_supported_archive;
+    use crate::commands::fs_commands::is_thumbnailable;
+    use crate::extractor::{get_content_type, ContentType};
+    use std::path::Path;
+
+    #[test]
+    fn test_listed_extensions_are_handled() {
+        let capabilities = capabilities();
+        for ext in capabilities.text_extensions.iter().chain(&capabilities.code_extensions) {
+            assert_eq!(get_content_type(Path::new(&format!("file.{}", ext))), ContentType::Text, "{}", ext);
+        }
+        for ext in &capabilities.image_extensions {
+            assert_eq!(get_content_type(Path::new(&format!("file.{}", ext))), ContentType::Image, "{}", ext);
+        }
+        for ext in &capabilities.archive_extensions {
+            assert!(is_supported_archive(Path::new(&format!("file.{}", ext))), "{}", ext);
+        }
+        assert!(capabilities.thumbnail_extensions.iter().all(|ext| is_thumbnailable(ext)));
+    }
+}
@@ -0,0 +1,176 @@
+use super::cancellation::CancellationToken;
+use crate::extractor::calculate_file_hash;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// What makes two files with the same relative path count as different
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffMode {
+    /// Only presence matters
+    Name,
+    /// Files differ when their sizes differ
+    Size,
+    /// Files differ when their contents differ (size first, then SHA-256)
+    Hash,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffStatus {
+    OnlyInA,
+    OnlyInB,
+    Differs,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffEntry {
+    /// Path relative to both roots, with `/` separators
+    pub relative_path: String,
+    pub status: DiffStatus,
+}
+
+/// All differences between two trees, sorted by relative path
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DirectoryDiff {
+    pub entries: Vec<DiffEntry>,
+    pub files_compared: usize,
+    /// True when the comparison was cancelled; `entries` then covers only part of the trees
+    pub cancelled: bool,
+}
+
+/// Relative path -> size of every regular file under `root`
+fn collect_files(root: &Path, token: &CancellationToken) -> Option<BTreeMap<String, u64>> {
+    let mut files = BTreeMap::new();
+    for entry in WalkDir::new(root).follow_links(false) {
+        if token.is_cancelled() {
+            return None;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Skipping unreadable entry while comparing {}: {}", root.display(), e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        let relative = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        files.insert(relative, size);
+    }
+    Some(files)
+}
+
+fn contents_differ(a: &Path, b: &Path) -> bool {
+    match (calculate_file_hash(a), calculate_file_hash(b)) {
+        (Ok(hash_a), Ok(hash_b)) => hash_a != hash_b,
+        // A file that can't be read can't be shown to match
+        _ => true,
+    }
+}
+
+/// Compares the files under `a` and `b`. Blocking; run it off the async runtime.
+pub fn compare_directories(a: &Path, b: &Path, mode: DiffMode, token: &CancellationToken) -> DirectoryDiff {
+    let mut diff = DirectoryDiff::default();
+    let (Some(files_a), Some(files_b)) = (collect_files(a, token), collect_files(b, token)) else {
+        diff.cancelled = true;
+        return diff;
+    };
+
+    for (relative_path, size_a) in &files_a {
+        if token.is_cancelled() {
+            diff.cancelled = true;
+            break;
+        }
+        let status = match files_b.get(relative_path) {
+            None => Some(DiffStatus::OnlyInA),
+            Some(size_b) => {
+                diff.files_compared += 1;
+                let differs = match mode {
+                    DiffMode::Name => false,
+                    DiffMode::Size => size_a != size_b,
+                    DiffMode::Hash => size_a != size_b || contents_differ(&a.join(relative_path), &b.join(relative_path)),
+                };
+                differs.then_some(DiffStatus::Differs)
+            }
+        };
+        if let Some(status) = status {
+            diff.entries.push(DiffEntry {
+                relative_path: relative_path.clone(),
+                status,
+            });
+        }
+    }
+
+    if !diff.cancelled {
+        diff.entries.extend(
+            files_b
+                .keys()
+                .filter(|path| !files_a.contains_key(*path))
+                .map(|path| DiffEntry {
+                    relative_path: path.clone(),
+                    status: DiffStatus::OnlyInB,
+                }),
+        );
+        diff.entries.sort_by(|x, y| x.relative_path.cmp(&y.relative_path));
+    }
+
+    info!(
+        "Compared {} and {} by {:?}: {} differences{}",
+        a.display(),
+        b.display(),
+        mode,
+        diff.entries.len(),
+        if diff.cancelled { " (cancelled)" } else { "" }
+    );
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_compare_directories_by_mode() {
+        let a = tempdir().unwrap();
+        let b = tempdir().unwrap();
+        fs::create_dir(a.path().join("sub")).unwrap();
+        fs::create_dir(b.path().join("sub")).unwrap();
+        fs::write(a.path().join("same.txt"), "same").unwrap();
+        fs::write(b.path().join("same.txt"), "same").unwrap();
+        fs::write(a.path().join("sub/edited.txt"), "abcd").unwrap();
+        fs::write(b.path().join("sub/edited.txt"), "abce").unwrap();
+        fs::write(a.path().join("only_a.txt"), "a").unwrap();
+        fs::write(b.path().join("only_b.txt"), "b").unwrap();
+
+        let token = CancellationToken::none();
+        let by_name = compare_directories(a.path(), b.path(), DiffMode::Name, &token);
+        assert_eq!(
+            by_name.entries,
+            vec![
+                DiffEntry { relative_path: "only_a.txt".into(), status: DiffStatus::OnlyInA },
+                DiffEntry { relative_path: "only_b.txt".into(), status: DiffStatus::OnlyInB },
+            ]
+        );
+        assert_eq!(by_name.files_compared, 2);
+
+        // Same size, different bytes: only the hash mode notices
+        let by_size = compare_directories(a.path(), b.path(), DiffMode::Size, &token);
+        assert_eq!(by_size.entries.len(), 2);
+        let by_hash = compare_directories(a.path(), b.path(), DiffMode::Hash, &token);
+        assert!(by_hash
+            .entries
+            .contains(&DiffEntry { relative_path: "sub/edited.txt".into(), status: DiffStatus::Differs }));
+        assert!(!by_hash.cancelled);
+    }
+}
@@ -0,0 +1,237 @@
+use super::exclusions::EXCLUSIONS_FILE_NAME;
+use super::favorites::FavoriteEntry;
+use super::recents::RecentEntry;
+use super::settings::AppSettings;
+use crate::commands::fs_commands::CustomLocation;
+use chrono::Utc;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever a bundle written by this version can't be read by an older one
+pub const CONFIG_BUNDLE_VERSION: u32 = 1;
+
+/// The user configuration files carried in a bundle. The filename index and
+/// the vector database are rebuilt from disk and are not part of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFile {
+    Settings,
+    CustomLocations,
+    RecentlyOpened,
+    Favorites,
+    Exclusions,
+}
+
+impl ConfigFile {
+    pub const ALL: [ConfigFile; 5] = [
+        ConfigFile::Settings,
+        ConfigFile::CustomLocations,
+        ConfigFile::RecentlyOpened,
+        ConfigFile::Favorites,
+        ConfigFile::Exclusions,
+    ];
+
+    /// File name on disk, also the key inside the bundle
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ConfigFile::Settings => "settings.json",
+            ConfigFile::CustomLocations => "custom_locations.json",
+            ConfigFile::RecentlyOpened => "recently_opened.json",
+            ConfigFile::Favorites => "favorites.json",
+            ConfigFile::Exclusions => EXCLUSIONS_FILE_NAME,
+        }
+    }
+
+    fn from_file_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|file| file.file_name() == name)
+    }
+
+    /// Checks that `value` has the shape the app expects for this file
+    fn validate(self, value: &Value) -> Result<(), serde_json::Error> {
+        match self {
+            ConfigFile::Settings => serde_json::from_value::<AppSettings>(value.clone()).map(|_| ()),
+            ConfigFile::CustomLocations => serde_json::from_value::<Vec<CustomLocation>>(value.clone()).map(|_| ()),
+            ConfigFile::RecentlyOpened => serde_json::from_value::<Vec<RecentEntry>>(value.clone()).map(|_| ()),
+            ConfigFile::Favorites => serde_json::from_value::<Vec<FavoriteEntry>>(value.clone()).map(|_| ()),
+            ConfigFile::Exclusions => serde_json::from_value::<Vec<PathBuf>>(value.clone()).map(|_| ()),
+        }
+    }
+}
+
+/// All configuration files in one JSON document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub version: u32,
+    /// Unix timestamp (seconds)
+    pub exported_at: i64,
+    /// File contents keyed by file name
+    pub files: BTreeMap<String, Value>,
+}
+
+/// Writes every existing file in `files` into a bundle at `dest`.
+/// Returns the names of the files included.
+pub fn export_config(files: &[(ConfigFile, PathBuf)], dest: &Path) -> Result<Vec<String>, String> {
+    let mut bundle = ConfigBundle {
+        version: CONFIG_BUNDLE_VERSION,
+        exported_at: Utc::now().timestamp(),
+        files: BTreeMap::new(),
+    };
+    for (file, path) in files {
+        if !path.exists() {
+            continue;
+        }
+        let json = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        match serde_json::from_str::<Value>(&json) {
+            Ok(value) => {
+                bundle.files.insert(file.file_name().to_string(), value);
+            }
+            Err(e) => warn!("Leaving malformed {} out of the config bundle: {}", path.display(), e),
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(dest, json).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+    info!("Exported {} config files to {}", bundle.files.len(), dest.display());
+    Ok(bundle.files.into_keys().collect())
+}
+
+/// The configuration files an import overwrote, as they were before it
+#[derive(Debug, Default)]
+pub struct ConfigBackup {
+    /// Previous contents by path; `None` for files the import created
+    files: Vec<(PathBuf, Option<String>)>,
+}
+
+impl ConfigBackup {
+    /// Puts every backed-up file back as it was before the import
+    pub fn restore(&self) -> Result<(), String> {
+        for (path, contents) in self.files.iter().rev() {
+            match contents {
+                Some(json) => fs::write(path, json).map_err(|e| format!("Failed to restore {}: {}", path.display(), e))?,
+                None if path.exists() => {
+                    fs::remove_file(path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Restores the files in the bundle at `src` to their paths in `files`.
+///
+/// The whole bundle is validated before anything is written, so a bad bundle
+/// leaves the current configuration untouched, and a failed write puts back
+/// the files already written. Files the bundle doesn't contain are kept as
+/// they are. Returns the names of the files restored, and a backup to undo
+/// the import with if the restored configuration turns out unusable.
+pub fn import_config(files: &[(ConfigFile, PathBuf)], src: &Path) -> Result<(Vec<String>, ConfigBackup), String> {
+    let json = fs::read_to_string(src).map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+    let bundle: ConfigBundle =
+        serde_json::from_str(&json).map_err(|e| format!("{} is not a config bundle: {}", src.display(), e))?;
+    if bundle.version == 0 || bundle.version > CONFIG_BUNDLE_VERSION {
+        return Err(format!(
+            "Config bundle version {} is not supported (expected at most {})",
+            bundle.version, CONFIG_BUNDLE_VERSION
+        ));
+    }
+
+    let mut restores = Vec::with_capacity(bundle.files.len());
+    for (name, value) in &bundle.files {
+        let file = ConfigFile::from_file_name(name).ok_or_else(|| format!("Unknown file in config bundle: {}", name))?;
+        file.validate(value).map_err(|e| format!("Invalid {} in config bundle: {}", name, e))?;
+        let Some((_, path)) = files.iter().find(|(known, _)| *known == file) else {
+            continue;
+        };
+        let json = serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+        restores.push((name.clone(), path, json));
+    }
+
+    let mut backup = ConfigBackup::default();
+    for (_, path, _) in &restores {
+        let previous = match fs::read_to_string(path) {
+            Ok(json) => Some(json),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(format!("Failed to back up {}: {}", path.display(), e)),
+        };
+        backup.files.push((path.to_path_buf(), previous));
+    }
+
+    for (_, path, json) in &restores {
+        let written = match path.parent() {
+            Some(parent) => fs::create_dir_all(parent).and_then(|_| fs::write(path, json)),
+            None => fs::write(path, json),
+        };
+        if let Err(e) = written {
+            // All or nothing: put back what was already overwritten
+            if let Err(restore_error) = backup.restore() {
+                warn!("Failed to undo a partial config import: {}", restore_error);
+            }
+            return Err(format!("Failed to write {}: {}", path.display(), e));
+        }
+    }
+    info!("Imported {} config files from {}", restores.len(), src.display());
+    Ok((restores.into_iter().map(|(name, _, _)| name).collect(), backup))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn config_paths(dir: &Path) -> Vec<(ConfigFile, PathBuf)> {
+        ConfigFile::ALL.into_iter().map(|file| (file, dir.join(file.file_name()))).collect()
+    }
+
+    #[test]
+    fn test_config_round_trip_and_validation() {
+        let source = tempdir().unwrap();
+        let settings = AppSettings {
+            min_text_chars: 42,
+            ..Default::default()
+        };
+        fs::write(source.path().join("settings.json"), serde_json::to_string(&settings).unwrap()).unwrap();
+        fs::write(source.path().join("recently_opened.json"), r#"[{"path":"/a.txt","opened_at":1}]"#).unwrap();
+        fs::write(source.path().join("excluded_paths.json"), r#"["/home/me/private"]"#).unwrap();
+
+        let bundle_path = source.path().join("bundle.json");
+        let exported = export_config(&config_paths(source.path()), &bundle_path).unwrap();
+        assert_eq!(exported, vec!["excluded_paths.json", "recently_opened.json", "settings.json"]);
+
+        let target = tempdir().unwrap();
+        fs::write(target.path().join("settings.json"), "{}").unwrap();
+        let (imported, backup) = import_config(&config_paths(target.path()), &bundle_path).unwrap();
+        assert_eq!(imported.len(), 3);
+        let restored: AppSettings =
+            serde_json::from_str(&fs::read_to_string(target.path().join("settings.json")).unwrap()).unwrap();
+        assert_eq!(restored, settings);
+        assert!(!target.path().join("custom_locations.json").exists());
+        let exclusions: Vec<PathBuf> =
+            serde_json::from_str(&fs::read_to_string(target.path().join("excluded_paths.json")).unwrap()).unwrap();
+        assert_eq!(exclusions, vec![PathBuf::from("/home/me/private")]);
+
+        // Undoing the import restores overwritten files and removes created ones
+        backup.restore().unwrap();
+        assert_eq!(fs::read_to_string(target.path().join("settings.json")).unwrap(), "{}");
+        assert!(!target.path().join("recently_opened.json").exists());
+        assert!(!target.path().join("excluded_paths.json").exists());
+
+        // A bundle with one invalid file restores nothing
+        let bad = target.path().join("bad.json");
+        fs::write(
+            &bad,
+            r#"{"version":1,"exported_at":0,"files":{"custom_locations.json":[],"recently_opened.json":{"oops":true}}}"#,
+        )
+        .unwrap();
+        let fresh = tempdir().unwrap();
+        assert!(import_config(&config_paths(fresh.path()), &bad).is_err());
+        assert!(!fresh.path().join("custom_locations.json").exists());
+
+        let future = target.path().join("future.json");
+        fs::write(&future, r#"{"version":99,"exported_at":0,"files":{}}"#).unwrap();
+        assert!(import_config(&config_paths(fresh.path()), &future).is_err());
+    }
+}
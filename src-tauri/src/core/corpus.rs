@@ -0,0 +1,273 @@
+use crate::core::file_types::{categorize_file, FileCategory};
+use crate::db::{index_version, list_indexed_documents, DbError, DocumentSortBy, IndexedDocument};
+use crate::extractor::{extract_text, DetectedLanguage};
+use lancedb::connection::Connection;
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Documents per file category
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryCount {
+    pub category: FileCategory,
+    pub documents: usize,
+}
+
+/// A document and when it was indexed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexedFileTime {
+    pub file_path: String,
+    /// Unix timestamp (seconds)
+    pub indexed_at: i64,
+}
+
+/// What the index holds, aggregated over every content table
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CorpusStats {
+    pub total_documents: usize,
+    pub total_chunks: usize,
+    /// Content isn't stored, so length is measured in embedded chunks
+    pub average_chunks_per_document: f64,
+    /// Documents per table: "text" (English and other languages), "amharic", "code" and "image"
+    pub documents_by_table: HashMap<String, usize>,
+    /// Most common category first
+    pub categories: Vec<CategoryCount>,
+    pub newest: Option<IndexedFileTime>,
+    pub oldest: Option<IndexedFileTime>,
+}
+
+/// Documents of the text table re-read to detect their language
+const LANGUAGE_SAMPLE_SIZE: usize = 200;
+
+/// Estimated documents in one language
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageShare {
+    pub language: DetectedLanguage,
+    pub documents: usize,
+    /// Fraction of all text documents, from 0 to 1
+    pub share: f64,
+}
+
+/// Languages of the indexed text documents. Amharic documents are counted
+/// from their own table; the rest are estimated from a sample of the text table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LanguageBreakdown {
+    pub total_documents: usize,
+    /// Text table documents whose language was actually detected
+    pub sampled_documents: usize,
+    /// Most common language first
+    pub languages: Vec<LanguageShare>,
+    pub dominant: Option<DetectedLanguage>,
+}
+
+/// Stats from the last computation and the index version they describe
+static CORPUS_STATS_CACHE: Lazy<Mutex<Option<(u64, CorpusStats)>>> = Lazy::new(|| Mutex::new(None));
+
+static LANGUAGE_BREAKDOWN_CACHE: Lazy<Mutex<Option<(u64, LanguageBreakdown)>>> = Lazy::new(|| Mutex::new(None));
+
+fn summarize(documents: &[IndexedDocument]) -> CorpusStats {
+    let mut stats = CorpusStats {
+        total_documents: documents.len(),
+        ..Default::default()
+    };
+    let mut categories: HashMap<FileCategory, usize> = HashMap::new();
+    for document in documents {
+        stats.total_chunks += document.chunk_count;
+        *stats.documents_by_table.entry(document.content_type.clone()).or_default() += 1;
+        *categories.entry(categorize_file(&PathBuf::from(&document.file_path))).or_default() += 1;
+
+        let time = || IndexedFileTime {
+            file_path: document.file_path.clone(),
+            indexed_at: document.last_modified,
+        };
+        if stats.newest.as_ref().map_or(true, |newest| document.last_modified > newest.indexed_at) {
+            stats.newest = Some(time());
+        }
+        if stats.oldest.as_ref().map_or(true, |oldest| document.last_modified < oldest.indexed_at) {
+            stats.oldest = Some(time());
+        }
+    }
+    if stats.total_documents > 0 {
+        stats.average_chunks_per_document = stats.total_chunks as f64 / stats.total_documents as f64;
+    }
+    stats.categories = categories
+        .into_iter()
+        .map(|(category, documents)| CategoryCount { category, documents })
+        .collect();
+    stats.categories.sort_by(|a, b| b.documents.cmp(&a.documents));
+    stats
+}
+
+/// Aggregate stats over the index. Reads only paths, hashes and timestamps,
+/// and reuses the last result until the index changes.
+pub async fn corpus_stats(conn: &Connection) -> Result<CorpusStats, DbError> {
+    let version = index_version();
+    if let Some((cached_version, stats)) = CORPUS_STATS_CACHE.lock().unwrap().as_ref() {
+        if *cached_version == version {
+            debug!("Returning cached corpus stats");
+            return Ok(stats.clone());
+        }
+    }
+
+    let documents = list_indexed_documents(conn, None, DocumentSortBy::Path).await?;
+    let stats = summarize(&documents);
+    *CORPUS_STATS_CACHE.lock().unwrap() = Some((version, stats.clone()));
+    Ok(stats)
+}
+
+/// Up to `size` items spread evenly over `items`
+fn sample_evenly<T>(items: &[T], size: usize) -> Vec<&T> {
+    if items.len() <= size {
+        return items.iter().collect();
+    }
+    (0..size).map(|i| &items[i * items.len() / size]).collect()
+}
+
+/// Scales the languages detected in the sample up to the `text_documents`
+/// the text table holds and adds the `amharic_documents` counted directly
+fn estimate_breakdown(text_documents: usize, amharic_documents: usize, sample: &[DetectedLanguage]) -> LanguageBreakdown {
+    let mut counts: HashMap<DetectedLanguage, usize> = HashMap::new();
+    if !sample.is_empty() {
+        let mut detected: HashMap<&DetectedLanguage, usize> = HashMap::new();
+        for language in sample {
+            *detected.entry(language).or_default() += 1;
+        }
+        for (language, found) in detected {
+            let estimate = (found as f64 / sample.len() as f64 * text_documents as f64).round() as usize;
+            counts.insert(language.clone(), estimate);
+        }
+    }
+    if amharic_documents > 0 {
+        *counts.entry(DetectedLanguage::Amharic).or_default() += amharic_documents;
+    }
+
+    let total: usize = counts.values().sum();
+    let mut languages: Vec<LanguageShare> = counts
+        .into_iter()
+        .filter(|(_, documents)| *documents > 0)
+        .map(|(language, documents)| LanguageShare {
+            language,
+            documents,
+            share: documents as f64 / total as f64,
+        })
+        .collect();
+    languages.sort_by(|a, b| {
+        b.documents
+            .cmp(&a.documents)
+            .then_with(|| format!("{:?}", a.language).cmp(&format!("{:?}", b.language)))
+    });
+    LanguageBreakdown {
+        total_documents: text_documents + amharic_documents,
+        sampled_documents: sample.len(),
+        dominant: languages.first().map(|share| share.language.clone()),
+        languages,
+    }
+}
+
+/// Language mix of the indexed text. Re-extracts a sample of the text
+/// table's files to detect their language, so it is slow the first time;
+/// the result is reused until the index changes. Code and images are left out.
+pub async fn language_breakdown(conn: &Connection) -> Result<LanguageBreakdown, DbError> {
+    let version = index_version();
+    if let Some((cached_version, breakdown)) = LANGUAGE_BREAKDOWN_CACHE.lock().unwrap().as_ref() {
+        if *cached_version == version {
+            debug!("Returning cached language breakdown");
+            return Ok(breakdown.clone());
+        }
+    }
+
+    let documents = list_indexed_documents(conn, None, DocumentSortBy::Path).await?;
+    let text_paths: Vec<String> = documents
+        .iter()
+        .filter(|doc| doc.content_type == "text")
+        .map(|doc| doc.file_path.clone())
+        .collect();
+    let amharic_documents = documents.iter().filter(|doc| doc.content_type == "amharic").count();
+    let sample: Vec<String> = sample_evenly(&text_paths, LANGUAGE_SAMPLE_SIZE).into_iter().cloned().collect();
+
+    // Files that moved or can't be read again (such as virtual content) are left out of the sample
+    let detected = tokio::task::spawn_blocking(move || {
+        sample
+            .iter()
+            .filter_map(|path| extract_text(Path::new(path)).ok())
+            .filter(|result| !result.text.trim().is_empty())
+            .map(|result| result.language)
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| DbError::Other(format!("Language detection task failed: {}", e)))?;
+
+    let breakdown = estimate_breakdown(text_paths.len(), amharic_documents, &detected);
+    info!(
+        "Language breakdown of {} documents from a sample of {}: dominant {:?}",
+        breakdown.total_documents, breakdown.sampled_documents, breakdown.dominant
+    );
+    *LANGUAGE_BREAKDOWN_CACHE.lock().unwrap() = Some((version, breakdown.clone()));
+    Ok(breakdown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(path: &str, content_type: &str, chunks: usize, indexed_at: i64) -> IndexedDocument {
+        IndexedDocument {
+            file_path: path.to_string(),
+            content_hash: "hash".to_string(),
+            last_modified: indexed_at,
+            chunk_count: chunks,
+            content_type: content_type.to_string(),
+            index_root: None,
+        }
+    }
+
+    #[test]
+    fn test_summarize_counts_tables_categories_and_dates() {
+        let stats = summarize(&[
+            document("/docs/a.txt", "text", 3, 200),
+            document("/docs/b.md", "amharic", 1, 100),
+            document("/src/main.rs", "code", 2, 300),
+            document("/photos/c.jpg", "image", 1, 150),
+        ]);
+        assert_eq!(stats.total_documents, 4);
+        assert_eq!(stats.total_chunks, 7);
+        assert!((stats.average_chunks_per_document - 1.75).abs() < 1e-9);
+        assert_eq!(stats.documents_by_table["amharic"], 1);
+        assert_eq!(stats.categories[0].category, FileCategory::Document);
+        assert_eq!(stats.categories[0].documents, 2);
+        assert_eq!(stats.newest.unwrap().file_path, "/src/main.rs");
+        assert_eq!(stats.oldest.unwrap().file_path, "/docs/b.md");
+
+        assert_eq!(summarize(&[]), CorpusStats::default());
+    }
+
+    #[test]
+    fn test_estimate_breakdown_scales_sample() {
+        let sample = [
+            DetectedLanguage::English,
+            DetectedLanguage::English,
+            DetectedLanguage::English,
+            DetectedLanguage::Other,
+        ];
+        let breakdown = estimate_breakdown(80, 20, &sample);
+        assert_eq!(breakdown.total_documents, 100);
+        assert_eq!(breakdown.dominant, Some(DetectedLanguage::English));
+        let documents: Vec<(DetectedLanguage, usize)> =
+            breakdown.languages.iter().map(|s| (s.language.clone(), s.documents)).collect();
+        assert_eq!(
+            documents,
+            vec![
+                (DetectedLanguage::English, 60),
+                (DetectedLanguage::Amharic, 20),
+                (DetectedLanguage::Other, 20),
+            ]
+        );
+        assert!((breakdown.languages[0].share - 0.6).abs() < 1e-9);
+
+        assert_eq!(sample_evenly(&[1, 2, 3, 4, 5, 6], 3), vec![&1, &3, &5]);
+        assert_eq!(estimate_breakdown(0, 0, &[]), LanguageBreakdown::default());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::core::indexer::acquire_indexing_slot;
+use crate::core::settings::{current_settings, update_settings};
+use crate::db::{
+    bump_db_location_generation, bump_index_version, connect_db_with_path, get_db_path, lock_db_for_move,
+    validate_embedding_dimensions,
+};
+use crate::watcher::hold_watchers;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Free space kept on the destination beyond the database itself
+const FREE_SPACE_MARGIN_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DatabaseMoveReport {
+    pub from: String,
+    pub to: String,
+    pub files_copied: usize,
+    pub bytes_copied: u64,
+    /// False when the old copy couldn't be deleted; the database still moved
+    pub source_removed: bool,
+}
+
+/// Files and total bytes under `dir`
+pub(crate) fn dir_footprint(dir: &Path) -> Result<(usize, u64), String> {
+    let mut files = 0;
+    let mut bytes = 0;
+    for entry in WalkDir::new(dir) {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        if entry.file_type().is_file() {
+            let metadata = entry.metadata().map_err(|e| format!("Failed to read {}: {}", entry.path().display(), e))?;
+            files += 1;
+            bytes += metadata.len();
+        }
+    }
+    Ok((files, bytes))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    for entry in WalkDir::new(from) {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+        let relative = entry.path().strip_prefix(from).map_err(|e| e.to_string())?;
+        let target = to.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target).map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target)
+                .map_err(|e| format!("Failed to copy {} to {}: {}", entry.path().display(), target.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks the destination can take the database: not inside the current one
+/// (or the other way round), empty if it exists, writable and with room.
+fn validate_destination(source: &Path, destination: &Path, needed_bytes: u64) -> Result<(), String> {
+    if !destination.is_absolute() {
+        return Err(format!("Database location must be an absolute path: {}", destination.display()));
+    }
+    if destination.starts_with(source) || source.starts_with(destination) {
+        return Err(format!(
+            "{} overlaps the current database at {}",
+            destination.display(),
+            source.display()
+        ));
+    }
+    if destination.exists() {
+        let mut entries = fs::read_dir(destination).map_err(|e| format!("Cannot read {}: {}", destination.display(), e))?;
+        if entries.next().is_some() {
+            return Err(format!("{} is not empty", destination.display()));
+        }
+    }
+    fs::create_dir_all(destination).map_err(|e| format!("Cannot create {}: {}", destination.display(), e))?;
+
+    let probe = destination.join(".write_test");
+    fs::write(&probe, b"ok").map_err(|e| format!("{} is not writable: {}", destination.display(), e))?;
+    let _ = fs::remove_file(&probe);
+
+    let available = fs2::available_space(destination)
+        .map_err(|e| format!("Cannot determine free space on {}: {}", destination.display(), e))?;
+    if available < needed_bytes + FREE_SPACE_MARGIN_BYTES {
+        return Err(format!(
+            "Not enough space on {}: {} bytes free, {} needed",
+            destination.display(),
+            available,
+            needed_bytes + FREE_SPACE_MARGIN_BYTES
+        ));
+    }
+    Ok(())
+}
+
+/// Copies the database to `destination`, verifies the copy and then points the
+/// `db_location` setting at it. Nothing writes to the database meanwhile:
+/// indexing is held off, new connections wait until the move is over, and
+/// watchers queue their changes and reopen their table at the new location
+/// before applying them. The old copy is only deleted once the new one is
+/// verified and in use; any failure before that removes the partial copy and
+/// leaves the database where it was.
+pub async fn move_database(destination: &Path) -> Result<DatabaseMoveReport, String> {
+    let _slot = acquire_indexing_slot("database move", false).await?;
+    // Dropped last, so the queued changes are applied once connections are back
+    let _watchers = hold_watchers();
+    let db_lock = lock_db_for_move().await;
+    let source = get_db_path().map_err(|e| e.to_string())?;
+    let destination = destination.to_path_buf();
+
+    let copy_source = source.clone();
+    let copy_destination = destination.clone();
+    let (files_copied, bytes_copied) = tokio::task::spawn_blocking(move || {
+        let (files, bytes) = dir_footprint(&copy_source)?;
+        validate_destination(&copy_source, &copy_destination, bytes)?;
+        info!("Copying {} database files ({} bytes) to {}", files, bytes, copy_destination.display());
+        let copied = copy_dir(&copy_source, &copy_destination).and_then(|_| {
+            let copied = dir_footprint(&copy_destination)?;
+            if copied != (files, bytes) {
+                return Err(format!(
+                    "Copy mismatch: {} files / {} bytes copied, expected {} / {}",
+                    copied.0, copied.1, files, bytes
+                ));
+            }
+            Ok(copied)
+        });
+        if copied.is_err() {
+            discard_partial_copy(&copy_destination);
+        }
+        copied
+    })
+    .await
+    .map_err(|e| format!("Database copy task failed: {}", e))??;
+
+    let previous = current_settings();
+    let mut settings = previous.clone();
+    settings.db_location = Some(destination.clone());
+    if let Err(e) = update_settings(settings) {
+        discard_partial_copy(&destination);
+        return Err(e);
+    }
+    // Make sure the copy opens, with embeddings the current models can search,
+    // before letting go of the original
+    let reopened = match connect_db_with_path(&destination.to_string_lossy()).await {
+        Ok(conn) => validate_embedding_dimensions(&conn).await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = reopened {
+        warn!("Moved database failed to open, reverting: {}", e);
+        update_settings(previous)?;
+        discard_partial_copy(&destination);
+        return Err(format!("Moved database failed to open: {}", e));
+    }
+    bump_db_location_generation();
+    bump_index_version();
+    drop(db_lock);
+
+    let source_removed = match fs::remove_dir_all(&source) {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Database moved but the old copy at {} remains: {}", source.display(), e);
+            false
+        }
+    };
+    info!("Moved database from {} to {}", source.display(), destination.display());
+    Ok(DatabaseMoveReport {
+        from: source.display().to_string(),
+        to: destination.display().to_string(),
+        files_copied,
+        bytes_copied,
+        source_removed,
+    })
+}
+
+fn discard_partial_copy(destination: &Path) {
+    if let Err(e) = fs::remove_dir_all(destination) {
+        warn!("Failed to clean up partial database copy at {}: {}", destination.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_copy_dir_preserves_footprint_and_rejects_overlap() {
+        let source = tempdir().unwrap();
+        fs::create_dir_all(source.path().join("text.lance/data")).unwrap();
+        fs::write(source.path().join("text.lance/data/0.lance"), b"rows").unwrap();
+        fs::write(source.path().join("text.lance/_latest.manifest"), b"manifest").unwrap();
+
+        let target = tempdir().unwrap();
+        let destination = target.path().join("lancedb");
+        let (files, bytes) = dir_footprint(source.path()).unwrap();
+        validate_destination(source.path(), &destination, bytes).unwrap();
+        copy_dir(source.path(), &destination).unwrap();
+        assert_eq!(dir_footprint(&destination).unwrap(), (files, bytes));
+        assert_eq!(files, 2);
+
+        assert!(validate_destination(source.path(), &source.path().join("nested"), 0).is_err());
+        // The copy is there now, so the same destination isn't empty any more
+        assert!(validate_destination(source.path(), &destination, 0).is_err());
+    }
+}
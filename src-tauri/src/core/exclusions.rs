@@ -0,0 +1,105 @@
+use crate::core::json_store::{load_json_or_default, save_json_pretty};
+use crate::db::get_app_data_dir;
+use log::info;
+use once_cell::sync::Lazy;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+pub(crate) const EXCLUSIONS_FILE_NAME: &str = "excluded_paths.json";
+
+/// Folders and files the user asked never to index, as absolute paths
+static EXCLUDED_PATHS: Lazy<RwLock<Vec<PathBuf>>> = Lazy::new(|| RwLock::new(load_exclusions()));
+
+pub(crate) fn exclusions_file_path() -> Result<PathBuf, String> {
+    get_app_data_dir()
+        .map(|dir| dir.join(EXCLUSIONS_FILE_NAME))
+        .map_err(|e| format!("Failed to resolve exclusions path: {}", e))
+}
+
+/// Reads the stored exclusions, starting empty if the file is missing or unreadable
+fn load_exclusions() -> Vec<PathBuf> {
+    load_json_or_default(exclusions_file_path(), "exclusions")
+}
+
+/// Re-reads the exclusions from disk, e.g. after they were replaced by a config import
+pub fn reload_exclusions() {
+    let paths = load_exclusions();
+    if let Ok(mut excluded) = EXCLUDED_PATHS.write() {
+        *excluded = paths;
+    }
+}
+
+fn save_exclusions(paths: &[PathBuf]) -> Result<(), String> {
+    save_json_pretty(&exclusions_file_path()?, paths, "exclusions")
+}
+
+/// Whether `path` is one of `exclusions` or lies inside one
+fn is_excluded_by(path: &Path, exclusions: &[PathBuf]) -> bool {
+    exclusions.iter().any(|excluded| path.starts_with(excluded))
+}
+
+fn validated(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path.trim());
+    if !path.is_absolute() {
+        return Err(format!("Excluded paths must be absolute: {}", path.display()));
+    }
+    // Components drop trailing separators, so "/a/b/" and "/a/b" are the same entry
+    Ok(path.components().collect())
+}
+
+/// Stops `path` and everything under it from being indexed.
+/// Returns false if it was already excluded.
+pub fn exclude_path(path: &str) -> Result<bool, String> {
+    let path = validated(path)?;
+    let mut paths = EXCLUDED_PATHS.write().map_err(|_| "Exclusions lock poisoned".to_string())?;
+    if paths.contains(&path) {
+        return Ok(false);
+    }
+    paths.push(path.clone());
+    save_exclusions(&paths)?;
+    info!("Excluded {} from indexing", path.display());
+    Ok(true)
+}
+
+/// Allows `path` to be indexed again. Returns false if it wasn't excluded.
+pub fn include_path(path: &str) -> Result<bool, String> {
+    let path = validated(path)?;
+    let mut paths = EXCLUDED_PATHS.write().map_err(|_| "Exclusions lock poisoned".to_string())?;
+    let before = paths.len();
+    paths.retain(|excluded| *excluded != path);
+    if paths.len() == before {
+        return Ok(false);
+    }
+    save_exclusions(&paths)?;
+    info!("Removed indexing exclusion for {}", path.display());
+    Ok(true)
+}
+
+/// Whether `path` is excluded, directly or through an excluded parent folder
+pub fn is_path_excluded(path: &Path) -> bool {
+    EXCLUDED_PATHS
+        .read()
+        .map(|paths| !paths.is_empty() && is_excluded_by(path, &paths))
+        .unwrap_or(false)
+}
+
+/// Every excluded path, in the order they were added
+pub fn excluded_paths() -> Vec<PathBuf> {
+    EXCLUDED_PATHS.read().map(|paths| paths.clone()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclusion_covers_subtree_only() {
+        let exclusions = vec![validated("/home/me/private/").unwrap()];
+        assert!(is_excluded_by(Path::new("/home/me/private"), &exclusions));
+        assert!(is_excluded_by(Path::new("/home/me/private/taxes/2024.pdf"), &exclusions));
+        // A sibling sharing the name as a prefix is not inside the excluded folder
+        assert!(!is_excluded_by(Path::new("/home/me/private-notes/a.txt"), &exclusions));
+        assert!(!is_excluded_by(Path::new("/home/me"), &exclusions));
+        assert!(validated("relative/path").is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use crate::core::json_store::{load_json_or_default, save_json};
+use crate::db::get_app_data_dir;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const FAVORITES_FILE_NAME: &str = "favorites.json";
+
+/// Score multiplier for favorites in searches that boost them, unless the
+/// settings say otherwise
+pub const DEFAULT_FAVORITE_BOOST: f32 = 1.5;
+
+/// A file or folder the user starred
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FavoriteEntry {
+    pub path: String,
+    /// Milliseconds since the Unix epoch
+    pub added_at: u64,
+}
+
+/// Most recently starred first
+static FAVORITES: Lazy<Mutex<Vec<FavoriteEntry>>> = Lazy::new(|| Mutex::new(load_favorites()));
+
+/// Bumped on every change, so cached searches that used favorites go stale
+static FAVORITES_VERSION: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn favorites_file_path() -> Result<PathBuf, String> {
+    get_app_data_dir()
+        .map(|dir| dir.join(FAVORITES_FILE_NAME))
+        .map_err(|e| format!("Failed to resolve favorites path: {}", e))
+}
+
+/// Reads the stored favorites, starting empty if the file is missing or unreadable
+fn load_favorites() -> Vec<FavoriteEntry> {
+    load_json_or_default(favorites_file_path(), "favorites")
+}
+
+/// Re-reads the favorites from disk, e.g. after they were replaced by a config import
+pub fn reload_favorites() {
+    let entries = load_favorites();
+    if let Ok(mut favorites) = FAVORITES.lock() {
+        *favorites = entries;
+        FAVORITES_VERSION.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn save_favorites(entries: &[FavoriteEntry]) -> Result<(), String> {
+    save_json(&favorites_file_path()?, entries, "favorites")
+}
+
+/// Puts `path` first unless it is already a favorite. Returns whether it was added.
+fn insert_entry(entries: &mut Vec<FavoriteEntry>, path: &str, added_at: u64) -> bool {
+    if entries.iter().any(|entry| entry.path == path) {
+        return false;
+    }
+    entries.insert(
+        0,
+        FavoriteEntry {
+            path: path.to_string(),
+            added_at,
+        },
+    );
+    true
+}
+
+/// Applies `change` to the favorites and saves them when it reports a change
+fn update_favorites(change: impl FnOnce(&mut Vec<FavoriteEntry>) -> bool) -> Result<bool, String> {
+    let mut entries = FAVORITES.lock().map_err(|_| "Favorites lock poisoned".to_string())?;
+    let mut updated = entries.clone();
+    if !change(&mut updated) {
+        return Ok(false);
+    }
+    save_favorites(&updated)?;
+    *entries = updated;
+    FAVORITES_VERSION.fetch_add(1, Ordering::SeqCst);
+    Ok(true)
+}
+
+/// Stars `path`. Returns false when it already was.
+pub fn add_favorite(path: &str) -> Result<bool, String> {
+    let added_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    update_favorites(|entries| insert_entry(entries, path, added_at))
+}
+
+/// Unstars `path`. Returns false when it wasn't a favorite.
+pub fn remove_favorite(path: &str) -> Result<bool, String> {
+    update_favorites(|entries| {
+        let before = entries.len();
+        entries.retain(|entry| entry.path != path);
+        entries.len() != before
+    })
+}
+
+/// Every favorite, most recently starred first
+pub fn list_favorites() -> Vec<FavoriteEntry> {
+    FAVORITES.lock().map(|entries| entries.clone()).unwrap_or_default()
+}
+
+pub fn favorite_paths() -> HashSet<String> {
+    FAVORITES
+        .lock()
+        .map(|entries| entries.iter().map(|entry| entry.path.clone()).collect())
+        .unwrap_or_default()
+}
+
+/// Changes whenever the favorites do
+pub fn favorites_version() -> u64 {
+    FAVORITES_VERSION.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_entry_keeps_one_entry_per_path() {
+        let mut entries = Vec::new();
+        assert!(insert_entry(&mut entries, "/a.txt", 1));
+        assert!(insert_entry(&mut entries, "/b.txt", 2));
+        assert!(!insert_entry(&mut entries, "/a.txt", 3));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0], FavoriteEntry { path: "/b.txt".into(), added_at: 2 });
+        assert_eq!(entries[1].added_at, 1, "Re-adding keeps the original time");
+    }
+}
@@ -2,10 +2,12 @@ use super::error::{map_io_error, FileSystemError};
 use super::models::FileInfo;
 use crate::commands::fs_commands::{ // Import helpers from commands module
     get_thumbnail_cache_dir,
-    hash_path_and_mtime,
     is_thumbnailable,
     generate_thumbnail_task,
 };
+use crate::core::file_types::file_type_info;
+use crate::core::thumbnail_cache::hash_path_and_mtime;
+use crate::core::settings::current_settings;
 use chrono::{DateTime, Utc}; // Import chrono
 use mime_guess; // Import mime_guess
 use std::path::{Path, PathBuf}; // Added PathBuf here
@@ -13,11 +15,17 @@ use std::time::SystemTime; // Still need this for conversion
 use tokio::fs;
 use tauri::AppHandle; // Import AppHandle
 
+/// Whether an entry named `name` is shown and indexed. Hidden (dot) entries
+/// only are when `include_hidden` is set.
+pub fn is_entry_visible(name: &str, include_hidden: bool) -> bool {
+    include_hidden || !name.starts_with('.')
+}
+
 // Helper function to determine file type string
 // src-tauri/src/core/file_system.rs
 
 // Helper function to determine file type string
-fn get_file_type(path: &Path, is_dir: bool) -> String {
+pub(crate) fn get_file_type(path: &Path, is_dir: bool) -> String {
     if is_dir {
         return "Directory".to_string();
     }
@@ -44,17 +52,62 @@ fn get_file_type(path: &Path, is_dir: bool) -> String {
     // No .unwrap_or_else needed here
 }
 
+/// Symlink-aware metadata for a single directory entry
+struct EntryMetadata {
+    /// Metadata of the link target, or of the link itself when the target is missing
+    metadata: std::fs::Metadata,
+    is_symlink: bool,
+    symlink_target: Option<String>,
+}
+
+/// Reads an entry's metadata without silently following symbolic links.
+/// Broken links are still returned, using the link's own metadata.
+async fn entry_metadata(path: &Path) -> std::io::Result<EntryMetadata> {
+    let link_metadata = fs::symlink_metadata(path).await?;
+    if !link_metadata.file_type().is_symlink() {
+        return Ok(EntryMetadata {
+            metadata: link_metadata,
+            is_symlink: false,
+            symlink_target: None,
+        });
+    }
+
+    let symlink_target = fs::read_link(path)
+        .await
+        .ok()
+        .map(|target| target.to_string_lossy().to_string());
+    let metadata = match fs::metadata(path).await {
+        Ok(target_metadata) => target_metadata,
+        Err(_) => link_metadata, // Broken link: best-effort metadata from the link itself
+    };
+    Ok(EntryMetadata {
+        metadata,
+        is_symlink: true,
+        symlink_target,
+    })
+}
+
 /// Lists the files and directories directly within the given path.
 /// Includes metadata and potentially triggers background thumbnail generation.
 pub async fn list_directory(
     path: &Path, 
     app_handle: AppHandle // Pass AppHandle for cache dir and task spawning
+) -> Result<Vec<FileInfo>, FileSystemError> {
+    list_directory_entries(path, current_settings().include_hidden, Some(&app_handle)).await
+}
+
+/// [`list_directory`] with the hidden-entry choice passed in. Thumbnails are
+/// only looked up and generated when an `app_handle` is given.
+pub(crate) async fn list_directory_entries(
+    path: &Path,
+    include_hidden: bool,
+    app_handle: Option<&AppHandle>,
 ) -> Result<Vec<FileInfo>, FileSystemError> {
     let path_str = path.to_string_lossy().to_string();
 
     // Provide explicit type annotation for the Result
-    let cache_dir_result: Result<PathBuf, crate::commands::fs_commands::LocationStorageError> 
-        = get_thumbnail_cache_dir(&app_handle);
+    let cache_dir_result: Option<Result<PathBuf, crate::commands::fs_commands::LocationStorageError>>
+        = app_handle.map(get_thumbnail_cache_dir);
 
     // 1. Check if path exists and is a directory (no change here)
     let dir_metadata = fs::metadata(path)
@@ -89,9 +142,12 @@ pub async fn list_directory(
                         continue; // Skip this entry and continue the loop
                     }
                 };
+                if !is_entry_visible(&file_name, include_hidden) {
+                    continue;
+                }
 
-                match entry.metadata().await {
-                    Ok(metadata) => {
+                match entry_metadata(&entry_path).await {
+                    Ok(EntryMetadata { metadata, is_symlink, symlink_target }) => {
                         let is_directory = metadata.is_dir();
                         let modified: Option<DateTime<Utc>> =
                             metadata.modified().ok().map(DateTime::<Utc>::from);
@@ -108,7 +164,7 @@ pub async fn list_directory(
                         
                         // Thumbnail logic
                         if !is_directory && is_thumbnailable(&file_type) {
-                            if let Ok(ref cache_dir) = cache_dir_result {
+                            if let (Some(app_handle), Some(Ok(cache_dir))) = (app_handle, &cache_dir_result) {
                                 let hash = hash_path_and_mtime(&entry_path, modified_sys_time);
                                 let cache_file_name = format!("{}.jpg", hash);
                                 let potential_cache_path = cache_dir.join(&cache_file_name);
@@ -128,7 +184,7 @@ pub async fn list_directory(
                                         task_app_handle
                                     ));
                                 }
-                            } else {
+                            } else if let Some(Err(_)) = cache_dir_result {
                                 // Log error if cache dir couldn't be determined
                                 tracing::error!("Could not get thumbnail cache directory.");
                             }
@@ -142,6 +198,9 @@ pub async fn list_directory(
                             modified,
                             file_type,
                             thumbnail_path, // Add the thumbnail path
+                            is_symlink,
+                            symlink_target,
+                            icon_key: file_type_info(&entry_path, is_directory).icon_key,
                         });
                     }
                     Err(e) => {
@@ -329,6 +388,53 @@ mod tests {
         // assert_eq!(item_x.file_type, "Directory");
     }
 
+    #[tokio::test]
+    async fn test_hidden_entries_listed_only_when_included() {
+        assert!(is_entry_visible("notes.txt", false));
+        assert!(!is_entry_visible(".env", false));
+        assert!(!is_entry_visible(".git", false));
+        assert!(is_entry_visible(".env", true));
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        create_dummy_file_with_size(&temp_dir.path().join("notes.txt"), 10).await;
+        create_dummy_file_with_size(&temp_dir.path().join(".env"), 10).await;
+        create_dummy_dir(&temp_dir.path().join(".git")).await;
+
+        let names = |items: Vec<FileInfo>| items.into_iter().map(|item| item.name).collect::<Vec<_>>();
+        let visible = list_directory_entries(temp_dir.path(), false, None).await.unwrap();
+        assert_eq!(names(visible), vec!["notes.txt"]);
+        let mut all = names(list_directory_entries(temp_dir.path(), true, None).await.unwrap());
+        all.sort();
+        assert_eq!(all, vec![".env", ".git", "notes.txt"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_entry_metadata_detects_symlinks() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let path = temp_dir.path();
+
+        let target = path.join("target.txt");
+        create_dummy_file_with_size(&target, 42).await;
+        let link = path.join("link.txt");
+        std::os::unix::fs::symlink(&target, &link).expect("Failed to create symlink");
+        let broken = path.join("broken.txt");
+        std::os::unix::fs::symlink(path.join("missing.txt"), &broken).expect("Failed to create symlink");
+
+        let regular = entry_metadata(&target).await.expect("Regular file metadata failed");
+        assert!(!regular.is_symlink);
+        assert!(regular.symlink_target.is_none());
+
+        let linked = entry_metadata(&link).await.expect("Symlink metadata failed");
+        assert!(linked.is_symlink);
+        assert_eq!(linked.symlink_target.as_deref(), Some(target.to_str().unwrap()));
+        assert_eq!(linked.metadata.len(), 42, "Metadata should describe the link target");
+
+        let dangling = entry_metadata(&broken).await.expect("Broken symlink should still be listed");
+        assert!(dangling.is_symlink);
+        assert!(!dangling.metadata.is_file());
+    }
+
     // Keep error tests (NotFound, NotADirectory) - they don't need metadata checks
     #[tokio::test]
     async fn test_list_directory_not_found() {
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)] // Ensure Clone, etc. are present if needed
+pub enum FileCategory {
+    Document,
+    Image,
+    Video,
+    Audio,
+    Archive,
+    Code,
+    Other
+}
+
+/// Category of a file from its extension
+pub(crate) fn categorize_file(path: &PathBuf) -> FileCategory {
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        match extension.to_lowercase().as_str() {
+            "pdf" | "doc" | "docx" | "txt" | "rtf" | "odt" | "md" | "csv" | "xls" | "xlsx" | "ppt" | "pptx" => FileCategory::Document,
+            "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "webp" | "svg" | "ico" | "heic" => FileCategory::Image,
+            "mp4" | "mkv" | "avi" | "mov" | "wmv" | "flv" | "webm" | "m4v" | "mpg" | "mpeg" => FileCategory::Video,
+            "mp3" | "wav" | "ogg" | "flac" | "aac" | "m4a" | "wma" => FileCategory::Audio,
+            "zip" | "rar" | "7z" | "tar" | "gz" | "bz2" | "xz" | "iso" => FileCategory::Archive,
+            "py" | "js" | "jsx" | "ts" | "tsx" | "html" | "css" | "scss" | "json" | "rs" | "go" | "java" | "cpp" | "c" | "h" | "cs" | "php" | "rb" => FileCategory::Code,
+            _ => FileCategory::Other,
+        }
+    } else {
+        FileCategory::Other
+    }
+}
+
+/// Normalized presentation of a file's type, so icon mapping lives in one place
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileTypeInfo {
+    pub category: FileCategory,
+    /// Human-readable type, e.g. "PDF document"
+    pub label: String,
+    /// Stable key the frontend maps to an icon, e.g. "pdf" or "folder"
+    pub icon_key: String,
+}
+
+/// Describes how a file type should be presented; directories get the "folder" icon
+pub(crate) fn file_type_info(path: &Path, is_dir: bool) -> FileTypeInfo {
+    let category = categorize_file(&path.to_path_buf());
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    let (label, icon_key) = if is_dir {
+        ("Folder", "folder")
+    } else {
+        match (&category, extension.as_str()) {
+            (FileCategory::Document, "pdf") => ("PDF document", "pdf"),
+            (FileCategory::Document, "doc" | "docx" | "odt" | "rtf") => ("Word document", "document"),
+            (FileCategory::Document, "xls" | "xlsx" | "csv") => ("Spreadsheet", "spreadsheet"),
+            (FileCategory::Document, "ppt" | "pptx") => ("Presentation", "presentation"),
+            (FileCategory::Document, "md") => ("Markdown", "markdown"),
+            (FileCategory::Document, _) => ("Text document", "text"),
+            (FileCategory::Image, _) => ("Image", "image"),
+            (FileCategory::Video, _) => ("Video", "video"),
+            (FileCategory::Audio, _) => ("Audio", "audio"),
+            (FileCategory::Archive, _) => ("Archive", "archive"),
+            (FileCategory::Code, _) => ("Source code", "code"),
+            (FileCategory::Other, _) => ("File", "file"),
+        }
+    };
+
+    FileTypeInfo {
+        category,
+        label: label.to_string(),
+        icon_key: icon_key.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_type_info_icon_keys() {
+        let pdf = file_type_info(Path::new("/docs/report.PDF"), false);
+        assert_eq!(pdf.category, FileCategory::Document);
+        assert_eq!(pdf.icon_key, "pdf");
+        assert_eq!(file_type_info(Path::new("/src/main.rs"), false).icon_key, "code");
+        assert_eq!(file_type_info(Path::new("/photos"), true).icon_key, "folder");
+        assert_eq!(file_type_info(Path::new("/tmp/unknown.xyz"), false).label, "File");
+    }
+}
@@ -0,0 +1,105 @@
+use super::file_system::get_file_type;
+use super::indexer::walk_entries_to_depth;
+use image::{imageops::FilterType, ImageFormat, RgbImage};
+use log::warn;
+use std::path::{Path, PathBuf};
+
+/// Thumbnails shown in a folder's mosaic
+pub const MOSAIC_TILES: usize = 4;
+
+/// Levels below the folder searched for media; its direct children are level 1
+const MOSAIC_SCAN_DEPTH: usize = 2;
+
+/// Entries looked at before giving up, so huge folders stay cheap
+const MOSAIC_SCAN_LIMIT: usize = 500;
+
+/// Fills the mosaic where tiles don't cover it
+const MOSAIC_BACKGROUND: [u8; 3] = [48, 48, 48];
+
+fn is_media(path: &Path) -> bool {
+    matches!(get_file_type(path, false).as_str(), "Image" | "Video")
+}
+
+/// Up to `MOSAIC_TILES` images and videos from `folder`, its own files first
+/// and then those one folder down, each level in name order. Looks at no more
+/// than `MOSAIC_SCAN_LIMIT` entries. Blocking; run it off the async runtime.
+pub fn pick_mosaic_sources(folder: &Path, follow_symlinks: bool) -> Vec<PathBuf> {
+    let mut candidates: Vec<(usize, PathBuf)> = walk_entries_to_depth(folder, follow_symlinks, MOSAIC_SCAN_DEPTH)
+        .take(MOSAIC_SCAN_LIMIT)
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_media(entry.path()))
+        .map(|entry| (entry.depth(), entry.into_path()))
+        .collect();
+    candidates.sort();
+    candidates.into_iter().take(MOSAIC_TILES).map(|(_, path)| path).collect()
+}
+
+/// Lays the `thumbnails` out in a `size` square (one fills it, two sit side by
+/// side, three or four share a 2x2 grid) and saves it as a JPEG at `dest`.
+/// Thumbnails that can't be read are left out; fails when none can be.
+pub fn compose_mosaic(thumbnails: &[PathBuf], dest: &Path, size: u32) -> Result<(), String> {
+    let tiles: Vec<_> = thumbnails
+        .iter()
+        .take(MOSAIC_TILES)
+        .filter_map(|path| match image::open(path) {
+            Ok(tile) => Some(tile),
+            Err(e) => {
+                warn!("Leaving {} out of the folder mosaic: {}", path.display(), e);
+                None
+            }
+        })
+        .collect();
+    if tiles.is_empty() {
+        return Err("No thumbnails to compose".to_string());
+    }
+
+    let columns = if tiles.len() == 1 { 1 } else { 2 };
+    let rows = if tiles.len() <= 2 { 1 } else { 2 };
+    let (tile_width, tile_height) = (size / columns, size / rows);
+    let mut canvas = RgbImage::from_pixel(size, size, image::Rgb(MOSAIC_BACKGROUND));
+    for (i, tile) in tiles.iter().enumerate() {
+        let tile = tile.resize_to_fill(tile_width, tile_height, FilterType::Triangle).to_rgb8();
+        let (column, row) = (i as u32 % columns, i as u32 / columns);
+        image::imageops::replace(&mut canvas, &tile, (column * tile_width) as i64, (row * tile_height) as i64);
+    }
+
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create cache directory: {}", e))?;
+    }
+    canvas
+        .save_with_format(dest, ImageFormat::Jpeg)
+        .map_err(|e| format!("Failed to save folder mosaic: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_mosaic_prefers_shallow_media_and_composes() {
+        let dir = tempdir().unwrap();
+        let folder = dir.path();
+        fs::create_dir_all(folder.join("nested/deeper")).unwrap();
+        fs::write(folder.join("notes.txt"), "not media").unwrap();
+        for name in ["b.png", "a.png", "nested/c.png", "nested/d.png", "nested/e.png", "nested/deeper/f.png"] {
+            RgbImage::from_pixel(8, 8, image::Rgb([200, 10, 10])).save(folder.join(name)).unwrap();
+        }
+
+        let sources = pick_mosaic_sources(folder, false);
+        let expected: Vec<PathBuf> = ["a.png", "b.png", "nested/c.png", "nested/d.png"]
+            .iter()
+            .map(|name| folder.join(name))
+            .collect();
+        assert_eq!(sources, expected);
+
+        let dest = folder.join("cache/mosaic.jpg");
+        compose_mosaic(&sources, &dest, 64).unwrap();
+        let mosaic = image::open(&dest).unwrap();
+        assert_eq!((mosaic.width(), mosaic.height()), (64, 64));
+
+        let empty = tempdir().unwrap();
+        assert!(pick_mosaic_sources(empty.path(), false).is_empty());
+    }
+}
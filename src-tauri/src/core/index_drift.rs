@@ -0,0 +1,372 @@
+use super::cancellation::CancellationToken;
+use super::indexer::{check_content, is_zero_byte_file, should_stream, walk_entries, ContentCheck};
+use crate::archive::{extract_archive_texts, split_composite_path};
+use crate::core::virtual_content::is_virtual_path;
+use crate::core::tombstones::existing_content_tables;
+use crate::db::{document_hash, list_indexed_documents, DocumentSortBy};
+use crate::extractor::{calculate_file_hash, calculate_hash, extract_text, get_content_type, ContentType};
+use crate::repair_db::indexed_path_exists;
+use lancedb::Connection;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Paths listed per kind of drift; the counts always cover everything
+pub const DRIFT_LIST_LIMIT: usize = 1000;
+
+/// How far the index has drifted from the files under one folder
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexDrift {
+    pub root: String,
+    /// Files indexing would pick up that aren't in the index
+    pub not_indexed: Vec<String>,
+    pub not_indexed_count: usize,
+    /// Index entries whose file (or archive) is gone
+    pub missing_from_disk: Vec<String>,
+    pub missing_from_disk_count: usize,
+    /// Indexed files whose content no longer matches the stored hash
+    pub changed: Vec<String>,
+    pub changed_count: usize,
+    pub unchanged_count: usize,
+    /// The walk was cancelled; the disk side of the report is partial
+    pub cancelled: bool,
+}
+
+/// Outcome of re-hashing the file behind one index entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileVerification {
+    pub path: String,
+    /// The file still hashes to what the index stored
+    pub matches: bool,
+    /// Hash stored in the index; `None` when the path isn't indexed
+    pub stored: Option<String>,
+    /// Hash of the file today; `None` when it is gone or indexing would skip it
+    pub actual: Option<String>,
+    pub file_exists: bool,
+}
+
+/// Index entries whose file no longer hashes to the stored value, from
+/// corruption or edits made while nothing was watching
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexVerification {
+    pub checked_count: usize,
+    pub matching_count: usize,
+    pub mismatched: Vec<FileVerification>,
+    pub mismatched_count: usize,
+    /// Index entries whose file (or archive) is gone
+    pub missing: Vec<String>,
+    pub missing_count: usize,
+    /// Stopped before every entry was checked
+    pub cancelled: bool,
+}
+
+/// Settings the comparison needs to hash files the way indexing does
+#[derive(Debug, Clone, Copy)]
+pub struct DriftOptions {
+    pub follow_symlinks: bool,
+    pub min_text_chars: usize,
+    pub streaming_threshold_bytes: u64,
+}
+
+fn push_limited(list: &mut Vec<String>, count: &mut usize, path: String) {
+    *count += 1;
+    if list.len() < DRIFT_LIST_LIMIT {
+        list.push(path);
+    }
+}
+
+/// The hash indexing would store for `path` today, or `None` when it would
+/// skip the file (unsupported, empty or too short)
+fn current_hash(path: &Path, options: &DriftOptions) -> Result<Option<String>, String> {
+    match get_content_type(path) {
+        ContentType::Image => calculate_file_hash(path).map(Some).map_err(|e| e.to_string()),
+        // Large plain text is stored under the hash of its raw bytes
+        ContentType::Text if should_stream(path, options.streaming_threshold_bytes) => {
+            calculate_file_hash(path).map(Some).map_err(|e| e.to_string())
+        }
+        ContentType::Text if is_zero_byte_file(path) => Ok(None),
+        ContentType::Text => {
+            let extraction = extract_text(path).map_err(|e| e.to_string())?;
+            Ok((check_content(&extraction.text, options.min_text_chars) == ContentCheck::Meaningful)
+                .then(|| calculate_hash(&extraction.text)))
+        }
+        ContentType::Unsupported => Ok(None),
+    }
+}
+
+/// Hashes of the members of an archive as indexing stores them, keyed by
+/// composite path
+fn archive_member_hashes(archive: &Path) -> Result<HashMap<String, String>, String> {
+    let members = extract_archive_texts(archive).map_err(|e| e.to_string())?;
+    Ok(members
+        .into_iter()
+        .map(|member| (member.composite_path, calculate_hash(&member.text)))
+        .collect())
+}
+
+fn verification(path: &str, stored: Option<&str>, actual: Option<String>, file_exists: bool) -> FileVerification {
+    FileVerification {
+        path: path.to_string(),
+        matches: stored.is_some() && stored == actual.as_deref(),
+        stored: stored.map(str::to_string),
+        actual,
+        file_exists,
+    }
+}
+
+/// Re-hashes the file behind the index entry `path` the way indexing would
+/// and compares it with `stored`. Blocking; run it off the async runtime.
+pub fn verify_file(path: &str, stored: Option<&str>, options: &DriftOptions) -> FileVerification {
+    let file_exists = !is_virtual_path(path) && indexed_path_exists(path);
+    let actual = if !file_exists {
+        None
+    } else if let Some((archive, _)) = split_composite_path(path) {
+        archive_member_hashes(Path::new(archive))
+            .ok()
+            .and_then(|mut hashes| hashes.remove(path))
+    } else {
+        current_hash(Path::new(path), options).unwrap_or_else(|e| {
+            debug!("Cannot hash {} to verify it: {}", path, e);
+            None
+        })
+    };
+    verification(path, stored, actual, file_exists)
+}
+
+/// Re-hashes every entry of `indexed` (path to stored hash) and reports those
+/// that no longer match. Archives are read once for all their members.
+/// Blocking; run it off the async runtime.
+pub fn verify_index(
+    indexed: &HashMap<String, String>,
+    options: &DriftOptions,
+    token: &CancellationToken,
+) -> IndexVerification {
+    let mut report = IndexVerification::default();
+    let mut paths: Vec<&String> = indexed.keys().collect();
+    paths.sort();
+    let mut archive_hashes: HashMap<&str, HashMap<String, String>> = HashMap::new();
+
+    for path in paths {
+        if token.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+        let stored = Some(indexed[path].as_str());
+        let checked = match split_composite_path(path) {
+            Some((archive, _)) if Path::new(archive).exists() => {
+                let hashes = archive_hashes
+                    .entry(archive)
+                    .or_insert_with(|| archive_member_hashes(Path::new(archive)).unwrap_or_default());
+                verification(path, stored, hashes.get(path.as_str()).cloned(), true)
+            }
+            _ => verify_file(path, stored, options),
+        };
+
+        report.checked_count += 1;
+        if checked.matches {
+            report.matching_count += 1;
+        } else if !checked.file_exists {
+            push_limited(&mut report.missing, &mut report.missing_count, checked.path);
+        } else {
+            report.mismatched_count += 1;
+            if report.mismatched.len() < DRIFT_LIST_LIMIT {
+                report.mismatched.push(checked);
+            }
+        }
+    }
+    report
+}
+
+/// Compares the files under `root` with the index entries under it.
+/// Files are extracted and hashed the way indexing would, so this reads every
+/// indexable file; archive members are only checked for their archive.
+/// Blocking; run it off the async runtime.
+pub fn compare_with_disk(
+    root: &Path,
+    indexed: &HashMap<String, String>,
+    options: &DriftOptions,
+    token: &CancellationToken,
+) -> IndexDrift {
+    let mut drift = IndexDrift {
+        root: root.to_string_lossy().into_owned(),
+        ..Default::default()
+    };
+    let archives: HashSet<&str> = indexed
+        .keys()
+        .filter_map(|path| split_composite_path(path).map(|(archive, _)| archive))
+        .collect();
+
+    for entry in walk_entries(root, options.follow_symlinks) {
+        if token.is_cancelled() {
+            drift.cancelled = true;
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Skipping unreadable entry while comparing {}: {}", root.display(), e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path();
+        let path_str = path.to_string_lossy().into_owned();
+        if archives.contains(path_str.as_str()) {
+            drift.unchanged_count += 1;
+            continue;
+        }
+        let hash = match current_hash(path, options) {
+            Ok(hash) => hash,
+            Err(e) => {
+                // Indexing would fail on it too; only an indexed copy is worth reporting
+                debug!("Cannot hash {} for drift: {}", path.display(), e);
+                None
+            }
+        };
+        match (indexed.get(&path_str), hash) {
+            (Some(stored), Some(hash)) if *stored == hash => drift.unchanged_count += 1,
+            (Some(_), _) => push_limited(&mut drift.changed, &mut drift.changed_count, path_str),
+            (None, Some(_)) => push_limited(&mut drift.not_indexed, &mut drift.not_indexed_count, path_str),
+            (None, None) => {}
+        }
+    }
+
+    let mut missing: Vec<&String> = indexed.keys().filter(|path| !indexed_path_exists(path)).collect();
+    missing.sort();
+    for path in missing {
+        push_limited(&mut drift.missing_from_disk, &mut drift.missing_from_disk_count, path.clone());
+    }
+    drift
+}
+
+/// Index entries (path to stored hash) backed by a file; virtual content has none
+pub async fn indexed_files(conn: &Connection) -> Result<HashMap<String, String>, String> {
+    let documents = list_indexed_documents(conn, None, DocumentSortBy::Path)
+        .await
+        .map_err(|e| format!("Failed to list indexed documents: {}", e))?;
+    Ok(documents
+        .into_iter()
+        .filter(|doc| !is_virtual_path(&doc.file_path))
+        .map(|doc| (doc.file_path, doc.content_hash))
+        .collect())
+}
+
+/// Stored hash of one indexed file, looked up by path in each content table
+pub async fn indexed_hash(conn: &Connection, path: &str) -> Result<Option<String>, String> {
+    if is_virtual_path(path) {
+        return Ok(None);
+    }
+    for (table, _) in existing_content_tables(conn).await? {
+        if let Some(hash) = document_hash(&table, path)
+            .await
+            .map_err(|e| format!("Failed to look up {}: {}", path, e))?
+        {
+            return Ok(Some(hash));
+        }
+    }
+    Ok(None)
+}
+
+/// Index entries (path to stored hash) for files under `root`. Archive members
+/// count as under it when their archive is; virtual content never does.
+pub async fn indexed_under(conn: &Connection, root: &Path) -> Result<HashMap<String, String>, String> {
+    let mut indexed = indexed_files(conn).await?;
+    indexed.retain(|path, _| {
+        let on_disk = split_composite_path(path).map_or(path.as_str(), |(archive, _)| archive);
+        Path::new(on_disk).starts_with(root)
+    });
+    Ok(indexed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{connect_db_with_path, open_or_create_text_table, upsert_document, TestDb, TEXT_EMBEDDING_DIM};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_drift_sorts_files_into_new_missing_and_changed() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let table = open_or_create_text_table(&conn).await.unwrap();
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("docs");
+        fs::create_dir_all(&root).unwrap();
+        let options = DriftOptions {
+            follow_symlinks: false,
+            min_text_chars: 10,
+            streaming_threshold_bytes: u64::MAX,
+        };
+
+        let same = root.join("same.txt");
+        let edited = root.join("edited.txt");
+        let fresh = root.join("fresh.txt");
+        let gone = root.join("gone.txt");
+        for file in [&same, &edited, &fresh] {
+            fs::write(file, format!("Quarterly notes kept in {}", file.display())).unwrap();
+        }
+        fs::write(root.join("tiny.txt"), "hi").unwrap();
+        let same_hash = current_hash(&same, &options).unwrap().unwrap();
+
+        let embedding = vec![vec![0.1f32; TEXT_EMBEDDING_DIM as usize]];
+        for (path, hash) in [(&same, same_hash.as_str()), (&edited, "stale_hash"), (&gone, "gone_hash")] {
+            upsert_document(&table, &path.to_string_lossy(), hash, &embedding, None).await.unwrap();
+        }
+        upsert_document(&table, "/elsewhere/other.txt", "other_hash", &embedding, None).await.unwrap();
+
+        let indexed = indexed_under(&conn, &root).await.unwrap();
+        assert_eq!(indexed.len(), 3);
+        assert_eq!(indexed_hash(&conn, &same.to_string_lossy()).await.unwrap(), Some(same_hash.clone()));
+        assert_eq!(indexed_hash(&conn, &fresh.to_string_lossy()).await.unwrap(), None);
+        let drift = compare_with_disk(&root, &indexed, &options, &CancellationToken::none());
+        assert_eq!(drift.not_indexed, vec![fresh.to_string_lossy().to_string()]);
+        assert_eq!(drift.changed, vec![edited.to_string_lossy().to_string()]);
+        assert_eq!(drift.missing_from_disk, vec![gone.to_string_lossy().to_string()]);
+        assert_eq!(
+            (drift.not_indexed_count, drift.changed_count, drift.missing_from_disk_count, drift.unchanged_count),
+            (1, 1, 1, 1)
+        );
+    }
+
+    #[test]
+    fn test_verify_index_reports_mismatched_and_missing_files() {
+        let dir = tempdir().unwrap();
+        let options = DriftOptions {
+            follow_symlinks: false,
+            min_text_chars: 10,
+            streaming_threshold_bytes: u64::MAX,
+        };
+        let intact = dir.path().join("intact.txt");
+        let corrupted = dir.path().join("corrupted.txt");
+        let gone = dir.path().join("gone.txt");
+        fs::write(&intact, "Minutes of the planning meeting").unwrap();
+        fs::write(&corrupted, "Budget figures for the third quarter").unwrap();
+        let stored_corrupted = current_hash(&corrupted, &options).unwrap().unwrap();
+        fs::write(&corrupted, "Budget figures for the thjrd quarter").unwrap();
+
+        let indexed: HashMap<String, String> = [
+            (intact.to_string_lossy().to_string(), current_hash(&intact, &options).unwrap().unwrap()),
+            (corrupted.to_string_lossy().to_string(), stored_corrupted.clone()),
+            (gone.to_string_lossy().to_string(), "gone_hash".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        let report = verify_index(&indexed, &options, &CancellationToken::none());
+        assert_eq!((report.checked_count, report.matching_count), (3, 1));
+        assert_eq!(report.missing, vec![gone.to_string_lossy().to_string()]);
+        assert_eq!(report.mismatched_count, 1);
+        let mismatch = &report.mismatched[0];
+        assert_eq!(mismatch.stored.as_deref(), Some(stored_corrupted.as_str()));
+        assert!(mismatch.file_exists && mismatch.actual.is_some() && !mismatch.matches);
+
+        let intact_path = intact.to_string_lossy().to_string();
+        assert!(verify_file(&intact_path, indexed.get(&intact_path).map(String::as_str), &options).matches);
+        let unindexed = verify_file(&intact_path, None, &options);
+        assert!(!unindexed.matches && unindexed.actual.is_some());
+    }
+}
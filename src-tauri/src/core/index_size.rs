@@ -0,0 +1,261 @@
+use crate::core::db_location::dir_footprint;
+use crate::core::recents::recently_opened;
+use crate::core::settings::current_settings;
+use crate::core::term_frequencies::forget_document_terms;
+use crate::core::tombstones::existing_content_tables;
+use crate::db::{
+    delete_documents, embedding_dimension, get_db_path, list_indexed_documents, DbError, DocumentSortBy,
+    IndexedDocument,
+};
+use lancedb::connection::Connection;
+use lancedb::table::{CompactionOptions, OptimizeAction, Table};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Files indexed between two checks of the on-disk index size
+pub const SIZE_CHECK_INTERVAL: u32 = 50;
+
+/// Rough per-row cost beyond the embedding itself: path, hash and timestamps
+const ROW_OVERHEAD_BYTES: u64 = 256;
+
+/// What to do when indexing would take the index past `max_index_bytes`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexEvictionPolicy {
+    /// Stop indexing with an `IndexSizeLimitReached` error
+    #[default]
+    None,
+    /// Drop the documents indexed longest ago
+    OldestIndexed,
+    /// Drop documents never opened, or opened longest ago, first; ties go by
+    /// indexing time
+    LeastRecentlyOpened,
+}
+
+/// On-disk size of the index against the configured limit
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexSizeUsage {
+    pub used_bytes: u64,
+    /// 0 when the index may grow without bound
+    pub max_bytes: u64,
+}
+
+/// Bytes the database folder takes on disk
+pub fn index_size_bytes() -> Result<u64, String> {
+    let db_path = get_db_path().map_err(|e| e.to_string())?;
+    if !db_path.exists() {
+        return Ok(0);
+    }
+    dir_footprint(&db_path).map(|(_, bytes)| bytes)
+}
+
+pub fn index_size_usage() -> Result<IndexSizeUsage, String> {
+    Ok(IndexSizeUsage {
+        used_bytes: index_size_bytes()?,
+        max_bytes: current_settings().max_index_bytes,
+    })
+}
+
+/// Approximate bytes `chunks` rows of `dimension`-wide embeddings add once stored
+pub fn estimated_row_bytes(chunks: usize, dimension: usize) -> u64 {
+    chunks as u64 * (dimension as u64 * std::mem::size_of::<f32>() as u64 + ROW_OVERHEAD_BYTES)
+}
+
+/// Documents in the order `policy` gives them up. `opened_at` maps paths to
+/// when they were last opened.
+fn eviction_order(
+    mut documents: Vec<IndexedDocument>,
+    policy: IndexEvictionPolicy,
+    opened_at: &HashMap<String, u64>,
+) -> Vec<IndexedDocument> {
+    match policy {
+        IndexEvictionPolicy::None => return Vec::new(),
+        IndexEvictionPolicy::OldestIndexed => {
+            documents.sort_by(|a, b| a.last_modified.cmp(&b.last_modified).then_with(|| a.file_path.cmp(&b.file_path)))
+        }
+        IndexEvictionPolicy::LeastRecentlyOpened => documents.sort_by(|a, b| {
+            let opened = |doc: &IndexedDocument| opened_at.get(&doc.file_path).copied().unwrap_or(0);
+            opened(a)
+                .cmp(&opened(b))
+                .then_with(|| a.last_modified.cmp(&b.last_modified))
+                .then_with(|| a.file_path.cmp(&b.file_path))
+        }),
+    }
+    documents
+}
+
+/// Compacts `tables` and drops the old versions that still hold deleted or
+/// rewritten rows, so their space is returned
+async fn reclaim_space(tables: &[(Table, &'static str)]) {
+    for (table, _) in tables {
+        let compacted = table
+            .optimize(OptimizeAction::Compact {
+                options: CompactionOptions::default(),
+                remap_options: None,
+            })
+            .await;
+        let pruned = match compacted {
+            Ok(_) => table
+                .optimize(OptimizeAction::Prune {
+                    older_than: chrono::Duration::zero(),
+                    delete_unverified: None,
+                })
+                .await
+                .map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = pruned {
+            warn!("Failed to reclaim space in '{}': {}", table.name(), e);
+        }
+    }
+}
+
+/// Whether `document` belongs to the folder being indexed, which eviction
+/// must leave alone
+fn is_protected(document: &IndexedDocument, protected_root: Option<&str>) -> bool {
+    let Some(root) = protected_root else {
+        return false;
+    };
+    document.index_root.as_deref() == Some(root) || Path::new(&document.file_path).starts_with(root)
+}
+
+/// Evicts documents per `policy`, skipping those under `protected_root`,
+/// until about `needed_bytes` are freed. Returns the estimated bytes freed.
+async fn evict_documents(
+    conn: &Connection,
+    tables: &[(Table, &'static str)],
+    policy: IndexEvictionPolicy,
+    needed_bytes: u64,
+    protected_root: Option<&str>,
+) -> Result<u64, String> {
+    let documents = list_indexed_documents(conn, None, DocumentSortBy::Path)
+        .await
+        .map_err(|e| format!("Failed to list indexed documents: {}", e))?;
+    let opened_at: HashMap<String, u64> = recently_opened(usize::MAX)
+        .into_iter()
+        .map(|entry| (entry.path, entry.opened_at))
+        .collect();
+    let mut dimensions = HashMap::new();
+    for (table, content_type) in tables {
+        let schema = table.schema().await.map_err(|e| format!("Failed to read '{}': {}", table.name(), e))?;
+        dimensions.insert(*content_type, embedding_dimension(&schema).unwrap_or_default());
+    }
+
+    // Pick the documents first so each table is touched once
+    let mut freed = 0;
+    let mut evicted: HashMap<String, Vec<String>> = HashMap::new();
+    for document in eviction_order(documents, policy, &opened_at) {
+        if freed >= needed_bytes {
+            break;
+        }
+        if is_protected(&document, protected_root) {
+            continue;
+        }
+        let Some(dimension) = dimensions.get(document.content_type.as_str()) else {
+            continue;
+        };
+        freed += estimated_row_bytes(document.chunk_count, *dimension as usize);
+        evicted.entry(document.content_type).or_default().push(document.file_path);
+    }
+
+    let mut evicted_count = 0;
+    for (table, content_type) in tables {
+        let Some(paths) = evicted.get(*content_type) else {
+            continue;
+        };
+        delete_documents(table, paths)
+            .await
+            .map_err(|e| format!("Failed to evict documents from '{}': {}", table.name(), e))?;
+        for path in paths {
+            forget_document_terms(path);
+        }
+        evicted_count += paths.len();
+    }
+    info!("Evicted {} documents (~{} bytes) to stay under the index size limit", evicted_count, freed);
+    Ok(freed)
+}
+
+/// Makes sure the index can take `pending_bytes` more without passing the
+/// `max_index_bytes` setting, evicting documents outside `protected_root` if
+/// the eviction policy allows. Fails with `IndexSizeLimitReached` when it
+/// can't.
+pub async fn ensure_index_room(conn: &Connection, pending_bytes: u64, protected_root: Option<&str>) -> Result<(), String> {
+    let settings = current_settings();
+    let max_bytes = settings.max_index_bytes;
+    if max_bytes == 0 {
+        return Ok(());
+    }
+    let fits = |used_bytes: u64| used_bytes.saturating_add(pending_bytes) <= max_bytes;
+    if fits(index_size_bytes()?) {
+        return Ok(());
+    }
+
+    // Old versions of rewritten rows count against the limit until they are
+    // compacted away, so reclaim them before measuring for real
+    let tables = existing_content_tables(conn).await?;
+    reclaim_space(&tables).await;
+    let used_bytes = index_size_bytes()?;
+    if fits(used_bytes) {
+        return Ok(());
+    }
+    let limit_reached = |used_bytes| DbError::IndexSizeLimitReached { used_bytes, max_bytes }.to_string();
+    if settings.index_eviction == IndexEvictionPolicy::None {
+        return Err(limit_reached(used_bytes));
+    }
+
+    let excess = used_bytes.saturating_add(pending_bytes) - max_bytes;
+    evict_documents(conn, &tables, settings.index_eviction, excess, protected_root).await?;
+    reclaim_space(&tables).await;
+    let used_bytes = index_size_bytes()?;
+    if !fits(used_bytes) {
+        return Err(limit_reached(used_bytes));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(path: &str, last_modified: i64) -> IndexedDocument {
+        IndexedDocument {
+            file_path: path.to_string(),
+            content_hash: String::new(),
+            last_modified,
+            chunk_count: 1,
+            content_type: "text".to_string(),
+            index_root: None,
+        }
+    }
+
+    #[test]
+    fn test_eviction_order_follows_policy() {
+        let documents = vec![document("/new.txt", 30), document("/old.txt", 10), document("/opened.txt", 20)];
+        let opened_at = HashMap::from([("/opened.txt".to_string(), 5)]);
+        let paths = |docs: Vec<IndexedDocument>| docs.into_iter().map(|d| d.file_path).collect::<Vec<_>>();
+
+        assert_eq!(
+            paths(eviction_order(documents.clone(), IndexEvictionPolicy::OldestIndexed, &opened_at)),
+            vec!["/old.txt", "/opened.txt", "/new.txt"]
+        );
+        assert_eq!(
+            paths(eviction_order(documents.clone(), IndexEvictionPolicy::LeastRecentlyOpened, &opened_at)),
+            vec!["/old.txt", "/new.txt", "/opened.txt"]
+        );
+        assert!(eviction_order(documents, IndexEvictionPolicy::None, &opened_at).is_empty());
+        assert_eq!(estimated_row_bytes(2, 384), 2 * (384 * 4 + ROW_OVERHEAD_BYTES));
+    }
+
+    #[test]
+    fn test_documents_under_the_indexed_root_are_protected() {
+        let mut rooted = document("/elsewhere/a.txt", 10);
+        rooted.index_root = Some("/docs".to_string());
+
+        assert!(is_protected(&document("/docs/notes/a.txt", 10), Some("/docs")));
+        assert!(is_protected(&rooted, Some("/docs")));
+        assert!(!is_protected(&document("/docs-old/a.txt", 10), Some("/docs")));
+        assert!(!is_protected(&document("/docs/a.txt", 10), None));
+    }
+}
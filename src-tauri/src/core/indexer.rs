@@ -1,19 +1,42 @@
 // src-tauri/src/core/indexer.rs
 
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use log::{info, warn, error, debug};
 use serde::{Serialize, Deserialize};
 use crate::db::{
     connect_db, 
+    get_vector_db_stats,
+    list_indexed_documents,
+    DocumentSortBy,
     open_or_create_text_table, 
     open_or_create_image_table,
     open_or_create_amharic_text_table, // Added for Amharic
     upsert_document, 
     upsert_amharic_document, // Added for Amharic
-    upsert_image
+    open_or_create_code_table,
+    upsert_code_document,
+    append_document_chunks,
+    set_document_hash,
+    PENDING_CONTENT_HASH,
+    delete_document,
+    delete_documents,
+    upsert_image,
+    IMAGE_EMBEDDING_DIM,
 };
-use crate::embedder::embed_text;
+use crate::archive::{extract_archive_texts, is_supported_archive};
+use crate::color::{dominant_color_of_image, to_hex_color};
+use crate::phash::{perceptual_hash_of_image, to_hex_hash};
+use crate::core::exclusions::is_path_excluded;
+use crate::core::file_system::is_entry_visible;
+use crate::core::index_size::{ensure_index_room, estimated_row_bytes, SIZE_CHECK_INTERVAL};
+use crate::core::metadata_index::index_folder_metadata;
+use crate::core::settings::current_settings;
+use crate::core::term_frequencies::{add_term_counts, forget_document_terms, record_document_terms, save_term_frequencies, TermFrequencyBatch};
+use crate::core::file_types::{categorize_file, FileCategory};
+use crate::embedder::{embed_code, embed_text, TextModel};
 use crate::image_embedder::embed_image;
+use crate::search::keyword_counts;
 use crate::extractor::{
     extract_text, 
     calculate_hash, 
@@ -21,15 +44,21 @@ use crate::extractor::{
     calculate_file_hash, 
     get_content_type, 
     ContentType,
-    DetectedLanguage
+    DetectedLanguage,
+    ExtractorError,
+    detect_language,
+    is_streamable_text,
+    stream_text_segments,
 };
 use walkdir::WalkDir;
-use std::time::Instant;
-use std::sync::{RwLock, Arc};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use tokio::task;
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use futures::future::join_all;
+use lancedb::connection::Connection;
 
 /// Directories to exclude from indexing
 pub const EXCLUDED_DIRS: &[&str] = &[
@@ -74,6 +103,78 @@ pub struct IndexingStats {
     
     pub indexed_files: Vec<String>,
     pub failed_files: Vec<String>,
+
+    /// Files skipped because they were already indexed with the same content hash
+    #[serde(default)]
+    pub files_unchanged: u32,
+
+    /// Files skipped because their extracted text is shorter than `min_text_chars`
+    #[serde(default)]
+    pub files_too_short: u32,
+
+    /// Zero-byte files and files holding nothing but whitespace
+    #[serde(default)]
+    pub files_empty: u32,
+
+    /// Rows in each table once the run finished; `None` if counting failed
+    #[serde(default)]
+    pub total_text_docs: Option<usize>,
+    #[serde(default)]
+    pub total_image_docs: Option<usize>,
+    #[serde(default)]
+    pub total_amharic_docs: Option<usize>,
+
+    /// Files and folders that couldn't be read for lack of permission.
+    /// Indexing continues past them.
+    #[serde(default)]
+    pub files_permission_denied: u32,
+    #[serde(default)]
+    pub permission_denied_paths: Vec<String>,
+
+    /// Why the Amharic table couldn't be opened, e.g. a legacy schema. Amharic
+    /// documents are left out of the run while everything else is indexed,
+    /// until the table is migrated or rebuilt.
+    #[serde(default)]
+    pub amharic_table_error: Option<String>,
+    /// Amharic documents left out because of `amharic_table_error`
+    #[serde(default)]
+    pub files_amharic_skipped: u32,
+
+    /// Large files indexed only up to the chunk cap of the streaming path
+    #[serde(default)]
+    pub files_truncated: u32,
+
+    /// Set while the run is still going; the counts are partial and the file
+    /// lists are left empty until it finishes
+    #[serde(default)]
+    pub in_progress: bool,
+}
+
+/// Options controlling a folder indexing run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexOptions {
+    /// Skip files that are already in the index with an unchanged content hash.
+    /// Useful when indexing a folder that contains previously indexed roots.
+    pub only_new: bool,
+    /// Index the text members of ZIP and TAR archives under composite
+    /// `archive!member` paths instead of skipping the archives.
+    pub include_archives: bool,
+    /// Wait for a running indexing operation to finish instead of failing
+    /// with [`INDEXING_BUSY_ERROR`].
+    pub queue_if_busy: bool,
+    /// Descend into symlinked folders. Defaults to the `follow_symlinks` setting.
+    pub follow_symlinks: Option<bool>,
+    /// Embed file names along with content. Defaults to the `embed_file_names` setting.
+    pub embed_file_names: Option<bool>,
+    /// Re-extract and re-embed every file even when `only_new` would skip it
+    /// as unchanged. Use after upgrading the extractor or embedder: the
+    /// content hash is taken from extracted text, so it can't tell that
+    /// extraction itself got better.
+    pub force: bool,
+    /// Only record each file's path, name, size, modification time and
+    /// category for `metadata_search`, skipping extraction and embedding.
+    pub metadata_only: bool,
 }
 
 // Static variable to store the last indexing statistics
@@ -89,6 +190,280 @@ fn set_last_indexing_stats(stats: IndexingStats) {
     *LAST_INDEXING_STATS.write().unwrap() = Some(stats.clone());
 }
 
+/// Prefix of the error for an Amharic document that wasn't stored because the
+/// Amharic table couldn't be opened for the run
+pub(crate) const AMHARIC_TABLE_UNAVAILABLE: &str = "Amharic table unavailable";
+
+/// Opens the Amharic table for an indexing run. Failing to is not fatal: it
+/// only holds the optional Amharic documents, so the run goes on without them
+/// and the error is kept for the stats.
+async fn open_amharic_table_for_run(conn: &Connection) -> (Option<lancedb::Table>, Option<String>) {
+    match open_or_create_amharic_text_table(conn).await {
+        Ok(table) => (Some(table), None),
+        Err(e) => {
+            warn!("Amharic text table unusable, indexing without Amharic documents: {}", e);
+            (None, Some(e.to_string()))
+        }
+    }
+}
+
+fn amharic_unavailable_error(path: &Path) -> String {
+    format!("{}: {}", AMHARIC_TABLE_UNAVAILABLE, path.display())
+}
+
+/// Files handled between two updates of the live stats
+pub(crate) const LIVE_STATS_INTERVAL: u32 = 25;
+
+/// Publishes partial counts of a running index so polling shows progress
+pub(crate) fn publish_live_stats(stats: IndexingStats, start_time: Instant) {
+    let elapsed = start_time.elapsed();
+    set_last_indexing_stats(IndexingStats {
+        elapsed_seconds: elapsed.as_secs() as u32,
+        elapsed_milliseconds: elapsed.subsec_millis(),
+        in_progress: true,
+        ..stats
+    });
+}
+
+/// Write-phase progress of a folder run, shared by its writer tasks. Each
+/// finished batch of writes publishes the scan's counts plus what was written.
+struct WriteProgress {
+    /// Live stats as of the end of the scan
+    scanned: IndexingStats,
+    written: AtomicU32,
+    failed: AtomicU32,
+    start_time: Instant,
+}
+
+impl WriteProgress {
+    fn record(&self, written: u32, failed: u32) {
+        let written = self.written.fetch_add(written, Ordering::SeqCst) + written;
+        let failed = self.failed.fetch_add(failed, Ordering::SeqCst) + failed;
+        publish_live_stats(
+            IndexingStats {
+                files_failed: self.scanned.files_failed + failed,
+                db_inserts: self.scanned.db_inserts + written,
+                text_files_indexed: self.scanned.text_files_indexed + written,
+                text_files_failed: self.scanned.text_files_failed + failed,
+                ..self.scanned.clone()
+            },
+            self.start_time,
+        );
+    }
+}
+
+/// Live stats of the run in progress. A run that finishes replaces them with
+/// its final stats; one that fails leaves its partial counts, marked finished.
+struct LiveStatsRun;
+
+impl LiveStatsRun {
+    fn start(start_time: Instant) -> Self {
+        publish_live_stats(IndexingStats::default(), start_time);
+        LiveStatsRun
+    }
+}
+
+impl Drop for LiveStatsRun {
+    fn drop(&mut self) {
+        if let Ok(mut stats) = LAST_INDEXING_STATS.write() {
+            if let Some(stats) = stats.as_mut() {
+                stats.in_progress = false;
+            }
+        }
+    }
+}
+
+/// Prefix of the error returned when an indexing run is rejected because another is active
+pub const INDEXING_BUSY_ERROR: &str = "Indexing already in progress";
+
+/// Held for the duration of an indexing run so runs never share the embedder and DB
+static INDEXING_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+static INDEXING_QUEUE: Lazy<Mutex<IndexingQueueStatus>> = Lazy::new(|| Mutex::new(IndexingQueueStatus::default()));
+
+/// Snapshot of the indexing queue
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexingQueueStatus {
+    /// Label of the running operation, e.g. the folder being indexed
+    pub running: Option<String>,
+    /// Unix timestamp (seconds) the running operation started at
+    pub running_since: Option<u64>,
+    /// Operations waiting for the running one to finish
+    pub queued: usize,
+}
+
+/// Returns the current state of the indexing queue
+pub fn get_indexing_queue_status() -> IndexingQueueStatus {
+    INDEXING_QUEUE.lock().map(|q| q.clone()).unwrap_or_default()
+}
+
+/// Exclusive right to index, released on drop
+pub(crate) struct IndexingSlot {
+    _guard: tokio::sync::MutexGuard<'static, ()>,
+}
+
+impl Drop for IndexingSlot {
+    fn drop(&mut self) {
+        if let Ok(mut queue) = INDEXING_QUEUE.lock() {
+            queue.running = None;
+            queue.running_since = None;
+        }
+    }
+}
+
+/// Counts a waiting operation; decrements even if the waiting future is dropped
+struct QueuedTicket;
+
+impl Drop for QueuedTicket {
+    fn drop(&mut self) {
+        if let Ok(mut queue) = INDEXING_QUEUE.lock() {
+            queue.queued = queue.queued.saturating_sub(1);
+        }
+    }
+}
+
+/// Claims the indexing slot for `label`. When another run is active this
+/// waits if `wait` is set and otherwise fails with [`INDEXING_BUSY_ERROR`].
+pub(crate) async fn acquire_indexing_slot(label: &str, wait: bool) -> Result<IndexingSlot, String> {
+    let guard = match INDEXING_LOCK.try_lock() {
+        Ok(guard) => guard,
+        Err(_) if wait => {
+            if let Ok(mut queue) = INDEXING_QUEUE.lock() {
+                queue.queued += 1;
+            }
+            let _ticket = QueuedTicket;
+            info!("Indexing busy, queueing: {}", label);
+            INDEXING_LOCK.lock().await
+        }
+        Err(_) => {
+            let running = get_indexing_queue_status().running.unwrap_or_default();
+            warn!("Rejecting indexing of {} while {} is running", label, running);
+            return Err(format!("{}: {}", INDEXING_BUSY_ERROR, running));
+        }
+    };
+
+    if let Ok(mut queue) = INDEXING_QUEUE.lock() {
+        queue.running = Some(label.to_string());
+        queue.running_since = SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs());
+    }
+    Ok(IndexingSlot { _guard: guard })
+}
+
+/// Whether a walk error means an entry couldn't be read for lack of permission
+pub(crate) fn is_permission_denied(error: &walkdir::Error) -> bool {
+    error
+        .io_error()
+        .is_some_and(|io| io.kind() == std::io::ErrorKind::PermissionDenied)
+}
+
+/// Whether extraction failed because the file couldn't be opened for lack of permission
+fn is_extraction_permission_denied(error: &ExtractorError) -> bool {
+    matches!(error, ExtractorError::IoError(_, io) if io.kind() == std::io::ErrorKind::PermissionDenied)
+}
+
+/// Logs one summary line for the locations a run couldn't read
+fn log_permission_denied_summary(paths: &[String]) {
+    if paths.is_empty() {
+        return;
+    }
+    const SHOWN: usize = 5;
+    let shown = paths.iter().take(SHOWN).map(String::as_str).collect::<Vec<_>>().join(", ");
+    let more = if paths.len() > SHOWN { format!(" and {} more", paths.len() - SHOWN) } else { String::new() };
+    warn!(
+        "Skipped {} locations without read permission: {}{}. Grant access to index them.",
+        paths.len(),
+        shown,
+        more
+    );
+}
+
+/// Walks `root` with the shared entry filter. When following symlinks, each
+/// directory is entered once by its canonical path, so links pointing back up
+/// the tree or several links to one folder can't make the walk loop or repeat.
+pub(crate) fn walk_entries(root: &Path, follow_symlinks: bool) -> impl Iterator<Item = walkdir::Result<walkdir::DirEntry>> {
+    walk_entries_to_depth(root, follow_symlinks, usize::MAX)
+}
+
+/// [`walk_entries`] without descending more than `max_depth` levels below `root`.
+/// Hidden entries are walked when the `include_hidden` setting says so.
+pub(crate) fn walk_entries_to_depth(
+    root: &Path,
+    follow_symlinks: bool,
+    max_depth: usize,
+) -> impl Iterator<Item = walkdir::Result<walkdir::DirEntry>> {
+    walk_entries_filtered(root, follow_symlinks, max_depth, current_settings().include_hidden)
+}
+
+fn walk_entries_filtered(
+    root: &Path,
+    follow_symlinks: bool,
+    max_depth: usize,
+    include_hidden: bool,
+) -> impl Iterator<Item = walkdir::Result<walkdir::DirEntry>> {
+    let mut visited_dirs: HashSet<PathBuf> = HashSet::new();
+    WalkDir::new(root)
+        .follow_links(follow_symlinks)
+        .max_depth(max_depth)
+        .into_iter()
+        .filter_entry(move |e| {
+            if !is_walk_entry_included(e, include_hidden) {
+                return false;
+            }
+            if follow_symlinks && e.file_type().is_dir() {
+                if let Ok(canonical) = e.path().canonicalize() {
+                    if !visited_dirs.insert(canonical) {
+                        debug!("Skipping already visited directory: {}", e.path().display());
+                        return false;
+                    }
+                }
+            }
+            true
+        })
+}
+
+/// Walk filter shared by every indexing pass: skips paths the user excluded,
+/// hidden entries (unless `include_hidden`), excluded directories and macOS
+/// bundles. Apart from user exclusions, the root was chosen explicitly and is
+/// always walked, even when it is hidden.
+fn is_walk_entry_included(e: &walkdir::DirEntry, include_hidden: bool) -> bool {
+    if is_path_excluded(e.path()) {
+        debug!("Skipping user-excluded path: {}", e.path().display());
+        return false;
+    }
+    if e.depth() == 0 {
+        return true;
+    }
+
+    // Skip hidden files and directories
+    if let Some(file_name) = e.file_name().to_str() {
+        if !is_entry_visible(file_name, include_hidden) {
+            return false;
+        }
+    }
+    
+    // Skip directories in the excluded list
+    if e.file_type().is_dir() {
+        if let Some(dir_name) = e.file_name().to_str() {
+            if EXCLUDED_DIRS.iter().any(|excluded| dir_name.contains(excluded)) {
+                debug!("Skipping excluded directory: {}", e.path().display());
+                return false;
+            }
+        }
+    }
+    
+    // Skip macOS application bundles and system extensions
+    if e.path().is_dir() {
+        if let Some(path_str) = e.path().to_str() {
+            if EXCLUDED_PATTERNS.iter().any(|pattern| path_str.contains(pattern)) {
+                debug!("Skipping macOS bundle: {}", e.path().display());
+                return false;
+            }
+        }
+    }
+    
+    true
+}
+
 /// Index the macOS Downloads folder at application startup
 pub async fn index_downloads_folder() -> Result<IndexingStats, String> {
     let start_time = Instant::now();
@@ -103,12 +478,16 @@ pub async fn index_downloads_folder() -> Result<IndexingStats, String> {
     println!("Starting Downloads folder indexing: {}", downloads_dir.display());
     
     info!("Starting Downloads folder indexing: {}", downloads_dir.display());
+
+    // Startup indexing is background work; let it wait behind a user-triggered run
+    let _slot = acquire_indexing_slot(&downloads_dir.to_string_lossy(), true).await?;
     
     // Ensure the directory exists
     if !downloads_dir.exists() || !downloads_dir.is_dir() {
         error!("Downloads directory does not exist at {}", downloads_dir.display());
         return Err("Downloads directory not found".to_string());
     }
+    let _live_stats = LiveStatsRun::start(start_time);
     
     info!("Excluding system folders and application bundles from indexing");
     
@@ -148,45 +527,22 @@ pub async fn index_downloads_folder() -> Result<IndexingStats, String> {
         format!("Image table error: {}", e)
     })?;
 
-    let amharic_text_table = open_or_create_amharic_text_table(&conn).await.map_err(|e| {
-        error!("Failed to open or create Amharic text table: {}", e);
-        format!("Amharic text table error: {}", e)
+    let (amharic_text_table, amharic_table_error) = open_amharic_table_for_run(&conn).await;
+    let mut files_amharic_skipped = 0;
+
+    let code_text_table = open_or_create_code_table(&conn).await.map_err(|e| {
+        error!("Failed to open or create code table: {}", e);
+        format!("Code table error: {}", e)
     })?;
     
+    let min_text_chars = current_settings().min_text_chars;
+    let mut files_too_short = 0;
+    let mut files_empty = 0;
+    let mut permission_denied_paths: Vec<String> = Vec::new();
+    let index_root = downloads_dir.to_string_lossy().to_string();
+
     // Walk through the directory and process files
-    for entry in WalkDir::new(&downloads_dir)
-        .follow_links(false)
-        .into_iter()
-        .filter_entry(|e| {
-            // Skip hidden files and directories
-            if let Some(file_name) = e.file_name().to_str() {
-                if file_name.starts_with(".") {
-                    return false;
-                }
-            }
-            
-            // Skip directories in the excluded list
-            if e.file_type().is_dir() {
-                if let Some(dir_name) = e.file_name().to_str() {
-                    if EXCLUDED_DIRS.iter().any(|excluded| dir_name.contains(excluded)) {
-                        debug!("Skipping excluded directory: {}", e.path().display());
-                        return false;
-                    }
-                }
-            }
-            
-            // Skip macOS application bundles and system extensions
-            if e.path().is_dir() {
-                if let Some(path_str) = e.path().to_str() {
-                    if EXCLUDED_PATTERNS.iter().any(|pattern| path_str.contains(pattern)) {
-                        debug!("Skipping macOS bundle: {}", e.path().display());
-                        return false;
-                    }
-                }
-            }
-            
-            true
-        }) {
+    for entry in walk_entries(&downloads_dir, current_settings().follow_symlinks) {
         match entry {
             Ok(entry) => {
                 let path = entry.path();
@@ -197,6 +553,30 @@ pub async fn index_downloads_folder() -> Result<IndexingStats, String> {
                 }
                 
                 files_processed += 1;
+                if files_processed % SIZE_CHECK_INTERVAL == 0 {
+                    ensure_index_room(&conn, 0, Some(&index_root)).await?;
+                }
+                if files_processed % LIVE_STATS_INTERVAL == 0 {
+                    publish_live_stats(
+                        IndexingStats {
+                            files_processed,
+                            files_failed,
+                            files_skipped,
+                            db_inserts,
+                            text_files_processed,
+                            text_files_indexed,
+                            text_files_failed,
+                            image_files_processed,
+                            image_files_indexed,
+                            image_files_failed,
+                            files_too_short,
+                            files_empty,
+                            files_permission_denied: permission_denied_paths.len() as u32,
+                            ..Default::default()
+                        },
+                        start_time,
+                    );
+                }
                 
                 // Determine content type and process accordingly
                 let content_type = get_content_type(path);
@@ -207,16 +587,25 @@ pub async fn index_downloads_folder() -> Result<IndexingStats, String> {
                         println!("path {:?}", path);
                         
                         // Process text file
-                        if let Err(e) = process_text_file(path, &text_table, &amharic_text_table).await {
-                            error!("Error processing text file {}: {}", path.display(), e);
-                            files_failed += 1;
-                            text_files_failed += 1;
-                            failed_files.push(path.to_string_lossy().to_string());
-                        } else {
-                            info!("Indexed text file: {}", path.display());
-                            db_inserts += 1;
-                            text_files_indexed += 1;
-                            indexed_files.push(path.to_string_lossy().to_string());
+                        match process_text_file(path, &text_table, amharic_text_table.as_ref(), &code_text_table, min_text_chars, Some(&index_root)).await {
+                            Err(e) if e.starts_with(AMHARIC_TABLE_UNAVAILABLE) => {
+                                debug!("Skipping Amharic document {}", path.display());
+                                files_amharic_skipped += 1;
+                            }
+                            Err(e) => {
+                                error!("Error processing text file {}: {}", path.display(), e);
+                                files_failed += 1;
+                                text_files_failed += 1;
+                                failed_files.push(path.to_string_lossy().to_string());
+                            }
+                            Ok(ContentCheck::Empty) => files_empty += 1,
+                            Ok(ContentCheck::TooShort) => files_too_short += 1,
+                            Ok(ContentCheck::Meaningful) => {
+                                info!("Indexed text file: {}", path.display());
+                                db_inserts += 1;
+                                text_files_indexed += 1;
+                                indexed_files.push(path.to_string_lossy().to_string());
+                            }
                         }
                     },
                     ContentType::Image => {
@@ -225,7 +614,7 @@ pub async fn index_downloads_folder() -> Result<IndexingStats, String> {
                        
                         println!("count: {}", image_files_processed);
                         // Process image file
-                        if let Err(e) = process_image_file(path, &image_table).await {
+                        if let Err(e) = process_image_file(path, &image_table, Some(&index_root)).await {
                             error!("Error processing image file {}: {}", path.display(), e);
                             files_failed += 1;
                             image_files_failed += 1;
@@ -243,6 +632,10 @@ pub async fn index_downloads_folder() -> Result<IndexingStats, String> {
                     }
                 }
             },
+            Err(e) if is_permission_denied(&e) => {
+                debug!("Permission denied while walking: {}", e);
+                permission_denied_paths.push(e.path().map(|p| p.display().to_string()).unwrap_or_default());
+            }
             Err(e) => {
                 error!("Error walking directory: {}", e);
             }
@@ -267,7 +660,16 @@ pub async fn index_downloads_folder() -> Result<IndexingStats, String> {
         image_files_failed,
         indexed_files,
         failed_files,
+        files_unchanged: 0,
+        files_too_short,
+        files_empty,
+        files_permission_denied: permission_denied_paths.len() as u32,
+        permission_denied_paths,
+        amharic_table_error,
+        files_amharic_skipped,
+        ..Default::default()
     };
+    log_permission_denied_summary(&stats.permission_denied_paths);
     
     info!(
         "Completed indexing in {}.{:03} seconds: {} files processed, {} failures, {} skipped, {} database inserts",
@@ -289,23 +691,223 @@ pub async fn index_downloads_folder() -> Result<IndexingStats, String> {
         stats.image_files_failed
     );
     
+    if let Err(e) = save_term_frequencies() {
+        warn!("Failed to save term frequencies: {}", e);
+    }
     // Save the stats for later retrieval
     set_last_indexing_stats(stats.clone());
     
     Ok(stats)
 }
 
-/// Process a text file for indexing - used by the single-threaded version
-async fn process_text_file(file_path: &Path, text_table: &lancedb::Table, amharic_text_table: &lancedb::Table) -> Result<(), String> {
+/// Most chunks embedded for one streamed file; the rest of the file is left out
+const MAX_STREAMED_CHUNKS: usize = 1000;
+
+/// What happened to a file sent through the streaming path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StreamedOutcome {
+    Indexed,
+    Unchanged,
+    TooShort,
+    /// Indexed up to the chunk cap; the rest of the file is left out
+    Truncated,
+    /// Not really text, e.g. binary data behind a `.txt` name
+    Unsupported,
+    /// Amharic text, with no Amharic table to store it in this run
+    AmharicSkipped,
+}
+
+/// Whether a text file is large enough to go through `index_streamed_text_file`
+pub(crate) fn should_stream(path: &Path, threshold_bytes: u64) -> bool {
+    is_streamable_text(path) && fs::metadata(path).is_ok_and(|m| m.len() > threshold_bytes)
+}
+
+/// Indexes a large plain text file one segment at a time. Each segment is
+/// chunked, embedded and appended to the table before the next one is read,
+/// so memory stays bounded regardless of file size.
+///
+/// The language is detected from the first segment. The stored hash is the
+/// hash of the raw file, which can be checked against `indexed_hash` without
+/// extracting anything. It is written once every chunk is stored; until then
+/// the rows carry `PENDING_CONTENT_HASH`.
+#[allow(clippy::too_many_arguments)]
+async fn index_streamed_text_file(
+    path: &Path,
+    text_table: &lancedb::Table,
+    amharic_text_table: Option<&lancedb::Table>,
+    indexed_hash: Option<&String>,
+    min_text_chars: usize,
+    max_chunks: usize,
+    embed_file_names: bool,
+    index_root: Option<&str>,
+) -> Result<StreamedOutcome, String> {
+    let content_hash = calculate_file_hash(path).map_err(|e| format!("Failed to hash file: {}", e))?;
+    if indexed_hash == Some(&content_hash) {
+        return Ok(StreamedOutcome::Unchanged);
+    }
+    let segments = match stream_text_segments(path) {
+        Ok(segments) => segments,
+        Err(ExtractorError::UnsupportedFileType(_)) => return Ok(StreamedOutcome::Unsupported),
+        Err(e) => return Err(format!("Failed to open file: {}", e)),
+    };
+    let path_str = path.to_string_lossy().to_string();
+    info!("Streaming large text file: {}", path.display());
+
+    let mut target: Option<(&lancedb::Table, DetectedLanguage)> = None;
+    let mut chunks_stored = 0;
+    let mut truncated = false;
+    let mut pending_short_text = String::new();
+    let mut term_counts = HashMap::new();
+    for segment in segments {
+        let segment = segment.map_err(|e| format!("Failed to extract text: {}", e))?;
+        let (table, language) = match target.clone() {
+            Some(target) => target,
+            None => {
+                // Tiny leading segments are held back until there's enough text to judge
+                pending_short_text.push_str(&segment);
+                if is_text_too_short(&pending_short_text, min_text_chars) {
+                    continue;
+                }
+                let language = detect_language(&pending_short_text);
+                let table = match language {
+                    DetectedLanguage::Amharic => match amharic_text_table {
+                        Some(table) => table,
+                        None => return Ok(StreamedOutcome::AmharicSkipped),
+                    },
+                    DetectedLanguage::English | DetectedLanguage::Other => text_table,
+                };
+                delete_document(table, &path_str)
+                    .await
+                    .map_err(|e| format!("Failed to remove the old rows of {}: {}", path.display(), e))?;
+                target = Some((table, language.clone()));
+                (table, language)
+            }
+        };
+        let text = if pending_short_text.is_empty() {
+            add_term_counts(&mut term_counts, &segment);
+            segment
+        } else {
+            // First segment: the only one that gets the file name prefix
+            let first_text = std::mem::take(&mut pending_short_text);
+            add_term_counts(&mut term_counts, &first_text);
+            text_for_embedding(path, &first_text, embed_file_names)
+        };
+
+        let mut embeddings = embed_text(&[text], &language, false).map_err(|e| {
+            error!("Embedding error for {}: {}", path.display(), e);
+            format!("Embedding generation failed: {}", e)
+        })?;
+        embeddings.truncate(max_chunks - chunks_stored);
+        append_document_chunks(table, &path_str, PENDING_CONTENT_HASH, chunks_stored, &embeddings, index_root)
+            .await
+            .map_err(|e| format!("Database append failed: {}", e))?;
+        chunks_stored += embeddings.len();
+        if chunks_stored >= max_chunks {
+            truncated = true;
+            warn!(
+                "Indexed only the first {} chunks of {}; the rest of the file is not searchable",
+                chunks_stored,
+                path.display()
+            );
+            break;
+        }
+    }
+
+    let Some((table, _)) = target else {
+        debug!("Skipping {}: extracted text shorter than {} characters", path.display(), min_text_chars);
+        return Ok(StreamedOutcome::TooShort);
+    };
+    set_document_hash(table, &path_str, &content_hash)
+        .await
+        .map_err(|e| format!("Failed to store the hash of {}: {}", path.display(), e))?;
+    record_document_terms(&path_str, term_counts);
+    Ok(if truncated { StreamedOutcome::Truncated } else { StreamedOutcome::Indexed })
+}
+
+/// Removes the rows of files that were indexed before but are now empty or
+/// too short, which would otherwise keep matching searches with old content
+async fn remove_unindexable_documents(tables: &[&lancedb::Table], paths: &[String]) -> Result<(), String> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    for table in tables {
+        delete_documents(table, paths)
+            .await
+            .map_err(|e| format!("Failed to remove files without enough text from '{}': {}", table.name(), e))?;
+    }
+    for path in paths {
+        forget_document_terms(path);
+    }
+    Ok(())
+}
+
+/// Process a text file for indexing - used by the single-threaded version.
+/// Returns `ContentCheck::Meaningful` once the file was indexed.
+async fn process_text_file(
+    file_path: &Path,
+    text_table: &lancedb::Table,
+    amharic_text_table: Option<&lancedb::Table>,
+    code_text_table: &lancedb::Table,
+    min_text_chars: usize,
+    index_root: Option<&str>,
+) -> Result<ContentCheck, String> {
+    let mut tables = vec![text_table, code_text_table];
+    tables.extend(amharic_text_table);
+    let unindexable = [file_path.to_string_lossy().to_string()];
+    if is_zero_byte_file(file_path) {
+        remove_unindexable_documents(&tables, &unindexable).await?;
+        return Ok(ContentCheck::Empty);
+    }
+    if should_stream(file_path, current_settings().streaming_threshold_bytes) {
+        let outcome = index_streamed_text_file(
+            file_path,
+            text_table,
+            amharic_text_table,
+            None,
+            min_text_chars,
+            MAX_STREAMED_CHUNKS,
+            current_settings().embed_file_names,
+            index_root,
+        )
+        .await?;
+        return match outcome {
+            StreamedOutcome::Unsupported => Err(format!("Unsupported content in {}", file_path.display())),
+            StreamedOutcome::TooShort => {
+                remove_unindexable_documents(&tables, &unindexable).await?;
+                Ok(ContentCheck::TooShort)
+            }
+            StreamedOutcome::AmharicSkipped => Err(amharic_unavailable_error(file_path)),
+            _ => Ok(ContentCheck::Meaningful),
+        };
+    }
+
     // Extract text content from the file
     let extraction_result = extract_text(file_path).map_err(|e| format!("Failed to extract text: {}", e))?;
+    let check = check_content(&extraction_result.text, min_text_chars);
+    if check != ContentCheck::Meaningful {
+        debug!("Skipping {}: {:?} content", file_path.display(), check);
+        remove_unindexable_documents(&tables, &unindexable).await?;
+        return Ok(check);
+    }
     
+    let text_model = text_model_for_path(file_path);
+    if text_model == TextModel::Default
+        && extraction_result.language == DetectedLanguage::Amharic
+        && amharic_text_table.is_none()
+    {
+        return Err(amharic_unavailable_error(file_path));
+    }
+
     // Calculate content hash
     let content_hash = calculate_hash(&extraction_result.text);
     
     // Get embeddings for the content
-    let content_vec = vec![extraction_result.text.clone()];
-    let embeddings = embed_text(&content_vec, &extraction_result.language, false).map_err(|e| {
+    let content_vec = vec![text_for_embedding(file_path, &extraction_result.text, current_settings().embed_file_names)];
+    let embed_result = match text_model {
+        TextModel::Code => embed_code(&content_vec, false),
+        TextModel::Default => embed_text(&content_vec, &extraction_result.language, false),
+    };
+    let embeddings = embed_result.map_err(|e| {
         error!("Embedding error for {}: {}", file_path.display(), e);
         format!("Embedding generation failed: {}", e)
     })?;
@@ -316,26 +918,36 @@ async fn process_text_file(file_path: &Path, text_table: &lancedb::Table, amhari
     
     // Store in the database - now passing all embeddings
     let file_path_str = file_path.to_string_lossy().to_string();
+    if text_model == TextModel::Code {
+        upsert_code_document(code_text_table, &file_path_str, &content_hash, &embeddings, index_root).await.map_err(|e| {
+            error!("Database error (Code) for {}: {}", file_path.display(), e);
+            format!("Database upsert failed: {}", e)
+        })?;
+        record_document_terms(&file_path_str, keyword_counts(&extraction_result.text));
+        return Ok(ContentCheck::Meaningful);
+    }
     match extraction_result.language {
         DetectedLanguage::English | DetectedLanguage::Other => {
-            upsert_document(text_table, &file_path_str, &content_hash, &embeddings).await.map_err(|e| {
+            upsert_document(text_table, &file_path_str, &content_hash, &embeddings, index_root).await.map_err(|e| {
                 error!("Database error (English/Other) for {}: {}", file_path.display(), e);
                 format!("Database upsert failed: {}", e)
             })?;
         }
         DetectedLanguage::Amharic => {
-            upsert_amharic_document(amharic_text_table, &file_path_str, &content_hash, &embeddings).await.map_err(|e| {
+            let amharic_text_table = amharic_text_table.ok_or_else(|| amharic_unavailable_error(file_path))?;
+            upsert_amharic_document(amharic_text_table, &file_path_str, &content_hash, &embeddings, index_root).await.map_err(|e| {
                 error!("Database error (Amharic) for {}: {}", file_path.display(), e);
                 format!("Database upsert failed: {}", e)
             })?;
         }
     }
+    record_document_terms(&file_path_str, keyword_counts(&extraction_result.text));
     
-    Ok(())
+    Ok(ContentCheck::Meaningful)
 }
 
 /// Process an image file for indexing - used by the single-threaded version
-async fn process_image_file(file_path: &Path, table: &lancedb::Table) -> Result<(), String> {
+async fn process_image_file(file_path: &Path, table: &lancedb::Table, index_root: Option<&str>) -> Result<(), String> {
     // Process the image and get the path as a string
     let image_path = process_image(file_path).map_err(|e| {
         warn!("Image processing error for {}: {}", file_path.display(), e);
@@ -362,6 +974,10 @@ async fn process_image_file(file_path: &Path, table: &lancedb::Table) -> Result<
     let width: Option<i32> = None;
     let height: Option<i32> = None;
     let thumbnail_path: Option<&str> = None;
+    // Decoded once for both the color and the perceptual hash
+    let decoded = image::open(&image_path).ok();
+    let dominant_color = decoded.as_ref().and_then(dominant_color_of_image).map(to_hex_color);
+    let phash = decoded.as_ref().map(|image| to_hex_hash(perceptual_hash_of_image(image)));
     
     upsert_image(
         table, 
@@ -370,7 +986,10 @@ async fn process_image_file(file_path: &Path, table: &lancedb::Table) -> Result<
         &embedding, 
         width, 
         height, 
-        thumbnail_path
+        thumbnail_path,
+        dominant_color.as_deref(),
+        phash.as_deref(),
+        index_root,
     ).await.map_err(|e| {
         error!("Database error for {}: {}", file_path.display(), e);
         format!("Database upsert failed: {}", e)
@@ -379,11 +998,60 @@ async fn process_image_file(file_path: &Path, table: &lancedb::Table) -> Result<
     Ok(())
 }
 
+/// Bytes the embeddings collected so far will add to the index, roughly
+fn pending_index_bytes(text_batches: &[&[(String, String, Vec<Vec<f32>>)]], image_count: usize) -> u64 {
+    let text_bytes: u64 = text_batches
+        .iter()
+        .flat_map(|batch| batch.iter())
+        .flat_map(|(_, _, embeddings)| embeddings.iter())
+        .map(|embedding| estimated_row_bytes(1, embedding.len()))
+        .sum();
+    text_bytes + estimated_row_bytes(image_count, IMAGE_EMBEDDING_DIM as usize)
+}
+
+/// Indexes one file into the table its type belongs in, replacing whatever
+/// was stored for it. Returns `ContentCheck::Meaningful` once it is stored.
+pub(crate) async fn index_single_file(
+    conn: &Connection,
+    file_path: &Path,
+    index_root: Option<&str>,
+) -> Result<ContentCheck, String> {
+    match get_content_type(file_path) {
+        ContentType::Text => {
+            let text_table = open_or_create_text_table(conn).await.map_err(|e| e.to_string())?;
+            let (amharic_text_table, _) = open_amharic_table_for_run(conn).await;
+            let code_text_table = open_or_create_code_table(conn).await.map_err(|e| e.to_string())?;
+            let check = process_text_file(
+                file_path,
+                &text_table,
+                amharic_text_table.as_ref(),
+                &code_text_table,
+                current_settings().min_text_chars,
+                index_root,
+            )
+            .await?;
+            if let Err(e) = save_term_frequencies() {
+                warn!("Failed to save term frequencies: {}", e);
+            }
+            Ok(check)
+        }
+        ContentType::Image => {
+            let image_table = open_or_create_image_table(conn).await.map_err(|e| e.to_string())?;
+            process_image_file(file_path, &image_table, index_root).await?;
+            Ok(ContentCheck::Meaningful)
+        }
+        ContentType::Unsupported => Err(format!("Unsupported file type: {}", file_path.display())),
+    }
+}
+
 /// Handle text file indexing with a batch of files in a separate thread
 async fn handle_specific_language_text_indexing(
     text_data_batch: Vec<(String, String, Vec<Vec<f32>>)>, // path_str, content_hash, embeddings
     table: Arc<lancedb::Table>,
-    language_name_for_log: &str // e.g., "English/Other" or "Amharic"
+    language_name_for_log: &str, // e.g., "English/Other" or "Amharic"
+    model: TextModel, // Decides which table layout the embeddings are written with
+    index_root: Option<String>, // Folder the batch was collected from
+    progress: Arc<WriteProgress>,
 ) -> HashMap<String, Result<(), String>> {
     let mut results = HashMap::new();
 
@@ -401,15 +1069,17 @@ async fn handle_specific_language_text_indexing(
             let hash_clone = content_hash.clone();
             let embeddings_clone = embeddings.clone(); // Vec<Vec<f32>> can be cloned
             let lang_log_clone = language_name_for_log.to_string(); // Clone for async move
+            let root_clone = index_root.clone();
 
             mut_futures.push(async move {
-                let upsert_result = upsert_document(
-                    &table_clone,
-                    &path_str_clone,
-                    &hash_clone,
-                    &embeddings_clone,
-                )
-                .await
+                let upsert_result = match model {
+                    TextModel::Default => {
+                        upsert_document(&table_clone, &path_str_clone, &hash_clone, &embeddings_clone, root_clone.as_deref()).await
+                    }
+                    TextModel::Code => {
+                        upsert_code_document(&table_clone, &path_str_clone, &hash_clone, &embeddings_clone, root_clone.as_deref()).await
+                    }
+                }
                 .map_err(|e| {
                     error!(
                         "Database error for {} file {}: {}",
@@ -425,6 +1095,8 @@ async fn handle_specific_language_text_indexing(
         }
 
         let chunk_results = join_all(mut_futures).await;
+        let failed = chunk_results.iter().filter(|(_, result)| result.is_err()).count() as u32;
+        progress.record(chunk_results.len() as u32 - failed, failed);
         for (path_str, result) in chunk_results {
             results.insert(path_str, result);
         }
@@ -449,8 +1121,118 @@ async fn create_empty_string_result_hashmap_async() -> HashMap<String, Result<()
     HashMap::new()
 }
 
+/// Parent folders named in a file's embedding prefix
+const PREFIX_FOLDER_DEPTH: usize = 2;
+
+/// Words from a file's name and its nearest folders, e.g. `budget 2024 xlsx
+/// (finance / reports)` for `.../reports/finance/budget-2024.xlsx`. Separators
+/// become spaces so the embedding model sees the words.
+pub(crate) fn file_name_prefix(path: &Path) -> String {
+    let words = |s: &str| {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let name = path.file_name().map(|n| words(&n.to_string_lossy())).unwrap_or_default();
+    let folders: Vec<String> = path
+        .ancestors()
+        .skip(1)
+        .filter_map(|dir| dir.file_name())
+        .take(PREFIX_FOLDER_DEPTH)
+        .map(|dir| words(&dir.to_string_lossy()))
+        .filter(|dir| !dir.is_empty())
+        .collect();
+    if folders.is_empty() {
+        name
+    } else {
+        format!("{} ({})", name, folders.join(" / "))
+    }
+}
+
+/// The text embedded for a file: its content, optionally led by its name
+fn text_for_embedding(path: &Path, text: &str, embed_file_names: bool) -> String {
+    if embed_file_names {
+        format!("{}\n\n{}", file_name_prefix(path), text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Whether extracted text is too short to produce a meaningful embedding
+pub(crate) fn is_text_too_short(text: &str, min_text_chars: usize) -> bool {
+    text.trim().chars().count() < min_text_chars
+}
+
+/// Whether extracted text is worth embedding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ContentCheck {
+    /// Nothing but whitespace, or no bytes at all
+    Empty,
+    /// Fewer than `min_text_chars` characters once trimmed
+    TooShort,
+    Meaningful,
+}
+
+/// The gate every indexing path (batch, Downloads and the watcher) runs
+/// extracted text through before embedding it
+pub(crate) fn check_content(text: &str, min_text_chars: usize) -> ContentCheck {
+    if text.trim().is_empty() {
+        ContentCheck::Empty
+    } else if is_text_too_short(text, min_text_chars) {
+        ContentCheck::TooShort
+    } else {
+        ContentCheck::Meaningful
+    }
+}
+
+/// Zero-byte files are skipped before extraction, which some formats reject outright
+pub(crate) fn is_zero_byte_file(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|metadata| metadata.len() == 0)
+}
+
+/// Picks the text embedding model for a file from its category
+fn text_model_for_path(path: &Path) -> TextModel {
+    match categorize_file(&path.to_path_buf()) {
+        FileCategory::Code => TextModel::Code,
+        _ => TextModel::Default,
+    }
+}
+
+/// Loads the content hash of every indexed file, keyed by path
+async fn load_indexed_hashes(conn: &Connection) -> Result<HashMap<String, String>, String> {
+    let documents = list_indexed_documents(conn, None, DocumentSortBy::Path).await.map_err(|e| {
+        error!("Failed to list indexed documents: {}", e);
+        format!("Failed to list indexed documents: {}", e)
+    })?;
+    Ok(documents
+        .into_iter()
+        .map(|doc| (doc.file_path, doc.content_hash))
+        .collect())
+}
+
 /// Index a specific folder with parallel processing for text and image files
 pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
+    index_folder_with_options(folder_path, &IndexOptions::default()).await
+}
+
+/// Index a specific folder with the given [`IndexOptions`]
+pub async fn index_folder_with_options(folder_path: &str, options: &IndexOptions) -> Result<IndexingStats, String> {
+    let _slot = acquire_indexing_slot(folder_path, options.queue_if_busy).await?;
+    // Open connection to database
+    let conn = connect_db().await.map_err(|e| {
+        error!("Failed to connect to database: {}", e);
+        format!("Database connection error: {}", e)
+    })?;
+    index_folder_in_db(&conn, folder_path, options).await
+}
+
+/// Indexes `folder_path` into the tables of the given connection
+pub(crate) async fn index_folder_in_db(
+    conn: &Connection,
+    folder_path: &str,
+    options: &IndexOptions,
+) -> Result<IndexingStats, String> {
     let start_time = Instant::now();
     
     // Ensure the directory exists
@@ -459,6 +1241,15 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
         error!("Directory does not exist at {}", folder_path);
         return Err(format!("Directory not found: {}", folder_path));
     }
+    let _live_stats = LiveStatsRun::start(start_time);
+
+    if options.metadata_only {
+        let follow_symlinks = options.follow_symlinks.unwrap_or_else(|| current_settings().follow_symlinks);
+        let stats = index_folder_metadata(conn, path, follow_symlinks, start_time).await?;
+        set_last_indexing_stats(stats.clone());
+        log_permission_denied_summary(&stats.permission_denied_paths);
+        return Ok(stats);
+    }
     
     info!("Starting folder indexing with parallel processing: {}", folder_path);
     info!("Excluding system folders and application bundles from indexing");
@@ -466,72 +1257,61 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
     // Initialize file lists for parallel processing
     let mut english_text_data_to_process: Vec<(String, String, Vec<Vec<f32>>)> = Vec::new(); // Path, Hash, Embeddings
     let mut amharic_text_data_to_process: Vec<(String, String, Vec<Vec<f32>>)> = Vec::new(); // Path, Hash, Embeddings
+    let mut code_text_data_to_process: Vec<(String, String, Vec<Vec<f32>>)> = Vec::new(); // Path, Hash, Embeddings
     let mut image_files: Vec<String> = Vec::new(); // Paths for images
     let mut files_skipped = 0;
     let mut files_failed_preprocessing = 0; // Added for errors during initial scan/extraction/embedding
-    
-    // Open connection to database
-    let conn = connect_db().await.map_err(|e| {
-        error!("Failed to connect to database: {}", e);
-        format!("Database connection error: {}", e)
-    })?;
+    let mut failed_preprocessing_files: Vec<String> = Vec::new();
+    let mut files_unchanged = 0;
+    let mut files_too_short = 0;
+    let mut files_empty = 0;
+    let mut streamed_files: Vec<String> = Vec::new(); // Large files already written while scanning
+    let mut unindexable_paths: Vec<String> = Vec::new(); // Empty or too short; old rows must go
+    let mut permission_denied_paths: Vec<String> = Vec::new();
+    // Term counts of collected files, recorded for those that get stored
+    let mut term_batch = TermFrequencyBatch::default();
+    let min_text_chars = current_settings().min_text_chars;
+    let streaming_threshold_bytes = current_settings().streaming_threshold_bytes;
+    let embed_file_names = options.embed_file_names.unwrap_or_else(|| current_settings().embed_file_names);
     
     // Access or create the tables
-    let text_table = open_or_create_text_table(&conn).await.map_err(|e| {
+    let text_table = open_or_create_text_table(conn).await.map_err(|e| {
         error!("Failed to open or create text table: {}", e);
         format!("Text table error: {}", e)
     })?;
     
-    let image_table = open_or_create_image_table(&conn).await.map_err(|e| {
+    let image_table = open_or_create_image_table(conn).await.map_err(|e| {
         error!("Failed to open or create image table: {}", e);
         format!("Image table error: {}", e)
     })?;
 
-    let amharic_text_table = open_or_create_amharic_text_table(&conn).await.map_err(|e| {
-        error!("Failed to open or create Amharic text table: {}", e);
-        format!("Amharic text table error: {}", e)
+    let (amharic_text_table, amharic_table_error) = open_amharic_table_for_run(conn).await;
+
+    let code_text_table = open_or_create_code_table(conn).await.map_err(|e| {
+        error!("Failed to open or create code table: {}", e);
+        format!("Code table error: {}", e)
     })?;
+
+    // Snapshot of what is already indexed, only needed to skip unchanged files
+    let indexed_hashes = if options.only_new && !options.force {
+        load_indexed_hashes(conn).await?
+    } else {
+        HashMap::new()
+    };
     
     // Wrap tables in Arc to make them thread-safe
     let text_table_arc = Arc::new(text_table);
     let image_table_arc = Arc::new(image_table);
-    let amharic_text_table_arc = Arc::new(amharic_text_table); // Added
+    let amharic_text_table_arc = amharic_text_table.map(Arc::new);
+    let mut files_amharic_skipped: u32 = 0;
+    let mut files_truncated: u32 = 0;
+    let code_text_table_arc = Arc::new(code_text_table);
     
     // First pass: collect files by type
     info!("Scanning directory and categorizing files...");
-    for entry in WalkDir::new(path)
-        .follow_links(false)
-        .into_iter()
-        .filter_entry(|e| {
-            // Skip hidden files and directories
-            if let Some(file_name) = e.file_name().to_str() {
-                if file_name.starts_with(".") {
-                    return false;
-                }
-            }
-            
-            // Skip directories in the excluded list
-            if e.file_type().is_dir() {
-                if let Some(dir_name) = e.file_name().to_str() {
-                    if EXCLUDED_DIRS.iter().any(|excluded| dir_name.contains(excluded)) {
-                        debug!("Skipping excluded directory: {}", e.path().display());
-                        return false;
-                    }
-                }
-            }
-            
-            // Skip macOS application bundles and system extensions
-            if e.path().is_dir() {
-                if let Some(path_str) = e.path().to_str() {
-                    if EXCLUDED_PATTERNS.iter().any(|pattern| path_str.contains(pattern)) {
-                        debug!("Skipping macOS bundle: {}", e.path().display());
-                        return false;
-                    }
-                }
-            }
-            
-            true
-        }) {
+    let follow_symlinks = options.follow_symlinks.unwrap_or_else(|| current_settings().follow_symlinks);
+    let mut files_scanned: u32 = 0;
+    for entry in walk_entries(path, follow_symlinks) {
         match entry {
             Ok(entry) => {
                 let path = entry.path();
@@ -540,23 +1320,119 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
                 if path.is_dir() {
                     continue;
                 }
+
+                files_scanned += 1;
+                if files_scanned % SIZE_CHECK_INTERVAL == 0 {
+                    let pending = pending_index_bytes(
+                        &[&english_text_data_to_process, &amharic_text_data_to_process, &code_text_data_to_process],
+                        image_files.len(),
+                    );
+                    ensure_index_room(conn, pending, Some(folder_path)).await?;
+                }
+                if files_scanned % LIVE_STATS_INTERVAL == 0 {
+                    // Collected files are counted as processed; they are written after the scan
+                    let text_files_collected = english_text_data_to_process.len()
+                        + amharic_text_data_to_process.len()
+                        + code_text_data_to_process.len()
+                        + streamed_files.len();
+                    publish_live_stats(
+                        IndexingStats {
+                            files_processed: files_scanned,
+                            files_failed: files_failed_preprocessing,
+                            files_skipped,
+                            text_files_processed: text_files_collected as u32,
+                            text_files_failed: files_failed_preprocessing,
+                            image_files_processed: image_files.len() as u32,
+                            files_unchanged,
+                            files_too_short,
+                            files_empty,
+                            files_permission_denied: permission_denied_paths.len() as u32,
+                            ..Default::default()
+                        },
+                        start_time,
+                    );
+                }
                 
                 // Determine content type and add to appropriate list
                 let content_type = get_content_type(path);
                 match content_type {
+                    ContentType::Text if should_stream(path, streaming_threshold_bytes) => {
+                        let outcome = index_streamed_text_file(
+                            path,
+                            &text_table_arc,
+                            amharic_text_table_arc.as_deref(),
+                            indexed_hashes.get(&*path.to_string_lossy()),
+                            min_text_chars,
+                            MAX_STREAMED_CHUNKS,
+                            embed_file_names,
+                            Some(folder_path),
+                        )
+                        .await;
+                        match outcome {
+                            Ok(StreamedOutcome::Indexed) => streamed_files.push(path.to_string_lossy().to_string()),
+                            Ok(StreamedOutcome::Truncated) => {
+                                streamed_files.push(path.to_string_lossy().to_string());
+                                files_truncated += 1;
+                            }
+                            Ok(StreamedOutcome::Unchanged) => files_unchanged += 1,
+                            Ok(StreamedOutcome::TooShort) => {
+                                files_too_short += 1;
+                                unindexable_paths.push(path.to_string_lossy().to_string());
+                            }
+                            Ok(StreamedOutcome::Unsupported) => files_skipped += 1,
+                            Ok(StreamedOutcome::AmharicSkipped) => files_amharic_skipped += 1,
+                            Err(e) => {
+                                error!("Failed to stream text file {}: {}", path.display(), e);
+                                files_failed_preprocessing += 1;
+                                failed_preprocessing_files.push(path.display().to_string());
+                            }
+                        }
+                    }
                     ContentType::Text => {
                         let file_path_display = path.display().to_string(); // For logging
+                        if is_zero_byte_file(path) {
+                            files_empty += 1;
+                            unindexable_paths.push(path.to_string_lossy().to_string());
+                            continue;
+                        }
                         match extract_text(path) {
                             Ok(extraction_result) => {
+                                match check_content(&extraction_result.text, min_text_chars) {
+                                    ContentCheck::Meaningful => {}
+                                    ContentCheck::Empty => {
+                                        debug!("Skipping {}: no text content", file_path_display);
+                                        files_empty += 1;
+                                        unindexable_paths.push(path.to_string_lossy().to_string());
+                                        continue;
+                                    }
+                                    ContentCheck::TooShort => {
+                                        debug!("Skipping {}: extracted text shorter than {} characters", file_path_display, min_text_chars);
+                                        files_too_short += 1;
+                                        unindexable_paths.push(path.to_string_lossy().to_string());
+                                        continue;
+                                    }
+                                }
                                 let content_hash = calculate_hash(&extraction_result.text);
+                                if indexed_hashes.get(&*path.to_string_lossy()) == Some(&content_hash) {
+                                    debug!("Skipping unchanged, already indexed file: {}", file_path_display);
+                                    files_unchanged += 1;
+                                    continue;
+                                }
                                 // embed_text expects Vec<String>, even if it's just one document
-                                let content_for_embedding = vec![extraction_result.text.clone()]; 
-                                match embed_text(&content_for_embedding, &extraction_result.language, false) {
+                                let content_for_embedding =
+                                    vec![text_for_embedding(path, &extraction_result.text, embed_file_names)];
+                                let text_model = text_model_for_path(path);
+                                let embed_result = match text_model {
+                                    TextModel::Code => embed_code(&content_for_embedding, false),
+                                    TextModel::Default => embed_text(&content_for_embedding, &extraction_result.language, false),
+                                };
+                                match embed_result {
                                     Ok(embeddings) => {
                                         // embed_text returns Vec<Vec<f32>>, one inner Vec per input string
                                         if embeddings.is_empty() || embeddings[0].is_empty() {
                                             error!("No embeddings generated for text file: {}", file_path_display);
                                             files_failed_preprocessing += 1;
+                                            failed_preprocessing_files.push(file_path_display.clone());
                                         } else {
                                             // We passed one string, so we expect one Vec<f32> in the outer Vec.
                                             // The db upsert functions expect &[Vec<f32>], which is effectively Vec<Vec<f32>> for multiple chunks of ONE document.
@@ -567,11 +1443,15 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
                                             // The db functions (upsert_document, upsert_amharic_document) take &[Vec<f32>] where each Vec<f32> is an embedding for a chunk.
                                             // So, 'embeddings' from embed_text (which is Vec<Vec<f32>>) fits this directly.
                                             let data_tuple = (path.to_string_lossy().to_string(), content_hash, embeddings);
-                                            match extraction_result.language {
-                                                DetectedLanguage::English | DetectedLanguage::Other => {
+                                            term_batch.add(&data_tuple.0, &extraction_result.text);
+                                            match (text_model, extraction_result.language) {
+                                                (TextModel::Code, _) => {
+                                                    code_text_data_to_process.push(data_tuple);
+                                                }
+                                                (TextModel::Default, DetectedLanguage::English | DetectedLanguage::Other) => {
                                                     english_text_data_to_process.push(data_tuple);
                                                 }
-                                                DetectedLanguage::Amharic => {
+                                                (TextModel::Default, DetectedLanguage::Amharic) => {
                                                     amharic_text_data_to_process.push(data_tuple);
                                                 }
                                             }
@@ -580,24 +1460,109 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
                                     Err(e) => {
                                         error!("Failed to embed text for {}: {}", file_path_display, e);
                                         files_failed_preprocessing += 1;
+                                        failed_preprocessing_files.push(file_path_display.clone());
                                     }
                                 }
                             }
+                            Err(ExtractorError::UnsupportedFileType(reason)) => {
+                                debug!("Skipping {}: {}", file_path_display, reason);
+                                files_skipped += 1;
+                            }
+                            Err(e) if is_extraction_permission_denied(&e) => {
+                                debug!("Permission denied reading {}", file_path_display);
+                                permission_denied_paths.push(file_path_display.clone());
+                            }
                             Err(e) => {
                                 error!("Failed to extract text from {}: {}", file_path_display, e);
                                 files_failed_preprocessing += 1;
+                                failed_preprocessing_files.push(file_path_display.clone());
                             }
                         }
                     },
                     ContentType::Image => {
-                        image_files.push(path.to_string_lossy().to_string());
+                        let path_string = path.to_string_lossy().to_string();
+                        if let Some(indexed_hash) = indexed_hashes.get(&path_string) {
+                            if calculate_file_hash(path).ok().as_ref() == Some(indexed_hash) {
+                                debug!("Skipping unchanged, already indexed image: {}", path.display());
+                                files_unchanged += 1;
+                                continue;
+                            }
+                        }
+                        image_files.push(path_string);
                     },
+                    ContentType::Unsupported if options.include_archives && is_supported_archive(path) => {
+                        let members = match extract_archive_texts(path) {
+                            Ok(members) => members,
+                            Err(e) => {
+                                warn!("Skipping archive {}: {}", path.display(), e);
+                                files_skipped += 1;
+                                continue;
+                            }
+                        };
+                        for member in members {
+                            match check_content(&member.text, min_text_chars) {
+                                ContentCheck::Meaningful => {}
+                                ContentCheck::Empty => {
+                                    files_empty += 1;
+                                    unindexable_paths.push(member.composite_path);
+                                    continue;
+                                }
+                                ContentCheck::TooShort => {
+                                    files_too_short += 1;
+                                    unindexable_paths.push(member.composite_path);
+                                    continue;
+                                }
+                            }
+                            let content_hash = calculate_hash(&member.text);
+                            if indexed_hashes.get(&member.composite_path) == Some(&content_hash) {
+                                files_unchanged += 1;
+                                continue;
+                            }
+                            let text_model = text_model_for_path(Path::new(&member.composite_path));
+                            let content_for_embedding = vec![text_for_embedding(
+                                Path::new(&member.composite_path),
+                                &member.text,
+                                embed_file_names,
+                            )];
+                            let embed_result = match text_model {
+                                TextModel::Code => embed_code(&content_for_embedding, false),
+                                TextModel::Default => embed_text(&content_for_embedding, &member.language, false),
+                            };
+                            match embed_result {
+                                Ok(embeddings) if !embeddings.is_empty() && !embeddings[0].is_empty() => {
+                                    term_batch.add(&member.composite_path, &member.text);
+                                    let data_tuple = (member.composite_path, content_hash, embeddings);
+                                    match (text_model, member.language) {
+                                        (TextModel::Code, _) => code_text_data_to_process.push(data_tuple),
+                                        (TextModel::Default, DetectedLanguage::Amharic) => {
+                                            amharic_text_data_to_process.push(data_tuple)
+                                        }
+                                        (TextModel::Default, _) => english_text_data_to_process.push(data_tuple),
+                                    }
+                                }
+                                Ok(_) => {
+                                    error!("No embeddings generated for archive member: {}", member.composite_path);
+                                    files_failed_preprocessing += 1;
+                                    failed_preprocessing_files.push(member.composite_path);
+                                }
+                                Err(e) => {
+                                    error!("Failed to embed archive member {}: {}", member.composite_path, e);
+                                    files_failed_preprocessing += 1;
+                                    failed_preprocessing_files.push(member.composite_path);
+                                }
+                            }
+                        }
+                    }
                     ContentType::Unsupported => {
                         debug!("Skipping unsupported file type: {}", path.display());
                         files_skipped += 1;
                     }
                 }
             },
+            Err(e) if is_permission_denied(&e) => {
+                debug!("Permission denied while walking: {}", e);
+                permission_denied_paths.push(e.path().map(|p| p.display().to_string()).unwrap_or_default());
+            }
             Err(e) => {
                 error!("Error walking directory: {}", e);
             }
@@ -607,33 +1572,94 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
     // Log collection summary
     info!("Found {} English/Other text items, {} Amharic text items, and {} image files to process. {} files failed pre-processing.", 
           english_text_data_to_process.len(), amharic_text_data_to_process.len(), image_files.len(), files_failed_preprocessing);
+    info!("Found {} source code items for the code model.", code_text_data_to_process.len());
     
+    let mut text_tables = vec![&*text_table_arc, &*code_text_table_arc];
+    text_tables.extend(amharic_text_table_arc.as_deref());
+    remove_unindexable_documents(&text_tables, &unindexable_paths).await?;
+
+    // Nothing collected is written yet; make sure all of it fits
+    let pending = pending_index_bytes(
+        &[&english_text_data_to_process, &amharic_text_data_to_process, &code_text_data_to_process],
+        image_files.len(),
+    );
+    ensure_index_room(conn, pending, Some(folder_path)).await?;
+
     // Second pass: process files in parallel using separate threads
     info!("Starting parallel processing of files...");
+    let text_files_collected = english_text_data_to_process.len()
+        + amharic_text_data_to_process.len()
+        + code_text_data_to_process.len()
+        + streamed_files.len();
+    let progress = Arc::new(WriteProgress {
+        scanned: IndexingStats {
+            files_processed: files_scanned,
+            files_failed: files_failed_preprocessing,
+            files_skipped,
+            // Streamed files were written during the scan
+            db_inserts: streamed_files.len() as u32,
+            text_files_processed: text_files_collected as u32,
+            text_files_indexed: streamed_files.len() as u32,
+            text_files_failed: files_failed_preprocessing,
+            image_files_processed: image_files.len() as u32,
+            files_unchanged,
+            files_too_short,
+            files_empty,
+            files_truncated,
+            files_permission_denied: permission_denied_paths.len() as u32,
+            ..Default::default()
+        },
+        written: AtomicU32::new(0),
+        failed: AtomicU32::new(0),
+        start_time,
+    });
+    progress.record(0, 0);
 
     // Define futures for each type of processing.
     let english_text_task_handle;
     if !english_text_data_to_process.is_empty() {
         let table_for_task = Arc::clone(&text_table_arc);
         let data_for_task = english_text_data_to_process.clone(); // Clone data for the task
+        let root_for_task = Some(folder_path.to_string());
+        let progress_for_task = Arc::clone(&progress);
         english_text_task_handle = task::spawn(async move {
-            handle_specific_language_text_indexing(data_for_task, table_for_task, "English/Other").await
+            handle_specific_language_text_indexing(data_for_task, table_for_task, "English/Other", TextModel::Default, root_for_task, progress_for_task).await
         });
     } else {
         english_text_task_handle = task::spawn(async move { HashMap::new() }); // Dummy task
     }
 
+    // Without the Amharic table there is nowhere to write these
+    if amharic_text_table_arc.is_none() && !amharic_text_data_to_process.is_empty() {
+        files_amharic_skipped += amharic_text_data_to_process.len() as u32;
+        amharic_text_data_to_process.clear();
+    }
     let amharic_text_task_handle;
-    if !amharic_text_data_to_process.is_empty() {
-        let table_for_task = Arc::clone(&amharic_text_table_arc);
+    if let (Some(amharic_table), false) = (&amharic_text_table_arc, amharic_text_data_to_process.is_empty()) {
+        let table_for_task = Arc::clone(amharic_table);
         let data_for_task = amharic_text_data_to_process.clone(); // Clone data for the task
+        let root_for_task = Some(folder_path.to_string());
+        let progress_for_task = Arc::clone(&progress);
         amharic_text_task_handle = task::spawn(async move {
-            handle_specific_language_text_indexing(data_for_task, table_for_task, "Amharic").await
+            handle_specific_language_text_indexing(data_for_task, table_for_task, "Amharic", TextModel::Default, root_for_task, progress_for_task).await
         });
     } else {
         amharic_text_task_handle = task::spawn(async move { HashMap::new() }); // Dummy task
     }
 
+    let code_text_task_handle;
+    if !code_text_data_to_process.is_empty() {
+        let table_for_task = Arc::clone(&code_text_table_arc);
+        let data_for_task = code_text_data_to_process.clone(); // Clone data for the task
+        let root_for_task = Some(folder_path.to_string());
+        let progress_for_task = Arc::clone(&progress);
+        code_text_task_handle = task::spawn(async move {
+            handle_specific_language_text_indexing(data_for_task, table_for_task, "Code", TextModel::Code, root_for_task, progress_for_task).await
+        });
+    } else {
+        code_text_task_handle = task::spawn(async move { HashMap::new() }); // Dummy task
+    }
+
     let image_task_handle;
     if !image_files.is_empty() {
         let image_table_for_task = Arc::clone(&image_table_arc);
@@ -647,10 +1673,12 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
     let (
         english_text_join_result,
         amharic_text_join_result,
+        code_text_join_result,
         image_join_result
     ) = tokio::join!(
         english_text_task_handle,
         amharic_text_task_handle,
+        code_text_task_handle,
         image_task_handle
     );
 
@@ -659,15 +1687,20 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
     stats.files_skipped = files_skipped; // From the first pass (file categorization)
     // Add failures from the pre-processing (extraction/embedding) stage to text_files_failed
     stats.text_files_failed += files_failed_preprocessing; 
+    stats.failed_files.extend(failed_preprocessing_files);
+    stats.text_files_processed += streamed_files.len() as u32;
+    stats.indexed_files.extend(streamed_files);
 
     // Process English text results
     match english_text_join_result {
         Ok(map) => {
-            for (_path, res) in map {
+            for (path, res) in map {
                 if res.is_ok() {
                     stats.text_files_processed += 1;
+                    stats.indexed_files.push(path);
                 } else {
                     stats.text_files_failed += 1;
+                    stats.failed_files.push(path);
                 }
             }
         }
@@ -681,11 +1714,13 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
     // Process Amharic text results
     match amharic_text_join_result {
         Ok(map) => {
-            for (_path, res) in map {
+            for (path, res) in map {
                 if res.is_ok() {
                     stats.text_files_processed += 1; // Aggregating all text together for now
+                    stats.indexed_files.push(path);
                 } else {
                     stats.text_files_failed += 1;    // Aggregating all text together for now
+                    stats.failed_files.push(path);
                 }
             }
         }
@@ -695,14 +1730,35 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
         }
     }
 
+    // Process source code results
+    match code_text_join_result {
+        Ok(map) => {
+            for (path, res) in map {
+                if res.is_ok() {
+                    stats.text_files_processed += 1;
+                    stats.indexed_files.push(path);
+                } else {
+                    stats.text_files_failed += 1;
+                    stats.failed_files.push(path);
+                }
+            }
+        }
+        Err(e) => {
+            error!("Code processing task failed to join: {}", e);
+            stats.text_files_failed += code_text_data_to_process.len() as u32;
+        }
+    }
+
     // Process Image results
     match image_join_result {
         Ok(map) => {
-            for (_path, res) in map {
+            for (path, res) in map {
                 if res.is_ok() {
                     stats.image_files_processed += 1;
+                    stats.indexed_files.push(path);
                 } else {
                     stats.image_files_failed += 1;
+                    stats.failed_files.push(path);
                 }
             }
         }
@@ -712,6 +1768,15 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
         }
     }
 
+    // Post-run index size, so callers don't need a second round-trip for it
+    let (total_text_docs, total_image_docs, total_amharic_docs) = match get_vector_db_stats(conn).await {
+        Ok((text, image, amharic)) => (Some(text), Some(image), Some(amharic)),
+        Err(e) => {
+            warn!("Failed to count indexed documents after indexing: {}", e);
+            (None, None, None)
+        }
+    };
+
     let elapsed_time = start_time.elapsed();
     let final_stats = IndexingStats {
         elapsed_seconds: elapsed_time.as_secs() as u32,
@@ -729,14 +1794,34 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
         image_files_indexed: stats.image_files_processed, // Assume processed means indexed for now
         image_files_failed: stats.image_files_failed,
         
-        indexed_files: Vec::new(), // Not populated in current parallel logic
-        failed_files: Vec::new(),  // Not populated in current parallel logic
+        indexed_files: stats.indexed_files,
+        failed_files: stats.failed_files,
+        files_unchanged,
+        files_too_short,
+        files_empty,
+        total_text_docs,
+        total_image_docs,
+        total_amharic_docs,
+        files_permission_denied: permission_denied_paths.len() as u32,
+        permission_denied_paths,
+        amharic_table_error,
+        files_amharic_skipped,
+        files_truncated,
+        in_progress: false,
     };
+    if files_amharic_skipped > 0 {
+        warn!("Left {} Amharic documents out because the Amharic table is unusable", files_amharic_skipped);
+    }
+    term_batch.apply(&final_stats.indexed_files);
+    if let Err(e) = save_term_frequencies() {
+        warn!("Failed to save term frequencies: {}", e);
+    }
 
     set_last_indexing_stats(final_stats.clone());
+    log_permission_denied_summary(&final_stats.permission_denied_paths);
 
     info!(
-        "Indexing complete for '{}' in {}.{:03}s: {} files processed ({} text, {} images), {} DB inserts, {} skipped, {} total failed ({} text, {} images)",
+        "Indexing complete for '{}' in {}.{:03}s: {} files processed ({} text, {} images), {} DB inserts, {} skipped, {} unchanged, {} too short, {} empty, {} total failed ({} text, {} images)",
         folder_path,
         final_stats.elapsed_seconds,
         final_stats.elapsed_milliseconds,
@@ -745,6 +1830,9 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
         final_stats.image_files_processed,
         final_stats.db_inserts,
         final_stats.files_skipped,
+        final_stats.files_unchanged,
+        final_stats.files_too_short,
+        final_stats.files_empty,
         final_stats.files_failed,
         final_stats.text_files_failed,
         final_stats.image_files_failed
@@ -753,11 +1841,171 @@ pub async fn index_folder(folder_path: &str) -> Result<IndexingStats, String> {
     Ok(final_stats)
 }
 
+/// Number of real text files embedded to measure throughput for an estimate
+const ESTIMATE_TEXT_SAMPLES: usize = 5;
+/// Number of real images embedded to measure throughput for an estimate
+const ESTIMATE_IMAGE_SAMPLES: usize = 2;
+
+/// File counts and sizes per content type, gathered without extracting or embedding anything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FolderAnalysis {
+    pub text_files: u64,
+    pub text_bytes: u64,
+    pub image_files: u64,
+    pub image_bytes: u64,
+    pub skipped_files: u64,
+}
+
+/// Timing of one real file pushed through extraction and embedding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingSample {
+    pub file_path: String,
+    pub bytes: u64,
+    pub elapsed_ms: u64,
+}
+
+/// Projected indexing duration for a folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexingEstimate {
+    pub analysis: FolderAnalysis,
+    pub samples: Vec<EmbeddingSample>,
+    pub estimated_ms: u64,
+    /// Projection using the fastest sampled file of each type
+    pub low_ms: u64,
+    /// Projection using the slowest sampled file of each type
+    pub high_ms: u64,
+    /// No text file could be sampled, so text was projected from the last
+    /// embedding throughput benchmark
+    #[serde(default)]
+    pub from_benchmark: bool,
+}
+
+/// Fast pass over a folder: walks it with the indexing filters and tallies
+/// files and bytes per content type
+pub fn analyze_folder(folder_path: &Path) -> FolderAnalysis {
+    let mut analysis = FolderAnalysis::default();
+    for entry in walk_entries(folder_path, current_settings().follow_symlinks)
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+    {
+        let bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        match get_content_type(entry.path()) {
+            ContentType::Text => {
+                analysis.text_files += 1;
+                analysis.text_bytes += bytes;
+            }
+            ContentType::Image => {
+                analysis.image_files += 1;
+                analysis.image_bytes += bytes;
+            }
+            ContentType::Unsupported => analysis.skipped_files += 1,
+        }
+    }
+    analysis
+}
+
+/// Projects (estimate, low, high) milliseconds for `file_count` files from sampled per-file timings
+fn project_duration(sample_ms: &[u64], file_count: u64) -> (u64, u64, u64) {
+    if sample_ms.is_empty() || file_count == 0 {
+        return (0, 0, 0);
+    }
+    let mean = sample_ms.iter().sum::<u64>() as f64 / sample_ms.len() as f64;
+    let min = *sample_ms.iter().min().unwrap_or(&0);
+    let max = *sample_ms.iter().max().unwrap_or(&0);
+    ((mean * file_count as f64).round() as u64, min * file_count, max * file_count)
+}
+
+/// Times extraction and embedding of one text file, routed like the indexer would
+fn sample_text_file(path: &Path) -> Option<u64> {
+    let start = Instant::now();
+    let extraction_result = extract_text(path).ok()?;
+    let content = vec![extraction_result.text];
+    let embeddings = match text_model_for_path(path) {
+        TextModel::Code => embed_code(&content, false),
+        TextModel::Default => embed_text(&content, &extraction_result.language, false),
+    };
+    embeddings.ok()?;
+    Some(start.elapsed().as_millis() as u64)
+}
+
+/// Estimates how long indexing `folder_path` will take by combining the fast
+/// analysis pass with embedding timings sampled from a few real files
+pub fn estimate_indexing_time(folder_path: &Path) -> Result<IndexingEstimate, String> {
+    if !folder_path.is_dir() {
+        return Err(format!("Directory not found: {}", folder_path.display()));
+    }
+    let analysis = analyze_folder(folder_path);
+
+    // Load the models up front so their one-time startup isn't counted as per-file cost
+    let _ = embed_text(&["warm up".to_string()], &DetectedLanguage::English, true);
+
+    let mut samples = Vec::new();
+    let mut text_ms = Vec::new();
+    let mut image_ms = Vec::new();
+    for entry in walk_entries(folder_path, current_settings().follow_symlinks)
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+    {
+        if text_ms.len() >= ESTIMATE_TEXT_SAMPLES && image_ms.len() >= ESTIMATE_IMAGE_SAMPLES {
+            break;
+        }
+        let path = entry.path();
+        let elapsed_ms = match get_content_type(path) {
+            ContentType::Text if text_ms.len() < ESTIMATE_TEXT_SAMPLES => {
+                let Some(ms) = sample_text_file(path) else { continue };
+                text_ms.push(ms);
+                ms
+            }
+            ContentType::Image if image_ms.len() < ESTIMATE_IMAGE_SAMPLES => {
+                let start = Instant::now();
+                if embed_image(&path.to_string_lossy()).is_err() {
+                    continue;
+                }
+                let ms = start.elapsed().as_millis() as u64;
+                image_ms.push(ms);
+                ms
+            }
+            _ => continue,
+        };
+        samples.push(EmbeddingSample {
+            file_path: path.to_string_lossy().to_string(),
+            bytes: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            elapsed_ms,
+        });
+    }
+
+    let mut from_benchmark = false;
+    if text_ms.is_empty() && analysis.text_files > 0 {
+        if let Some(text) = crate::benchmark::last_embedding_throughput().and_then(|report| report.text) {
+            text_ms.push(text.average_latency_ms.round() as u64);
+            from_benchmark = true;
+        }
+    }
+    let (text_est, text_low, text_high) = project_duration(&text_ms, analysis.text_files);
+    let (image_est, image_low, image_high) = project_duration(&image_ms, analysis.image_files);
+    info!(
+        "Estimated indexing time for {}: ~{} ms ({} samples)",
+        folder_path.display(),
+        text_est + image_est,
+        samples.len()
+    );
+
+    Ok(IndexingEstimate {
+        analysis,
+        samples,
+        estimated_ms: text_est + image_est,
+        low_ms: text_low + image_low,
+        high_ms: text_high + image_high,
+        from_benchmark,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::tempdir;
     use crate::extractor::{get_content_type, ContentType}; // Added import
+    use crate::db::{connect_db_with_path, TestDb};
     use std::fs::File;
     use std::io::Write;
 
@@ -790,6 +2038,14 @@ mod tests {
         assert_eq!(get_content_type(&zip_path), ContentType::Unsupported, "ZIP should be Unsupported");
     }
     
+    #[test]
+    fn test_source_files_route_to_the_code_model() {
+        assert_eq!(text_model_for_path(Path::new("/src/main.rs")), TextModel::Code);
+        assert_eq!(text_model_for_path(Path::new("/web/App.TSX")), TextModel::Code);
+        assert_eq!(text_model_for_path(Path::new("/docs/notes.md")), TextModel::Default);
+        assert_eq!(text_model_for_path(Path::new("/docs/README")), TextModel::Default);
+    }
+
     #[test]
     fn test_content_type_detection() {
         // Text files
@@ -828,4 +2084,312 @@ mod tests {
         assert!(file_path.exists());
         assert_eq!(get_content_type(&file_path), ContentType::Image);
     }
+
+    #[tokio::test]
+    async fn test_only_new_skips_already_indexed_files() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("notes.txt")).unwrap();
+        write!(file, "Quarterly planning notes about the budget and the hiring roadmap.").unwrap();
+        let folder = dir.path().to_str().unwrap();
+
+        let options = IndexOptions { only_new: true, ..Default::default() };
+        let first = index_folder_in_db(&conn, folder, &options).await.expect("First indexing failed");
+        assert_eq!(first.text_files_processed, 1);
+        assert_eq!(first.files_unchanged, 0);
+        assert_eq!(first.total_text_docs, Some(1), "Totals should reflect the index after the run");
+        assert_eq!(first.total_image_docs, Some(0));
+        let documents = list_indexed_documents(&conn, None, DocumentSortBy::Path).await.unwrap();
+        assert_eq!(documents[0].index_root.as_deref(), Some(folder), "Rows should record the folder they were indexed under");
+
+        let second = index_folder_in_db(&conn, folder, &options).await.expect("Second indexing failed");
+        assert_eq!(second.text_files_processed, 0, "Nothing should be embedded on the second run");
+        assert_eq!(second.files_unchanged, 1);
+
+        let forced = IndexOptions { force: true, ..options };
+        let third = index_folder_in_db(&conn, folder, &forced).await.expect("Forced indexing failed");
+        assert_eq!(third.text_files_processed, 1, "force should re-process the unchanged file");
+        assert_eq!(third.files_unchanged, 0);
+        assert_eq!(third.total_text_docs, Some(1), "Re-processing replaces rows instead of adding them");
+    }
+
+    #[tokio::test]
+    async fn test_short_text_files_are_skipped() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("tiny.txt")).unwrap().write_all(b"abc").unwrap();
+        // Tests get their own app data dir, so these are the default settings
+        assert_eq!(current_settings().min_text_chars, crate::core::settings::AppSettings::default().min_text_chars);
+        assert!(current_settings().min_text_chars > 3);
+        assert!(is_text_too_short(" abc\n", 4));
+        assert!(!is_text_too_short("abcd", 4));
+
+        let stats = index_folder_in_db(&conn, dir.path().to_str().unwrap(), &IndexOptions::default())
+            .await
+            .expect("Indexing failed");
+        assert_eq!(stats.files_too_short, 1);
+        assert_eq!(stats.text_files_processed, 0);
+        assert!(stats.failed_files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_empty_and_whitespace_files_are_never_embedded() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("zero.txt")).unwrap();
+        File::create(dir.path().join("spaces.txt")).unwrap().write_all(b"   \n\t  \n").unwrap();
+        assert_eq!(check_content(" \n\t", 1), ContentCheck::Empty);
+        assert_eq!(check_content("abc", 10), ContentCheck::TooShort);
+
+        let stats = index_folder_in_db(&conn, dir.path().to_str().unwrap(), &IndexOptions::default())
+            .await
+            .expect("Indexing failed");
+        assert_eq!(stats.files_empty, 2);
+        assert_eq!(stats.files_too_short, 0);
+        assert_eq!(stats.text_files_processed, 0);
+        assert!(stats.failed_files.is_empty());
+        assert!(list_indexed_documents(&conn, None, DocumentSortBy::Path).await.unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlinked_folder_indexed_only_when_following() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+
+        let outside = tempdir().unwrap();
+        let mut file = File::create(outside.path().join("linked.txt")).unwrap();
+        write!(file, "Notes that are only reachable through a symbolic link.").unwrap();
+
+        let root = tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("shared")).unwrap();
+        // A link back to the root must not make the walk loop
+        std::os::unix::fs::symlink(root.path(), root.path().join("loop")).unwrap();
+        let folder = root.path().to_str().unwrap();
+
+        let not_following = IndexOptions { follow_symlinks: Some(false), ..Default::default() };
+        let stats = index_folder_in_db(&conn, folder, &not_following).await.expect("Indexing failed");
+        assert_eq!(stats.text_files_processed, 0);
+
+        let following = IndexOptions { follow_symlinks: Some(true), ..Default::default() };
+        let stats = index_folder_in_db(&conn, folder, &following).await.expect("Indexing failed");
+        assert_eq!(stats.text_files_processed, 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unreadable_folder_is_counted_and_siblings_indexed() {
+        use std::os::unix::fs::PermissionsExt;
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+
+        let root = tempdir().unwrap();
+        let mut file = File::create(root.path().join("readable.txt")).unwrap();
+        write!(file, "A readable note next to a folder the user cannot open.").unwrap();
+        let locked = root.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        File::create(locked.join("secret.txt")).unwrap().write_all(b"hidden contents of the locked folder").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        if fs::read_dir(&locked).is_ok() {
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+            println!(
+                "Skipping test_unreadable_folder_is_counted_and_siblings_indexed: \
+                 this user (likely root) reads through permission bits, so no folder can be made unreadable"
+            );
+            return;
+        }
+
+        let stats = index_folder_in_db(&conn, root.path().to_str().unwrap(), &IndexOptions::default()).await;
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        let stats = stats.expect("Indexing should continue past an unreadable folder");
+        assert_eq!(stats.text_files_processed, 1);
+        assert_eq!(stats.files_permission_denied, 1);
+        assert_eq!(stats.permission_denied_paths, vec![locked.display().to_string()]);
+        assert!(stats.failed_files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_name_prefix_lets_name_match_query() {
+        use crate::search::{multimodal_search, SearchContentType};
+
+        assert_eq!(
+            file_name_prefix(Path::new("/home/me/finance/q3_reports/budget-2024.xlsx")),
+            "budget 2024 xlsx (q3 reports / finance)"
+        );
+
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("budget-2024.txt")).unwrap();
+        write!(file, "Tomatoes need full sun, deep watering and a sturdy cage to climb.").unwrap();
+        let mut file = File::create(dir.path().join("notes.txt")).unwrap();
+        write!(file, "Roses want pruning in early spring and a thick layer of mulch.").unwrap();
+
+        let options = IndexOptions { embed_file_names: Some(true), ..Default::default() };
+        let stats = index_folder_in_db(&conn, dir.path().to_str().unwrap(), &options).await.expect("Indexing failed");
+        assert_eq!(stats.text_files_processed, 2);
+
+        let results = multimodal_search(&conn, "budget 2024", Some(2), Some(0.0), Some(SearchContentType::TextOnly))
+            .await
+            .expect("Search failed");
+        assert!(results[0].file_path.ends_with("budget-2024.txt"), "Got {:?}", results);
+    }
+
+    #[tokio::test]
+    async fn test_file_that_became_too_short_leaves_the_index() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("draft.txt");
+        fs::write(&path, "The garden plan covers tomatoes, roses and a new compost bin by the shed.").unwrap();
+        let folder = dir.path().to_str().unwrap();
+
+        index_folder_in_db(&conn, folder, &IndexOptions::default()).await.expect("Indexing failed");
+        let text_table = open_or_create_text_table(&conn).await.unwrap();
+        let filter = format!("file_path = {}", crate::db::sql_string_literal(&path.to_string_lossy()));
+        assert!(text_table.count_rows(Some(filter.clone())).await.unwrap() > 0);
+
+        fs::write(&path, "todo").unwrap();
+        let stats = index_folder_in_db(&conn, folder, &IndexOptions::default()).await.expect("Indexing failed");
+        assert_eq!(stats.files_too_short, 1);
+        assert_eq!(text_table.count_rows(Some(filter.clone())).await.unwrap(), 0);
+
+        // The same holds when the file is emptied and indexed on its own
+        fs::write(&path, "The garden plan covers tomatoes, roses and a new compost bin by the shed.").unwrap();
+        index_single_file(&conn, &path, Some(folder)).await.expect("Indexing failed");
+        fs::write(&path, "").unwrap();
+        assert_eq!(index_single_file(&conn, &path, Some(folder)).await.unwrap(), ContentCheck::Empty);
+        assert_eq!(text_table.count_rows(Some(filter)).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_large_text_file_is_indexed_by_streaming() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+        let text_table = open_or_create_text_table(&conn).await.unwrap();
+        let amharic_table = open_or_create_amharic_text_table(&conn).await.unwrap();
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("ledger.txt");
+        let mut file = std::io::BufWriter::new(File::create(&path).unwrap());
+        for i in 0..200_000 {
+            writeln!(file, "Entry {}: the shipment of oranges arrived at the harbour on time.", i).unwrap();
+        }
+        drop(file);
+        assert!(should_stream(&path, 4 * 1024 * 1024));
+        assert!(!should_stream(&path, u64::MAX));
+
+        // A small chunk cap keeps the test fast; the whole file is never held in memory
+        let outcome = index_streamed_text_file(&path, &text_table, Some(&amharic_table), None, 10, 4, false, None).await.unwrap();
+        assert_eq!(outcome, StreamedOutcome::Truncated);
+        let filter = format!("file_path = '{}'", path.to_string_lossy());
+        assert_eq!(text_table.count_rows(Some(filter.clone())).await.unwrap(), 4);
+
+        // Re-streaming replaces the old rows instead of adding to them
+        let outcome = index_streamed_text_file(&path, &text_table, Some(&amharic_table), None, 10, 4, false, None).await.unwrap();
+        assert_eq!(outcome, StreamedOutcome::Truncated);
+        assert_eq!(text_table.count_rows(Some(filter)).await.unwrap(), 4);
+
+        // Every row carries the real hash once the file is fully written
+        let hash = calculate_file_hash(&path).unwrap();
+        let stored = load_indexed_hashes(&conn).await.unwrap();
+        assert_eq!(stored.get(path.to_string_lossy().as_ref()), Some(&hash));
+        let outcome = index_streamed_text_file(&path, &text_table, Some(&amharic_table), Some(&hash), 10, 4, false, None).await.unwrap();
+        assert_eq!(outcome, StreamedOutcome::Unchanged);
+    }
+
+    #[tokio::test]
+    async fn test_indexing_continues_without_amharic_table() {
+        use arrow_array::{RecordBatch, RecordBatchIterator};
+        use arrow_schema::{DataType, Field, Schema};
+        use crate::db::AMHARIC_TEXT_TABLE_NAME;
+
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+        // A table under the Amharic name that no migration can make sense of
+        let legacy_schema = Arc::new(Schema::new(vec![Field::new("legacy", DataType::Utf8, false)]));
+        let reader = RecordBatchIterator::new(vec![Ok(RecordBatch::new_empty(legacy_schema.clone()))], legacy_schema);
+        conn.create_table(AMHARIC_TEXT_TABLE_NAME, Box::new(reader)).execute().await.unwrap();
+
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("garden.txt")).unwrap();
+        write!(file, "Tomatoes need full sun, deep watering and a sturdy cage to climb.").unwrap();
+
+        let stats = index_folder_in_db(&conn, dir.path().to_str().unwrap(), &IndexOptions::default())
+            .await
+            .expect("Indexing should go on without the Amharic table");
+        assert_eq!(stats.text_files_processed, 1);
+        assert!(stats.amharic_table_error.is_some());
+        assert_eq!(stats.files_amharic_skipped, 0);
+        assert!(!stats.in_progress);
+    }
+
+    #[tokio::test]
+    async fn test_indexing_slot_rejects_or_queues_second_run() {
+        let first = acquire_indexing_slot("/first", false).await.expect("Slot should be free");
+        assert_eq!(get_indexing_queue_status().running.as_deref(), Some("/first"));
+
+        let rejected = acquire_indexing_slot("/second", false).await;
+        assert!(rejected.err().unwrap().starts_with(INDEXING_BUSY_ERROR));
+
+        let waiting = tokio::spawn(async { acquire_indexing_slot("/third", true).await.map(|_| ()) });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(get_indexing_queue_status().queued, 1);
+
+        drop(first);
+        waiting.await.unwrap().expect("Queued run should get the slot");
+        let status = get_indexing_queue_status();
+        assert_eq!(status.queued, 0);
+        assert!(status.running.is_none());
+    }
+
+    #[test]
+    fn test_walk_skips_hidden_entries_unless_included() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".config")).unwrap();
+        File::create(dir.path().join("notes.txt")).unwrap();
+        File::create(dir.path().join(".profile.txt")).unwrap();
+        File::create(dir.path().join(".config/app.txt")).unwrap();
+
+        let files = |include_hidden| {
+            let mut names: Vec<String> = walk_entries_filtered(dir.path(), false, usize::MAX, include_hidden)
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(files(false), vec!["notes.txt"]);
+        assert_eq!(files(true), vec![".profile.txt", "app.txt", "notes.txt"]);
+    }
+
+    #[test]
+    fn test_analyze_folder_counts_by_type() {
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("a.txt")).unwrap().write_all(b"hello").unwrap();
+        File::create(dir.path().join("b.png")).unwrap().write_all(b"png!!!").unwrap();
+        File::create(dir.path().join("c.zip")).unwrap();
+        File::create(dir.path().join(".hidden.txt")).unwrap();
+
+        let analysis = analyze_folder(dir.path());
+        assert_eq!(analysis.text_files, 1);
+        assert_eq!(analysis.text_bytes, 5);
+        assert_eq!(analysis.image_files, 1);
+        assert_eq!(analysis.image_bytes, 6);
+        assert_eq!(analysis.skipped_files, 1);
+    }
+
+    #[test]
+    fn test_project_duration() {
+        assert_eq!(project_duration(&[], 100), (0, 0, 0));
+        assert_eq!(project_duration(&[10, 20, 30], 0), (0, 0, 0));
+        assert_eq!(project_duration(&[10, 20, 30], 100), (2000, 1000, 3000));
+    }
 }
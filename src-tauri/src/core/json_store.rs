@@ -0,0 +1,73 @@
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Reads `T` from the JSON file at `path`, starting from `T::default()` if
+/// the path can't be resolved or the file is missing or unreadable. `what`
+/// names the file in the warnings.
+pub(crate) fn load_json_or_default<T: DeserializeOwned + Default>(path: Result<PathBuf, String>, what: &str) -> T {
+    let path = match path {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("{}; starting with no {}", e, what);
+            return T::default();
+        }
+    };
+    if !path.exists() {
+        return T::default();
+    }
+    match fs::read_to_string(&path).map(|json| serde_json::from_str::<T>(&json)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(e)) => {
+            warn!("Ignoring malformed {} file {}: {}", what, path.display(), e);
+            T::default()
+        }
+        Err(e) => {
+            warn!("Failed to read {} file {}: {}", what, path.display(), e);
+            T::default()
+        }
+    }
+}
+
+/// Writes `value` to `path` as compact JSON, creating its folder if needed
+pub(crate) fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T, what: &str) -> Result<(), String> {
+    let json = serde_json::to_string(value).map_err(|e| format!("Failed to serialize {}: {}", what, e))?;
+    write_json(path, json)
+}
+
+/// Like `save_json`, for files people may read or edit by hand
+pub(crate) fn save_json_pretty<T: Serialize + ?Sized>(path: &Path, value: &T, what: &str) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize {}: {}", what, e))?;
+    write_json(path, json)
+}
+
+fn write_json(path: &Path, json: String) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_json_round_trip_and_fallbacks() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("nested").join("values.json");
+
+        let missing: Vec<u32> = load_json_or_default(Ok(path.clone()), "values");
+        assert!(missing.is_empty());
+
+        save_json(&path, &[1u32, 2, 3], "values").unwrap();
+        assert_eq!(load_json_or_default::<Vec<u32>>(Ok(path.clone()), "values"), vec![1, 2, 3]);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(load_json_or_default::<Vec<u32>>(Ok(path), "values").is_empty());
+        assert!(load_json_or_default::<Vec<u32>>(Err("no app data dir".to_string()), "values").is_empty());
+    }
+}
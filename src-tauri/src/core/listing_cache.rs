@@ -0,0 +1,126 @@
+use crate::core::models::FileInfo;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Listings kept for quick filtering; the least recently listed folder goes first
+const MAX_CACHED_LISTINGS: usize = 16;
+
+/// A folder listing and the token that refers to it in `quick_filter`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryListing {
+    /// Derived from the folder path, so listing the same folder again keeps it
+    pub token: String,
+    pub items: Vec<FileInfo>,
+}
+
+static LISTINGS: Lazy<Mutex<VecDeque<DirectoryListing>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+fn listing_token(path: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Remembers `items` as the listing of `path`, replacing an older listing of
+/// the same folder, and returns it with its token
+pub fn cache_listing(path: &str, items: Vec<FileInfo>) -> DirectoryListing {
+    let listing = DirectoryListing {
+        token: listing_token(path),
+        items,
+    };
+    let mut listings = LISTINGS.lock().unwrap();
+    listings.retain(|cached| cached.token != listing.token);
+    if listings.len() >= MAX_CACHED_LISTINGS {
+        listings.pop_front();
+    }
+    listings.push_back(listing.clone());
+    listing
+}
+
+/// Lowercased with accents stripped, so "Résumé" matches "resume"
+fn fold(text: &str) -> String {
+    text.nfd().filter(|c| !is_combining_mark(*c)).flat_map(char::to_lowercase).collect()
+}
+
+/// How well `name` matches a folded query, lower is better
+fn match_rank(name: &str, folded_query: &str) -> Option<u8> {
+    let name = fold(name);
+    if name.starts_with(folded_query) {
+        Some(0)
+    } else if name
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| word.starts_with(folded_query))
+    {
+        Some(1)
+    } else if name.contains(folded_query) {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Entries whose names match `query`: names starting with it first, then
+/// names with a word starting with it, then names containing it anywhere.
+/// Each group keeps the listing's order. An empty query matches everything.
+pub fn filter_items(items: &[FileInfo], query: &str) -> Vec<FileInfo> {
+    let folded_query = fold(query.trim());
+    if folded_query.is_empty() {
+        return items.to_vec();
+    }
+    let mut matches: Vec<(u8, &FileInfo)> = items
+        .iter()
+        .filter_map(|item| match_rank(&item.name, &folded_query).map(|rank| (rank, item)))
+        .collect();
+    // Stable, so ties stay in listing order
+    matches.sort_by_key(|(rank, _)| *rank);
+    matches.into_iter().map(|(_, item)| item.clone()).collect()
+}
+
+/// Filters a cached listing without touching the disk. `None` when the token
+/// is unknown or its listing was evicted; list the folder again then.
+pub fn quick_filter(token: &str, query: &str) -> Option<Vec<FileInfo>> {
+    let listings = LISTINGS.lock().unwrap();
+    let listing = listings.iter().find(|listing| listing.token == token)?;
+    Some(filter_items(&listing.items, query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str) -> FileInfo {
+        FileInfo {
+            name: name.to_string(),
+            path: format!("/docs/{}", name),
+            is_directory: false,
+            size: Some(1),
+            modified: None,
+            file_type: "Document".to_string(),
+            thumbnail_path: None,
+            is_symlink: false,
+            symlink_target: None,
+            icon_key: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_filter_ranks_prefix_then_word_then_substring() {
+        let items = vec![item("old-report.pdf"), item("Résumé.docx"), item("Report 2024.pdf"), item("preport.txt")];
+        let names = |found: Vec<FileInfo>| found.into_iter().map(|i| i.name).collect::<Vec<_>>();
+
+        assert_eq!(names(filter_items(&items, "rep")), vec!["Report 2024.pdf", "old-report.pdf", "preport.txt"]);
+        assert_eq!(names(filter_items(&items, "RESUME")), vec!["Résumé.docx"]);
+        assert_eq!(filter_items(&items, "  ").len(), 4);
+
+        let listing = cache_listing("/docs", items.clone());
+        assert_eq!(cache_listing("/docs", items).token, listing.token);
+        assert_eq!(names(quick_filter(&listing.token, "résu").unwrap()), vec!["Résumé.docx"]);
+        assert!(quick_filter("unknown", "a").is_none());
+    }
+}
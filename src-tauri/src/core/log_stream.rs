@@ -0,0 +1,187 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Filter, Layer};
+
+/// Event the UI listens on for forwarded log records
+pub const APP_LOG_EVENT: &str = "app-log";
+
+/// One log record as sent to the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+struct LogSubscription {
+    app_handle: AppHandle,
+    level: Level,
+}
+
+static SUBSCRIPTION: Lazy<RwLock<Option<LogSubscription>>> = Lazy::new(|| RwLock::new(None));
+
+thread_local! {
+    // Set while a record is being emitted, so logging done by the emit itself
+    // isn't forwarded again
+    static FORWARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Parses "error", "warn", "info", "debug" or "trace", case-insensitively
+pub fn parse_log_level(level: &str) -> Result<Level, String> {
+    Level::from_str(level.trim()).map_err(|_| {
+        format!(
+            "Unknown log level '{}': expected error, warn, info, debug or trace",
+            level
+        )
+    })
+}
+
+/// Starts forwarding records at `level` or more severe to the UI. Calling it
+/// again while subscribed just changes the level.
+pub fn subscribe_logs(app_handle: AppHandle, level: Level) {
+    *SUBSCRIPTION.write().unwrap() = Some(LogSubscription { app_handle, level });
+    // Lets `ForwardFilter::max_level_hint` raise or lower the global level
+    tracing::callsite::rebuild_interest_cache();
+}
+
+/// Stops forwarding. Returns false when nothing was subscribed.
+pub fn unsubscribe_logs() -> bool {
+    let was_subscribed = SUBSCRIPTION.write().unwrap().take().is_some();
+    tracing::callsite::rebuild_interest_cache();
+    was_subscribed
+}
+
+/// Most verbose level forwarded; `OFF` while nothing is subscribed
+fn forwarded_level() -> LevelFilter {
+    SUBSCRIPTION
+        .read()
+        .unwrap()
+        .as_ref()
+        .map_or(LevelFilter::OFF, |subscription| LevelFilter::from_level(subscription.level))
+}
+
+/// Filter for [`LogForwardLayer`]. Without it the layer would enable every
+/// level, so even `trace!` records everywhere would be built just to be
+/// dropped; with it nothing reaches the layer above the subscribed level.
+pub struct ForwardFilter;
+
+impl<S> Filter<S> for ForwardFilter {
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: &Context<'_, S>) -> bool {
+        *metadata.level() <= forwarded_level()
+    }
+
+    fn callsite_enabled(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        // The level changes at runtime, so ask again for every record
+        Interest::sometimes()
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(forwarded_level())
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: Vec<String>,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{:?}", value),
+            // Added by the log bridge; the target is reported separately
+            name if name.starts_with("log.") => {}
+            name => self.fields.push(format!("{}={:?}", name, value)),
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+}
+
+impl MessageVisitor {
+    fn into_message(self) -> String {
+        if self.fields.is_empty() {
+            self.message
+        } else if self.message.is_empty() {
+            self.fields.join(" ")
+        } else {
+            format!("{} {}", self.message, self.fields.join(" "))
+        }
+    }
+}
+
+/// Forwards records to the subscribed window as `app-log` events. Installed
+/// once at startup behind a [`ForwardFilter`]; it does nothing until
+/// `subscribe_logs` is called.
+pub struct LogForwardLayer;
+
+impl<S: Subscriber> Layer<S> for LogForwardLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if FORWARDING.with(Cell::get) {
+            return;
+        }
+        // Records above the subscribed level are already held back by `ForwardFilter`
+        let Some(app_handle) = SUBSCRIPTION.read().unwrap().as_ref().map(|s| s.app_handle.clone()) else {
+            return;
+        };
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let record = LogRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            level: event.metadata().level().to_string(),
+            target: event.metadata().target().to_string(),
+            message: visitor.into_message(),
+        };
+
+        FORWARDING.with(|forwarding| forwarding.set(true));
+        // Reporting a failed emit through the log would loop straight back here
+        let _ = app_handle.emit(APP_LOG_EVENT, record);
+        FORWARDING.with(|forwarding| forwarding.set(false));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_level() {
+        assert_eq!(parse_log_level("WARN").unwrap(), Level::WARN);
+        assert_eq!(parse_log_level(" debug ").unwrap(), Level::DEBUG);
+        assert!(parse_log_level("verbose").is_err());
+        // Forwarding at INFO lets warnings through but not debug records
+        assert!(Level::WARN <= Level::INFO);
+        assert!(Level::DEBUG > Level::INFO);
+    }
+
+    #[test]
+    fn test_forward_filter_is_off_until_subscribed() {
+        let hint = Filter::<tracing_subscriber::Registry>::max_level_hint(&ForwardFilter);
+        assert_eq!(hint, Some(LevelFilter::OFF));
+        // Even errors are more verbose than OFF, so none get through
+        assert!(Level::ERROR > LevelFilter::OFF);
+    }
+}
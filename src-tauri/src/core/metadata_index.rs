@@ -0,0 +1,531 @@
+use super::file_system::get_file_type;
+use super::indexer::{is_permission_denied, publish_live_stats, walk_entries, IndexingStats, LIVE_STATS_INTERVAL};
+use super::models::FileInfo;
+use super::query::FileFilter;
+use crate::core::file_types::{categorize_file, file_type_info};
+use crate::db::{
+    bump_index_version, create_metadata_schema, escape_like, file_path_under_predicate, open_or_create_metadata_table,
+    sql_string_literal, METADATA_TABLE_NAME,
+};
+use arrow_array::{Array, RecordBatch, RecordBatchIterator, StringArray, TimestampSecondArray, UInt64Array};
+use chrono::DateTime;
+use futures::TryStreamExt;
+use lancedb::connection::Connection;
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use lancedb::table::Table;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Instant, UNIX_EPOCH};
+
+/// Rows written to the metadata table at a time
+const METADATA_BATCH_SIZE: usize = 5000;
+
+/// Matches returned when the caller doesn't say
+pub const DEFAULT_METADATA_SEARCH_LIMIT: usize = 500;
+
+/// Structured query over the metadata table. Every predicate that is set must hold.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetadataQuery {
+    /// Case-insensitive substring of the file name
+    pub name_contains: Option<String>,
+    #[serde(flatten)]
+    pub filter: FileFilter,
+    /// Only files indexed as part of this folder
+    pub root: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Default)]
+struct MetadataRows {
+    paths: Vec<String>,
+    names: Vec<String>,
+    extensions: Vec<String>,
+    sizes: Vec<u64>,
+    modified: Vec<i64>,
+    categories: Vec<String>,
+}
+
+impl MetadataRows {
+    fn push(&mut self, path: &Path, size: u64, modified: i64) {
+        self.paths.push(path.to_string_lossy().into_owned());
+        self.names.push(path.file_name().unwrap_or_default().to_string_lossy().into_owned());
+        self.extensions.push(
+            path.extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default(),
+        );
+        self.sizes.push(size);
+        self.modified.push(modified);
+        // Debug names match the serialized variants
+        self.categories.push(format!("{:?}", categorize_file(&path.to_path_buf())));
+    }
+
+    fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Writes the collected rows and leaves the buffer empty
+    async fn flush(&mut self, table: &Table, index_root: &str) -> Result<(), String> {
+        if self.paths.is_empty() {
+            return Ok(());
+        }
+        let rows = std::mem::take(self);
+        let count = rows.len();
+        let schema = create_metadata_schema();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(rows.paths)),
+                Arc::new(StringArray::from(rows.names)),
+                Arc::new(StringArray::from(rows.extensions)),
+                Arc::new(UInt64Array::from(rows.sizes)),
+                Arc::new(TimestampSecondArray::from(rows.modified)),
+                Arc::new(StringArray::from(rows.categories)),
+                Arc::new(StringArray::from(vec![Some(index_root); count])),
+            ],
+        )
+        .map_err(|e| format!("Failed to build metadata batch: {}", e))?;
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        table
+            .add(Box::new(reader))
+            .execute()
+            .await
+            .map_err(|e| format!("Failed to write metadata rows: {}", e))
+    }
+}
+
+/// Prefix of the `index_root` given to rows of a metadata run that hasn't
+/// finished; searches skip them until the run swaps them in
+const STAGING_ROOT_PREFIX: &str = "staging:";
+
+fn staging_root(index_root: &str) -> String {
+    format!("{}{}", STAGING_ROOT_PREFIX, index_root)
+}
+
+fn index_root_predicate(index_root: &str) -> String {
+    format!("index_root = {}", sql_string_literal(index_root))
+}
+
+/// Records path, name, size, modification time and category of every file
+/// under `root`, without extracting or embedding anything. The rows are
+/// staged and only replace those of an earlier run over the same folder once
+/// the walk succeeds, so a failed run leaves the old rows in place.
+pub(crate) async fn index_folder_metadata(
+    conn: &Connection,
+    root: &Path,
+    follow_symlinks: bool,
+    start_time: Instant,
+) -> Result<IndexingStats, String> {
+    let table = open_or_create_metadata_table(conn)
+        .await
+        .map_err(|e| format!("Metadata table error: {}", e))?;
+    let index_root = root.to_string_lossy().into_owned();
+    let staged_root = staging_root(&index_root);
+    // Rows an interrupted run left behind
+    table
+        .delete(&index_root_predicate(&staged_root))
+        .await
+        .map_err(|e| format!("Failed to clear staged metadata for {}: {}", index_root, e))?;
+
+    let stats = match stage_folder_metadata(&table, root, &staged_root, follow_symlinks, start_time).await {
+        Ok(stats) => stats,
+        Err(e) => {
+            if let Err(cleanup) = table.delete(&index_root_predicate(&staged_root)).await {
+                warn!("Failed to clear staged metadata for {}: {}", index_root, cleanup);
+            }
+            return Err(e);
+        }
+    };
+    table
+        .delete(&index_root_predicate(&index_root))
+        .await
+        .map_err(|e| format!("Failed to clear old metadata for {}: {}", index_root, e))?;
+    table
+        .update()
+        .only_if(index_root_predicate(&staged_root))
+        .column("index_root", sql_string_literal(&index_root))
+        .execute()
+        .await
+        .map_err(|e| format!("Failed to swap in metadata for {}: {}", index_root, e))?;
+    bump_index_version();
+
+    let elapsed = start_time.elapsed();
+    info!(
+        "Metadata indexing of '{}' recorded {} files in {}.{:03}s ({} failed)",
+        index_root,
+        stats.files_processed,
+        elapsed.as_secs(),
+        elapsed.subsec_millis(),
+        stats.files_failed
+    );
+    Ok(IndexingStats {
+        elapsed_seconds: elapsed.as_secs() as u32,
+        elapsed_milliseconds: elapsed.subsec_millis(),
+        ..stats
+    })
+}
+
+/// Walks `root` and writes a row per file under `staged_root`
+async fn stage_folder_metadata(
+    table: &Table,
+    root: &Path,
+    staged_root: &str,
+    follow_symlinks: bool,
+    start_time: Instant,
+) -> Result<IndexingStats, String> {
+    let mut rows = MetadataRows::default();
+    let mut files_processed: u32 = 0;
+    let mut files_failed: u32 = 0;
+    let mut failed_files = Vec::new();
+    let mut permission_denied_paths = Vec::new();
+    for entry in walk_entries(root, follow_symlinks) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) if is_permission_denied(&e) => {
+                if let Some(path) = e.path() {
+                    permission_denied_paths.push(path.to_string_lossy().into_owned());
+                }
+                continue;
+            }
+            Err(e) => {
+                warn!("Failed to read entry while indexing metadata: {}", e);
+                files_failed += 1;
+                continue;
+            }
+        };
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                warn!("Failed to read metadata of {}: {}", entry.path().display(), e);
+                files_failed += 1;
+                failed_files.push(entry.path().to_string_lossy().into_owned());
+                continue;
+            }
+        };
+        if metadata.is_dir() {
+            continue;
+        }
+        rows.push(entry.path(), metadata.len(), modified_seconds(&metadata));
+        files_processed += 1;
+
+        if rows.len() >= METADATA_BATCH_SIZE {
+            rows.flush(table, staged_root).await?;
+        }
+        if files_processed % LIVE_STATS_INTERVAL == 0 {
+            publish_live_stats(
+                IndexingStats {
+                    files_processed,
+                    files_failed,
+                    files_permission_denied: permission_denied_paths.len() as u32,
+                    ..Default::default()
+                },
+                start_time,
+            );
+        }
+    }
+    rows.flush(table, staged_root).await?;
+    Ok(IndexingStats {
+        files_processed,
+        files_failed,
+        db_inserts: files_processed,
+        failed_files,
+        files_permission_denied: permission_denied_paths.len() as u32,
+        permission_denied_paths,
+        ..Default::default()
+    })
+}
+
+fn modified_seconds(metadata: &std::fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// The metadata table, or `None` when no folder has been indexed in
+/// metadata-only mode yet
+async fn existing_metadata_table(conn: &Connection) -> Result<Option<Table>, String> {
+    let table_names = conn.table_names().execute().await.map_err(|e| e.to_string())?;
+    if !table_names.iter().any(|name| name == METADATA_TABLE_NAME) {
+        return Ok(None);
+    }
+    open_or_create_metadata_table(conn)
+        .await
+        .map(Some)
+        .map_err(|e| format!("Metadata table error: {}", e))
+}
+
+/// Brings the metadata rows of `path` in line with the file system after a
+/// change: drops them when the path is gone, and re-records a file that lies
+/// in a folder indexed in metadata-only mode. A no-op for other paths.
+pub async fn sync_file_metadata(conn: &Connection, path: &Path) -> Result<(), String> {
+    let Some(table) = existing_metadata_table(conn).await? else {
+        return Ok(());
+    };
+    let path_str = path.to_string_lossy().into_owned();
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => metadata,
+        // Gone, or a folder: forget everything recorded at or below it
+        _ => {
+            table
+                .delete(&file_path_under_predicate(&[path_str.clone()]))
+                .await
+                .map_err(|e| format!("Failed to forget metadata of {}: {}", path_str, e))?;
+            bump_index_version();
+            return Ok(());
+        }
+    };
+
+    let Some(index_root) = metadata_root_of(&table, path).await? else {
+        return Ok(());
+    };
+    table
+        .delete(&format!("file_path = {}", sql_string_literal(&path_str)))
+        .await
+        .map_err(|e| format!("Failed to replace metadata of {}: {}", path_str, e))?;
+    let mut rows = MetadataRows::default();
+    rows.push(path, metadata.len(), modified_seconds(&metadata));
+    rows.flush(&table, &index_root).await?;
+    bump_index_version();
+    Ok(())
+}
+
+/// The metadata-indexed folder `path` lies in, if any
+async fn metadata_root_of(table: &Table, path: &Path) -> Result<Option<String>, String> {
+    let ancestors: Vec<String> = path
+        .ancestors()
+        .skip(1)
+        .map(|ancestor| sql_string_literal(&ancestor.to_string_lossy()))
+        .collect();
+    if ancestors.is_empty() {
+        return Ok(None);
+    }
+    let batches: Vec<RecordBatch> = table
+        .query()
+        .only_if(format!("index_root IN ({})", ancestors.join(", ")))
+        .select(Select::columns(&["index_root"]))
+        .limit(1)
+        .execute()
+        .await
+        .map_err(|e| format!("Failed to look up the metadata root of {}: {}", path.display(), e))?
+        .try_collect()
+        .await
+        .map_err(|e| format!("Failed to look up the metadata root of {}: {}", path.display(), e))?;
+    Ok(batches.iter().find_map(|batch| {
+        let roots = batch.column_by_name("index_root")?.as_any().downcast_ref::<StringArray>()?;
+        (roots.len() > 0 && roots.is_valid(0)).then(|| roots.value(0).to_string())
+    }))
+}
+
+fn timestamp_literal(seconds: i64) -> String {
+    let time = DateTime::from_timestamp(seconds, 0).unwrap_or_default();
+    format!("timestamp '{}'", time.format("%Y-%m-%d %H:%M:%S"))
+}
+
+/// SQL predicate for `query`
+fn metadata_predicate(query: &MetadataQuery) -> Option<String> {
+    let filter = &query.filter;
+    // Rows of a metadata run still in progress aren't results yet
+    let staged = format!("{}%", escape_like(STAGING_ROOT_PREFIX));
+    let mut clauses = vec![format!("index_root NOT LIKE {}", sql_string_literal(&staged))];
+    if let Some(name) = query.name_contains.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        let pattern = format!("%{}%", escape_like(&name.to_lowercase()));
+        clauses.push(format!("lower(name) LIKE {}", sql_string_literal(&pattern)));
+    }
+    if let Some(min) = filter.min_size {
+        clauses.push(format!("size >= {}", min));
+    }
+    if let Some(max) = filter.max_size {
+        clauses.push(format!("size <= {}", max));
+    }
+    if let Some(after) = filter.modified_after {
+        clauses.push(format!("last_modified >= {}", timestamp_literal(after)));
+    }
+    if let Some(before) = filter.modified_before {
+        clauses.push(format!("last_modified <= {}", timestamp_literal(before)));
+    }
+    if let Some(extensions) = &filter.extensions {
+        let quoted: Vec<String> = extensions
+            .iter()
+            .map(|e| sql_string_literal(&e.trim_start_matches('.').to_lowercase()))
+            .collect();
+        if quoted.is_empty() {
+            // No extension is allowed, so nothing matches
+            clauses.push("false".to_string());
+        } else {
+            clauses.push(format!("extension IN ({})", quoted.join(", ")));
+        }
+    }
+    if let Some(category) = &filter.category {
+        clauses.push(format!("category = {}", sql_string_literal(&format!("{:?}", category))));
+    }
+    if let Some(root) = &query.root {
+        clauses.push(index_root_predicate(root));
+    }
+    clauses.join(" AND ")
+}
+
+/// Files in the metadata table matching `query`. Empty when no folder has
+/// been indexed in metadata-only mode yet.
+pub async fn metadata_search(conn: &Connection, query: &MetadataQuery) -> Result<Vec<FileInfo>, String> {
+    let Some(table) = existing_metadata_table(conn).await? else {
+        return Ok(Vec::new());
+    };
+
+    let search = table
+        .query()
+        .only_if(metadata_predicate(query))
+        .select(Select::columns(&["file_path", "name", "size", "last_modified"]))
+        .limit(query.limit.unwrap_or(DEFAULT_METADATA_SEARCH_LIMIT));
+    let batches: Vec<RecordBatch> = search
+        .execute()
+        .await
+        .map_err(|e| format!("Metadata search failed: {}", e))?
+        .try_collect()
+        .await
+        .map_err(|e| format!("Metadata search failed: {}", e))?;
+
+    let mut files = Vec::new();
+    for batch in batches {
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .ok_or_else(|| format!("Missing {} column", name))
+        };
+        let paths = column("file_path")?.as_any().downcast_ref::<StringArray>().ok_or("Bad file_path column")?;
+        let names = column("name")?.as_any().downcast_ref::<StringArray>().ok_or("Bad name column")?;
+        let sizes = column("size")?.as_any().downcast_ref::<UInt64Array>().ok_or("Bad size column")?;
+        let modified = column("last_modified")?
+            .as_any()
+            .downcast_ref::<TimestampSecondArray>()
+            .ok_or("Bad last_modified column")?;
+        for i in 0..batch.num_rows() {
+            let path = Path::new(paths.value(i));
+            files.push(FileInfo {
+                name: names.value(i).to_string(),
+                path: paths.value(i).to_string(),
+                is_directory: false,
+                size: Some(sizes.value(i)),
+                modified: modified
+                    .is_valid(i)
+                    .then(|| DateTime::from_timestamp(modified.value(i), 0))
+                    .flatten(),
+                file_type: get_file_type(path, false),
+                thumbnail_path: None,
+                is_symlink: false,
+                symlink_target: None,
+                icon_key: file_type_info(path, false).icon_key,
+            });
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::file_types::FileCategory;
+    use crate::db::{connect_db_with_path, TestDb};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_metadata_predicate() {
+        let query = MetadataQuery {
+            name_contains: Some("50%_Off's".to_string()),
+            filter: FileFilter {
+                min_size: Some(10),
+                extensions: Some(vec![".PDF".to_string()]),
+                category: Some(FileCategory::Document),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            metadata_predicate(&query),
+            "index_root NOT LIKE 'staging:%' AND lower(name) LIKE '%50\\%\\_off''s%' AND size >= 10 \
+             AND extension IN ('pdf') AND category = 'Document'"
+        );
+        assert_eq!(metadata_predicate(&MetadataQuery::default()), "index_root NOT LIKE 'staging:%'");
+    }
+
+    #[tokio::test]
+    async fn test_metadata_index_and_search() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("Report.pdf"), b"12345").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"1").unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub").join("report-draft.txt"), b"123").unwrap();
+
+        let stats = index_folder_metadata(&conn, dir.path(), false, Instant::now()).await.unwrap();
+        assert_eq!(stats.files_processed, 3);
+
+        let names = |files: Vec<FileInfo>| {
+            let mut names: Vec<String> = files.into_iter().map(|f| f.name).collect();
+            names.sort();
+            names
+        };
+        let by_name = MetadataQuery {
+            name_contains: Some("REPORT".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(names(metadata_search(&conn, &by_name).await.unwrap()), vec!["Report.pdf", "report-draft.txt"]);
+
+        let small_text = MetadataQuery {
+            filter: FileFilter {
+                max_size: Some(3),
+                extensions: Some(vec!["txt".to_string()]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(names(metadata_search(&conn, &small_text).await.unwrap()), vec!["notes.txt", "report-draft.txt"]);
+
+        // Indexing again replaces the folder's rows instead of duplicating them
+        index_folder_metadata(&conn, dir.path(), false, Instant::now()).await.unwrap();
+        assert_eq!(metadata_search(&conn, &MetadataQuery::default()).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_sync_file_metadata_follows_changes() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let dir = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub").join("old.txt"), b"1").unwrap();
+        index_folder_metadata(&conn, dir.path(), false, Instant::now()).await.unwrap();
+        let all_paths = |files: Vec<FileInfo>| {
+            let mut paths: Vec<String> = files.into_iter().map(|f| f.path).collect();
+            paths.sort();
+            paths
+        };
+
+        let added = dir.path().join("sub").join("new.txt");
+        std::fs::write(&added, b"12").unwrap();
+        sync_file_metadata(&conn, &added).await.unwrap();
+        let stray = outside.path().join("stray.txt");
+        std::fs::write(&stray, b"1").unwrap();
+        sync_file_metadata(&conn, &stray).await.unwrap();
+        let by_root = MetadataQuery {
+            root: Some(dir.path().to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            all_paths(metadata_search(&conn, &by_root).await.unwrap()),
+            vec![added.to_string_lossy().into_owned(), dir.path().join("sub").join("old.txt").to_string_lossy().into_owned()]
+        );
+        assert_eq!(metadata_search(&conn, &MetadataQuery::default()).await.unwrap().len(), 2);
+
+        // Removing the folder forgets everything below it
+        std::fs::remove_dir_all(dir.path().join("sub")).unwrap();
+        sync_file_metadata(&conn, &dir.path().join("sub")).await.unwrap();
+        assert!(metadata_search(&conn, &MetadataQuery::default()).await.unwrap().is_empty());
+    }
+}
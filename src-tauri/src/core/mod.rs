@@ -2,3 +2,29 @@ pub mod error;
 pub mod file_system;
 pub mod models;
 pub mod indexer;
+pub mod settings;
+pub mod cancellation;
+pub mod capabilities;
+pub mod compare;
+pub mod config_bundle;
+pub mod corpus;
+pub mod db_location;
+pub mod exclusions;
+pub mod favorites;
+pub mod file_types;
+pub mod folder_preview;
+pub mod index_drift;
+pub mod index_size;
+pub mod json_store;
+pub mod listing_cache;
+pub mod log_stream;
+pub mod metadata_index;
+pub mod query;
+pub mod recents;
+pub mod scheduler;
+pub mod storage;
+pub mod term_frequencies;
+pub mod thumbnail_cache;
+pub mod tombstones;
+pub mod tree;
+pub mod virtual_content;
@@ -18,6 +18,15 @@ pub struct FileInfo {
     /// Optional path to a generated thumbnail in the cache directory.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thumbnail_path: Option<String>,
+    /// True if the entry itself is a symbolic link (metadata above describes its target when reachable).
+    #[serde(default)]
+    pub is_symlink: bool,
+    /// Where the symbolic link points, as stored in the link. None for regular entries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symlink_target: Option<String>,
+    /// Icon the frontend should show, from `get_file_type_info`.
+    #[serde(default)]
+    pub icon_key: String,
 }
 
 // Note: The default Ord derived above will sort primarily by `name`.
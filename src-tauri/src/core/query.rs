@@ -0,0 +1,198 @@
+use super::cancellation::CancellationToken;
+use super::file_system::get_file_type;
+use super::indexer::walk_entries;
+use super::models::FileInfo;
+use crate::core::file_types::{categorize_file, file_type_info, FileCategory};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::Metadata;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// Matches are handed to the caller in batches of this size
+const RESULT_BATCH_SIZE: usize = 100;
+
+/// Metadata predicates for `query_files`. Every predicate that is set must hold.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileFilter {
+    /// Smallest size in bytes, inclusive
+    pub min_size: Option<u64>,
+    /// Largest size in bytes, inclusive
+    pub max_size: Option<u64>,
+    /// Unix timestamp (seconds); files modified earlier don't match
+    pub modified_after: Option<i64>,
+    /// Unix timestamp (seconds); files modified later don't match
+    pub modified_before: Option<i64>,
+    /// Allowed extensions without the dot, case-insensitive
+    pub extensions: Option<Vec<String>>,
+    pub category: Option<FileCategory>,
+}
+
+impl FileFilter {
+    pub fn matches(&self, path: &Path, metadata: &Metadata) -> bool {
+        let size = metadata.len();
+        if self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max) {
+            return false;
+        }
+
+        if self.modified_after.is_some() || self.modified_before.is_some() {
+            let Some(modified) = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64)
+            else {
+                return false;
+            };
+            if self.modified_after.is_some_and(|after| modified < after)
+                || self.modified_before.is_some_and(|before| modified > before)
+            {
+                return false;
+            }
+        }
+
+        if let Some(extensions) = &self.extensions {
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+            if !extensions
+                .iter()
+                .any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(extension))
+            {
+                return false;
+            }
+        }
+
+        if let Some(category) = &self.category {
+            if categorize_file(&path.to_path_buf()) != *category {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileQuerySummary {
+    pub files_scanned: usize,
+    pub files_matched: usize,
+    /// Stopped at `max_results`
+    pub truncated: bool,
+    pub cancelled: bool,
+}
+
+pub(super) fn file_info(path: &Path, metadata: &Metadata, is_symlink: bool) -> FileInfo {
+    let is_directory = metadata.is_dir();
+    FileInfo {
+        name: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+        path: path.to_string_lossy().into_owned(),
+        is_directory,
+        size: (!is_directory).then(|| metadata.len()),
+        modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+        file_type: get_file_type(path, is_directory),
+        thumbnail_path: None,
+        is_symlink,
+        symlink_target: None,
+        icon_key: file_type_info(path, is_directory).icon_key,
+    }
+}
+
+/// Walks `root` (skipping excluded folders, and hidden entries unless
+/// `include_hidden` is set, like indexing does)
+/// and passes matching files to `on_batch` as they are found. Blocking; run it
+/// off the async runtime.
+pub fn query_files(
+    root: &Path,
+    filter: &FileFilter,
+    follow_symlinks: bool,
+    max_results: usize,
+    token: &CancellationToken,
+    mut on_batch: impl FnMut(Vec<FileInfo>),
+) -> FileQuerySummary {
+    let mut summary = FileQuerySummary::default();
+    let mut batch = Vec::with_capacity(RESULT_BATCH_SIZE);
+
+    for entry in walk_entries(root, follow_symlinks) {
+        if token.is_cancelled() {
+            summary.cancelled = true;
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Skipping unreadable entry while querying {}: {}", root.display(), e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        summary.files_scanned += 1;
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !filter.matches(entry.path(), &metadata) {
+            continue;
+        }
+
+        batch.push(file_info(entry.path(), &metadata, entry.path_is_symlink()));
+        summary.files_matched += 1;
+        if batch.len() == RESULT_BATCH_SIZE {
+            on_batch(std::mem::replace(&mut batch, Vec::with_capacity(RESULT_BATCH_SIZE)));
+        }
+        if summary.files_matched >= max_results {
+            summary.truncated = true;
+            break;
+        }
+    }
+    if !batch.is_empty() {
+        on_batch(batch);
+    }
+
+    info!(
+        "File query under {} matched {} of {} files{}",
+        root.display(),
+        summary.files_matched,
+        summary.files_scanned,
+        if summary.cancelled { " (cancelled)" } else { "" }
+    );
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_query_files_combines_predicates() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("big.mp4"), vec![0u8; 2048]).unwrap();
+        fs::write(dir.path().join("small.mp4"), vec![0u8; 10]).unwrap();
+        fs::write(dir.path().join("big.txt"), vec![0u8; 2048]).unwrap();
+        fs::create_dir(dir.path().join("node_modules")).unwrap();
+        fs::write(dir.path().join("node_modules/huge.mp4"), vec![0u8; 4096]).unwrap();
+
+        let filter = FileFilter {
+            min_size: Some(1024),
+            category: Some(FileCategory::Video),
+            ..Default::default()
+        };
+        let mut found = Vec::new();
+        let summary = query_files(dir.path(), &filter, false, 100, &CancellationToken::none(), |batch| {
+            found.extend(batch)
+        });
+        assert_eq!(summary.files_matched, 1);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "big.mp4");
+
+        let by_extension = FileFilter {
+            extensions: Some(vec![".TXT".to_string()]),
+            modified_after: Some(0),
+            ..Default::default()
+        };
+        let summary = query_files(dir.path(), &by_extension, false, 100, &CancellationToken::none(), |_| {});
+        assert_eq!(summary.files_matched, 1);
+    }
+}
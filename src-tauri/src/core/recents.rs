@@ -0,0 +1,105 @@
+use crate::core::json_store::{load_json_or_default, save_json};
+use crate::db::get_app_data_dir;
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const RECENTS_FILE_NAME: &str = "recently_opened.json";
+
+/// Oldest entries fall off the log past this many paths
+const MAX_RECENT_ENTRIES: usize = 200;
+
+/// A path the user opened and when
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentEntry {
+    pub path: String,
+    /// Milliseconds since the Unix epoch
+    pub opened_at: u64,
+}
+
+/// Most recently opened first
+static RECENTS: Lazy<Mutex<Vec<RecentEntry>>> = Lazy::new(|| Mutex::new(load_recents()));
+
+pub(crate) fn recents_file_path() -> Result<PathBuf, String> {
+    get_app_data_dir()
+        .map(|dir| dir.join(RECENTS_FILE_NAME))
+        .map_err(|e| format!("Failed to resolve recents path: {}", e))
+}
+
+/// Reads the stored log, starting empty if the file is missing or unreadable
+fn load_recents() -> Vec<RecentEntry> {
+    load_json_or_default(recents_file_path(), "recents")
+}
+
+/// Re-reads the log from disk, e.g. after it was replaced by a config import
+pub fn reload_recents() {
+    let entries = load_recents();
+    if let Ok(mut recents) = RECENTS.lock() {
+        *recents = entries;
+    }
+}
+
+fn save_recents(entries: &[RecentEntry]) -> Result<(), String> {
+    save_json(&recents_file_path()?, entries, "recents")
+}
+
+/// Moves `path` to the top of the log, dropping its older entry and anything past the cap
+fn push_entry(entries: &mut Vec<RecentEntry>, path: &str, opened_at: u64) {
+    entries.retain(|entry| entry.path != path);
+    entries.insert(
+        0,
+        RecentEntry {
+            path: path.to_string(),
+            opened_at,
+        },
+    );
+    entries.truncate(MAX_RECENT_ENTRIES);
+}
+
+/// Records that `path` was just opened. Failures are logged, never surfaced:
+/// opening a file must not fail because the log couldn't be written.
+pub fn record_opened(path: &str) {
+    let opened_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let Ok(mut entries) = RECENTS.lock() else {
+        warn!("Recents lock poisoned; not recording {}", path);
+        return;
+    };
+    push_entry(&mut entries, path, opened_at);
+    if let Err(e) = save_recents(&entries) {
+        warn!("{}", e);
+    }
+}
+
+/// Up to `limit` recently opened paths, newest first
+pub fn recently_opened(limit: usize) -> Vec<RecentEntry> {
+    RECENTS
+        .lock()
+        .map(|entries| entries.iter().take(limit).cloned().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_entry_dedupes_and_caps() {
+        let mut entries = Vec::new();
+        push_entry(&mut entries, "/a.txt", 1);
+        push_entry(&mut entries, "/b.txt", 2);
+        push_entry(&mut entries, "/a.txt", 3);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0], RecentEntry { path: "/a.txt".into(), opened_at: 3 });
+
+        for i in 0..MAX_RECENT_ENTRIES + 10 {
+            push_entry(&mut entries, &format!("/file{}.txt", i), i as u64);
+        }
+        assert_eq!(entries.len(), MAX_RECENT_ENTRIES);
+    }
+}
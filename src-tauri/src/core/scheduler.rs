@@ -0,0 +1,209 @@
+use super::indexer::{index_folder_with_options, IndexOptions, INDEXING_BUSY_ERROR};
+use super::settings::current_settings;
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often the scheduler wakes up to compare the clock against the schedule
+const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+/// When indexing runs on its own, stored in the app settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexSchedule {
+    pub enabled: bool,
+    /// Minutes between the start of one scheduled run and the next
+    pub interval_minutes: u64,
+    /// Folders indexed on each run; empty means the Downloads folder
+    pub roots: Vec<String>,
+}
+
+impl Default for IndexSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 24 * 60,
+            roots: Vec::new(),
+        }
+    }
+}
+
+impl IndexSchedule {
+    /// The configured roots, or the Downloads folder when none are set
+    fn effective_roots(&self) -> Vec<String> {
+        if !self.roots.is_empty() {
+            return self.roots.clone();
+        }
+        dirs::home_dir()
+            .map(|home| vec![home.join("Downloads").to_string_lossy().into_owned()])
+            .unwrap_or_default()
+    }
+}
+
+/// Where the schedule stands, for display
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleStatus {
+    pub enabled: bool,
+    pub interval_minutes: u64,
+    pub roots: Vec<String>,
+    /// Unix timestamp (seconds) the last scheduled or manual run started at
+    pub last_run: Option<u64>,
+    /// Unix timestamp (seconds) of the next scheduled run; `None` while disabled
+    pub next_run: Option<u64>,
+    pub running: bool,
+}
+
+/// Outcome of one pass over the roots
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduledRunSummary {
+    pub roots_indexed: Vec<String>,
+    /// Roots not indexed because another indexing operation held the queue
+    pub roots_busy: Vec<String>,
+    pub roots_failed: Vec<String>,
+    pub files_indexed: u32,
+    /// True when the whole run was skipped because the previous one was still going
+    pub skipped: bool,
+}
+
+/// Start of the last run; the scheduler counts the interval from here
+static LAST_RUN: Lazy<Mutex<Option<u64>>> = Lazy::new(|| Mutex::new(None));
+
+static RUN_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Marks a scheduled run as going until dropped, so a run that panics or is
+/// cancelled mid-way doesn't block every later one
+struct RunInProgress;
+
+impl RunInProgress {
+    /// Claims the run, or returns `None` while another one holds it
+    fn claim() -> Option<Self> {
+        if RUN_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+            None
+        } else {
+            Some(RunInProgress)
+        }
+    }
+}
+
+impl Drop for RunInProgress {
+    fn drop(&mut self) {
+        RUN_IN_PROGRESS.store(false, Ordering::SeqCst);
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn last_run() -> Option<u64> {
+    LAST_RUN.lock().ok().and_then(|last| *last)
+}
+
+/// When the next scheduled run is due, or `None` while the schedule is disabled
+fn next_run_at(schedule: &IndexSchedule, last_run: Option<u64>, now: u64) -> Option<u64> {
+    if !schedule.enabled {
+        return None;
+    }
+    let interval = schedule.interval_minutes.max(1) * 60;
+    Some(last_run.map_or(now, |last| last + interval))
+}
+
+/// Reports the schedule and when it fires next
+pub fn schedule_status() -> ScheduleStatus {
+    let schedule = current_settings().index_schedule;
+    let last_run = last_run();
+    ScheduleStatus {
+        next_run: next_run_at(&schedule, last_run, now_secs()),
+        enabled: schedule.enabled,
+        interval_minutes: schedule.interval_minutes,
+        roots: schedule.effective_roots(),
+        last_run,
+        running: RUN_IN_PROGRESS.load(Ordering::SeqCst),
+    }
+}
+
+/// Indexes every scheduled root once. Skipped entirely while a previous run is
+/// still going; single roots are skipped while a user-triggered run holds the queue.
+pub async fn run_scheduled_indexing() -> ScheduledRunSummary {
+    let Some(_run) = RunInProgress::claim() else {
+        info!("Previous scheduled indexing run is still going; skipping");
+        return ScheduledRunSummary {
+            skipped: true,
+            ..Default::default()
+        };
+    };
+    if let Ok(mut last) = LAST_RUN.lock() {
+        *last = Some(now_secs());
+    }
+
+    let mut summary = ScheduledRunSummary::default();
+    for root in current_settings().index_schedule.effective_roots() {
+        info!("Scheduled indexing of {}", root);
+        match index_folder_with_options(&root, &IndexOptions::default()).await {
+            Ok(stats) => {
+                summary.files_indexed += stats.db_inserts;
+                summary.roots_indexed.push(root);
+            }
+            Err(e) if e.starts_with(INDEXING_BUSY_ERROR) => {
+                warn!("Skipping scheduled indexing of {}: {}", root, e);
+                summary.roots_busy.push(root);
+            }
+            Err(e) => {
+                error!("Scheduled indexing of {} failed: {}", root, e);
+                summary.roots_failed.push(root);
+            }
+        }
+    }
+
+    info!(
+        "Scheduled indexing finished: {} roots indexed, {} busy, {} failed, {} files indexed",
+        summary.roots_indexed.len(),
+        summary.roots_busy.len(),
+        summary.roots_failed.len(),
+        summary.files_indexed
+    );
+    summary
+}
+
+/// Runs forever, starting a scheduled run whenever one is due. The interval is
+/// counted from `started_at`, normally the end of startup indexing.
+pub async fn run_index_scheduler(started_at: u64) {
+    if let Ok(mut last) = LAST_RUN.lock() {
+        last.get_or_insert(started_at);
+    }
+    loop {
+        tokio::time::sleep(SCHEDULER_TICK).await;
+        let schedule = current_settings().index_schedule;
+        let due = next_run_at(&schedule, last_run(), now_secs()).is_some_and(|next| next <= now_secs());
+        if due {
+            run_scheduled_indexing().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_run_counts_from_last_run() {
+        let mut schedule = IndexSchedule::default();
+        assert_eq!(next_run_at(&schedule, Some(1_000), 2_000), None, "Disabled schedules never fire");
+
+        schedule.enabled = true;
+        schedule.interval_minutes = 30;
+        assert_eq!(next_run_at(&schedule, Some(1_000), 2_000), Some(1_000 + 30 * 60));
+        assert_eq!(next_run_at(&schedule, None, 2_000), Some(2_000), "Never-run schedules are due now");
+    }
+
+    #[test]
+    fn test_run_claim_is_released_on_drop() {
+        let run = RunInProgress::claim().expect("No run is going yet");
+        assert!(RunInProgress::claim().is_none(), "A second run is refused while the first holds the claim");
+        drop(run);
+        assert!(RunInProgress::claim().is_some(), "Dropping the claim, even on unwind, frees it");
+    }
+}
@@ -0,0 +1,206 @@
+use super::favorites::DEFAULT_FAVORITE_BOOST;
+use super::index_size::IndexEvictionPolicy;
+use super::scheduler::IndexSchedule;
+use super::tombstones::DEFAULT_TOMBSTONE_RETENTION_DAYS;
+use crate::db::get_app_data_dir;
+use crate::extractor::DEFAULT_STREAMING_THRESHOLD_BYTES;
+use crate::filename_index::FilenameSearchMode;
+use crate::watcher::PausedEventPolicy;
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+const SETTINGS_FILE_NAME: &str = "settings.json";
+
+/// Thumbnails generated at once while browsing unless the settings say otherwise
+pub const DEFAULT_THUMBNAIL_CONCURRENCY: usize = 4;
+
+/// Distance used to rank vector search results.
+///
+/// The text models (BGE-small, multilingual E5, Jina code) and the Nomic
+/// vision model were all trained with cosine similarity, so `Cosine` matches
+/// their training. They also emit unit-length vectors, for which `L2` ranks
+/// identically; `L2` stays the default because existing thresholds were tuned
+/// on it. `Dot` is only equivalent for normalized vectors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceMetric {
+    #[default]
+    L2,
+    Cosine,
+    Dot,
+}
+
+impl DistanceMetric {
+    /// Maps a LanceDB distance to a similarity where higher is better and
+    /// 1.0 is an exact match. For unit vectors every metric yields the cosine
+    /// similarity: squared L2 is `2 - 2cos`, cosine and dot distances are `1 - cos`.
+    pub fn score(self, distance: f32) -> f32 {
+        match self {
+            DistanceMetric::L2 => 1.0 - (distance / 2.0),
+            DistanceMetric::Cosine | DistanceMetric::Dot => 1.0 - distance,
+        }
+    }
+}
+
+/// How scores are shown to the user. Every mapping clamps the raw score to
+/// 0..1 first and never decreases as the score grows, so ranking is preserved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreScale {
+    /// The clamped score itself, 0.0 to 1.0
+    #[default]
+    Unit,
+    /// Whole percentages, 0 to 100
+    Percent,
+    /// Half-star steps, 0.0 to 5.0 (0.87 shows as 4.5 stars)
+    Stars,
+}
+
+impl ScoreScale {
+    pub fn display(self, score: f32) -> f32 {
+        let score = if score.is_nan() { 0.0 } else { score.clamp(0.0, 1.0) };
+        match self {
+            ScoreScale::Unit => score,
+            ScoreScale::Percent => (score * 100.0).round(),
+            ScoreScale::Stars => (score * 10.0).round() / 2.0,
+        }
+    }
+}
+
+/// User-adjustable behaviour of the indexer, watcher and search.
+/// Missing fields in the stored file fall back to their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Files whose trimmed extracted text is shorter than this many characters
+    /// are not embedded; their vectors are too noisy to be useful in results.
+    pub min_text_chars: usize,
+    /// Descend into symlinked folders while indexing
+    pub follow_symlinks: bool,
+    /// Distance used to rank text and image search results
+    pub distance_metric: DistanceMetric,
+    /// Search file names live on disk or in the persistent filename index
+    pub filename_search_mode: FilenameSearchMode,
+    /// Recurring background indexing
+    pub index_schedule: IndexSchedule,
+    /// Plain text files larger than this many bytes are read, chunked and
+    /// embedded a segment at a time instead of being loaded whole
+    pub streaming_threshold_bytes: u64,
+    /// Put each file's name and parent folders in front of its text before
+    /// embedding, so descriptive names help it rank
+    pub embed_file_names: bool,
+    /// Scale of the `display_score` attached to search results
+    pub score_scale: ScoreScale,
+    /// Thumbnails generated at once while browsing; the rest wait their turn
+    pub thumbnail_concurrency: usize,
+    /// Searches lower `min_score` (not below `MIN_SCORE_FLOOR`) until at least
+    /// this many results clear it; 0 never lowers it
+    pub min_results: usize,
+    /// Folder holding the LanceDB database; the app data folder when unset.
+    /// Change it through `move_database_command` so the data moves along.
+    pub db_location: Option<PathBuf>,
+    /// Tombstones of deleted files older than this many days are pruned;
+    /// 0 keeps them until pruned explicitly
+    pub tombstone_retention_days: u64,
+    /// Indexing stops once the database folder would grow past this many
+    /// bytes; 0 lets it grow without bound
+    pub max_index_bytes: u64,
+    /// Documents given up to stay under `max_index_bytes`
+    pub index_eviction: IndexEvictionPolicy,
+    /// Whether paused watchers queue changed paths for re-syncing or drop them
+    pub paused_watcher_events: PausedEventPolicy,
+    /// Score multiplier for favorite files in searches that boost them
+    pub favorite_boost: f32,
+    /// Show and index hidden (dot) files and folders: in listings, filename
+    /// search, indexing and the watcher alike
+    pub include_hidden: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            min_text_chars: 10,
+            follow_symlinks: false,
+            distance_metric: DistanceMetric::default(),
+            filename_search_mode: FilenameSearchMode::default(),
+            index_schedule: IndexSchedule::default(),
+            streaming_threshold_bytes: DEFAULT_STREAMING_THRESHOLD_BYTES,
+            embed_file_names: false,
+            score_scale: ScoreScale::default(),
+            thumbnail_concurrency: DEFAULT_THUMBNAIL_CONCURRENCY,
+            min_results: 0,
+            db_location: None,
+            tombstone_retention_days: DEFAULT_TOMBSTONE_RETENTION_DAYS,
+            max_index_bytes: 0,
+            index_eviction: IndexEvictionPolicy::default(),
+            paused_watcher_events: PausedEventPolicy::default(),
+            favorite_boost: DEFAULT_FAVORITE_BOOST,
+            include_hidden: false,
+        }
+    }
+}
+
+static SETTINGS: Lazy<RwLock<AppSettings>> = Lazy::new(|| RwLock::new(load_settings()));
+
+pub(crate) fn settings_file_path() -> Result<PathBuf, String> {
+    get_app_data_dir()
+        .map(|dir| dir.join(SETTINGS_FILE_NAME))
+        .map_err(|e| format!("Failed to resolve settings path: {}", e))
+}
+
+/// Reads the stored settings, falling back to defaults if the file is missing or unreadable
+fn load_settings() -> AppSettings {
+    let path = match settings_file_path() {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("{}; using default settings", e);
+            return AppSettings::default();
+        }
+    };
+    if !path.exists() {
+        return AppSettings::default();
+    }
+    match fs::read_to_string(&path).map(|json| serde_json::from_str::<AppSettings>(&json)) {
+        Ok(Ok(settings)) => settings,
+        Ok(Err(e)) => {
+            warn!("Ignoring malformed settings file {}: {}", path.display(), e);
+            AppSettings::default()
+        }
+        Err(e) => {
+            warn!("Failed to read settings file {}: {}", path.display(), e);
+            AppSettings::default()
+        }
+    }
+}
+
+/// Returns a snapshot of the current settings
+pub fn current_settings() -> AppSettings {
+    SETTINGS.read().map(|s| s.clone()).unwrap_or_default()
+}
+
+/// Re-reads the settings file, e.g. after it was replaced by a config import
+pub fn reload_settings() {
+    let settings = load_settings();
+    if let Ok(mut current) = SETTINGS.write() {
+        *current = settings;
+    }
+}
+
+/// Persists `settings` and makes them current
+pub fn update_settings(settings: AppSettings) -> Result<AppSettings, String> {
+    let path = settings_file_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let mut current = SETTINGS.write().map_err(|_| "Settings lock poisoned".to_string())?;
+    *current = settings.clone();
+    info!("Saved settings to {}", path.display());
+    Ok(settings)
+}
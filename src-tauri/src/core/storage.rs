@@ -0,0 +1,105 @@
+use super::cancellation::CancellationToken;
+use crate::core::file_types::{categorize_file, FileCategory};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Progress is reported after every this many files
+const PROGRESS_INTERVAL_FILES: usize = 1000;
+
+/// Space taken by one file category
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryUsage {
+    pub category: FileCategory,
+    pub bytes: u64,
+    pub files: usize,
+}
+
+/// Disk usage under a root, largest category first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageBreakdown {
+    pub categories: Vec<CategoryUsage>,
+    pub total_bytes: u64,
+    pub total_files: usize,
+    /// True when the scan was cancelled; the totals then cover only part of the tree
+    pub cancelled: bool,
+}
+
+/// Sums file sizes under `root` per category. `progress` is called with the
+/// files and bytes counted so far. Blocking; run it off the async runtime.
+pub fn storage_by_category(
+    root: &Path,
+    token: &CancellationToken,
+    mut progress: impl FnMut(usize, u64),
+) -> StorageBreakdown {
+    let mut totals: HashMap<FileCategory, (u64, usize)> = HashMap::new();
+    let mut breakdown = StorageBreakdown::default();
+
+    for entry in WalkDir::new(root).follow_links(false) {
+        if token.is_cancelled() {
+            breakdown.cancelled = true;
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Skipping unreadable entry while measuring {}: {}", root.display(), e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        let usage = totals.entry(categorize_file(&entry.path().to_path_buf())).or_default();
+        usage.0 += size;
+        usage.1 += 1;
+        breakdown.total_bytes += size;
+        breakdown.total_files += 1;
+        if breakdown.total_files % PROGRESS_INTERVAL_FILES == 0 {
+            progress(breakdown.total_files, breakdown.total_bytes);
+        }
+    }
+
+    breakdown.categories = totals
+        .into_iter()
+        .map(|(category, (bytes, files))| CategoryUsage { category, bytes, files })
+        .collect();
+    breakdown.categories.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    progress(breakdown.total_files, breakdown.total_bytes);
+
+    info!(
+        "Measured {}: {} files, {} bytes{}",
+        root.display(),
+        breakdown.total_files,
+        breakdown.total_bytes,
+        if breakdown.cancelled { " (cancelled)" } else { "" }
+    );
+    breakdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_storage_by_category_sums_sizes() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("photos")).unwrap();
+        fs::write(dir.path().join("photos/a.jpg"), vec![0u8; 300]).unwrap();
+        fs::write(dir.path().join("photos/b.png"), vec![0u8; 200]).unwrap();
+        fs::write(dir.path().join("notes.txt"), vec![0u8; 50]).unwrap();
+
+        let breakdown = storage_by_category(dir.path(), &CancellationToken::none(), |_, _| {});
+        assert_eq!(breakdown.total_files, 3);
+        assert_eq!(breakdown.total_bytes, 550);
+        assert_eq!(breakdown.categories[0].category, FileCategory::Image);
+        assert_eq!(breakdown.categories[0].bytes, 500);
+        assert_eq!(breakdown.categories[0].files, 2);
+        assert_eq!(breakdown.categories[1].category, FileCategory::Document);
+    }
+}
@@ -0,0 +1,431 @@
+use super::cancellation::CancellationToken;
+use super::indexer::should_stream;
+use super::json_store::{load_json_or_default, save_json};
+use super::virtual_content::is_virtual_path;
+use crate::archive::{extract_archive_texts, split_composite_path};
+use crate::db::{get_app_data_dir, list_indexed_documents, DocumentSortBy};
+use crate::extractor::{extract_text, stream_text_segments};
+use crate::search::keyword_counts;
+use lancedb::Connection;
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const TERM_FREQUENCIES_FILE_NAME: &str = "term_frequencies.json";
+
+/// Terms kept per document, its most frequent first. The rare terms left out
+/// barely move the totals.
+const MAX_TERMS_PER_DOCUMENT: usize = 200;
+
+/// Distinct terms kept across all documents; the rarest are dropped first
+const MAX_STORED_TERMS: usize = 50_000;
+
+/// Suggestions returned when the caller doesn't say
+pub const DEFAULT_SUGGESTION_LIMIT: usize = 8;
+
+/// A completion of the query being typed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchSuggestion {
+    /// The query with its last word completed
+    pub text: String,
+    /// The word that completes it
+    pub term: String,
+    /// Times the term occurs in indexed documents
+    pub frequency: u64,
+}
+
+/// Term counts of one document
+type DocumentTerms = HashMap<String, u32>;
+
+/// What is written to disk; the totals are summed up again on load
+#[derive(Default, Serialize, Deserialize)]
+struct StoredTermFrequencies {
+    documents: HashMap<String, DocumentTerms>,
+}
+
+/// Term counts per indexed document and summed over all of them. Storing a
+/// document replaces the counts of its previous version and deleting it
+/// subtracts them, so the totals never need a full rescan.
+#[derive(Default)]
+struct TermFrequencies {
+    documents: HashMap<String, DocumentTerms>,
+    totals: HashMap<String, u64>,
+    /// Changed since it was last saved
+    dirty: bool,
+}
+
+impl TermFrequencies {
+    fn from_documents(documents: HashMap<String, DocumentTerms>) -> Self {
+        let mut frequencies = TermFrequencies::default();
+        for (path, terms) in documents {
+            frequencies.set_document(path, terms);
+        }
+        frequencies.dirty = false;
+        frequencies
+    }
+
+    fn remove_document(&mut self, path: &str) {
+        let Some(terms) = self.documents.remove(path) else {
+            return;
+        };
+        for (term, count) in terms {
+            if let Some(total) = self.totals.get_mut(&term) {
+                *total = total.saturating_sub(count as u64);
+                if *total == 0 {
+                    self.totals.remove(&term);
+                }
+            }
+        }
+        self.dirty = true;
+    }
+
+    fn set_document(&mut self, path: String, terms: DocumentTerms) {
+        self.remove_document(&path);
+        if terms.is_empty() {
+            return;
+        }
+        for (term, count) in &terms {
+            *self.totals.entry(term.clone()).or_default() += *count as u64;
+        }
+        self.documents.insert(path, terms);
+        self.dirty = true;
+    }
+
+    /// Keeps the `limit` most frequent terms, in the totals and in every
+    /// document, so the two stay consistent
+    fn prune_rarest(&mut self, limit: usize) {
+        if self.totals.len() <= limit {
+            return;
+        }
+        let mut frequencies: Vec<u64> = self.totals.values().copied().collect();
+        frequencies.sort_unstable_by(|a, b| b.cmp(a));
+        let cutoff = frequencies[limit];
+        self.totals.retain(|_, total| *total > cutoff);
+        let totals = &self.totals;
+        for terms in self.documents.values_mut() {
+            terms.retain(|term, _| totals.contains_key(term));
+        }
+        self.documents.retain(|_, terms| !terms.is_empty());
+        self.dirty = true;
+    }
+}
+
+static TERM_FREQUENCIES: Lazy<Mutex<TermFrequencies>> =
+    Lazy::new(|| Mutex::new(TermFrequencies::from_documents(load_term_frequencies())));
+
+fn term_frequencies_file_path() -> Result<PathBuf, String> {
+    get_app_data_dir()
+        .map(|dir| dir.join(TERM_FREQUENCIES_FILE_NAME))
+        .map_err(|e| format!("Failed to resolve term frequencies path: {}", e))
+}
+
+/// Reads the stored counts, starting empty if the file is missing or unreadable
+fn load_term_frequencies() -> HashMap<String, DocumentTerms> {
+    load_json_or_default::<StoredTermFrequencies>(term_frequencies_file_path(), "term frequencies").documents
+}
+
+/// Keeps the `MAX_TERMS_PER_DOCUMENT` most frequent of `counts`
+fn top_terms(counts: HashMap<String, usize>) -> DocumentTerms {
+    let mut terms: Vec<(String, usize)> = counts.into_iter().collect();
+    if terms.len() > MAX_TERMS_PER_DOCUMENT {
+        terms.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        terms.truncate(MAX_TERMS_PER_DOCUMENT);
+    }
+    terms
+        .into_iter()
+        .map(|(term, count)| (term, count.min(u32::MAX as usize) as u32))
+        .collect()
+}
+
+/// Adds the term counts of `text` to `counts`, for documents read in pieces
+pub fn add_term_counts(counts: &mut HashMap<String, usize>, text: &str) {
+    for (term, count) in keyword_counts(text) {
+        *counts.entry(term).or_default() += count;
+    }
+}
+
+/// Replaces the term counts of the document at `path`. Call it once the
+/// document is stored, so a failed write leaves the old counts. Cheap;
+/// nothing is written until `save_term_frequencies`.
+pub fn record_document_terms(path: &str, counts: HashMap<String, usize>) {
+    if let Ok(mut frequencies) = TERM_FREQUENCIES.lock() {
+        frequencies.set_document(path.to_string(), top_terms(counts));
+    }
+}
+
+/// Subtracts the term counts of a document removed from the index. Callers
+/// that delete a document call it once the delete succeeded.
+pub fn forget_document_terms(path: &str) {
+    if let Ok(mut frequencies) = TERM_FREQUENCIES.lock() {
+        frequencies.remove_document(path);
+    }
+}
+
+/// Term counts collected during a bulk indexing run, whose writes happen in
+/// batches at its end. Applied then for the documents that were stored.
+#[derive(Default)]
+pub struct TermFrequencyBatch {
+    documents: HashMap<String, DocumentTerms>,
+}
+
+impl TermFrequencyBatch {
+    pub fn add(&mut self, path: &str, text: &str) {
+        self.documents.insert(path.to_string(), top_terms(keyword_counts(text)));
+    }
+
+    /// Records the collected counts of the `stored` documents
+    pub fn apply(self, stored: &[String]) {
+        let stored: HashSet<&str> = stored.iter().map(String::as_str).collect();
+        if let Ok(mut frequencies) = TERM_FREQUENCIES.lock() {
+            for (path, terms) in self.documents {
+                if stored.contains(path.as_str()) {
+                    frequencies.set_document(path, terms);
+                }
+            }
+        }
+    }
+}
+
+/// Writes the counts to disk if they changed since the last save, e.g. at the
+/// end of an indexing run
+pub fn save_term_frequencies() -> Result<(), String> {
+    let mut frequencies = TERM_FREQUENCIES.lock().map_err(|_| "Term frequencies lock poisoned".to_string())?;
+    if !frequencies.dirty {
+        return Ok(());
+    }
+    frequencies.prune_rarest(MAX_STORED_TERMS);
+    let stored = StoredTermFrequencies {
+        documents: frequencies.documents.clone(),
+    };
+    save_json(&term_frequencies_file_path()?, &stored, "term frequencies")?;
+    frequencies.dirty = false;
+    info!(
+        "Saved frequencies of {} terms in {} documents",
+        frequencies.totals.len(),
+        frequencies.documents.len()
+    );
+    Ok(())
+}
+
+/// Forgets every count, e.g. when the index is cleared
+pub fn clear_term_frequencies() -> Result<(), String> {
+    replace_term_frequencies(HashMap::new())
+}
+
+fn replace_term_frequencies(documents: HashMap<String, DocumentTerms>) -> Result<(), String> {
+    let mut frequencies = TERM_FREQUENCIES.lock().map_err(|_| "Term frequencies lock poisoned".to_string())?;
+    *frequencies = TermFrequencies::from_documents(documents);
+    frequencies.dirty = true;
+    drop(frequencies);
+    save_term_frequencies()
+}
+
+/// Outcome of `rebuild_term_frequencies`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TermRebuildSummary {
+    /// Indexed text documents whose terms were counted
+    pub documents: usize,
+    /// Distinct terms across them
+    pub terms: usize,
+    /// Documents that couldn't be read again, or virtual content with no file behind it
+    pub skipped: usize,
+    /// Stopped before the end; the previous counts were kept
+    pub cancelled: bool,
+}
+
+/// Reads the text of one indexed file again and counts its terms
+fn count_file_terms(path: &Path, streaming_threshold_bytes: u64) -> Option<HashMap<String, usize>> {
+    let mut counts = HashMap::new();
+    if should_stream(path, streaming_threshold_bytes) {
+        for segment in stream_text_segments(path).ok()? {
+            add_term_counts(&mut counts, &segment.ok()?);
+        }
+    } else {
+        add_term_counts(&mut counts, &extract_text(path).ok()?.text);
+    }
+    Some(counts)
+}
+
+/// Counts the terms of `paths` by reading them again; archive members are
+/// read once per archive. Returns `None` when cancelled. Blocking.
+fn count_terms_of(
+    paths: &[String],
+    streaming_threshold_bytes: u64,
+    token: &CancellationToken,
+) -> Option<(HashMap<String, DocumentTerms>, usize)> {
+    let mut documents = HashMap::new();
+    let mut skipped = 0;
+    let mut archives: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for path in paths {
+        if token.is_cancelled() {
+            return None;
+        }
+        if is_virtual_path(path) {
+            skipped += 1;
+            continue;
+        }
+        if let Some((archive, _)) = split_composite_path(path) {
+            archives.entry(archive).or_default().insert(path.as_str());
+            continue;
+        }
+        match count_file_terms(Path::new(path), streaming_threshold_bytes) {
+            Some(counts) => {
+                documents.insert(path.clone(), top_terms(counts));
+            }
+            None => {
+                debug!("Cannot re-read {} to count its terms", path);
+                skipped += 1;
+            }
+        }
+    }
+
+    for (archive, members) in archives {
+        if token.is_cancelled() {
+            return None;
+        }
+        let Ok(extracted) = extract_archive_texts(Path::new(archive)) else {
+            skipped += members.len();
+            continue;
+        };
+        let mut found = 0;
+        for member in extracted {
+            if members.contains(member.composite_path.as_str()) {
+                found += 1;
+                documents.insert(member.composite_path, top_terms(keyword_counts(&member.text)));
+            }
+        }
+        skipped += members.len() - found;
+    }
+    Some((documents, skipped))
+}
+
+/// Recounts the terms of every indexed text document from scratch, for when
+/// the stored counts were lost or drifted. Reads every document again.
+pub async fn rebuild_term_frequencies(
+    conn: &Connection,
+    streaming_threshold_bytes: u64,
+    token: CancellationToken,
+) -> Result<TermRebuildSummary, String> {
+    let mut paths = Vec::new();
+    for content_type in ["text", "amharic", "code"] {
+        let documents = list_indexed_documents(conn, Some(content_type), DocumentSortBy::Path)
+            .await
+            .map_err(|e| format!("Failed to list indexed documents: {}", e))?;
+        paths.extend(documents.into_iter().map(|doc| doc.file_path));
+    }
+
+    let counted = tokio::task::spawn_blocking(move || count_terms_of(&paths, streaming_threshold_bytes, &token))
+        .await
+        .map_err(|e| format!("Term counting task failed: {}", e))?;
+    let Some((documents, skipped)) = counted else {
+        info!("Term frequency rebuild cancelled");
+        return Ok(TermRebuildSummary {
+            cancelled: true,
+            ..Default::default()
+        });
+    };
+
+    let document_count = documents.len();
+    replace_term_frequencies(documents)?;
+    let terms = TERM_FREQUENCIES
+        .lock()
+        .map(|frequencies| frequencies.totals.len())
+        .unwrap_or_default();
+    info!("Rebuilt term frequencies of {} documents ({} skipped)", document_count, skipped);
+    Ok(TermRebuildSummary {
+        documents: document_count,
+        terms,
+        skipped,
+        cancelled: false,
+    })
+}
+
+/// Completions of the last word of `prefix` from `counts`, most frequent
+/// first; among equally frequent terms the shortest, then alphabetical.
+/// Words before the last one are kept as typed.
+fn suggest_from(counts: &HashMap<String, u64>, prefix: &str, limit: usize) -> Vec<SearchSuggestion> {
+    // A trailing space means the last word is finished
+    if prefix.ends_with(char::is_whitespace) {
+        return Vec::new();
+    }
+    let Some(last_word) = prefix.split_whitespace().last() else {
+        return Vec::new();
+    };
+    let typed = &prefix[..prefix.len() - last_word.len()];
+    let partial = last_word.to_lowercase();
+
+    let mut matches: Vec<(&String, u64)> = counts
+        .iter()
+        .filter(|(term, _)| term.starts_with(&partial) && **term != partial)
+        .map(|(term, count)| (term, *count))
+        .collect();
+    matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.len().cmp(&b.0.len())).then_with(|| a.0.cmp(b.0)));
+    matches
+        .into_iter()
+        .take(limit)
+        .map(|(term, frequency)| SearchSuggestion {
+            text: format!("{}{}", typed, term),
+            term: term.clone(),
+            frequency,
+        })
+        .collect()
+}
+
+/// Completions of what the user has typed so far, from the terms of the
+/// indexed documents
+pub fn search_suggestions(prefix: &str, limit: usize) -> Vec<SearchSuggestion> {
+    TERM_FREQUENCIES
+        .lock()
+        .map(|frequencies| suggest_from(&frequencies.totals, prefix, limit))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggestions_complete_the_last_word_by_frequency() {
+        let counts: HashMap<String, u64> =
+            [("budget", 12), ("budgets", 12), ("buddy", 30), ("bud", 50), ("report", 9), ("reports", 2)]
+                .into_iter()
+                .map(|(term, count)| (term.to_string(), count))
+                .collect();
+
+        let terms: Vec<String> = suggest_from(&counts, "Bud", 10).into_iter().map(|s| s.term).collect();
+        assert_eq!(terms, vec!["buddy", "budget", "budgets"]);
+
+        let suggestions = suggest_from(&counts, "budget rep", 1);
+        assert_eq!(
+            suggestions,
+            vec![SearchSuggestion { text: "budget report".into(), term: "report".into(), frequency: 9 }]
+        );
+        assert!(suggest_from(&counts, "budget ", 10).is_empty());
+        assert!(suggest_from(&counts, "", 10).is_empty());
+    }
+
+    #[test]
+    fn test_totals_follow_stored_and_deleted_documents() {
+        let terms = |text: &str| top_terms(keyword_counts(text));
+        let mut frequencies = TermFrequencies::default();
+        frequencies.set_document("/a.txt".into(), terms("garden garden tomato"));
+        frequencies.set_document("/b.txt".into(), terms("garden roses"));
+        assert_eq!(frequencies.totals.get("garden"), Some(&3));
+
+        // Pruning drops the rarest terms from the documents too
+        frequencies.prune_rarest(1);
+        assert_eq!(frequencies.totals.keys().collect::<Vec<_>>(), vec!["garden"]);
+        assert_eq!(frequencies.documents["/b.txt"].len(), 1);
+
+        frequencies.set_document("/a.txt".into(), terms("tomato harvest"));
+        assert_eq!(frequencies.totals.get("garden"), Some(&1));
+        assert_eq!(frequencies.totals.get("tomato"), Some(&1));
+
+        frequencies.remove_document("/b.txt");
+        assert!(!frequencies.totals.contains_key("garden"), "Terms no document uses are dropped");
+        assert_eq!(frequencies.totals.len(), 2);
+    }
+}
@@ -0,0 +1,173 @@
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Image extensions thumbnails are rendered for
+pub(crate) const THUMBNAIL_IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "webp", "svg"];
+/// Video extensions thumbnails are taken from a frame of, through FFmpeg
+pub(crate) const THUMBNAIL_VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "avi", "mkv", "webm"];
+
+/// Extension of the file next to each thumbnail that names its source file
+const SOURCE_SIDECAR_EXTENSION: &str = "source";
+
+/// Size of the thumbnail cache
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThumbnailCacheStats {
+    pub thumbnails: usize,
+    /// Thumbnails and their source records together
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThumbnailPruneReport {
+    /// Thumbnails whose source was deleted or has changed since
+    pub removed: usize,
+    /// Thumbnails cached before sources were recorded; they can't be checked,
+    /// so they are removed and regenerated when next shown
+    pub untraced_removed: usize,
+    pub freed_bytes: u64,
+}
+
+// Creates a hash string from path and modified time
+pub(crate) fn hash_path_and_mtime(path: &Path, modified: Option<SystemTime>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(path.to_string_lossy().as_bytes());
+    if let Some(mtime) = modified {
+        if let Ok(duration) = mtime.duration_since(SystemTime::UNIX_EPOCH) {
+            hasher.update(duration.as_secs().to_le_bytes());
+        }
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn sidecar_path(thumbnail: &Path) -> PathBuf {
+    thumbnail.with_extension(SOURCE_SIDECAR_EXTENSION)
+}
+
+/// Notes which file `thumbnail` was generated from, so pruning can tell when
+/// that file is gone. Failing to write it only costs the ability to prune.
+pub fn record_thumbnail_source(thumbnail: &Path, source: &Path) {
+    if let Err(e) = fs::write(sidecar_path(thumbnail), source.to_string_lossy().as_bytes()) {
+        warn!("Failed to record thumbnail source for {}: {}", source.display(), e);
+    }
+}
+
+/// Whether the thumbnail is still the one its source would get today. The
+/// cache name hashes path and mtime, so an edited source gets a new name.
+fn is_current(thumbnail: &Path, source: &Path) -> bool {
+    let Ok(metadata) = fs::metadata(source) else {
+        return false;
+    };
+    let expected = hash_path_and_mtime(source, metadata.modified().ok());
+    thumbnail.file_stem().and_then(|stem| stem.to_str()) == Some(expected.as_str())
+}
+
+fn cache_files(cache_dir: &Path) -> Vec<(PathBuf, u64)> {
+    let entries = match fs::read_dir(cache_dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            // No cache directory yet just means nothing has been cached
+            debug!("Cannot read thumbnail cache {}: {}", cache_dir.display(), e);
+            return Vec::new();
+        }
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| (entry.path(), metadata.len()))
+        })
+        .collect()
+}
+
+fn is_thumbnail(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("jpg")
+}
+
+pub fn thumbnail_cache_stats(cache_dir: &Path) -> ThumbnailCacheStats {
+    let files = cache_files(cache_dir);
+    ThumbnailCacheStats {
+        thumbnails: files.iter().filter(|(path, _)| is_thumbnail(path)).count(),
+        total_bytes: files.iter().map(|(_, len)| len).sum(),
+    }
+}
+
+/// Removes thumbnails whose source file was deleted or modified, along with
+/// source records left without a thumbnail.
+pub fn prune_thumbnail_cache(cache_dir: &Path) -> ThumbnailPruneReport {
+    let mut report = ThumbnailPruneReport::default();
+    let files = cache_files(cache_dir);
+    for (path, len) in &files {
+        if is_thumbnail(path) {
+            let sidecar = sidecar_path(path);
+            let source = fs::read_to_string(&sidecar).ok();
+            if source.as_deref().is_some_and(|source| is_current(path, Path::new(source))) {
+                continue;
+            }
+            if let Err(e) = fs::remove_file(path) {
+                warn!("Failed to remove stale thumbnail {}: {}", path.display(), e);
+                continue;
+            }
+            report.freed_bytes += len;
+            match source {
+                Some(_) => report.removed += 1,
+                None => report.untraced_removed += 1,
+            }
+            if let Ok(sidecar_len) = fs::metadata(&sidecar).map(|m| m.len()) {
+                if fs::remove_file(&sidecar).is_ok() {
+                    report.freed_bytes += sidecar_len;
+                }
+            }
+        } else if path.extension().and_then(|ext| ext.to_str()) == Some(SOURCE_SIDECAR_EXTENSION)
+            && !path.with_extension("jpg").exists()
+            && fs::remove_file(path).is_ok()
+        {
+            report.freed_bytes += len;
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn cache_thumbnail(cache_dir: &Path, source: &Path) -> PathBuf {
+        let modified = fs::metadata(source).unwrap().modified().ok();
+        let thumbnail = cache_dir.join(format!("{}.jpg", hash_path_and_mtime(source, modified)));
+        fs::write(&thumbnail, b"jpeg").unwrap();
+        record_thumbnail_source(&thumbnail, source);
+        thumbnail
+    }
+
+    #[test]
+    fn test_prune_removes_thumbnails_of_missing_sources() {
+        let files = tempdir().unwrap();
+        let cache = tempdir().unwrap();
+        let kept = files.path().join("kept.png");
+        let deleted = files.path().join("deleted.png");
+        fs::write(&kept, b"png").unwrap();
+        fs::write(&deleted, b"png").unwrap();
+
+        let kept_thumbnail = cache_thumbnail(cache.path(), &kept);
+        let deleted_thumbnail = cache_thumbnail(cache.path(), &deleted);
+        fs::write(cache.path().join("legacy.jpg"), b"jpeg").unwrap();
+        fs::remove_file(&deleted).unwrap();
+
+        let stats = thumbnail_cache_stats(cache.path());
+        assert_eq!(stats.thumbnails, 3);
+        assert!(stats.total_bytes > 12);
+
+        let report = prune_thumbnail_cache(cache.path());
+        assert_eq!(report.removed, 1);
+        assert_eq!(report.untraced_removed, 1);
+        assert!(kept_thumbnail.exists());
+        assert!(!deleted_thumbnail.exists());
+        assert!(!sidecar_path(&deleted_thumbnail).exists());
+        assert_eq!(thumbnail_cache_stats(cache.path()).thumbnails, 1);
+    }
+}
@@ -0,0 +1,349 @@
+use crate::core::indexer::{index_single_file, ContentCheck};
+use crate::core::metadata_index::sync_file_metadata;
+use crate::core::term_frequencies::forget_document_terms;
+use crate::db::{
+    bump_index_version, create_tombstone_schema, file_path_in_predicate, list_indexed_documents,
+    open_or_create_tombstone_table, DbError, DocumentSortBy, CONTENT_TABLES,
+};
+use arrow_array::{Array, RecordBatch, RecordBatchIterator, StringArray, TimestampSecondArray};
+use chrono::Utc;
+use futures::TryStreamExt;
+use lancedb::connection::Connection;
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use lancedb::table::Table;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Days a tombstone is kept before `prune_expired_tombstones` removes it
+pub const DEFAULT_TOMBSTONE_RETENTION_DAYS: u64 = 30;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Most files tombstoned per query, keeping predicates a manageable size
+const TOMBSTONE_BATCH_SIZE: usize = 500;
+
+/// What the index knew about a file when its entries were deleted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub file_path: String,
+    pub content_hash: String,
+    /// When the file was last indexed, in seconds since the epoch
+    pub last_modified: i64,
+    pub deleted_at: i64,
+    /// Table the entries came from: "text", "amharic", "code" or "image"
+    pub content_type: String,
+    #[serde(default)]
+    pub index_root: Option<String>,
+    /// A file is at the path again, so restoring will re-index it
+    #[serde(default)]
+    pub exists_on_disk: bool,
+}
+
+/// Result of [`restore_tombstone`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestoredTombstone {
+    pub file_path: String,
+    /// False when the file came back empty or too short to embed
+    pub indexed: bool,
+}
+
+/// Moves the entries `table` holds for `file_paths` into tombstones. Returns
+/// how many of the paths had entries.
+async fn tombstone_rows(
+    table: &Table,
+    tombstones: &Table,
+    content_type: &str,
+    file_paths: &[String],
+) -> Result<usize, DbError> {
+    let schema = table.schema().await?;
+    // Images are hashed by file, everything else by extracted content
+    let hash_column = if schema.field_with_name("file_hash").is_ok() { "file_hash" } else { "content_hash" };
+    let mut columns = vec!["file_path", hash_column, "last_modified"];
+    if schema.field_with_name("index_root").is_ok() {
+        columns.push("index_root");
+    }
+    let batches: Vec<RecordBatch> = table
+        .query()
+        .only_if(file_path_in_predicate(file_paths))
+        .select(Select::columns(&columns))
+        .execute()
+        .await?
+        .try_collect()
+        .await?;
+
+    // Every chunk of a file carries the same hash, root and indexing time, so
+    // its first row stands for the file
+    let mut seen = HashSet::new();
+    let (mut paths, mut hashes, mut modified, mut roots) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for batch in &batches {
+        let string_column = |name| batch.column_by_name(name).and_then(|c| c.as_any().downcast_ref::<StringArray>());
+        let times = batch
+            .column_by_name("last_modified")
+            .and_then(|c| c.as_any().downcast_ref::<TimestampSecondArray>());
+        let (Some(batch_paths), Some(batch_hashes), Some(times)) = (string_column("file_path"), string_column(hash_column), times)
+        else {
+            return Err(DbError::Other(format!("Unexpected column layout in table '{}'", table.name())));
+        };
+        let batch_roots = string_column("index_root");
+        for row in 0..batch.num_rows() {
+            if !seen.insert(batch_paths.value(row)) {
+                continue;
+            }
+            paths.push(batch_paths.value(row).to_string());
+            hashes.push(batch_hashes.value(row).to_string());
+            modified.push(times.value(row));
+            roots.push(batch_roots.filter(|r| r.is_valid(row)).map(|r| r.value(row).to_string()));
+        }
+    }
+    if paths.is_empty() {
+        return Ok(0);
+    }
+
+    let schema = create_tombstone_schema();
+    let record = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(paths.clone())),
+            Arc::new(StringArray::from(hashes)),
+            Arc::new(TimestampSecondArray::from(modified)),
+            Arc::new(TimestampSecondArray::from(vec![Utc::now().timestamp(); paths.len()])),
+            Arc::new(StringArray::from(vec![content_type; paths.len()])),
+            Arc::new(StringArray::from(roots)),
+        ],
+    )
+    .map_err(DbError::SchemaError)?;
+    let found = file_path_in_predicate(&paths);
+    // A file deleted twice keeps only its latest tombstone
+    tombstones.delete(&found).await?;
+    let reader = RecordBatchIterator::new(vec![Ok(record)], schema);
+    tombstones.add(Box::new(reader)).execute().await?;
+
+    table.delete(&found).await?;
+    bump_index_version();
+    for path in &paths {
+        forget_document_terms(path);
+    }
+    Ok(paths.len())
+}
+
+/// Content tables present in the database, with the content type each holds
+pub(crate) async fn existing_content_tables(conn: &Connection) -> Result<Vec<(Table, &'static str)>, String> {
+    let table_names = conn.table_names().execute().await.map_err(|e| e.to_string())?;
+    let mut tables = Vec::new();
+    for (table_name, content_type) in CONTENT_TABLES {
+        if !table_names.iter().any(|name| name == table_name) {
+            continue;
+        }
+        let table = conn
+            .open_table(table_name)
+            .execute()
+            .await
+            .map_err(|e| format!("Failed to open table '{}': {}", table_name, e))?;
+        tables.push((table, content_type));
+    }
+    Ok(tables)
+}
+
+/// Removes a deleted file from the index, keeping a tombstone of it, and
+/// from the metadata table. Returns the number of content tables it was
+/// removed from.
+pub async fn tombstone_path(conn: &Connection, file_path: &str) -> Result<usize, String> {
+    let tombstones = open_or_create_tombstone_table(conn).await.map_err(|e| e.to_string())?;
+    let mut removed = 0;
+    let file_paths = [file_path.to_string()];
+    for (table, content_type) in existing_content_tables(conn).await? {
+        removed += tombstone_rows(&table, &tombstones, content_type, &file_paths)
+            .await
+            .map_err(|e| format!("Failed to tombstone {} in '{}': {}", file_path, table.name(), e))?;
+    }
+    sync_file_metadata(conn, Path::new(file_path)).await?;
+    Ok(removed)
+}
+
+/// Tombstones every indexed file under a deleted folder and drops the
+/// folder's metadata rows. Returns the number of files tombstoned.
+pub async fn tombstone_folder(conn: &Connection, folder: &Path) -> Result<usize, String> {
+    let documents = list_indexed_documents(conn, None, DocumentSortBy::Path)
+        .await
+        .map_err(|e| format!("Failed to list indexed documents: {}", e))?;
+    let tombstones = open_or_create_tombstone_table(conn).await.map_err(|e| e.to_string())?;
+    let mut removed = 0;
+    for (table, content_type) in existing_content_tables(conn).await? {
+        let under_folder: Vec<String> = documents
+            .iter()
+            .filter(|doc| doc.content_type == content_type && Path::new(&doc.file_path).starts_with(folder))
+            .map(|doc| doc.file_path.clone())
+            .collect();
+        for batch in under_folder.chunks(TOMBSTONE_BATCH_SIZE) {
+            removed += tombstone_rows(&table, &tombstones, content_type, batch)
+                .await
+                .map_err(|e| format!("Failed to tombstone files under {}: {}", folder.display(), e))?;
+        }
+    }
+    sync_file_metadata(conn, folder).await?;
+    info!("Tombstoned {} indexed files under {}", removed, folder.display());
+    Ok(removed)
+}
+
+/// All tombstones, most recently deleted first
+pub async fn list_tombstones(conn: &Connection) -> Result<Vec<Tombstone>, String> {
+    let table = open_or_create_tombstone_table(conn).await.map_err(|e| e.to_string())?;
+    let batches: Vec<RecordBatch> = table
+        .query()
+        .execute()
+        .await
+        .map_err(|e| e.to_string())?
+        .try_collect()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut tombstones = Vec::new();
+    for batch in batches {
+        let string_column = |name| batch.column_by_name(name).and_then(|c| c.as_any().downcast_ref::<StringArray>());
+        let time_column =
+            |name| batch.column_by_name(name).and_then(|c| c.as_any().downcast_ref::<TimestampSecondArray>());
+        let (Some(paths), Some(hashes), Some(types), Some(roots), Some(modified), Some(deleted)) = (
+            string_column("file_path"),
+            string_column("content_hash"),
+            string_column("content_type"),
+            string_column("index_root"),
+            time_column("last_modified"),
+            time_column("deleted_at"),
+        ) else {
+            warn!("Unexpected column layout in the tombstone table, skipping batch");
+            continue;
+        };
+        for row in 0..batch.num_rows() {
+            tombstones.push(Tombstone {
+                file_path: paths.value(row).to_string(),
+                content_hash: hashes.value(row).to_string(),
+                last_modified: modified.value(row),
+                deleted_at: deleted.value(row),
+                content_type: types.value(row).to_string(),
+                index_root: roots.is_valid(row).then(|| roots.value(row).to_string()),
+                exists_on_disk: Path::new(paths.value(row)).exists(),
+            });
+        }
+    }
+    tombstones.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at).then_with(|| a.file_path.cmp(&b.file_path)));
+    Ok(tombstones)
+}
+
+/// Re-indexes a tombstoned file that is back on disk and drops its tombstone.
+/// The embeddings were deleted with the entry, so a file that is still gone
+/// can't be restored.
+pub async fn restore_tombstone(conn: &Connection, file_path: &str) -> Result<RestoredTombstone, String> {
+    let tombstone = list_tombstones(conn)
+        .await?
+        .into_iter()
+        .find(|tombstone| tombstone.file_path == file_path)
+        .ok_or_else(|| format!("No tombstone for {}", file_path))?;
+    if !tombstone.exists_on_disk {
+        return Err(format!("{} is no longer on disk; there is nothing to re-index", file_path));
+    }
+
+    let check = index_single_file(conn, Path::new(file_path), tombstone.index_root.as_deref()).await?;
+    let table = open_or_create_tombstone_table(conn).await.map_err(|e| e.to_string())?;
+    table
+        .delete(&file_path_in_predicate(&[file_path.to_string()]))
+        .await
+        .map_err(|e| format!("Failed to remove tombstone for {}: {}", file_path, e))?;
+    info!("Restored {} from its tombstone ({:?})", file_path, check);
+    Ok(RestoredTombstone {
+        file_path: file_path.to_string(),
+        indexed: check == ContentCheck::Meaningful,
+    })
+}
+
+/// Removes tombstones deleted before `cutoff` (seconds since the epoch).
+/// Returns how many were removed.
+async fn prune_tombstones_before(conn: &Connection, cutoff: i64) -> Result<usize, String> {
+    let expired: Vec<Tombstone> = list_tombstones(conn)
+        .await?
+        .into_iter()
+        .filter(|tombstone| tombstone.deleted_at < cutoff)
+        .collect();
+    if expired.is_empty() {
+        return Ok(0);
+    }
+    let table = open_or_create_tombstone_table(conn).await.map_err(|e| e.to_string())?;
+    let expired_paths: Vec<String> = expired.iter().map(|tombstone| tombstone.file_path.clone()).collect();
+    for batch in expired_paths.chunks(TOMBSTONE_BATCH_SIZE) {
+        table
+            .delete(&file_path_in_predicate(batch))
+            .await
+            .map_err(|e| format!("Failed to prune expired tombstones: {}", e))?;
+    }
+    info!("Pruned {} expired tombstones", expired.len());
+    Ok(expired.len())
+}
+
+/// Removes tombstones older than `max_age_days`
+pub async fn prune_tombstones(conn: &Connection, max_age_days: u64) -> Result<usize, String> {
+    let max_age = i64::try_from(max_age_days).unwrap_or(i64::MAX / SECONDS_PER_DAY) * SECONDS_PER_DAY;
+    prune_tombstones_before(conn, Utc::now().timestamp().saturating_sub(max_age)).await
+}
+
+/// Prunes by the `tombstone_retention_days` setting; a retention of 0 keeps
+/// every tombstone
+pub async fn prune_expired_tombstones(conn: &Connection, retention_days: u64) -> Result<usize, String> {
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    prune_tombstones(conn, retention_days).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{connect_db_with_path, open_or_create_text_table, upsert_document, TestDb, TEXT_EMBEDDING_DIM};
+
+    #[tokio::test]
+    async fn test_tombstone_keeps_metadata_and_prunes_by_age() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let table = open_or_create_text_table(&conn).await.unwrap();
+        let embedding = vec![0.1; TEXT_EMBEDDING_DIM as usize];
+        upsert_document(&table, "/gone/notes.txt", "hash-1", &[embedding.clone(), embedding], Some("/gone"))
+            .await
+            .unwrap();
+
+        assert_eq!(tombstone_path(&conn, "/gone/notes.txt").await.unwrap(), 1);
+        assert_eq!(table.count_rows(None).await.unwrap(), 0);
+        // Nothing left to tombstone the second time
+        assert_eq!(tombstone_path(&conn, "/gone/notes.txt").await.unwrap(), 0);
+
+        let tombstones = list_tombstones(&conn).await.unwrap();
+        assert_eq!(tombstones.len(), 1);
+        assert_eq!(tombstones[0].content_hash, "hash-1");
+        assert_eq!(tombstones[0].content_type, "text");
+        assert_eq!(tombstones[0].index_root.as_deref(), Some("/gone"));
+        assert!(!tombstones[0].exists_on_disk);
+        assert!(restore_tombstone(&conn, "/gone/notes.txt").await.is_err());
+
+        assert_eq!(prune_tombstones(&conn, 1).await.unwrap(), 0);
+        assert_eq!(prune_tombstones_before(&conn, tombstones[0].deleted_at + 1).await.unwrap(), 1);
+        assert!(list_tombstones(&conn).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tombstone_folder_takes_every_file_under_it() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let table = open_or_create_text_table(&conn).await.unwrap();
+        let embedding = vec![0.1; TEXT_EMBEDDING_DIM as usize];
+        for path in ["/gone/a.txt", "/gone/deep/b.txt", "/gone-too/c.txt"] {
+            upsert_document(&table, path, "hash", &[embedding.clone(), embedding.clone()], None).await.unwrap();
+        }
+
+        assert_eq!(tombstone_folder(&conn, Path::new("/gone")).await.unwrap(), 2);
+        let mut paths: Vec<String> = list_tombstones(&conn).await.unwrap().into_iter().map(|t| t.file_path).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["/gone/a.txt", "/gone/deep/b.txt"]);
+        // Both chunks of the file outside the folder are left
+        assert_eq!(table.count_rows(None).await.unwrap(), 2);
+    }
+}
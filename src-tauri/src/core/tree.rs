@@ -0,0 +1,130 @@
+use super::cancellation::CancellationToken;
+use super::indexer::walk_entries_to_depth;
+use super::models::FileInfo;
+use super::query::file_info;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Entries are handed to the caller in batches of this size
+const TREE_BATCH_SIZE: usize = 200;
+
+/// A file or folder found by `walk_tree`, placed in the tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeEntry {
+    #[serde(flatten)]
+    pub info: FileInfo,
+    /// 1 for the root's direct children
+    pub depth: usize,
+    /// Path of the folder holding this entry
+    pub parent: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TreeWalkSummary {
+    pub entries: usize,
+    pub directories: usize,
+    /// Stopped at `max_entries`
+    pub truncated: bool,
+    pub cancelled: bool,
+}
+
+/// Walks `root` down to `max_depth` levels (skipping excluded folders, and
+/// hidden entries unless `include_hidden` is set, like indexing does) and passes entries to `on_batch` as
+/// they are found. A folder always arrives before anything inside it, so the
+/// caller can attach each entry to its parent as soon as it sees it. Blocking;
+/// run it off the async runtime.
+pub fn walk_tree(
+    root: &Path,
+    max_depth: usize,
+    follow_symlinks: bool,
+    max_entries: usize,
+    token: &CancellationToken,
+    mut on_batch: impl FnMut(Vec<TreeEntry>),
+) -> TreeWalkSummary {
+    let mut summary = TreeWalkSummary::default();
+    let mut batch = Vec::with_capacity(TREE_BATCH_SIZE);
+
+    for entry in walk_entries_to_depth(root, follow_symlinks, max_depth) {
+        if token.is_cancelled() {
+            summary.cancelled = true;
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Skipping unreadable entry while walking {}: {}", root.display(), e);
+                continue;
+            }
+        };
+        if entry.depth() == 0 {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        let info = file_info(entry.path(), &metadata, entry.path_is_symlink());
+        if info.is_directory {
+            summary.directories += 1;
+        }
+        batch.push(TreeEntry {
+            info,
+            depth: entry.depth(),
+            parent: entry.path().parent().unwrap_or(root).to_string_lossy().into_owned(),
+        });
+        summary.entries += 1;
+        if batch.len() == TREE_BATCH_SIZE {
+            on_batch(std::mem::replace(&mut batch, Vec::with_capacity(TREE_BATCH_SIZE)));
+        }
+        if summary.entries >= max_entries {
+            summary.truncated = true;
+            break;
+        }
+    }
+    if !batch.is_empty() {
+        on_batch(batch);
+    }
+
+    info!(
+        "Tree walk under {} found {} entries ({} folders){}",
+        root.display(),
+        summary.entries,
+        summary.directories,
+        if summary.cancelled { " (cancelled)" } else { "" }
+    );
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_walk_tree_reports_depth_and_parent() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("docs/2024/q1")).unwrap();
+        fs::write(dir.path().join("docs/2024/q1/report.txt"), "deep").unwrap();
+        fs::write(dir.path().join("docs/readme.md"), "shallow").unwrap();
+        fs::write(dir.path().join(".hidden"), "skip").unwrap();
+        fs::create_dir(dir.path().join("node_modules")).unwrap();
+
+        let mut found = Vec::new();
+        let summary = walk_tree(dir.path(), 2, false, 100, &CancellationToken::none(), |batch| found.extend(batch));
+        let names: Vec<(&str, usize)> = found.iter().map(|e| (e.info.name.as_str(), e.depth)).collect();
+        assert_eq!(summary.entries, 3, "Unexpected entries: {:?}", names);
+        assert_eq!(summary.directories, 2);
+        assert_eq!(found[0].info.name, "docs");
+        assert_eq!(found[0].parent, dir.path().to_string_lossy());
+        let year = found.iter().find(|e| e.info.name == "2024").unwrap();
+        assert_eq!(year.depth, 2);
+        assert!(year.info.is_directory && year.info.size.is_none());
+        assert!(!names.iter().any(|(name, _)| *name == "q1" || *name == ".hidden" || *name == "node_modules"));
+
+        let summary = walk_tree(dir.path(), usize::MAX, false, 2, &CancellationToken::none(), |_| {});
+        assert_eq!(summary.entries, 2);
+        assert!(summary.truncated);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::core::indexer::is_text_too_short;
+use crate::core::settings::current_settings;
+use crate::db::{
+    delete_document, open_or_create_amharic_text_table, open_or_create_code_table, open_or_create_text_table,
+    upsert_amharic_document, upsert_code_document, upsert_document,
+};
+use crate::embedder::{embed_code, embed_text};
+use crate::extractor::{calculate_hash, detect_language, DetectedLanguage};
+use lancedb::connection::Connection;
+use log::info;
+
+/// Prefix of every path indexed from caller-supplied content. No filesystem
+/// path starts with it, so virtual entries can't collide with real files.
+pub const VIRTUAL_PATH_PREFIX: &str = "virtual://";
+
+/// Whether an indexed path names caller-supplied content rather than a file
+pub fn is_virtual_path(path: &str) -> bool {
+    path.starts_with(VIRTUAL_PATH_PREFIX)
+}
+
+/// Which embedding model indexes the content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VirtualContentKind {
+    /// Prose; lands in the text or Amharic table by detected language
+    Text,
+    Code,
+}
+
+fn parse_content_kind(content_type: &str) -> Result<VirtualContentKind, String> {
+    match content_type.trim().to_lowercase().as_str() {
+        "text" => Ok(VirtualContentKind::Text),
+        "code" => Ok(VirtualContentKind::Code),
+        other => Err(format!("Unsupported content type '{}': expected \"text\" or \"code\"", other)),
+    }
+}
+
+fn validated_virtual_path(virtual_path: &str) -> Result<&str, String> {
+    let path = virtual_path.trim();
+    let Some(name) = path.strip_prefix(VIRTUAL_PATH_PREFIX) else {
+        return Err(format!("Virtual paths must start with {}: {}", VIRTUAL_PATH_PREFIX, path));
+    };
+    if name.trim_matches('/').is_empty() {
+        return Err(format!("Virtual path has no name after {}", VIRTUAL_PATH_PREFIX));
+    }
+    Ok(path)
+}
+
+/// Chunks, embeds and stores `content` under `virtual_path` without touching
+/// the filesystem. Indexing the same virtual path again replaces its content.
+pub async fn index_content(
+    conn: &Connection,
+    virtual_path: &str,
+    content: &str,
+    content_type: &str,
+) -> Result<(), String> {
+    let path = validated_virtual_path(virtual_path)?;
+    let kind = parse_content_kind(content_type)?;
+    let min_text_chars = current_settings().min_text_chars;
+    if is_text_too_short(content, min_text_chars) {
+        return Err(format!("Content for {} is shorter than {} characters", path, min_text_chars));
+    }
+
+    let text_table = open_or_create_text_table(conn).await.map_err(|e| e.to_string())?;
+    let amharic_table = open_or_create_amharic_text_table(conn).await.map_err(|e| e.to_string())?;
+    let code_table = open_or_create_code_table(conn).await.map_err(|e| e.to_string())?;
+
+    let content_hash = calculate_hash(content);
+    let language = detect_language(content);
+    let texts = vec![content.to_string()];
+    let embed_language = language.clone();
+    let embeddings = tokio::task::spawn_blocking(move || match kind {
+        VirtualContentKind::Code => embed_code(&texts, false),
+        VirtualContentKind::Text => embed_text(&texts, &embed_language, false),
+    })
+    .await
+    .map_err(|e| format!("Embedding task failed for {}: {}", path, e))?
+    .map_err(|e| format!("Embedding generation failed for {}: {}", path, e))?;
+    if embeddings.is_empty() {
+        return Err(format!("No embeddings generated for {}", path));
+    }
+
+    // The language or kind may differ from last time; only one table may hold the path
+    remove_virtual_content(conn, path).await?;
+    let stored = match (kind, language) {
+        (VirtualContentKind::Code, _) => upsert_code_document(&code_table, path, &content_hash, &embeddings, None).await,
+        (VirtualContentKind::Text, DetectedLanguage::Amharic) => {
+            upsert_amharic_document(&amharic_table, path, &content_hash, &embeddings, None).await
+        }
+        (VirtualContentKind::Text, _) => upsert_document(&text_table, path, &content_hash, &embeddings, None).await,
+    };
+    stored.map_err(|e| format!("Database upsert failed for {}: {}", path, e))?;
+    info!("Indexed {} chunks of caller-supplied content as {}", embeddings.len(), path);
+    Ok(())
+}
+
+/// Removes a virtual path from every text table
+pub async fn remove_virtual_content(conn: &Connection, virtual_path: &str) -> Result<(), String> {
+    let path = validated_virtual_path(virtual_path)?;
+    let tables = [
+        open_or_create_text_table(conn).await,
+        open_or_create_amharic_text_table(conn).await,
+        open_or_create_code_table(conn).await,
+    ];
+    for table in tables {
+        let table = table.map_err(|e| e.to_string())?;
+        delete_document(&table, path)
+            .await
+            .map_err(|e| format!("Failed to remove {} from '{}': {}", path, table.name(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{connect_db_with_path, sql_string_literal, TestDb};
+
+    #[test]
+    fn test_virtual_paths_are_validated() {
+        assert_eq!(validated_virtual_path(" virtual://clips/page-1 ").unwrap(), "virtual://clips/page-1");
+        assert!(validated_virtual_path("/home/me/notes.txt").is_err());
+        assert!(validated_virtual_path("virtual://").is_err());
+        assert_eq!(validated_virtual_path("virtual://it's").unwrap(), "virtual://it's");
+        assert!(is_virtual_path("virtual://clips/page-1"));
+        assert!(!is_virtual_path("/virtual/clips"));
+
+        assert_eq!(parse_content_kind("Code").unwrap(), VirtualContentKind::Code);
+        assert!(parse_content_kind("image").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_index_content_round_trip() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let path = "virtual://clips/it's-a-page";
+        let filter = format!("file_path = {}", sql_string_literal(path));
+        let content = "The meeting notes cover the budget review and the hiring plan for next quarter.";
+
+        index_content(&conn, path, content, "text").await.unwrap();
+        let text_table = open_or_create_text_table(&conn).await.unwrap();
+        let code_table = open_or_create_code_table(&conn).await.unwrap();
+        assert!(text_table.count_rows(Some(filter.clone())).await.unwrap() > 0);
+        assert_eq!(code_table.count_rows(Some(filter.clone())).await.unwrap(), 0);
+
+        // Re-indexing as another kind moves the path instead of duplicating it
+        index_content(&conn, path, "fn budget_review() -> u32 { hiring_plan(next_quarter()) }", "code")
+            .await
+            .unwrap();
+        assert_eq!(text_table.count_rows(Some(filter.clone())).await.unwrap(), 0);
+        assert!(code_table.count_rows(Some(filter.clone())).await.unwrap() > 0);
+
+        remove_virtual_content(&conn, path).await.unwrap();
+        assert_eq!(code_table.count_rows(Some(filter)).await.unwrap(), 0);
+    }
+}
@@ -1,17 +1,21 @@
 // src-tauri/src/db.rs
 
-use arrow_array::{RecordBatch, RecordBatchIterator, StringArray, FixedSizeListArray, TimestampSecondArray, Int32Array};
+use arrow_array::{Array, RecordBatch, RecordBatchIterator, StringArray, FixedSizeListArray, TimestampSecondArray, Int32Array};
 use arrow_array::builder::Float32Builder;
 use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
-use lancedb::{connection::Connection, table::Table, Error as LanceError};
+use lancedb::{connection::Connection, table::{NewColumnTransform, Table}, Error as LanceError};
 use lancedb::query::{QueryBase, ExecutableQuery, Select};
 use futures::TryStreamExt; // For stream operations
-use std::{path::{Path, PathBuf}, sync::Arc};
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::Arc};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
 use std::fs;
 use tempfile::TempDir; // Add this line for temporary directory support
 use thiserror::Error;
 use chrono::Utc;
-use log::{info, warn, debug};
+use serde::{Deserialize, Serialize};
+use log::{debug, error, info, warn};
 
 use lance_arrow::FixedSizeListArrayExt;
 pub const TEXT_TABLE_NAME: &str = "documents";
@@ -20,6 +24,26 @@ pub const TEXT_EMBEDDING_DIM: i32 = 384;  // BGESmallENV15 dimension
 pub const IMAGE_EMBEDDING_DIM: i32 = 768; // NomicEmbedVisionV15 dimension
 pub const AMHARIC_TEXT_TABLE_NAME: &str = "amharic_documents";
 pub const AMHARIC_EMBEDDING_DIM: i32 = 384; // Dimension for multilingual-e5-small
+pub const CODE_TEXT_TABLE_NAME: &str = "code_documents";
+pub const CODE_EMBEDDING_DIM: i32 = 768; // Dimension for jina-embeddings-v2-base-code
+pub const DELETED_DOCUMENTS_TABLE_NAME: &str = "deleted_documents"; // Tombstones of deleted files
+pub const METADATA_TABLE_NAME: &str = "file_metadata"; // Files indexed in metadata-only mode
+
+/// Content tables and the `IndexedDocument::content_type` each one holds
+pub const CONTENT_TABLES: [(&str, &str); 4] = [
+    (TEXT_TABLE_NAME, "text"),
+    (AMHARIC_TEXT_TABLE_NAME, "amharic"),
+    (CODE_TEXT_TABLE_NAME, "code"),
+    (IMAGE_TABLE_NAME, "image"),
+];
+
+/// Table holding documents of the given `IndexedDocument::content_type`
+pub fn content_table_name(content_type: &str) -> Option<&'static str> {
+    CONTENT_TABLES
+        .iter()
+        .find(|(_, table_content_type)| *table_content_type == content_type)
+        .map(|(table_name, _)| *table_name)
+}
 
 pub const APP_DATA_DIR_NAME: &str = "semantic_file_explorer";
 
@@ -27,6 +51,52 @@ pub const APP_DATA_DIR_NAME: &str = "semantic_file_explorer";
 pub const TABLE_NAME: &str = TEXT_TABLE_NAME;
 pub const EMBEDDING_DIM: i32 = TEXT_EMBEDDING_DIM;
 
+/// Incremented on every write to the index, so caches of query results can
+/// tell when they have gone stale.
+static INDEX_VERSION: AtomicU64 = AtomicU64::new(0);
+
+/// Current version of the index contents
+pub fn index_version() -> u64 {
+    INDEX_VERSION.load(Ordering::SeqCst)
+}
+
+pub(crate) fn bump_index_version() {
+    INDEX_VERSION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Held for writing while the database moves. `connect_db` waits on it, so
+/// nothing connects to the old location mid-copy and the waiting callers
+/// connect to the new one.
+static DB_MOVE_LOCK: Lazy<tokio::sync::RwLock<()>> = Lazy::new(|| tokio::sync::RwLock::new(()));
+
+/// Incremented whenever the database changes location, so holders of
+/// long-lived tables know to reopen them
+static DB_LOCATION_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+pub fn db_location_generation() -> u64 {
+    DB_LOCATION_GENERATION.load(Ordering::SeqCst)
+}
+
+/// Blocks new connections until the returned guard is dropped
+pub(crate) async fn lock_db_for_move() -> tokio::sync::RwLockWriteGuard<'static, ()> {
+    DB_MOVE_LOCK.write().await
+}
+
+pub(crate) fn bump_db_location_generation() {
+    DB_LOCATION_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// One lock per database and table, held while a table is checked and
+/// created, so concurrent first-time opens don't race each other's
+/// `create_table`
+static TABLE_CREATION_LOCKS: Lazy<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn table_creation_lock(conn: &Connection, table_name: &str) -> Arc<tokio::sync::Mutex<()>> {
+    let key = format!("{}\0{}", conn.uri(), table_name);
+    TABLE_CREATION_LOCKS.lock().unwrap().entry(key).or_default().clone()
+}
+
 #[derive(Error, Debug)]
 pub enum DbError {
     #[error("LanceDB connection failed: {0}")]
@@ -55,13 +125,64 @@ pub enum DbError {
     Other(String),
     #[error("Image Embedding Error: {0}")]
     ImageEmbeddingError(#[from] crate::image_embedder::ImageEmbeddingError),
+    #[error("Embedding for '{path}' has dimension {found}, expected {expected}")]
+    EmbeddingDimensionMismatch {
+        path: String,
+        expected: i32,
+        found: usize,
+    },
+    #[error("IndexSizeLimitReached: the index uses {used_bytes} bytes of its {max_bytes} byte limit")]
+    IndexSizeLimitReached { used_bytes: u64, max_bytes: u64 },
 }
 
-pub fn get_db_path() -> Result<PathBuf, DbError> {
+/// Ensures every embedding has the dimension the target table was created with.
+/// Checked before any rows are touched so a bad vector never leaves a file half-updated.
+fn validate_embedding_dims(file_path: &str, embeddings: &[Vec<f32>], expected: i32) -> Result<(), DbError> {
+    if let Some(bad) = embeddings.iter().find(|e| e.len() != expected as usize) {
+        warn!(
+            "Rejecting embedding for {}: dimension {} does not match expected {}",
+            file_path, bad.len(), expected
+        );
+        return Err(DbError::EmbeddingDimensionMismatch {
+            path: file_path.to_string(),
+            expected,
+            found: bad.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Stands in for the app data directory in tests, so they never read or
+/// change the user's settings, favorites or database
+#[cfg(test)]
+static TEST_APP_DATA_DIR: Lazy<TempDir> =
+    Lazy::new(|| TempDir::new().expect("Failed to create temp app data dir for tests"));
+
+/// Directory holding the app's own files (database, settings), without creating it
+#[cfg(test)]
+pub fn get_app_data_dir() -> Result<PathBuf, DbError> {
+    Ok(TEST_APP_DATA_DIR.path().to_path_buf())
+}
+
+/// Directory holding the app's own files (database, settings), without creating it
+#[cfg(not(test))]
+pub fn get_app_data_dir() -> Result<PathBuf, DbError> {
     let app_data_dir = dirs::config_dir()
         .or_else(|| dirs::data_local_dir())
         .ok_or_else(|| DbError::AppDataDirError("Failed to locate application data directory".to_string()))?;
-    let db_dir = app_data_dir.join(APP_DATA_DIR_NAME).join("lancedb");
+    Ok(app_data_dir.join(APP_DATA_DIR_NAME))
+}
+
+/// Where the database lives unless the `db_location` setting moves it
+pub fn default_db_path() -> Result<PathBuf, DbError> {
+    Ok(get_app_data_dir()?.join("lancedb"))
+}
+
+pub fn get_db_path() -> Result<PathBuf, DbError> {
+    let db_dir = match crate::core::settings::current_settings().db_location {
+        Some(location) => location,
+        None => default_db_path()?,
+    };
     if !db_dir.exists() {
         fs::create_dir_all(&db_dir).map_err(|e| DbError::IoError(db_dir.display().to_string(), e))?;
     }
@@ -82,10 +203,29 @@ fn create_amharic_schema() -> SchemaRef {
             true,
         ),
         Field::new("last_modified", DataType::Timestamp(TimeUnit::Second, None), false),
+        Field::new("index_root", DataType::Utf8, true),  // Folder the file was indexed under
     ]))
 }
 
-fn create_text_schema() -> SchemaRef {
+fn create_code_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("file_path", DataType::Utf8, false),
+        Field::new("content_hash", DataType::Utf8, false),
+        Field::new("chunk_id", DataType::Int32, false),
+        Field::new(
+            "embedding",
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, true)),
+                CODE_EMBEDDING_DIM,
+            ),
+            true,
+        ),
+        Field::new("last_modified", DataType::Timestamp(TimeUnit::Second, None), false),
+        Field::new("index_root", DataType::Utf8, true),  // Folder the file was indexed under
+    ]))
+}
+
+pub(crate) fn create_text_schema() -> SchemaRef {
     Arc::new(Schema::new(vec![
         Field::new("file_path", DataType::Utf8, false),
         Field::new("content_hash", DataType::Utf8, false),
@@ -99,6 +239,7 @@ fn create_text_schema() -> SchemaRef {
             true,
         ),
         Field::new("last_modified", DataType::Timestamp(TimeUnit::Second, None), false),
+        Field::new("index_root", DataType::Utf8, true),  // Folder the file was indexed under
     ]))
 }
 
@@ -124,10 +265,90 @@ fn create_image_schema() -> SchemaRef {
         Field::new("width", DataType::Int32, true),      // Image width in pixels
         Field::new("height", DataType::Int32, true),     // Image height in pixels
         Field::new("thumbnail_path", DataType::Utf8, true),  // Path to thumbnail if generated
+        Field::new("dominant_color", DataType::Utf8, true),  // "#rrggbb", for color search
+        Field::new("index_root", DataType::Utf8, true),  // Folder the image was indexed under
+        // Columns added later go last: migrations append them to existing tables
+        Field::new("phash", DataType::Utf8, true),  // Perceptual hash as 16 hex digits, for near-duplicates
     ]))
 }
 
+/// Create the schema for tombstones: what was known about a file when its
+/// index entries were deleted. No embeddings are kept.
+pub(crate) fn create_tombstone_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("file_path", DataType::Utf8, false),
+        Field::new("content_hash", DataType::Utf8, false),
+        Field::new("last_modified", DataType::Timestamp(TimeUnit::Second, None), false),
+        Field::new("deleted_at", DataType::Timestamp(TimeUnit::Second, None), false),
+        Field::new("content_type", DataType::Utf8, false), // "text", "amharic", "code" or "image"
+        Field::new("index_root", DataType::Utf8, true),
+    ]))
+}
+
+/// Schema of the metadata-only index: no content, no embedding
+pub(crate) fn create_metadata_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("file_path", DataType::Utf8, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("extension", DataType::Utf8, false), // Lowercase, empty when there is none
+        Field::new("size", DataType::UInt64, false),
+        Field::new("last_modified", DataType::Timestamp(TimeUnit::Second, None), false),
+        Field::new("category", DataType::Utf8, false),
+        Field::new("index_root", DataType::Utf8, true),
+    ]))
+}
+
+/// SQL for a null of `data_type`, filling columns added to existing tables
+fn null_column_expression(data_type: &DataType) -> Option<&'static str> {
+    match data_type {
+        DataType::Utf8 => Some("CAST(NULL AS STRING)"),
+        DataType::Int32 => Some("CAST(NULL AS INT)"),
+        DataType::Int64 => Some("CAST(NULL AS BIGINT)"),
+        DataType::Float32 => Some("CAST(NULL AS FLOAT)"),
+        _ => None,
+    }
+}
+
+/// The columns of `expected` that `existing` lacks, if that is all that
+/// differs and they are nullable, so the table can be migrated in place
+fn missing_nullable_columns(existing: &Schema, expected: &Schema) -> Option<Vec<Field>> {
+    let mut missing = Vec::new();
+    for field in expected.fields() {
+        match existing.field_with_name(field.name()) {
+            Ok(found) if data_types_compatible(found.data_type(), field.data_type()) => {}
+            Err(_) if field.is_nullable() && null_column_expression(field.data_type()).is_some() => {
+                missing.push(field.as_ref().clone())
+            }
+            _ => return None,
+        }
+    }
+    let only_missing = existing.fields().len() + missing.len() == expected.fields().len();
+    (only_missing && !missing.is_empty()).then_some(missing)
+}
+
+/// Adds columns introduced after `table` was created (such as
+/// `dominant_color`), null in its existing rows. They fill in as files are
+/// re-indexed. The columns land in one new table version, so an interrupted
+/// migration leaves the table as it was.
+async fn add_missing_columns(table: &Table, missing: &[Field]) -> Result<(), DbError> {
+    let expressions: Vec<(String, String)> = missing
+        .iter()
+        .filter_map(|field| {
+            null_column_expression(field.data_type()).map(|sql| (field.name().to_string(), sql.to_string()))
+        })
+        .collect();
+    info!(
+        "Adding columns {:?} to the '{}' table",
+        expressions.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(),
+        table.name()
+    );
+    table.add_columns(NewColumnTransform::SqlExpressions(expressions), None).await?;
+    bump_index_version();
+    Ok(())
+}
+
 pub async fn connect_db() -> Result<Connection, DbError> {
+    let _not_moving = DB_MOVE_LOCK.read().await;
     // Get the database path from application data directory
     let db_path = get_db_path()?;
     let db_path_str = db_path.to_string_lossy();
@@ -181,12 +402,35 @@ pub async fn open_or_create_amharic_text_table(
     open_or_create_table_with_schema(conn, AMHARIC_TEXT_TABLE_NAME, create_amharic_schema()).await
 }
 
+/// Open or create the table for source code embedded with the code model
+pub async fn open_or_create_code_table(
+    conn: &Connection,
+) -> Result<Table, DbError> {
+    open_or_create_table_with_schema(conn, CODE_TEXT_TABLE_NAME, create_code_schema()).await
+}
+
+/// Open or create the table of deleted files' tombstones
+pub async fn open_or_create_tombstone_table(
+    conn: &Connection,
+) -> Result<Table, DbError> {
+    open_or_create_table_with_schema(conn, DELETED_DOCUMENTS_TABLE_NAME, create_tombstone_schema()).await
+}
+
+/// Open or create the table of files indexed in metadata-only mode
+pub async fn open_or_create_metadata_table(
+    conn: &Connection,
+) -> Result<Table, DbError> {
+    open_or_create_table_with_schema(conn, METADATA_TABLE_NAME, create_metadata_schema()).await
+}
+
 /// Generic function to open or create a table with a specific schema
 async fn open_or_create_table_with_schema(
     conn: &Connection,
     table_name: &str,
     expected_schema: SchemaRef,
 ) -> Result<Table, DbError> {
+    let lock = table_creation_lock(conn, table_name);
+    let _guard = lock.lock().await;
     let table_names = conn.table_names().execute().await?;
 
     if table_names.iter().any(|name| name == table_name) {
@@ -198,7 +442,17 @@ async fn open_or_create_table_with_schema(
             .map_err(|e| DbError::TableOpenError(table_name.to_string(), e))?;
 
         let existing_schema = table.schema().await?;
+        if let Some(missing) = missing_nullable_columns(&existing_schema, &expected_schema) {
+            add_missing_columns(&table, &missing).await?;
+            return Ok(table);
+        }
         if !schemas_compatible(&*existing_schema, &*expected_schema) {
+            if let Some((stored, wanted)) = embedding_dimension_mismatch(&existing_schema, &expected_schema) {
+                error!(
+                    "Table '{}' stores {}-dimensional embeddings but the current model produces {}; the index must be rebuilt",
+                    table_name, stored, wanted
+                );
+            }
              eprintln!(
                 "Schema mismatch for table '{}':\nExpected: {:?}\nFound: {:?}",
                 table_name, expected_schema, existing_schema
@@ -211,10 +465,16 @@ async fn open_or_create_table_with_schema(
         let batch = RecordBatch::new_empty(expected_schema.clone());
         let reader = RecordBatchIterator::new(vec![Ok(batch)], expected_schema);
 
-        conn.create_table(table_name, Box::new(reader))
-            .execute()
-            .await
-            .map_err(|e| DbError::TableCreationError(table_name.to_string(), e))
+        match conn.create_table(table_name, Box::new(reader)).execute().await {
+            Ok(table) => Ok(table),
+            // Another process created it in the meantime
+            Err(LanceError::TableAlreadyExists { .. }) => conn
+                .open_table(table_name)
+                .execute()
+                .await
+                .map_err(|e| DbError::TableOpenError(table_name.to_string(), e)),
+            Err(e) => Err(DbError::TableCreationError(table_name.to_string(), e)),
+        }
     }
 }
 
@@ -223,19 +483,130 @@ fn schemas_compatible(schema1: &Schema, schema2: &Schema) -> bool {
         return false;
     }
     for (f1, f2) in schema1.fields.iter().zip(schema2.fields.iter()) {
-        if f1.name() != f2.name() || f1.data_type() != f2.data_type() {
+        if f1.name() != f2.name() || !data_types_compatible(f1.data_type(), f2.data_type()) {
             return false;
         }
     }
     true
 }
 
+/// Compares column types. Fixed-size lists (embeddings) must agree on element
+/// type and length; the name and nullability of the list's item field are
+/// ignored since they don't affect what the column can store.
+fn data_types_compatible(type1: &DataType, type2: &DataType) -> bool {
+    match (type1, type2) {
+        (DataType::FixedSizeList(item1, len1), DataType::FixedSizeList(item2, len2)) => {
+            len1 == len2 && item1.data_type() == item2.data_type()
+        }
+        _ => type1 == type2,
+    }
+}
+
+/// Returns `(stored, expected)` when the `embedding` columns differ in length
+/// Width of the `embedding` column, if the schema has one
+pub fn embedding_dimension(schema: &Schema) -> Option<i32> {
+    match schema.field_with_name("embedding").ok()?.data_type() {
+        DataType::FixedSizeList(_, len) => Some(*len),
+        _ => None,
+    }
+}
+
+fn embedding_dimension_mismatch(existing: &Schema, expected: &Schema) -> Option<(i32, i32)> {
+    match (embedding_dimension(existing), embedding_dimension(expected)) {
+        (Some(stored), Some(wanted)) if stored != wanted => Some((stored, wanted)),
+        _ => None,
+    }
+}
+
+/// Checks that every content table in the database stores embeddings of the
+/// dimension the current models produce. Run on any database taken from
+/// elsewhere before it is used, since search against mismatched vectors fails
+/// table by table rather than up front. Tables not created yet are fine.
+pub async fn validate_embedding_dimensions(conn: &Connection) -> Result<(), DbError> {
+    let expected_schemas = [
+        (TEXT_TABLE_NAME, create_text_schema()),
+        (AMHARIC_TEXT_TABLE_NAME, create_amharic_schema()),
+        (CODE_TEXT_TABLE_NAME, create_code_schema()),
+        (IMAGE_TABLE_NAME, create_image_schema()),
+    ];
+    let table_names = conn.table_names().execute().await?;
+    for (table_name, expected_schema) in expected_schemas {
+        if !table_names.iter().any(|name| name == table_name) {
+            continue;
+        }
+        let table = conn
+            .open_table(table_name)
+            .execute()
+            .await
+            .map_err(|e| DbError::TableOpenError(table_name.to_string(), e))?;
+        let existing_schema = table.schema().await?;
+        if let Some((stored, wanted)) = embedding_dimension_mismatch(&existing_schema, &expected_schema) {
+            error!(
+                "Table '{}' stores {}-dimensional embeddings but the current model produces {}",
+                table_name, stored, wanted
+            );
+            return Err(DbError::SchemaMismatch(table_name.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Quotes `value` as a SQL string literal, doubling any single quotes in it
+pub fn sql_string_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Predicate matching rows whose `file_path` is one of `paths`
+pub fn file_path_in_predicate(paths: &[String]) -> String {
+    let quoted: Vec<String> = paths.iter().map(|path| sql_string_literal(path)).collect();
+    format!("file_path IN ({})", quoted.join(", "))
+}
+
+/// `text` with LIKE wildcards escaped, so it only matches literally
+pub fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Predicate matching rows whose `file_path` is one of `paths` or lies in a
+/// folder among them
+pub fn file_path_under_predicate(paths: &[String]) -> String {
+    let mut clauses = vec![file_path_in_predicate(paths)];
+    for path in paths {
+        let folder = path.trim_end_matches(std::path::MAIN_SEPARATOR);
+        let pattern = format!("{}{}%", escape_like(folder), std::path::MAIN_SEPARATOR);
+        clauses.push(format!("file_path LIKE {}", sql_string_literal(&pattern)));
+    }
+    clauses.join(" OR ")
+}
+
+/// Paths per delete statement, keeping predicates a manageable size
+const DELETE_BATCH_SIZE: usize = 500;
+
+/// Deletes every row of `file_paths`, a batch of paths per statement
+pub async fn delete_documents(table: &Table, file_paths: &[String]) -> Result<(), DbError> {
+    for batch in file_paths.chunks(DELETE_BATCH_SIZE) {
+        table.delete(&file_path_in_predicate(batch)).await?;
+    }
+    if !file_paths.is_empty() {
+        bump_index_version();
+    }
+    Ok(())
+}
+
 /// Deletes a document from the table based on its file path.
 pub async fn delete_document(table: &Table, file_path: &str) -> Result<(), DbError> {
     debug!("Deleting document: {}", file_path);
     // Use a SQL-like WHERE clause to specify the record to delete
-    let predicate = format!("file_path = '{}'", file_path);
+    let predicate = format!("file_path = {}", sql_string_literal(file_path));
     table.delete(&predicate).await?; // Map LanceError to DbError via From
+    bump_index_version();
     Ok(())
 }
 
@@ -250,6 +621,7 @@ pub async fn upsert_document(
     file_path: &str,
     content_hash: &str,
     embeddings: &[Vec<f32>],
+    index_root: Option<&str>,
 ) -> Result<(), DbError> {
     if embeddings.is_empty() {
         warn!("No embeddings provided for {}, skipping upsert", file_path);
@@ -257,6 +629,7 @@ pub async fn upsert_document(
     }
 
     debug!("Upserting document: {} with {} chunks", file_path, embeddings.len());
+    validate_embedding_dims(file_path, embeddings, TEXT_EMBEDDING_DIM)?;
     
     // 1. Delete existing entries for this file path (ignore error if not found)
     let _ = delete_document(table, file_path).await; // Allow delete to fail if not present
@@ -274,13 +647,13 @@ pub async fn upsert_document(
         let content_hash_array = StringArray::from(vec![content_hash]);
         let chunk_id_array = Int32Array::from(vec![i as i32]);
         let last_modified_array = TimestampSecondArray::from(vec![now_ts]);
+        let index_root_array = StringArray::from(vec![index_root]);
 
         // Create the FixedSizeList array for the embedding
         let mut embedding_builder = Float32Builder::new();
         embedding_builder.append_slice(embedding);
         let values_array = Arc::new(embedding_builder.finish()) as Arc<dyn arrow_array::Array>;
-        let embedding_array = FixedSizeListArray::try_new_from_values(values_array, TEXT_EMBEDDING_DIM)
-            .expect("Failed to create FixedSizeListArray");
+        let embedding_array = FixedSizeListArray::try_new_from_values(values_array, TEXT_EMBEDDING_DIM)?;
 
         // Create the RecordBatch
         let batch = RecordBatch::try_new(
@@ -291,6 +664,7 @@ pub async fn upsert_document(
                 Arc::new(chunk_id_array),
                 Arc::new(embedding_array),
                 Arc::new(last_modified_array),
+                Arc::new(index_root_array),
             ],
         ).map_err(|e| DbError::SchemaError(e))?; // Convert ArrowError to DbError
         
@@ -302,14 +676,119 @@ pub async fn upsert_document(
     table.add(Box::new(reader)).execute().await?; // Map LanceError via From
 
     debug!("Successfully upserted document: {} with {} chunks", file_path, embeddings.len());
+    bump_index_version();
+    Ok(())
+}
+
+/// Appends chunk rows for a file without touching the rows already stored for it.
+///
+/// Used when a file is embedded a piece at a time: the caller deletes the old
+/// rows once, then appends each batch with increasing `first_chunk_id` under
+/// `PENDING_CONTENT_HASH`, and sets the real hash with `set_document_hash`
+/// once the last batch is in. Works for any of the chunked text tables; the
+/// embedding size is taken from the table's own schema.
+pub async fn append_document_chunks(
+    table: &Table,
+    file_path: &str,
+    content_hash: &str,
+    first_chunk_id: usize,
+    embeddings: &[Vec<f32>],
+    index_root: Option<&str>,
+) -> Result<(), DbError> {
+    if embeddings.is_empty() {
+        return Ok(());
+    }
+
+    let schema = table.schema().await?;
+    let Some(dim) = embedding_dimension(&schema) else {
+        return Err(DbError::SchemaMismatch(table.name().to_string()));
+    };
+    validate_embedding_dims(file_path, embeddings, dim)?;
+    debug!("Appending {} chunks to {} from chunk {}", embeddings.len(), file_path, first_chunk_id);
+
+    let now_ts = Utc::now().timestamp();
+    let count = embeddings.len();
+    let mut embedding_builder = Float32Builder::new();
+    for embedding in embeddings {
+        embedding_builder.append_slice(embedding);
+    }
+    let values_array = Arc::new(embedding_builder.finish()) as Arc<dyn arrow_array::Array>;
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec![file_path; count])),
+            Arc::new(StringArray::from(vec![content_hash; count])),
+            Arc::new(Int32Array::from_iter_values((first_chunk_id..first_chunk_id + count).map(|i| i as i32))),
+            Arc::new(FixedSizeListArray::try_new_from_values(values_array, dim)?),
+            Arc::new(TimestampSecondArray::from(vec![now_ts; count])),
+            Arc::new(StringArray::from(vec![index_root; count])),
+        ],
+    )?;
+
+    let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+    table.add(Box::new(reader)).execute().await?;
+    bump_index_version();
     Ok(())
 }
 
+/// Content hash of a file whose chunks are still being appended. It never
+/// matches a real hash, so a file left half-written is re-indexed by the next
+/// `only_new` run instead of looking unchanged.
+pub const PENDING_CONTENT_HASH: &str = "pending";
+
+/// Sets the content hash on every row of `file_path`
+pub async fn set_document_hash(table: &Table, file_path: &str, content_hash: &str) -> Result<(), DbError> {
+    table
+        .update()
+        .only_if(format!("file_path = {}", sql_string_literal(file_path)))
+        .column("content_hash", sql_string_literal(content_hash))
+        .execute()
+        .await?;
+    bump_index_version();
+    Ok(())
+}
+
+/// Content hash stored for `file_path`, if the table holds it
+pub async fn document_hash(table: &Table, file_path: &str) -> Result<Option<String>, DbError> {
+    let batches: Vec<RecordBatch> = table
+        .query()
+        .only_if(format!("file_path = {}", sql_string_literal(file_path)))
+        .select(Select::columns(&["content_hash"]))
+        .limit(1)
+        .execute()
+        .await?
+        .try_collect()
+        .await?;
+    Ok(batches.iter().find(|batch| batch.num_rows() > 0).and_then(|batch| {
+        let hashes = batch.column_by_name("content_hash")?.as_any().downcast_ref::<StringArray>()?;
+        Some(hashes.value(0).to_string())
+    }))
+}
+
+/// Root folder recorded for `file_path`, so re-indexing the file outside
+/// `index_folder` (e.g. from a watcher) can keep it
+pub async fn document_index_root(table: &Table, file_path: &str) -> Result<Option<String>, DbError> {
+    let batches: Vec<RecordBatch> = table
+        .query()
+        .only_if(format!("file_path = {} AND index_root IS NOT NULL", sql_string_literal(file_path)))
+        .select(Select::columns(&["index_root"]))
+        .limit(1)
+        .execute()
+        .await?
+        .try_collect()
+        .await?;
+    Ok(batches.iter().find(|batch| batch.num_rows() > 0).and_then(|batch| {
+        let roots = batch.column_by_name("index_root")?.as_any().downcast_ref::<StringArray>()?;
+        Some(roots.value(0).to_string())
+    }))
+}
+
 pub async fn upsert_amharic_document(
     table: &Table,
     file_path: &str,
     content_hash: &str,
     embeddings: &[Vec<f32>],
+    index_root: Option<&str>,
 ) -> Result<(), DbError> {
     if embeddings.is_empty() {
         warn!("No embeddings provided for {}, skipping upsert", file_path);
@@ -317,6 +796,7 @@ pub async fn upsert_amharic_document(
     }
 
     debug!("Upserting Amharic document: {} with {} chunks", file_path, embeddings.len());
+    validate_embedding_dims(file_path, embeddings, AMHARIC_EMBEDDING_DIM)?;
     
     // 1. Delete existing entries for this file path (ignore error if not found)
     let _ = delete_document(table, file_path).await; // Allow delete to fail if not present
@@ -334,13 +814,13 @@ pub async fn upsert_amharic_document(
         let content_hash_array = StringArray::from(vec![content_hash]);
         let chunk_id_array = Int32Array::from(vec![i as i32]);
         let last_modified_array = TimestampSecondArray::from(vec![now_ts]);
+        let index_root_array = StringArray::from(vec![index_root]);
 
         // Create the FixedSizeList array for the embedding
         let mut embedding_builder = Float32Builder::new();
         embedding_builder.append_slice(embedding);
         let values_array = Arc::new(embedding_builder.finish()) as Arc<dyn arrow_array::Array>;
-        let embedding_array = FixedSizeListArray::try_new_from_values(values_array, AMHARIC_EMBEDDING_DIM)
-            .expect("Failed to create FixedSizeListArray");
+        let embedding_array = FixedSizeListArray::try_new_from_values(values_array, AMHARIC_EMBEDDING_DIM)?;
 
         // Create the RecordBatch
         let batch = RecordBatch::try_new(
@@ -351,6 +831,7 @@ pub async fn upsert_amharic_document(
                 Arc::new(chunk_id_array),
                 Arc::new(embedding_array),
                 Arc::new(last_modified_array),
+                Arc::new(index_root_array),
             ],
         ).map_err(|e| DbError::SchemaError(e))?; // Convert ArrowError to DbError
         
@@ -362,10 +843,66 @@ pub async fn upsert_amharic_document(
     table.add(Box::new(reader)).execute().await?; // Map LanceError via From
 
     debug!("Successfully upserted Amharic document: {} with {} chunks", file_path, embeddings.len());
+    bump_index_version();
+    Ok(())
+}
+
+/// Adds or updates a source code file in the code table, one row per chunk.
+pub async fn upsert_code_document(
+    table: &Table,
+    file_path: &str,
+    content_hash: &str,
+    embeddings: &[Vec<f32>],
+    index_root: Option<&str>,
+) -> Result<(), DbError> {
+    if embeddings.is_empty() {
+        warn!("No embeddings provided for {}, skipping upsert", file_path);
+        return Ok(());
+    }
+
+    debug!("Upserting code document: {} with {} chunks", file_path, embeddings.len());
+    validate_embedding_dims(file_path, embeddings, CODE_EMBEDDING_DIM)?;
+
+    // 1. Delete existing entries for this file path (ignore error if not found)
+    let _ = delete_document(table, file_path).await;
+
+    // 2. Prepare the new record batches
+    let schema = create_code_schema();
+    let now_ts = Utc::now().timestamp();
+
+    let mut batches = Vec::with_capacity(embeddings.len());
+    for (i, embedding) in embeddings.iter().enumerate() {
+        let mut embedding_builder = Float32Builder::new();
+        embedding_builder.append_slice(embedding);
+        let values_array = Arc::new(embedding_builder.finish()) as Arc<dyn arrow_array::Array>;
+        let embedding_array = FixedSizeListArray::try_new_from_values(values_array, CODE_EMBEDDING_DIM)?;
+
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![file_path])),
+                Arc::new(StringArray::from(vec![content_hash])),
+                Arc::new(Int32Array::from(vec![i as i32])),
+                Arc::new(embedding_array),
+                Arc::new(TimestampSecondArray::from(vec![now_ts])),
+                Arc::new(StringArray::from(vec![index_root])),
+            ],
+        ).map_err(|e| DbError::SchemaError(e))?;
+
+        batches.push(Ok(batch));
+    }
+
+    // 3. Add all record batches to the table
+    let reader = RecordBatchIterator::new(batches, schema);
+    table.add(Box::new(reader)).execute().await?;
+
+    debug!("Successfully upserted code document: {} with {} chunks", file_path, embeddings.len());
+    bump_index_version();
     Ok(())
 }
 
 /// Adds or updates an image record in the LanceDB image table.
+#[allow(clippy::too_many_arguments)]
 pub async fn upsert_image(
     table: &Table,
     file_path: &str,
@@ -374,8 +911,12 @@ pub async fn upsert_image(
     width: Option<i32>,
     height: Option<i32>,
     thumbnail_path: Option<&str>,
+    dominant_color: Option<&str>,
+    phash: Option<&str>,
+    index_root: Option<&str>,
 ) -> Result<(), DbError> {
     debug!("Upserting image: {}", file_path);
+    validate_embedding_dims(file_path, &[embedding.to_vec()], IMAGE_EMBEDDING_DIM)?;
     
     // 1. Delete existing entries for this file path (ignore error if not found)
     let _ = delete_document(table, file_path).await; // Allow delete to fail if not present
@@ -391,13 +932,15 @@ pub async fn upsert_image(
     let width_array = Int32Array::from(vec![width]);
     let height_array = Int32Array::from(vec![height]);
     let thumbnail_path_array = StringArray::from(vec![thumbnail_path]);
+    let dominant_color_array = StringArray::from(vec![dominant_color]);
+    let phash_array = StringArray::from(vec![phash]);
+    let index_root_array = StringArray::from(vec![index_root]);
 
     // Create the FixedSizeList array for the embedding
     let mut embedding_builder = Float32Builder::new();
     embedding_builder.append_slice(embedding);
     let values_array = Arc::new(embedding_builder.finish()) as Arc<dyn arrow_array::Array>;
-    let embedding_array = FixedSizeListArray::try_new_from_values(values_array, IMAGE_EMBEDDING_DIM)
-        .expect("Failed to create FixedSizeListArray");
+    let embedding_array = FixedSizeListArray::try_new_from_values(values_array, IMAGE_EMBEDDING_DIM)?;
 
     // Create the RecordBatch
     let batch = RecordBatch::try_new(
@@ -410,6 +953,9 @@ pub async fn upsert_image(
             Arc::new(width_array),
             Arc::new(height_array),
             Arc::new(thumbnail_path_array),
+            Arc::new(dominant_color_array),
+            Arc::new(index_root_array),
+            Arc::new(phash_array),
         ],
     ).map_err(|e| DbError::SchemaError(e))?;
 
@@ -418,6 +964,7 @@ pub async fn upsert_image(
     table.add(Box::new(reader)).execute().await?;
 
     debug!("Successfully upserted image: {}", file_path);
+    bump_index_version();
     Ok(())
 }
 
@@ -449,6 +996,34 @@ mod tests {
         assert!(Path::new(&test_db.path).is_dir(), "DB path is not a directory");
     }
 
+    #[test]
+    fn test_sql_string_literal_escapes_quotes() {
+        assert_eq!(sql_string_literal("/docs/bob's notes.txt"), "'/docs/bob''s notes.txt'");
+        assert_eq!(
+            file_path_in_predicate(&["/a.txt".to_string(), "/it's.txt".to_string()]),
+            "file_path IN ('/a.txt', '/it''s.txt')"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_table_creation() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let conn = conn.clone();
+                tokio::spawn(async move { open_or_create_text_table(&conn).await })
+            })
+            .collect();
+        for handle in handles {
+            let table = handle.await.unwrap().expect("Concurrent open_or_create failed");
+            assert_eq!(table.name(), TEXT_TABLE_NAME);
+        }
+        let table_names = conn.table_names().execute().await.unwrap();
+        assert_eq!(table_names.iter().filter(|name| *name == TEXT_TABLE_NAME).count(), 1);
+    }
+
     #[tokio::test]
     async fn test_invalid_db_path_is_file() {
         let test_db = TestDb::new();
@@ -513,6 +1088,141 @@ mod tests {
         }
     }
 
+    /// `schema` without the columns added after the first release
+    fn legacy_schema(schema: SchemaRef) -> SchemaRef {
+        let fields: Vec<Field> = schema
+            .fields()
+            .iter()
+            .filter(|field| !matches!(field.name().as_str(), "dominant_color" | "phash" | "index_root"))
+            .map(|field| field.as_ref().clone())
+            .collect();
+        Arc::new(Schema::new(fields))
+    }
+
+    #[tokio::test]
+    async fn test_legacy_image_table_gains_dominant_color() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+
+        // One row in the layout image tables had before colors were stored
+        let legacy_schema = legacy_schema(create_image_schema());
+        let mut embedding_builder = Float32Builder::new();
+        embedding_builder.append_slice(&vec![0.1f32; IMAGE_EMBEDDING_DIM as usize]);
+        let embeddings =
+            FixedSizeListArray::try_new_from_values(embedding_builder.finish(), IMAGE_EMBEDDING_DIM).unwrap();
+        let batch = RecordBatch::try_new(
+            legacy_schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["/photos/cat.jpg"])),
+                Arc::new(StringArray::from(vec!["hash_cat"])),
+                Arc::new(embeddings),
+                Arc::new(TimestampSecondArray::from(vec![0])),
+                Arc::new(Int32Array::from(vec![Some(640)])),
+                Arc::new(Int32Array::from(vec![Some(480)])),
+                Arc::new(StringArray::from(vec![None::<&str>])),
+            ],
+        )
+        .unwrap();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], legacy_schema);
+        conn.create_table(IMAGE_TABLE_NAME, Box::new(reader)).execute().await.unwrap();
+
+        let table = open_or_create_image_table(&conn).await.expect("Legacy table should be migrated");
+        let schema = table.schema().await.unwrap();
+        assert!(schema.field_with_name("dominant_color").is_ok());
+        assert_eq!(table.count_rows(None).await.unwrap(), 1, "Existing rows should survive the migration");
+
+        upsert_image(&table, "/photos/sky.jpg", "hash_sky", &vec![0.2f32; IMAGE_EMBEDDING_DIM as usize], None, None, None, Some("#3366cc"), Some("f0e1d2c3b4a59687"), None)
+            .await
+            .expect("New rows should carry a color");
+        assert_eq!(table.count_rows(None).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_legacy_text_table_gains_index_root() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+
+        let legacy_schema = legacy_schema(create_text_schema());
+        let mut embedding_builder = Float32Builder::new();
+        embedding_builder.append_slice(&generate_dummy_embedding(1.0));
+        let embeddings =
+            FixedSizeListArray::try_new_from_values(embedding_builder.finish(), TEXT_EMBEDDING_DIM).unwrap();
+        let batch = RecordBatch::try_new(
+            legacy_schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["/old/notes.txt"])),
+                Arc::new(StringArray::from(vec!["hash_old"])),
+                Arc::new(Int32Array::from(vec![0])),
+                Arc::new(embeddings),
+                Arc::new(TimestampSecondArray::from(vec![0])),
+            ],
+        )
+        .unwrap();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], legacy_schema);
+        conn.create_table(TEXT_TABLE_NAME, Box::new(reader)).execute().await.unwrap();
+
+        let table = open_or_create_text_table(&conn).await.expect("Legacy table should be migrated");
+        upsert_document(&table, "/projects/app/readme.md", "hash_new", &[generate_dummy_embedding(2.0)], Some("/projects/app"))
+            .await
+            .unwrap();
+
+        let docs = list_indexed_documents(&conn, Some("text"), DocumentSortBy::Path).await.unwrap();
+        assert_eq!(docs.len(), 2, "Existing rows should survive the migration");
+        assert_eq!(docs[0].file_path, "/old/notes.txt");
+        assert_eq!(docs[0].index_root, None);
+        assert_eq!(docs[1].index_root.as_deref(), Some("/projects/app"));
+
+        assert_eq!(document_index_root(&table, "/projects/app/readme.md").await.unwrap().as_deref(), Some("/projects/app"));
+        assert_eq!(document_index_root(&table, "/old/notes.txt").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_schema_mismatch_on_wrong_embedding_dimension() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
+
+        // Same columns as the text table, but with a smaller embedding
+        let expected_schema = create_text_schema();
+        let fields: Vec<Field> = expected_schema
+            .fields()
+            .iter()
+            .map(|field| {
+                if field.name() == "embedding" {
+                    Field::new(
+                        "embedding",
+                        DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), TEXT_EMBEDDING_DIM / 2),
+                        field.is_nullable(),
+                    )
+                } else {
+                    field.as_ref().clone()
+                }
+            })
+            .collect();
+        let wrong_dim_schema = Arc::new(Schema::new(fields));
+        assert_eq!(
+            embedding_dimension_mismatch(&wrong_dim_schema, &expected_schema),
+            Some((TEXT_EMBEDDING_DIM / 2, TEXT_EMBEDDING_DIM))
+        );
+
+        let batch = RecordBatch::new_empty(wrong_dim_schema.clone());
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], wrong_dim_schema);
+        conn.create_table(TEXT_TABLE_NAME, Box::new(reader))
+            .execute()
+            .await
+            .expect("Manual create failed");
+
+        match open_or_create_text_table(&conn).await {
+            Err(DbError::SchemaMismatch(name)) => assert_eq!(name, TEXT_TABLE_NAME),
+            other => panic!("Expected schema mismatch, got {:?}", other.map(|t| t.name().to_string())),
+        }
+
+        open_or_create_image_table(&conn).await.unwrap();
+        match validate_embedding_dimensions(&conn).await {
+            Err(DbError::SchemaMismatch(name)) => assert_eq!(name, TEXT_TABLE_NAME),
+            other => panic!("Expected a dimension mismatch, got {:?}", other),
+        }
+    }
+
     async fn setup_test_table() -> (TestDb, Connection, Table) {
         let test_db = TestDb::new();
         let conn = connect_db_with_path(&test_db.path).await.expect("DB connection failed");
@@ -533,7 +1243,7 @@ mod tests {
         let embed1 = generate_dummy_embedding(1.0);
 
         // 1. Upsert initial document
-        let upsert_result1 = upsert_document(&table, file_path1, hash1, &[embed1]).await;
+        let upsert_result1 = upsert_document(&table, file_path1, hash1, &[embed1], None).await;
         assert!(upsert_result1.is_ok(), "Upsert 1 failed: {:?}", upsert_result1.err());
 
         // Check if data exists (simple count)
@@ -543,7 +1253,7 @@ mod tests {
         // 2. Upsert the same document with a new hash (update)
         let hash2 = "hash2";
         let embed2 = generate_dummy_embedding(2.0);
-        let upsert_result2 = upsert_document(&table, file_path1, hash2, &[embed2]).await;
+        let upsert_result2 = upsert_document(&table, file_path1, hash2, &[embed2], None).await;
         assert!(upsert_result2.is_ok(), "Upsert 2 failed: {:?}", upsert_result2.err());
 
         // Count should still be 1 after update
@@ -566,6 +1276,58 @@ mod tests {
         let delete_result_nonexistent = delete_document(&table, "/path/does/not/exist.txt").await;
         assert!(delete_result_nonexistent.is_ok(), "Delete non-existent failed: {:?}", delete_result_nonexistent.err());
     }
+
+    #[tokio::test]
+    async fn test_upsert_wrong_dimension_returns_error() {
+        let (_test_db, conn, table) = setup_test_table().await;
+
+        // Store a valid document first so we can check it survives the failed update
+        let file_path = "/path/to/doc.txt";
+        upsert_document(&table, file_path, "hash1", &[generate_dummy_embedding(1.0)], None)
+            .await
+            .expect("Valid upsert failed");
+
+        let wrong_embedding = vec![0.5f32; 10];
+        let result = upsert_document(&table, file_path, "hash2", &[wrong_embedding.clone()], None).await;
+        match result {
+            Err(DbError::EmbeddingDimensionMismatch { expected, found, .. }) => {
+                assert_eq!(expected, TEXT_EMBEDDING_DIM);
+                assert_eq!(found, 10);
+            }
+            other => panic!("Expected EmbeddingDimensionMismatch, got {:?}", other),
+        }
+        assert_eq!(table.count_rows(None).await.expect("Count failed"), 1, "Existing rows should be untouched");
+
+        let image_table = open_or_create_image_table(&conn).await.expect("Creating image table failed");
+        let image_result = upsert_image(&image_table, "/path/to/img.jpg", "hash", &wrong_embedding, None, None, None, None, None, None).await;
+        assert!(matches!(image_result, Err(DbError::EmbeddingDimensionMismatch { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_list_indexed_documents_aggregates_chunks() {
+        let (_test_db, conn, table) = setup_test_table().await;
+
+        upsert_document(&table, "/docs/b.txt", "hash_b", &[generate_dummy_embedding(1.0), generate_dummy_embedding(2.0)], None)
+            .await
+            .expect("Upsert b failed");
+        upsert_document(&table, "/docs/a.txt", "hash_a", &[generate_dummy_embedding(3.0)], None)
+            .await
+            .expect("Upsert a failed");
+
+        let docs = list_indexed_documents(&conn, None, DocumentSortBy::Path).await.expect("Listing failed");
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0].file_path, "/docs/a.txt");
+        assert_eq!(docs[0].chunk_count, 1);
+        assert_eq!(docs[1].file_path, "/docs/b.txt");
+        assert_eq!(docs[1].chunk_count, 2);
+        assert_eq!(docs[1].content_hash, "hash_b");
+        assert_eq!(docs[1].content_type, "text");
+
+        let images = list_indexed_documents(&conn, Some("image"), DocumentSortBy::Path).await.expect("Listing failed");
+        assert!(images.is_empty(), "Image table was never created");
+
+        assert!(list_indexed_documents(&conn, Some("video"), DocumentSortBy::Path).await.is_err());
+    }
 }
 
 /// Force drops a table by removing it directly from the database
@@ -587,6 +1349,7 @@ pub async fn force_drop_table(conn: &Connection, table_name: &str) -> Result<(),
     conn.drop_table(table_name).await?;
     
     info!("Successfully force-dropped table: {}", table_name);
+    bump_index_version();
     Ok(())
 }
 
@@ -610,6 +1373,7 @@ pub async fn clear_data(conn: &Connection, table_name: &str) -> Result<(), DbErr
     }
     
     // Return success
+    bump_index_version();
     Ok(())
 }
 
@@ -724,3 +1488,113 @@ pub async fn get_vector_db_stats(conn: &Connection) -> Result<(usize, usize, usi
     // Return the document counts
     Ok((text_docs_count, image_docs_count, amharic_docs_count))
 }
+
+/// A single indexed file, aggregated across all of its stored chunks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexedDocument {
+    pub file_path: String,
+    pub content_hash: String,
+    pub last_modified: i64,
+    pub chunk_count: usize,
+    /// Which table the file lives in: "text", "amharic", "code" or "image"
+    pub content_type: String,
+    /// Folder the file was indexed under; `None` for files indexed by the
+    /// watcher or before roots were recorded
+    #[serde(default)]
+    pub index_root: Option<String>,
+}
+
+/// Ordering for [`list_indexed_documents`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentSortBy {
+    #[default]
+    Path,
+    /// Most recently modified first
+    LastModified,
+}
+
+/// Lists every indexed file across the content tables without reading the
+/// embedding column. `content_type_filter` may be "text", "amharic", "code" or
+/// "image"; `None` lists all tables.
+pub async fn list_indexed_documents(
+    conn: &Connection,
+    content_type_filter: Option<&str>,
+    sort_by: DocumentSortBy,
+) -> Result<Vec<IndexedDocument>, DbError> {
+    if let Some(filter) = content_type_filter {
+        if content_table_name(filter).is_none() {
+            return Err(DbError::Other(format!("Unknown content type filter: {}", filter)));
+        }
+    }
+
+    let table_names = conn.table_names().execute().await?;
+    let mut documents: HashMap<(String, &str), IndexedDocument> = HashMap::new();
+
+    for (table_name, content_type) in CONTENT_TABLES {
+        // Images are hashed by file, everything else by extracted content
+        let hash_column = if table_name == IMAGE_TABLE_NAME { "file_hash" } else { "content_hash" };
+        if content_type_filter.is_some_and(|filter| filter != content_type) {
+            continue;
+        }
+        if !table_names.iter().any(|name| name == table_name) {
+            debug!("Table '{}' does not exist yet, skipping listing", table_name);
+            continue;
+        }
+
+        let table = conn
+            .open_table(table_name)
+            .execute()
+            .await
+            .map_err(|e| DbError::TableOpenError(table_name.to_string(), e))?;
+        // Tables opened here may predate the column; their rows have no root
+        let mut columns = vec!["file_path", hash_column, "last_modified"];
+        if table.schema().await?.field_with_name("index_root").is_ok() {
+            columns.push("index_root");
+        }
+        let batches: Vec<RecordBatch> = table
+            .query()
+            .select(Select::columns(&columns))
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        for batch in batches {
+            let paths = batch.column_by_name("file_path").and_then(|c| c.as_any().downcast_ref::<StringArray>());
+            let hashes = batch.column_by_name(hash_column).and_then(|c| c.as_any().downcast_ref::<StringArray>());
+            let modified = batch
+                .column_by_name("last_modified")
+                .and_then(|c| c.as_any().downcast_ref::<TimestampSecondArray>());
+            let (Some(paths), Some(hashes), Some(modified)) = (paths, hashes, modified) else {
+                warn!("Unexpected column layout in table '{}', skipping batch", table_name);
+                continue;
+            };
+            let roots = batch.column_by_name("index_root").and_then(|c| c.as_any().downcast_ref::<StringArray>());
+
+            for row in 0..batch.num_rows() {
+                let entry = documents
+                    .entry((paths.value(row).to_string(), content_type))
+                    .or_insert_with(|| IndexedDocument {
+                        file_path: paths.value(row).to_string(),
+                        content_hash: hashes.value(row).to_string(),
+                        last_modified: modified.value(row),
+                        chunk_count: 0,
+                        content_type: content_type.to_string(),
+                        index_root: roots.filter(|r| r.is_valid(row)).map(|r| r.value(row).to_string()),
+                    });
+                entry.chunk_count += 1;
+                entry.last_modified = entry.last_modified.max(modified.value(row));
+            }
+        }
+    }
+
+    let mut documents: Vec<IndexedDocument> = documents.into_values().collect();
+    match sort_by {
+        DocumentSortBy::Path => documents.sort_by(|a, b| a.file_path.cmp(&b.file_path)),
+        DocumentSortBy::LastModified => documents.sort_by(|a, b| {
+            b.last_modified.cmp(&a.last_modified).then_with(|| a.file_path.cmp(&b.file_path))
+        }),
+    }
+    Ok(documents)
+}
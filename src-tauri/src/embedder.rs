@@ -3,52 +3,195 @@
 use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
 use thiserror::Error;
 use log::{error, info, debug};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use once_cell::sync::Lazy;
+use std::sync::{Arc, Mutex, RwLock};
 use crate::chunker::{chunk_text, ChunkerError};
 use crate::extractor::DetectedLanguage;
 use log::warn;
 
-const DEFAULT_MODEL_NAME: EmbeddingModel = EmbeddingModel::BGESmallENV15;
-const AMHARIC_MODEL_NAME: EmbeddingModel = EmbeddingModel::MultilingualE5Small;
+pub(crate) const DEFAULT_MODEL_NAME: EmbeddingModel = EmbeddingModel::BGESmallENV15;
+pub(crate) const AMHARIC_MODEL_NAME: EmbeddingModel = EmbeddingModel::MultilingualE5Small;
+pub(crate) const CODE_MODEL_NAME: EmbeddingModel = EmbeddingModel::JinaEmbeddingsV2BaseCode;
 const CACHE_DIR_NAME: &str = ".cache";
 
 #[derive(Error, Debug)]
 pub enum EmbeddingError {
-    #[error("Model initialization failed: {0}")]
-    InitializationError(String),
     #[error("Embedding generation failed: {0}")]
     GenerationError(String),
-    #[error("Model loading error: {0}")]
-    ModelLoadError(String),
     #[error("Text chunking error: {0}")]
     ChunkingError(#[from] ChunkerError),
     #[error("Unsupported language for embedding: {0:?}")]
     UnsupportedLanguage(DetectedLanguage),
+    #[error("Embedder unavailable: {0}")]
+    Unavailable(String),
 }
 
-static DEFAULT_MODEL: Lazy<Result<TextEmbedding, EmbeddingError>> = Lazy::new(|| {
-    info!("Initializing default embedding model (Lazy)...");
-    let init_options = InitOptions::new(DEFAULT_MODEL_NAME)
-        .with_cache_dir(PathBuf::from(CACHE_DIR_NAME))
-        .with_show_download_progress(true);
-    TextEmbedding::try_new(init_options).map_err(|e| {
-        EmbeddingError::ModelLoadError(format!("Failed to initialize default embedding model: {}", e))
-    })
-});
+/// A text model loaded on first use. A failed load is remembered, so every
+/// embed reports the same "embedder unavailable" error instead of retrying the
+/// download, until [`reload_embedders`] manages to load it.
+struct ModelSlot {
+    model: EmbeddingModel,
+    label: &'static str,
+    state: RwLock<Option<Result<Arc<TextEmbedding>, String>>>,
+    /// Keeps two loads or reloads from downloading the same model at once.
+    /// Held instead of `state` while downloading, so readers aren't blocked.
+    loading: Mutex<()>,
+}
+
+/// Whether one text model is usable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedderStatus {
+    pub model: String,
+    /// False until the model is first used, or while it fails to load
+    pub loaded: bool,
+    /// Why the last load failed
+    pub error: Option<String>,
+}
+
+impl ModelSlot {
+    const fn new(model: EmbeddingModel, label: &'static str) -> Self {
+        Self {
+            model,
+            label,
+            state: RwLock::new(None),
+            loading: Mutex::new(()),
+        }
+    }
+
+    fn loaded(state: &Result<Arc<TextEmbedding>, String>) -> Result<Arc<TextEmbedding>, EmbeddingError> {
+        state.clone().map_err(EmbeddingError::Unavailable)
+    }
+
+    fn get(&self) -> Result<Arc<TextEmbedding>, EmbeddingError> {
+        if let Some(state) = self.state.read().unwrap().as_ref() {
+            return Self::loaded(state);
+        }
+        // Callers arriving during the first load wait for it, as they would on a Lazy
+        let _loading = self.loading.lock().unwrap();
+        if let Some(state) = self.state.read().unwrap().as_ref() {
+            return Self::loaded(state);
+        }
+        let loaded = load_model(&self.model, self.label).map(Arc::new).map_err(|e| {
+            error!("{}", e);
+            e
+        });
+        let mut state = self.state.write().unwrap();
+        Self::loaded(state.get_or_insert(loaded))
+    }
 
-static AMHARIC_MODEL: Lazy<Result<TextEmbedding, EmbeddingError>> = Lazy::new(|| {
-    info!("Initializing Amharic embedding model (MultilingualE5Small) (Lazy)...");
-    let init_options = InitOptions::new(AMHARIC_MODEL_NAME)
+    /// Loads the model again, first deleting its cached files when `repair`
+    /// is set so they are downloaded afresh. Embeds keep using the current
+    /// model while this runs; a failed reload leaves a working model in place.
+    fn reload(&self, repair: bool) -> Result<(), String> {
+        let _loading = self.loading.lock().unwrap();
+        if repair {
+            remove_model_cache(&self.model, self.label);
+        }
+        let loaded = load_model(&self.model, self.label);
+        let mut state = self.state.write().unwrap();
+        match loaded {
+            Ok(model) => {
+                info!("Reloaded {} embedding model", self.label);
+                *state = Some(Ok(Arc::new(model)));
+                Ok(())
+            }
+            Err(e) => {
+                error!("{}", e);
+                if !matches!(*state, Some(Ok(_))) {
+                    *state = Some(Err(e.clone()));
+                }
+                Err(e)
+            }
+        }
+    }
+
+    fn status(&self) -> EmbedderStatus {
+        let state = self.state.read().unwrap();
+        EmbedderStatus {
+            model: self.label.to_string(),
+            loaded: matches!(*state, Some(Ok(_))),
+            error: match state.as_ref() {
+                Some(Err(e)) => Some(e.clone()),
+                _ => None,
+            },
+        }
+    }
+
+    fn has_failed(&self) -> bool {
+        matches!(*self.state.read().unwrap(), Some(Err(_)))
+    }
+}
+
+static DEFAULT_MODEL: ModelSlot = ModelSlot::new(DEFAULT_MODEL_NAME, "default");
+static AMHARIC_MODEL: ModelSlot = ModelSlot::new(AMHARIC_MODEL_NAME, "Amharic");
+static CODE_MODEL: ModelSlot = ModelSlot::new(CODE_MODEL_NAME, "code");
+
+/// Loads a model, downloading it into the cache if it isn't there, and checks
+/// it produces a usable embedding before handing it out
+fn load_model(model: &EmbeddingModel, label: &str) -> Result<TextEmbedding, String> {
+    info!("Initializing {} embedding model ({:?})...", label, model);
+    let init_options = InitOptions::new(model.clone())
         .with_cache_dir(PathBuf::from(CACHE_DIR_NAME))
         .with_show_download_progress(true);
-    TextEmbedding::try_new(init_options).map_err(|e| {
-        EmbeddingError::ModelLoadError(format!("Failed to initialize Amharic embedding model: {}", e))
-    })
-});
+    let loaded = TextEmbedding::try_new(init_options)
+        .map_err(|e| format!("Failed to initialize {} embedding model: {}", label, e))?;
+    let probe = loaded
+        .embed(vec!["embedding model check".to_string()], None)
+        .map_err(|e| format!("The {} embedding model failed to embed: {}", label, e))?;
+    match probe.first() {
+        Some(vector) if !vector.is_empty() && vector.iter().all(|v| v.is_finite()) => Ok(loaded),
+        _ => Err(format!("The {} embedding model produced an unusable embedding", label)),
+    }
+}
+
+/// Where fastembed keeps a model's downloaded files (the Hugging Face hub layout)
+fn model_cache_dir(model: &EmbeddingModel) -> Option<PathBuf> {
+    let info = TextEmbedding::get_model_info(model).ok()?;
+    Some(PathBuf::from(CACHE_DIR_NAME).join(format!("models--{}", info.model_code.replace('/', "--"))))
+}
+
+fn remove_model_cache(model: &EmbeddingModel, label: &str) {
+    let Some(dir) = model_cache_dir(model) else {
+        warn!("Unknown cache location for the {} embedding model; reloading without repair", label);
+        return;
+    };
+    match std::fs::remove_dir_all(&dir) {
+        Ok(()) => info!("Removed cached {} model files at {}", label, dir.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!("Failed to remove cached {} model files at {}: {}", label, dir.display(), e),
+    }
+}
+
+/// Retries every text model whose load failed, re-downloading its files first
+/// when `repair` is set. Models that loaded, or haven't been needed yet, are
+/// left alone. Blocking; downloads can take minutes.
+pub fn reload_embedders(repair: bool) -> Vec<EmbedderStatus> {
+    for slot in [&DEFAULT_MODEL, &AMHARIC_MODEL, &CODE_MODEL] {
+        if slot.has_failed() {
+            // The failure is logged and kept in the slot's status
+            let _ = slot.reload(repair);
+        }
+    }
+    embedder_status()
+}
+
+pub fn embedder_status() -> Vec<EmbedderStatus> {
+    [&DEFAULT_MODEL, &AMHARIC_MODEL, &CODE_MODEL].iter().map(|slot| slot.status()).collect()
+}
+
+/// Text embedding configurations. Each one stores its vectors in its own
+/// table, since the models produce different dimensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextModel {
+    /// Prose: BGESmallENV15, or MultilingualE5Small for Amharic
+    Default,
+    /// Source code: JinaEmbeddingsV2BaseCode
+    Code,
+}
 
 fn embed_with_model(
-    model_instance: &Result<TextEmbedding, EmbeddingError>,
+    slot: &ModelSlot,
     content: &[String],
     query: bool,
     lang_prefix: Option<&str> // e.g., "query" or "passage" for E5
@@ -88,16 +231,11 @@ fn embed_with_model(
     
     debug!("Embedding {} final chunks.", final_chunks_to_embed.len());
 
-    match model_instance {
-        Ok(model) => model.embed(final_chunks_to_embed, None).map_err(|e| {
-            error!("Embedding generation failed: {}", e);
-            EmbeddingError::GenerationError(format!("Embedding generation failed: {}", e))
-        }),
-        Err(init_error) => {
-            error!("Model not initialized, cannot embed: {}", init_error);
-            Err(EmbeddingError::InitializationError(format!("Model not initialized: {}", init_error)))
-        }
-    }
+    let model = slot.get()?;
+    model.embed(final_chunks_to_embed, None).map_err(|e| {
+        error!("Embedding generation failed: {}", e);
+        EmbeddingError::GenerationError(format!("Embedding generation failed: {}", e))
+    })
 }
 
 pub fn embed_text(content: &[String], language: &DetectedLanguage, query: bool) -> Result<Vec<Vec<f32>>, EmbeddingError> {
@@ -123,6 +261,11 @@ pub fn embed_amharic_text(content: &[String], query: bool) -> Result<Vec<Vec<f32
     embed_with_model(&AMHARIC_MODEL, content, query, Some(prefix))
 }
 
+/// Embeds source code (or a query against source code) with the code model
+pub fn embed_code(content: &[String], query: bool) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+    debug!("Embedding code with code model.");
+    embed_with_model(&CODE_MODEL, content, query, None)
+}
 
 #[cfg(test)]
 fn embed_text_test(content: &[String], _query: bool) -> Result<Vec<Vec<f32>>, EmbeddingError> {
@@ -138,6 +281,25 @@ fn embed_amharic_text_test(content: &[String], _query: bool) -> Result<Vec<Vec<f
     Ok(content.iter().map(|_| vec![0.2f32; AMHARIC_EMBEDDING_DIM as usize]).collect())
 }
 
+/// Euclidean length of a vector
+pub fn l2_norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|v| v * v).sum::<f32>().sqrt()
+}
+
+/// Cosine similarity of two vectors, or `None` if their lengths differ or
+/// either is all zeros
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f32> {
+    if a.len() != b.len() {
+        return None;
+    }
+    let norms = l2_norm(a) * l2_norm(b);
+    if norms == 0.0 {
+        return None;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    Some(dot / norms)
+}
+
 pub fn get_chunk_count(text: &str) -> Result<usize, EmbeddingError> {
     if text.trim().is_empty() {
         return Ok(0); 
@@ -181,6 +343,24 @@ mod tests {
         assert!(result.unwrap().is_empty());
     }
     
+    #[test]
+    fn test_model_cache_dir_follows_hub_layout() {
+        let dir = model_cache_dir(&DEFAULT_MODEL_NAME).unwrap();
+        assert_eq!(dir.parent(), Some(PathBuf::from(CACHE_DIR_NAME).as_path()));
+        let name = dir.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("models--") && name.contains("--bge-small-en-v1.5"), "{}", name);
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert_eq!(l2_norm(&[3.0, 4.0]), 5.0);
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]).unwrap() - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).unwrap().abs() < 1e-6);
+        assert!((cosine_similarity(&[1.0, 1.0], &[-1.0, -1.0]).unwrap() + 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), None);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), None);
+    }
+
     #[test]
     fn mock_embed_amharic_text_empty() {
         let texts: Vec<String> = Vec::new();
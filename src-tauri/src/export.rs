@@ -0,0 +1,241 @@
+use crate::db::{
+    DbError, AMHARIC_EMBEDDING_DIM, AMHARIC_TEXT_TABLE_NAME, CODE_EMBEDDING_DIM, CODE_TEXT_TABLE_NAME, IMAGE_EMBEDDING_DIM,
+    IMAGE_TABLE_NAME, TEXT_EMBEDDING_DIM, TEXT_TABLE_NAME,
+};
+use crate::embedder::{AMHARIC_MODEL_NAME, CODE_MODEL_NAME, DEFAULT_MODEL_NAME};
+use crate::image_embedder::MODEL_NAME as IMAGE_MODEL_NAME;
+use arrow_array::{Array, FixedSizeListArray, Float32Array, StringArray};
+use futures_util::TryStreamExt;
+use lancedb::connection::Connection;
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use log::info;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use thiserror::Error;
+use tokio::fs::File;
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Unsupported export format '{0}'; only 'ndjson' is available")]
+    UnsupportedFormat(String),
+    #[error("Database error: {0}")]
+    Database(#[from] DbError),
+    #[error("Failed to write {0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("Failed to serialize row: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Embedding output formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One JSON object per line: a header per table followed by its rows
+    Ndjson,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Result<Self, ExportError> {
+        match format.to_lowercase().as_str() {
+            "ndjson" | "jsonl" => Ok(ExportFormat::Ndjson),
+            other => Err(ExportError::UnsupportedFormat(other.to_string())),
+        }
+    }
+}
+
+/// Describes the vectors that follow it, so consumers know how to compare them
+#[derive(Debug, Serialize)]
+struct TableHeader<'a> {
+    table: &'a str,
+    model: String,
+    dimension: i32,
+    /// Chunk vectors are averaged per file, then scaled to unit length
+    aggregation: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct EmbeddingRow<'a> {
+    table: &'a str,
+    file_path: &'a str,
+    embedding: &'a [f32],
+}
+
+/// Rows written per table
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportSummary {
+    pub tables: Vec<(String, usize)>,
+    pub files_exported: usize,
+}
+
+/// The embedding tables with the model and dimension their vectors come from
+fn exported_tables() -> Vec<(&'static str, String, i32)> {
+    vec![
+        (TEXT_TABLE_NAME, format!("{:?}", DEFAULT_MODEL_NAME), TEXT_EMBEDDING_DIM),
+        (AMHARIC_TEXT_TABLE_NAME, format!("{:?}", AMHARIC_MODEL_NAME), AMHARIC_EMBEDDING_DIM),
+        (CODE_TEXT_TABLE_NAME, format!("{:?}", CODE_MODEL_NAME), CODE_EMBEDDING_DIM),
+        (IMAGE_TABLE_NAME, format!("{:?}", IMAGE_MODEL_NAME), IMAGE_EMBEDDING_DIM),
+    ]
+}
+
+/// Per-file sums of chunk vectors. Rows are grouped by path, since a table
+/// returns a file's chunks in no particular order once it has been compacted
+/// or updated.
+#[derive(Default)]
+struct FileEmbeddings {
+    sums: BTreeMap<String, Vec<f32>>,
+}
+
+impl FileEmbeddings {
+    fn add(&mut self, file_path: &str, values: &[f32]) {
+        let sum = self
+            .sums
+            .entry(file_path.to_string())
+            .or_insert_with(|| vec![0.0; values.len()]);
+        for (acc, value) in sum.iter_mut().zip(values) {
+            *acc += value;
+        }
+    }
+
+    /// The averaged vectors scaled to unit length, in path order. Scaling a
+    /// sum to unit length gives the same vector as scaling the mean.
+    fn into_normalized(self) -> impl Iterator<Item = (String, Vec<f32>)> {
+        self.sums.into_iter().map(|(file_path, mut sum)| {
+            let norm = sum.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                sum.iter_mut().for_each(|v| *v /= norm);
+            }
+            (file_path, sum)
+        })
+    }
+}
+
+/// Serializes `value` as one NDJSON line
+async fn write_line<W: AsyncWrite + Unpin, T: Serialize>(out: &mut W, value: &T, dest: &str) -> Result<(), ExportError> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    out.write_all(&line).await.map_err(|e| ExportError::Io(dest.to_string(), e))
+}
+
+/// Writes one averaged, unit-length embedding per indexed file to `dest`.
+///
+/// Rows are streamed batch by batch and summed per file, so memory grows with
+/// the number of files in a table rather than the number of chunks.
+pub async fn export_embeddings(conn: &Connection, dest: &Path, format: ExportFormat) -> Result<ExportSummary, ExportError> {
+    // NDJSON is the only format so far
+    let ExportFormat::Ndjson = format;
+    let dest_str = dest.display().to_string();
+    let file = File::create(dest).await.map_err(|e| ExportError::Io(dest_str.clone(), e))?;
+    let mut out = BufWriter::new(file);
+    let existing = conn.table_names().execute().await.map_err(DbError::from)?;
+
+    let mut summary = ExportSummary::default();
+    for (table_name, model, dimension) in exported_tables() {
+        if !existing.iter().any(|name| name == table_name) {
+            continue;
+        }
+        let table = conn.open_table(table_name).execute().await.map_err(DbError::from)?;
+
+        let header = TableHeader {
+            table: table_name,
+            model,
+            dimension,
+            aggregation: "mean_normalized",
+        };
+        write_line(&mut out, &serde_json::json!({ "header": header }), &dest_str).await?;
+
+        let mut stream = table
+            .query()
+            .select(Select::columns(&["file_path", "embedding"]))
+            .execute()
+            .await
+            .map_err(DbError::from)?;
+        let mut files = FileEmbeddings::default();
+        while let Some(batch) = stream.try_next().await.map_err(DbError::from)? {
+            let (Some(paths), Some(embeddings)) = (
+                batch.column_by_name("file_path").and_then(|a| a.as_any().downcast_ref::<StringArray>()),
+                batch.column_by_name("embedding").and_then(|a| a.as_any().downcast_ref::<FixedSizeListArray>()),
+            ) else {
+                continue;
+            };
+            for i in 0..batch.num_rows() {
+                if embeddings.is_null(i) {
+                    continue;
+                }
+                let chunk = embeddings.value(i);
+                let Some(values) = chunk.as_any().downcast_ref::<Float32Array>() else {
+                    continue;
+                };
+                files.add(paths.value(i), values.values());
+            }
+        }
+
+        let mut files_written = 0;
+        for (file_path, embedding) in files.into_normalized() {
+            let row = EmbeddingRow {
+                table: table_name,
+                file_path: &file_path,
+                embedding: &embedding,
+            };
+            write_line(&mut out, &row, &dest_str).await?;
+            files_written += 1;
+        }
+
+        info!("Exported {} files from table {}", files_written, table_name);
+        summary.files_exported += files_written;
+        summary.tables.push((table_name.to_string(), files_written));
+    }
+
+    out.flush().await.map_err(|e| ExportError::Io(dest_str, e))?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{connect_db_with_path, open_or_create_text_table, upsert_document, TestDb};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_export_averages_chunks_per_file() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let table = open_or_create_text_table(&conn).await.unwrap();
+
+        let dim = TEXT_EMBEDDING_DIM as usize;
+        let mut first = vec![0.0f32; dim];
+        first[0] = 1.0;
+        let mut second = vec![0.0f32; dim];
+        second[1] = 1.0;
+        upsert_document(&table, "/docs/a.txt", "hash_a", &[first, second], None).await.unwrap();
+
+        let out_dir = tempdir().unwrap();
+        let dest = out_dir.path().join("embeddings.ndjson");
+        let summary = export_embeddings(&conn, &dest, ExportFormat::Ndjson).await.unwrap();
+        assert_eq!(summary.files_exported, 1);
+
+        let contents = std::fs::read_to_string(&dest).unwrap();
+        let lines: Vec<serde_json::Value> = contents.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines[0]["header"]["dimension"], TEXT_EMBEDDING_DIM);
+        assert_eq!(lines[1]["file_path"], "/docs/a.txt");
+        let embedding: Vec<f32> = serde_json::from_value(lines[1]["embedding"].clone()).unwrap();
+        assert_eq!(embedding.len(), dim);
+        assert!((embedding[0] - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-5);
+        assert!((embedding[1] - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-5);
+        assert!(ExportFormat::parse("parquet").is_err());
+    }
+
+    #[test]
+    fn test_interleaved_chunks_are_grouped_by_path() {
+        let mut files = FileEmbeddings::default();
+        files.add("/docs/b.txt", &[0.0, 2.0]);
+        files.add("/docs/a.txt", &[3.0, 0.0]);
+        files.add("/docs/b.txt", &[0.0, 4.0]);
+        files.add("/docs/a.txt", &[0.0, 4.0]);
+
+        let exported: Vec<(String, Vec<f32>)> = files.into_normalized().collect();
+        assert_eq!(exported.len(), 2, "One row per file, however its chunks were ordered");
+        assert_eq!(exported[0], ("/docs/a.txt".to_string(), vec![0.6, 0.8]));
+        assert_eq!(exported[1], ("/docs/b.txt".to_string(), vec![0.0, 1.0]));
+    }
+}
@@ -1,8 +1,8 @@
 // src-tauri/src/extractor.rs
 
-use std::fs;
+use std::fs::{self, File};
 use std::path::Path;
-use std::io::Read;
+use std::io::{self, Read};
 use extractous::Extractor;
 use log::{debug, error, info, warn};
 use dotext::{Docx, MsDoc};
@@ -10,6 +10,8 @@ use whatlang::{detect, Lang};
 use thiserror::Error;
 use sha2::{Sha256, Digest};
 use serde::{Serialize, Deserialize};
+use encoding_rs::{Decoder, Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use unicode_normalization::UnicodeNormalization;
 
 #[derive(Error, Debug)]
 pub enum ExtractorError {
@@ -23,9 +25,11 @@ pub enum ExtractorError {
     ImageHandling(String),
     #[error("DOCX extraction failed for {0}: {1}")]
     DocxExtractionFailed(String, String),
+    #[error("Archive extraction failed for {0}: {1}")]
+    ArchiveExtractionFailed(String, String),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DetectedLanguage {
     English,
     Amharic,
@@ -39,7 +43,7 @@ pub struct TextExtractionResult {
 }
 
 /// Content type enum to distinguish between different file types
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ContentType {
     Text,
     Image,
@@ -47,25 +51,285 @@ pub enum ContentType {
 }
 
 /// Lists of supported file extensions
-pub const SUPPORTED_TEXT_EXTENSIONS: &[&str] = &["md", "pdf", "docx", "txt"];
+pub const SUPPORTED_TEXT_EXTENSIONS: &[&str] = &["md", "pdf", "docx", "txt", "csv"];
 pub const SUPPORTED_IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "svg"];
+/// Source files indexed as text and embedded with the code model
+pub const SUPPORTED_CODE_EXTENSIONS: &[&str] = &[
+    "py", "js", "jsx", "ts", "tsx", "rs", "go", "java", "cpp", "c", "h", "cs", "php", "rb",
+];
 
-/// Determines the content type of a file based on its extension
-pub fn get_content_type(file_path: &Path) -> ContentType {
-    match file_path
+/// Extensions that say nothing reliable about a file's bytes, so the content is sniffed
+const PLAIN_TEXT_EXTENSIONS: &[&str] = &["txt", "md", "csv"];
+
+fn content_type_for_extension(ext: &str) -> ContentType {
+    if SUPPORTED_TEXT_EXTENSIONS.contains(&ext) || SUPPORTED_CODE_EXTENSIONS.contains(&ext) {
+        ContentType::Text
+    } else if SUPPORTED_IMAGE_EXTENSIONS.contains(&ext) {
+        ContentType::Image
+    } else {
+        ContentType::Unsupported
+    }
+}
+
+/// Identifies a file by its magic bytes, returning the canonical extension
+/// (e.g. `"pdf"`, `"png"`). `None` if the file can't be read or isn't recognised.
+pub fn sniff_extension(file_path: &Path) -> Option<&'static str> {
+    match infer::get_from_path(file_path) {
+        Ok(Some(kind)) => Some(kind.extension()),
+        _ => None,
+    }
+}
+
+/// The extension a file should be treated as: its own when it names a supported
+/// format that is hard to fake, otherwise whatever its magic bytes say.
+pub fn effective_extension(file_path: &Path) -> Option<String> {
+    let extension = file_path
         .extension()
         .and_then(|ext| ext.to_str())
-        .map(|s| s.to_lowercase())
-    {
-        Some(ext) if SUPPORTED_TEXT_EXTENSIONS.contains(&ext.as_str()) => ContentType::Text,
-        Some(ext) if SUPPORTED_IMAGE_EXTENSIONS.contains(&ext.as_str()) => ContentType::Image,
-        _ => ContentType::Unsupported,
+        .map(|s| s.to_lowercase());
+
+    // Fast path: binary formats and source code are trusted as named
+    if let Some(ext) = extension.as_deref() {
+        if content_type_for_extension(ext) != ContentType::Unsupported && !PLAIN_TEXT_EXTENSIONS.contains(&ext) {
+            return extension;
+        }
+    }
+
+    match sniff_extension(file_path) {
+        Some(sniffed) if content_type_for_extension(sniffed) != ContentType::Unsupported => {
+            if extension.as_deref() != Some(sniffed) {
+                debug!("{} looks like a .{} file by its content", file_path.display(), sniffed);
+            }
+            Some(sniffed.to_string())
+        }
+        _ => extension,
+    }
+}
+
+/// Determines the content type of a file from its extension, falling back to
+/// its magic bytes when the extension is missing, unknown or plain text
+pub fn get_content_type(file_path: &Path) -> ContentType {
+    effective_extension(file_path)
+        .map(|ext| content_type_for_extension(&ext))
+        .unwrap_or(ContentType::Unsupported)
+}
+
+/// How many leading bytes are inspected when guessing a text file's encoding
+const ENCODING_SNIFF_BYTES: usize = 4096;
+
+/// Guesses the encoding of raw text bytes.
+///
+/// A byte order mark wins. Otherwise a regular pattern of NUL bytes in every
+/// other position means BOM-less UTF-16, valid UTF-8 stays UTF-8, and anything
+/// else is treated as Windows-1252 (the WHATWG superset of Latin-1).
+pub fn detect_encoding(bytes: &[u8]) -> (&'static Encoding, bool) {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return (encoding, true);
+    }
+
+    let sample = &bytes[..bytes.len().min(ENCODING_SNIFF_BYTES)];
+    if sample.len() >= 4 {
+        let pairs = sample.len() / 2;
+        let even_zeros = sample.iter().step_by(2).filter(|b| **b == 0).count();
+        let odd_zeros = sample.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+        if odd_zeros * 10 > pairs * 3 && even_zeros * 10 < pairs {
+            return (UTF_16LE, false);
+        }
+        if even_zeros * 10 > pairs * 3 && odd_zeros * 10 < pairs {
+            return (UTF_16BE, false);
+        }
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(_) => (UTF_8, false),
+        // Only a character cut off at the end of a partial read; still UTF-8
+        Err(e) if e.error_len().is_none() => (UTF_8, false),
+        Err(_) => (WINDOWS_1252, false),
+    }
+}
+
+/// Decodes text bytes with their detected encoding, dropping any byte order mark
+pub fn decode_text(bytes: &[u8]) -> (String, &'static Encoding) {
+    let (encoding, has_bom) = detect_encoding(bytes);
+    let text = if has_bom {
+        encoding.decode_with_bom_removal(bytes).0
+    } else {
+        encoding.decode_without_bom_handling(bytes).0
+    };
+    if encoding != UTF_8 {
+        debug!("Decoded text as {}", encoding.name());
+    }
+    (text.into_owned(), encoding)
+}
+
+/// Leading bytes inspected by [`looks_binary`]
+const BINARY_SNIFF_BYTES: usize = 1024;
+
+/// Share of control characters above which content is treated as binary
+const MAX_CONTROL_CHAR_RATIO: f32 = 0.1;
+
+/// Whether the start of a supposed text file is really binary: it contains a
+/// NUL byte or too many control characters. UTF-16 text is full of NULs, so
+/// it is recognised by [`detect_encoding`] first and never counts as binary.
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(BINARY_SNIFF_BYTES)];
+    if sample.is_empty() {
+        return false;
+    }
+    let (encoding, _) = detect_encoding(sample);
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        return false;
+    }
+    if sample.contains(&0) {
+        return true;
+    }
+    // Tab, line feed, form feed, carriage return and escape all appear in real text
+    let control = sample
+        .iter()
+        .filter(|b| b.is_ascii_control() && !matches!(**b, b'\t' | b'\n' | 0x0c | b'\r' | 0x1b))
+        .count();
+    control as f32 / sample.len() as f32 > MAX_CONTROL_CHAR_RATIO
+}
+
+/// Fails with `UnsupportedFileType` when a text-named file holds binary data
+fn reject_binary(file_path: &Path, ext: &str) -> Result<(), ExtractorError> {
+    let mut head = Vec::with_capacity(BINARY_SNIFF_BYTES);
+    File::open(file_path)
+        .and_then(|file| file.take(BINARY_SNIFF_BYTES as u64).read_to_end(&mut head))
+        .map_err(|e| ExtractorError::IoError(file_path.display().to_string(), e))?;
+    if looks_binary(&head) {
+        warn!("Skipping {}: binary content in a .{} file", file_path.display(), ext);
+        return Err(ExtractorError::UnsupportedFileType(format!("binary content in .{} file", ext)));
+    }
+    Ok(())
+}
+
+/// Reads a plain text file, decoding it with its detected encoding
+fn read_text_file(file_path: &Path) -> std::io::Result<String> {
+    fs::read(file_path).map(|bytes| decode_text(&bytes).0)
+}
+
+/// Plain text files larger than this are extracted in segments by default
+/// rather than read whole (see `AppSettings::streaming_threshold_bytes`)
+pub const DEFAULT_STREAMING_THRESHOLD_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Bytes read per streamed segment. Small enough that a segment never
+/// produces more chunks than the chunker keeps from a single text.
+const STREAM_SEGMENT_BYTES: usize = 32 * 1024;
+
+/// Maps whatlang's guess onto the languages that have their own embedding model
+pub fn detect_language(text: &str) -> DetectedLanguage {
+    match detect(text) {
+        Some(info) if info.lang() == Lang::Eng => DetectedLanguage::English,
+        Some(info) if info.lang() == Lang::Amh => DetectedLanguage::Amharic,
+        _ => DetectedLanguage::Other,
+    }
+}
+
+/// Whether a file is plain text that [`stream_text_segments`] can read
+pub fn is_streamable_text(file_path: &Path) -> bool {
+    effective_extension(file_path).is_some_and(|ext| PLAIN_TEXT_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Normalized pieces of a plain text file, read a segment at a time.
+///
+/// Segments end on a line break, so no line is split between two of them
+/// unless a single line is longer than a whole segment.
+pub struct TextSegments {
+    file: File,
+    path: String,
+    decoder: Option<Decoder>,
+    /// Decoded text after the last line break, carried into the next segment
+    carry: String,
+    done: bool,
+}
+
+/// Opens a plain text file for segment-by-segment extraction. Memory use
+/// stays around one segment no matter how large the file is.
+pub fn stream_text_segments(file_path: &Path) -> Result<TextSegments, ExtractorError> {
+    let ext = effective_extension(file_path).unwrap_or_default();
+    reject_binary(file_path, &ext)?;
+    let file = File::open(file_path).map_err(|e| ExtractorError::IoError(file_path.display().to_string(), e))?;
+    Ok(TextSegments {
+        file,
+        path: file_path.display().to_string(),
+        decoder: None,
+        carry: String::new(),
+        done: false,
+    })
+}
+
+impl TextSegments {
+    /// Fills `buf` from the file, returning fewer bytes only at end of file
+    fn read_block(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.file.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(filled)
+    }
+}
+
+impl Iterator for TextSegments {
+    type Item = Result<String, ExtractorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let mut buf = vec![0u8; STREAM_SEGMENT_BYTES];
+            let read = match self.read_block(&mut buf) {
+                Ok(read) => read,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(ExtractorError::IoError(self.path.clone(), e)));
+                }
+            };
+            let bytes = &buf[..read];
+            let last = read < STREAM_SEGMENT_BYTES;
+
+            // The encoding is guessed once, from the first block
+            let decoder = self.decoder.get_or_insert_with(|| match detect_encoding(bytes) {
+                (encoding, true) => encoding.new_decoder_with_bom_removal(),
+                (encoding, false) => encoding.new_decoder_without_bom_handling(),
+            });
+            let capacity = decoder.max_utf8_buffer_length(bytes.len()).unwrap_or(bytes.len() * 3);
+            let mut text = std::mem::take(&mut self.carry);
+            text.reserve(capacity);
+            let _ = decoder.decode_to_string(bytes, &mut text, last);
+
+            let segment = if last {
+                self.done = true;
+                text
+            } else {
+                match text.rfind('\n') {
+                    Some(end) => {
+                        self.carry = text[end + 1..].to_string();
+                        text.truncate(end + 1);
+                        text
+                    }
+                    // A single line longer than a segment is cut where the block ends
+                    None => text,
+                }
+            };
+
+            let normalized = normalize_text(&segment);
+            if !normalized.is_empty() {
+                return Some(Ok(normalized));
+            }
+        }
+        None
     }
 }
 
 /// Extracts text content from a supported file.
 ///
-/// Currently supports `.txt`, `.md` and `.pdf` files.
+/// Currently supports `.txt`, `.md`, `.pdf`, `.docx` and source code files.
+/// The text is passed through [`normalize_text`], so identical content always
+/// hashes and embeds the same way regardless of how it was encoded on disk.
 ///
 /// # Arguments
 ///
@@ -76,12 +340,57 @@ pub fn get_content_type(file_path: &Path) -> ContentType {
 /// * `Ok(String)` containing the extracted text content.
 /// * `Err(ExtractorError)` if the file is unsupported or cannot be read.
 pub fn extract_text(file_path: &Path) -> Result<TextExtractionResult, ExtractorError> {
+    let mut result = extract_raw_text(file_path)?;
+    result.text = normalize_text(&result.text);
+    Ok(result)
+}
+
+/// Normalizes extracted text before hashing and embedding.
+///
+/// Applies Unicode NFC (macOS file names and some PDFs produce NFD), strips
+/// trailing whitespace, collapses runs of spaces and tabs inside a line while
+/// keeping leading indentation, and folds consecutive blank lines into one.
+pub fn normalize_text(text: &str) -> String {
+    let composed: String = text.nfc().collect();
+    let mut normalized = String::with_capacity(composed.len());
+    let mut pending_blank_line = false;
+
+    for line in composed.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            pending_blank_line = true;
+            continue;
+        }
+        if !normalized.is_empty() {
+            normalized.push('\n');
+            if pending_blank_line {
+                normalized.push('\n');
+            }
+        }
+        pending_blank_line = false;
+
+        let body = line.trim_start();
+        normalized.push_str(&line[..line.len() - body.len()]);
+        let mut previous_was_space = false;
+        for c in body.chars() {
+            if c.is_whitespace() {
+                if !previous_was_space {
+                    normalized.push(' ');
+                }
+                previous_was_space = true;
+            } else {
+                normalized.push(c);
+                previous_was_space = false;
+            }
+        }
+    }
+    normalized
+}
+
+fn extract_raw_text(file_path: &Path) -> Result<TextExtractionResult, ExtractorError> {
     debug!("Attempting to extract text from: {}", file_path.display());
 
-    let extension = file_path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .map(|s| s.to_lowercase());
+    let extension = effective_extension(file_path);
 
     match extension.as_deref() {
         Some("pdf") => {
@@ -140,12 +449,13 @@ pub fn extract_text(file_path: &Path) -> Result<TextExtractionResult, ExtractorE
                 }
             }
         }
-        Some("txt") | Some("md") => {
+        Some("txt") | Some("md") | Some("csv") => {
             let ext_str = extension.as_ref().unwrap();
             info!("Extracting text from {}: {}", ext_str, file_path.display());
+            reject_binary(file_path, ext_str)?;
             
-            // Simple file read for text files
-            std::fs::read_to_string(file_path).and_then(|text_content| {
+            // Plain read, decoded with the detected encoding so Latin-1 and UTF-16 files work
+            read_text_file(file_path).and_then(|text_content| {
                 let lang_info = detect(&text_content);
                 let detected_lang = match lang_info {
                     Some(info) if info.lang() == Lang::Eng => DetectedLanguage::English,
@@ -158,6 +468,17 @@ pub fn extract_text(file_path: &Path) -> Result<TextExtractionResult, ExtractorE
                 ExtractorError::IoError(file_path.display().to_string(), e)
             })
         },
+        Some(ext) if SUPPORTED_CODE_EXTENSIONS.contains(&ext) => {
+            info!("Extracting source code from {}: {}", ext, file_path.display());
+            reject_binary(file_path, ext)?;
+            // Natural-language detection is meaningless for code
+            read_text_file(file_path)
+                .map(|text_content| TextExtractionResult { text: text_content, language: DetectedLanguage::Other })
+                .map_err(|e| {
+                    error!("Failed to read {} file {}: {}", ext, file_path.display(), e);
+                    ExtractorError::IoError(file_path.display().to_string(), e)
+                })
+        },
         Some(ext) => {
             error!("Unsupported file type attempted: {}", ext);
             Err(ExtractorError::UnsupportedFileType(ext.to_string()))
@@ -247,15 +568,14 @@ pub fn calculate_hash(content: &str) -> String {
 /// * `Ok(String)` - A hex-encoded string representing the SHA256 hash
 /// * `Err(ExtractorError)` - If the file cannot be read
 pub fn calculate_file_hash(file_path: &Path) -> Result<String, ExtractorError> {
-    // Read the file in binary mode
-    let file_content = fs::read(file_path).map_err(|e| {
-        error!("Failed to read file for hashing {}: {}", file_path.display(), e);
-        ExtractorError::IoError(file_path.display().to_string(), e)
-    })?;
-    
-    // Calculate hash
+    // Stream the file through the hasher so large files aren't loaded whole
     let mut hasher = Sha256::new();
-    hasher.update(&file_content);
+    File::open(file_path)
+        .and_then(|mut file| io::copy(&mut file, &mut hasher))
+        .map_err(|e| {
+            error!("Failed to read file for hashing {}: {}", file_path.display(), e);
+            ExtractorError::IoError(file_path.display().to_string(), e)
+        })?;
     let result = hasher.finalize();
     
     // Format as hex string
@@ -268,6 +588,53 @@ mod tests {
     use std::io::Write;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_extract_utf16_and_windows_1252_text() {
+        let dir = tempdir().unwrap();
+        let expected = "Café résumé naïve";
+
+        // UTF-16LE with a byte order mark
+        let utf16_path = dir.path().join("utf16.txt");
+        let mut utf16: Vec<u8> = vec![0xFF, 0xFE];
+        utf16.extend(expected.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        fs::write(&utf16_path, &utf16).unwrap();
+        assert_eq!(detect_encoding(&utf16), (UTF_16LE, true));
+        assert_eq!(extract_text(&utf16_path).unwrap().text, expected);
+
+        // BOM-less UTF-16LE is recognised from its NUL pattern
+        let bare: Vec<u8> = "Plain ASCII in UTF-16".encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        assert_eq!(detect_encoding(&bare).0, UTF_16LE);
+
+        // Windows-1252 encodes é, ï as single bytes that aren't valid UTF-8
+        let cp1252_path = dir.path().join("latin.txt");
+        let (cp1252, _, _) = WINDOWS_1252.encode(expected);
+        fs::write(&cp1252_path, &*cp1252).unwrap();
+        assert_eq!(detect_encoding(&cp1252).0, WINDOWS_1252);
+        assert_eq!(extract_text(&cp1252_path).unwrap().text, expected);
+    }
+
+    #[test]
+    fn test_content_type_sniffed_from_magic_bytes() {
+        let dir = tempdir().unwrap();
+
+        // A PNG saved with a text extension must not be embedded as text
+        let mislabeled = dir.path().join("photo.txt");
+        fs::write(&mislabeled, [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D]).unwrap();
+        assert_eq!(get_content_type(&mislabeled), ContentType::Image);
+
+        // A PDF without an extension is still a document
+        let extensionless = dir.path().join("report");
+        fs::write(&extensionless, b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n1 0 obj\n").unwrap();
+        assert_eq!(get_content_type(&extensionless), ContentType::Text);
+        assert_eq!(effective_extension(&extensionless).as_deref(), Some("pdf"));
+
+        // Genuine text keeps its extension
+        let text = dir.path().join("notes.txt");
+        fs::write(&text, "Plain notes").unwrap();
+        assert_eq!(get_content_type(&text), ContentType::Text);
+        assert_eq!(effective_extension(&text).as_deref(), Some("txt"));
+    }
+
     #[test]
     fn test_extract_txt_success() {
         let dir = tempdir().unwrap();
@@ -341,6 +708,20 @@ mod tests {
         let expected_hash = "315f5bdb76d078c43b8ac0064e4a0164612b1fce77c869345bfc94c75894edd3";
         assert_eq!(hash1, expected_hash);
     }
+
+    #[test]
+    fn test_nfc_and_nfd_hash_identically_after_normalization() {
+        let nfc = "Caf\u{e9} r\u{e9}sum\u{e9}";
+        let nfd = "Cafe\u{301} re\u{301}sume\u{301}";
+        assert_ne!(calculate_hash(nfc), calculate_hash(nfd), "Raw forms differ byte-wise");
+        assert_eq!(calculate_hash(&normalize_text(nfc)), calculate_hash(&normalize_text(nfd)));
+    }
+
+    #[test]
+    fn test_normalize_text_collapses_whitespace() {
+        let text = "  fn main() {\r\n    let x  =\t 1;   \n\n\n\n}\n\n";
+        assert_eq!(normalize_text(text), "  fn main() {\n    let x = 1;\n\n}");
+    }
     
     #[test]
     fn test_process_image_valid_file() {
@@ -388,6 +769,10 @@ mod tests {
         assert_eq!(get_content_type(Path::new("photo.webp")), ContentType::Image);
         assert_eq!(get_content_type(Path::new("screenshot.bmp")), ContentType::Image);
         
+        // Source code is indexed as text
+        assert_eq!(get_content_type(Path::new("main.rs")), ContentType::Text);
+        assert_eq!(get_content_type(Path::new("app.py")), ContentType::Text);
+
         // Unsupported files
         assert_eq!(get_content_type(Path::new("archive.zip")), ContentType::Unsupported);
         assert_eq!(get_content_type(Path::new("unknown")), ContentType::Unsupported);
@@ -412,4 +797,52 @@ mod tests {
         // Hashes should match
         assert_eq!(file_hash, content_hash);
     }
+
+    #[test]
+    fn test_binary_content_in_text_file_is_rejected() {
+        let dir = tempdir().unwrap();
+        let binary_path = dir.path().join("notes.txt");
+        let mut bytes = b"header".to_vec();
+        bytes.extend((0..2048u32).map(|i| (i * 7 % 256) as u8));
+        fs::write(&binary_path, &bytes).unwrap();
+        assert!(matches!(
+            extract_text(&binary_path),
+            Err(ExtractorError::UnsupportedFileType(_))
+        ));
+
+        // Control-character noise without NULs is caught too
+        assert!(looks_binary(&[0x01, 0x02, 0x03, b'a', 0x04, 0x05, b'b', 0x06]));
+        assert!(!looks_binary(b"Plain text\twith tabs\r\nand line breaks"));
+        let utf16: Vec<u8> = "UTF-16 text".encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        assert!(!looks_binary(&utf16));
+    }
+
+    #[test]
+    fn test_large_text_streams_in_bounded_segments() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("shipments.csv");
+        let mut file = io::BufWriter::new(fs::File::create(&file_path).unwrap());
+        let rows = 100_000;
+        for i in 0..rows {
+            writeln!(file, "{},invoice,{} crates shipped to the northern warehouse", i, i % 97).unwrap();
+        }
+        drop(file);
+        let file_len = fs::metadata(&file_path).unwrap().len() as usize;
+        assert!(file_len > 4 * 1024 * 1024);
+        assert!(is_streamable_text(&file_path));
+
+        let mut segments = 0;
+        let mut lines = 0;
+        let mut longest = 0;
+        for segment in stream_text_segments(&file_path).unwrap() {
+            let segment = segment.unwrap();
+            longest = longest.max(segment.len());
+            lines += segment.lines().count();
+            segments += 1;
+        }
+        assert!(segments > file_len / STREAM_SEGMENT_BYTES);
+        // A segment is one block plus the partial line carried over from the last
+        assert!(longest <= STREAM_SEGMENT_BYTES + 100);
+        assert_eq!(lines, rows);
+    }
 }
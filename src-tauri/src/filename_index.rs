@@ -0,0 +1,235 @@
+use crate::core::indexer::walk_entries;
+use crate::core::json_store::{load_json_or_default, save_json};
+use crate::db::get_app_data_dir;
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::UNIX_EPOCH;
+
+const INDEX_FILE_NAME: &str = "filename_index.json";
+
+/// Watcher updates are written to disk in batches of this many changes
+const FLUSH_EVERY_CHANGES: usize = 200;
+
+/// Where `filename_search_command` looks for matches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilenameSearchMode {
+    /// Walk the filesystem with rust_search on every query
+    #[default]
+    Live,
+    /// Query the persistent filename index kept current by the watcher
+    Indexed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilenameEntry {
+    pub path: String,
+    /// Lowercased file name, the only part queries match against
+    name_lower: String,
+    /// Milliseconds since the Unix epoch
+    pub last_modified: u64,
+    pub size: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FilenameIndex {
+    entries: HashMap<String, FilenameEntry>,
+    #[serde(skip)]
+    unsaved_changes: usize,
+}
+
+static INDEX: Lazy<RwLock<FilenameIndex>> = Lazy::new(|| RwLock::new(load_index()));
+
+fn index_file_path() -> Result<PathBuf, String> {
+    get_app_data_dir()
+        .map(|dir| dir.join(INDEX_FILE_NAME))
+        .map_err(|e| format!("Failed to resolve filename index path: {}", e))
+}
+
+/// Reads the stored index, starting empty if the file is missing or unreadable
+fn load_index() -> FilenameIndex {
+    let index: FilenameIndex = load_json_or_default(index_file_path(), "filename index");
+    if !index.entries.is_empty() {
+        info!("Loaded filename index with {} entries", index.entries.len());
+    }
+    index
+}
+
+fn save_index(index: &mut FilenameIndex) -> Result<(), String> {
+    save_json(&index_file_path()?, index, "filename index")?;
+    index.unsaved_changes = 0;
+    Ok(())
+}
+
+/// Saves once enough watcher changes have piled up
+fn note_change(index: &mut FilenameIndex) {
+    index.unsaved_changes += 1;
+    if index.unsaved_changes >= FLUSH_EVERY_CHANGES {
+        if let Err(e) = save_index(index) {
+            warn!("{}", e);
+        }
+    }
+}
+
+fn make_entry(path: &str, last_modified: u64, size: u64) -> Option<FilenameEntry> {
+    let name = Path::new(path).file_name()?.to_string_lossy().to_lowercase();
+    Some(FilenameEntry {
+        path: path.to_string(),
+        name_lower: name,
+        last_modified,
+        size,
+    })
+}
+
+/// Adds or updates one file
+pub fn upsert_file(path: &str, last_modified: u64, size: u64) -> Result<(), String> {
+    let entry = make_entry(path, last_modified, size).ok_or_else(|| format!("Path has no file name: {}", path))?;
+    let mut index = INDEX.write().map_err(|_| "Filename index lock poisoned".to_string())?;
+    index.entries.insert(path.to_string(), entry);
+    note_change(&mut index);
+    Ok(())
+}
+
+/// Removes a file, or everything under it when `path` was a directory
+pub fn remove_path(path: &str) -> Result<usize, String> {
+    let mut index = INDEX.write().map_err(|_| "Filename index lock poisoned".to_string())?;
+    let before = index.entries.len();
+    let root = Path::new(path);
+    index.entries.retain(|p, _| !Path::new(p).starts_with(root));
+    let removed = before - index.entries.len();
+    if removed > 0 {
+        note_change(&mut index);
+    }
+    Ok(removed)
+}
+
+/// Walks `dir` and replaces every entry under it. Returns (files indexed, errors).
+pub fn scan_directory(dir: &Path, follow_symlinks: bool) -> Result<(usize, usize), String> {
+    if !dir.is_dir() {
+        return Err(format!("Not a directory: {}", dir.display()));
+    }
+
+    let mut scanned = Vec::new();
+    let mut errors = 0;
+    for entry in walk_entries(dir, follow_symlinks) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Skipping entry while building filename index: {}", e);
+                errors += 1;
+                continue;
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let (last_modified, size) = match entry.metadata() {
+            Ok(meta) => (
+                meta.modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or(0),
+                meta.len(),
+            ),
+            Err(_) => (0, 0),
+        };
+        if let Some(entry) = make_entry(&entry.path().to_string_lossy(), last_modified, size) {
+            scanned.push(entry);
+        }
+    }
+
+    let count = scanned.len();
+    let mut index = INDEX.write().map_err(|_| "Filename index lock poisoned".to_string())?;
+    // Files deleted while the watcher wasn't running disappear here
+    index.entries.retain(|p, _| !Path::new(p).starts_with(dir));
+    index.entries.extend(scanned.into_iter().map(|e| (e.path.clone(), e)));
+    save_index(&mut index)?;
+    info!("Filename index: {} files under {} ({} errors)", count, dir.display(), errors);
+    Ok((count, errors))
+}
+
+/// Drops every entry and deletes the stored index
+pub fn clear() -> Result<(), String> {
+    let mut index = INDEX.write().map_err(|_| "Filename index lock poisoned".to_string())?;
+    index.entries.clear();
+    save_index(&mut index)
+}
+
+/// Number of indexed files
+pub fn len() -> usize {
+    INDEX.read().map(|index| index.entries.len()).unwrap_or(0)
+}
+
+/// How well a lowercased file name matches a lowercased query, from 0 to 1.
+/// Exact names beat stems, stems beat prefixes, and prefixes beat matches
+/// starting at a word boundary, which beat any other substring. Within a tier,
+/// shorter names (where the query covers more of the name) score higher.
+pub(crate) fn match_score(name: &str, query: &str) -> Option<f32> {
+    if query.is_empty() {
+        return None;
+    }
+    let stem = name.rsplit_once('.').map(|(stem, _)| stem).filter(|s| !s.is_empty()).unwrap_or(name);
+    let coverage = query.len() as f32 / name.len().max(1) as f32;
+
+    if name == query {
+        return Some(1.0);
+    }
+    if stem == query {
+        return Some(0.95);
+    }
+    if name.starts_with(query) {
+        return Some(0.8 + 0.1 * coverage);
+    }
+    let position = name.find(query)?;
+    let at_word_boundary = name[..position].chars().last().map_or(true, |c| !c.is_alphanumeric());
+    let base = if at_word_boundary { 0.6 } else { 0.4 };
+    Some(base + 0.1 * coverage)
+}
+
+/// Matches `query` against indexed file names under `root` (or everywhere),
+/// best first. `limit` applies after `keep` has filtered the matches.
+pub fn search(
+    query: &str,
+    root: Option<&Path>,
+    limit: usize,
+    keep: impl Fn(&FilenameEntry) -> bool,
+) -> Vec<(FilenameEntry, f32)> {
+    let query = query.trim().to_lowercase();
+    let Ok(index) = INDEX.read() else {
+        return Vec::new();
+    };
+    let mut matches: Vec<(FilenameEntry, f32)> = index
+        .entries
+        .values()
+        .filter(|e| root.map_or(true, |root| Path::new(&e.path).starts_with(root)))
+        .filter_map(|e| match_score(&e.name_lower, &query).map(|score| (e, score)))
+        .filter(|(e, _)| keep(e))
+        .map(|(e, score)| (e.clone(), score))
+        .collect();
+    matches.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.0.path.cmp(&b.0.path)));
+    matches.truncate(limit);
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_score_tiers() {
+        let exact = match_score("report.pdf", "report.pdf").unwrap();
+        let stem = match_score("report.pdf", "report").unwrap();
+        let prefix = match_score("report_2024.pdf", "report").unwrap();
+        let word = match_score("q3-report.pdf", "report").unwrap();
+        let inner = match_score("myreport.pdf", "report").unwrap();
+        assert!(exact > stem && stem > prefix && prefix > word && word > inner);
+        assert!(match_score("notes.txt", "report").is_none());
+        // The query covering more of the name ranks higher within a tier
+        assert!(match_score("report.md", "rep").unwrap() > match_score("reporting_long_name.md", "rep").unwrap());
+    }
+}
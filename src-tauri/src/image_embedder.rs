@@ -7,7 +7,9 @@ use once_cell::sync::Lazy;
 use std::sync::Mutex;
 
 // Constants for the image embedding model
-const MODEL_NAME: ImageEmbeddingModel = ImageEmbeddingModel::NomicEmbedVisionV15;
+pub(crate) const MODEL_NAME: ImageEmbeddingModel = ImageEmbeddingModel::NomicEmbedVisionV15;
+/// Text model sharing an embedding space with `MODEL_NAME`, used for text-to-image search
+pub(crate) const TEXT_MODEL_NAME: EmbeddingModel = EmbeddingModel::NomicEmbedTextV15;
 const CACHE_DIR_NAME: &str = ".cache"; // Same cache directory as text model
 
 // Define potential errors during image embedding
@@ -48,7 +50,7 @@ static IMAGE_MODEL: Lazy<Mutex<Result<ImageEmbedding, ImageEmbeddingError>>> = L
 // Use lazy to initialize a special text embedding model for image searches
 static TEXT_FOR_IMAGE_MODEL: Lazy<Mutex<Result<TextEmbedding, ImageEmbeddingError>>> = Lazy::new(|| {
     info!("Initializing text embedding model for image search (Lazy)...");
-    let init_options = InitOptions::new(EmbeddingModel::NomicEmbedTextV15)
+    let init_options = InitOptions::new(TEXT_MODEL_NAME)
         .with_cache_dir(std::path::PathBuf::from(CACHE_DIR_NAME))
         .with_show_download_progress(true);
 
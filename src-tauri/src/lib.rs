@@ -1,30 +1,59 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-use commands::benchmark_commands::run_benchmarks;
+use commands::benchmark_commands::{
+    benchmark_embedding_command, cosine_between_command, embed_preview_command, extract_preview_command, run_benchmarks, run_search_benchmark,
+};
 use commands::fs_commands::{
-    get_documents_dir, get_downloads_dir, get_home_dir, get_hostname_command, get_movies_dir,
-    list_directory_command, load_custom_locations, open_path_command, save_custom_locations,
+    capabilities_command, get_documents_dir, get_downloads_dir, get_home_dir, get_hostname_command, get_movies_dir,
+    add_favorite, folder_preview_command, get_or_generate_thumbnail_command, list_favorites, remove_favorite, list_directory_command, list_directory_with_token_command, quick_filter_command, query_files_command, regenerate_thumbnails_command, walk_directory_tree_command, load_custom_locations, open_path_command, open_paths_command, recently_opened_command,
+    prune_thumbnail_cache_command, save_custom_locations, thumbnail_cache_stats_command,
 };
 use commands::file_operations::{
-    copy_item, create_directory, delete_item, get_item_info, move_item, rename_item,
+    cancel_operation_command, compare_directories_command, copy_item, create_directory, delete_item,
+    detect_encoding_command, document_outline_command, get_content_preview_command, get_item_info, move_item,
+    move_to_new_folder_command, read_file_range_command, rename_item, storage_by_category_command,
 };
 use commands::indexing_commands::{
-    clear_index_command, get_indexing_stats_command, get_vector_db_stats_command,
-    index_downloads_command, index_folder_command, run_startup_indexing,
+    clear_index_command, estimate_indexing_time_command, export_embeddings_command, get_indexing_queue_status_command, get_indexing_stats_command,
+    get_next_scheduled_index_command, get_vector_db_stats_command,
+    corpus_language_breakdown_command, corpus_stats_command, embedder_status_command, index_drift_command, rebuild_term_frequencies_command, verify_all_command, verify_indexed_file_command, reload_embedder_command, exclude_path_command, index_content_command, move_database_command, include_path_command, list_tombstones_command, prune_tombstones_command, restore_tombstone_command, is_path_excluded_command,
+    list_excluded_paths_command, index_downloads_command, index_folder_command, list_indexed_documents_command,
+    pause_watchers_command, resume_watchers_command, run_startup_indexing, trigger_index_now_command,
+    watcher_status_command,
 };
 use commands::search_commands::{
-    add_file_to_index, clear_filename_index, filename_search_command, get_filename_index_stats,
+    add_file_to_index, clear_filename_index, filename_search_command, get_file_type_info, get_filename_index_stats,
     initialize_filename_index, remove_file_from_index, scan_directory_for_filename_index,
 };
-use commands::search_commands::{get_document_count, semantic_search_command};
+use commands::search_commands::{
+    clear_search_cache_command, file_similarity_command, search_suggestions_command, get_document_count, multi_query_search_command, related_files_command,
+    search_images_by_color_command, find_similar_images_by_phash_command, search_with_text_command, search_within_command, metadata_search_command, semantic_search_command,
+};
+use commands::settings_commands::{
+    export_config_command, get_settings_command, import_config_command, subscribe_logs_command,
+    unsubscribe_logs_command, update_settings_command,
+};
+use crate::core::log_stream::{ForwardFilter, LogForwardLayer};
+use crate::core::scheduler::run_index_scheduler;
+use crate::core::settings::current_settings;
+use crate::filename_index::FilenameSearchMode;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+pub mod archive;
 pub mod benchmark;
 pub mod chunker;
+pub mod color;
 pub mod commands;
 pub mod core;
 pub mod db;
 pub mod embedder;
 pub mod embedding;
+pub mod export;
 pub mod extractor;
+pub mod filename_index;
 pub mod image_embedder;
+pub mod outline;
+pub mod phash;
 pub mod repair_db;
 pub mod search;
 pub mod watcher;
@@ -34,12 +63,29 @@ async fn repair_database_command() -> Result<String, String> {
     Ok("Database successfully repaired".to_string())
 }
 
+#[tauri::command]
+async fn check_index_integrity_command(fix: Option<bool>) -> Result<repair_db::IntegrityReport, String> {
+    let conn = db::connect_db().await.map_err(|e| format!("Database connection failed: {}", e))?;
+    repair_db::check_index_integrity(&conn, fix.unwrap_or(false)).await
+}
+
+#[tauri::command]
+async fn reconcile_moved_files_command() -> Result<repair_db::MovedFilesReport, String> {
+    let conn = db::connect_db().await.map_err(|e| format!("Database connection failed: {}", e))?;
+    repair_db::reconcile_moved_files(&conn).await
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .with_target(false)
-        .compact()
+    // RUST_LOG only filters stdout; the UI stream filters by its own level
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .compact()
+                .with_filter(tracing_subscriber::EnvFilter::from_default_env()),
+        )
+        .with(LogForwardLayer.with_filter(ForwardFilter))
         .init();
     tracing::info!("Application starting up...");
 
@@ -54,9 +100,21 @@ pub fn run() {
             // Initialize the semantic search index
             tracing::info!("Starting Downloads folder indexing for semantic search");
             run_startup_indexing().await;
+            let startup_finished = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
 
             // Initialize the filename index with common directories
-            tracing::info!("Starting filename index initialization");
+            if current_settings().filename_search_mode == FilenameSearchMode::Indexed && filename_index::len() == 0 {
+                tracing::info!("Starting filename index initialization");
+                if let Err(e) = initialize_filename_index().await {
+                    tracing::error!("Filename index initialization failed: {}", e);
+                }
+            }
+
+            // Keep this runtime alive for recurring indexing
+            run_index_scheduler(startup_finished).await;
         });
     });
 
@@ -75,17 +133,42 @@ pub fn register_commands(builder: tauri::Builder<tauri::Wry>) -> tauri::Builder<
     builder.invoke_handler(tauri::generate_handler![
         // Filesystem commands
         list_directory_command,
+        list_directory_with_token_command,
+        quick_filter_command,
         get_home_dir,
         open_path_command,
+        open_paths_command,
+        recently_opened_command,
+        add_favorite,
+        remove_favorite,
+        list_favorites,
         get_downloads_dir,
         get_movies_dir,
         get_documents_dir,
         load_custom_locations,
         save_custom_locations,
         get_hostname_command,
+        capabilities_command,
+        get_or_generate_thumbnail_command,
+        folder_preview_command,
+        regenerate_thumbnails_command,
+        thumbnail_cache_stats_command,
+        prune_thumbnail_cache_command,
+        query_files_command,
+        walk_directory_tree_command,
         // Semantic search commands
         semantic_search_command,
         get_document_count,
+        clear_search_cache_command,
+        related_files_command,
+        file_similarity_command,
+        search_suggestions_command,
+        multi_query_search_command,
+        search_with_text_command,
+        search_within_command,
+        metadata_search_command,
+        search_images_by_color_command,
+        find_similar_images_by_phash_command,
         // Filename search commands
         filename_search_command,
         add_file_to_index,
@@ -94,22 +177,71 @@ pub fn register_commands(builder: tauri::Builder<tauri::Wry>) -> tauri::Builder<
         clear_filename_index,
         scan_directory_for_filename_index,
         initialize_filename_index,
+        get_file_type_info,
         // Indexing commands
         index_downloads_command,
         index_folder_command,
         get_indexing_stats_command,
+        get_indexing_queue_status_command,
+        get_next_scheduled_index_command,
+        export_embeddings_command,
+        trigger_index_now_command,
         clear_index_command,
         get_vector_db_stats_command,
+        list_indexed_documents_command,
+        corpus_stats_command,
+        corpus_language_breakdown_command,
+        reload_embedder_command,
+        index_drift_command,
+        rebuild_term_frequencies_command,
+        verify_indexed_file_command,
+        verify_all_command,
+        embedder_status_command,
+        exclude_path_command,
+        include_path_command,
+        is_path_excluded_command,
+        list_excluded_paths_command,
+        estimate_indexing_time_command,
+        index_content_command,
+        move_database_command,
+        list_tombstones_command,
+        restore_tombstone_command,
+        prune_tombstones_command,
+        pause_watchers_command,
+        resume_watchers_command,
+        watcher_status_command,
         // Benchmark commands
         run_benchmarks,
+        run_search_benchmark,
+        embed_preview_command,
+        extract_preview_command,
+        cosine_between_command,
+        benchmark_embedding_command,
         // File operations commands
         copy_item,
         move_item,
+        move_to_new_folder_command,
         delete_item,
         rename_item,
         create_directory,
         get_item_info,
-        // Database repair command
-        repair_database_command
+        get_content_preview_command,
+        read_file_range_command,
+        storage_by_category_command,
+        detect_encoding_command,
+        document_outline_command,
+        compare_directories_command,
+        cancel_operation_command,
+        // Settings commands
+        get_settings_command,
+        update_settings_command,
+        export_config_command,
+        import_config_command,
+        subscribe_logs_command,
+        unsubscribe_logs_command,
+        // Database repair commands
+        repair_database_command,
+        check_index_integrity_command,
+        reconcile_moved_files_command
     ])
 }
@@ -0,0 +1,275 @@
+// src-tauri/src/outline.rs
+
+use crate::extractor::{effective_extension, ExtractorError};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+/// Deepest heading level kept; Markdown and Word both stop at 6 in practice
+const MAX_HEADING_LEVEL: usize = 6;
+
+/// `word/document.xml` larger than this is not scanned for headings
+const MAX_DOCX_XML_BYTES: u64 = 32 * 1024 * 1024;
+
+/// A heading and the headings nested under it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutlineEntry {
+    pub title: String,
+    /// 1 for top-level headings
+    pub level: usize,
+    /// 1-based page the heading points to, for PDFs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<usize>,
+    /// Byte offset of the heading line in the file, for Markdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<OutlineEntry>,
+}
+
+impl OutlineEntry {
+    fn new(title: String, level: usize) -> Self {
+        Self {
+            title,
+            level: level.clamp(1, MAX_HEADING_LEVEL),
+            page: None,
+            offset: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Reads the headings of a Markdown, PDF (bookmarks) or DOCX (heading styles)
+/// file and nests each under the closest preceding heading of a higher level.
+/// Documents without headings give an empty outline.
+pub fn document_outline(path: &Path) -> Result<Vec<OutlineEntry>, ExtractorError> {
+    let path_str = path.display().to_string();
+    let headings = match effective_extension(path).as_deref() {
+        Some("md") | Some("markdown") => {
+            let bytes = fs::read(path).map_err(|e| ExtractorError::IoError(path_str.clone(), e))?;
+            markdown_headings(&bytes)
+        }
+        Some("pdf") => pdf_bookmarks(path)?,
+        Some("docx") => docx_headings(path)?,
+        other => {
+            return Err(ExtractorError::UnsupportedFileType(format!(
+                "No outline support for {} ({})",
+                path_str,
+                other.unwrap_or("no extension")
+            )))
+        }
+    };
+    debug!("Found {} headings in {}", headings.len(), path_str);
+    Ok(nest_headings(headings))
+}
+
+/// ATX headings (`# Title` to `###### Title`) outside fenced code blocks.
+/// Lines are decoded one at a time so offsets stay byte positions in the
+/// file even when it holds invalid UTF-8.
+fn markdown_headings(bytes: &[u8]) -> Vec<OutlineEntry> {
+    let mut headings = Vec::new();
+    let mut in_fence = false;
+    let mut offset = 0;
+    for raw_line in bytes.split_inclusive(|b| *b == b'\n') {
+        let line_offset = offset;
+        offset += raw_line.len();
+        let line = String::from_utf8_lossy(raw_line);
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        // Four spaces of indentation make a code block, not a heading
+        if in_fence || line.len() - trimmed.len() > 3 {
+            continue;
+        }
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if level == 0 || level > MAX_HEADING_LEVEL {
+            continue;
+        }
+        let rest = &trimmed[level..];
+        if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            continue;
+        }
+        // Closing hashes are decoration: "## Title ##"
+        let title = rest.trim().trim_end_matches('#').trim_end();
+        if title.is_empty() {
+            continue;
+        }
+        let mut heading = OutlineEntry::new(title.to_string(), level);
+        heading.offset = Some(line_offset);
+        headings.push(heading);
+    }
+    headings
+}
+
+fn pdf_bookmarks(path: &Path) -> Result<Vec<OutlineEntry>, ExtractorError> {
+    let path_str = path.display().to_string();
+    let document =
+        lopdf::Document::load(path).map_err(|e| ExtractorError::PdfExtractionFailed(path_str.clone(), e.to_string()))?;
+    let toc = match document.get_toc() {
+        Ok(toc) => toc,
+        Err(e) => {
+            // Most PDFs simply have no bookmarks
+            debug!("No bookmarks in {}: {}", path_str, e);
+            return Ok(Vec::new());
+        }
+    };
+    if !toc.errors.is_empty() {
+        warn!("Skipped {} unreadable bookmarks in {}", toc.errors.len(), path_str);
+    }
+    Ok(toc
+        .toc
+        .into_iter()
+        .map(|entry| {
+            let mut heading = OutlineEntry::new(entry.title.trim().to_string(), entry.level);
+            heading.page = Some(entry.page);
+            heading
+        })
+        .collect())
+}
+
+fn docx_headings(path: &Path) -> Result<Vec<OutlineEntry>, ExtractorError> {
+    let path_str = path.display().to_string();
+    let docx_error = |e: String| ExtractorError::DocxExtractionFailed(path_str.clone(), e);
+    let file = File::open(path).map_err(|e| ExtractorError::IoError(path_str.clone(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| docx_error(e.to_string()))?;
+    let document = archive.by_name("word/document.xml").map_err(|e| docx_error(e.to_string()))?;
+    let mut xml = String::new();
+    document
+        .take(MAX_DOCX_XML_BYTES)
+        .read_to_string(&mut xml)
+        .map_err(|e| docx_error(e.to_string()))?;
+    Ok(docx_xml_headings(&xml))
+}
+
+/// Paragraphs styled `Title` or `HeadingN` in a DOCX `document.xml`
+fn docx_xml_headings(xml: &str) -> Vec<OutlineEntry> {
+    let mut headings = Vec::new();
+    for paragraph in xml.split("</w:p>") {
+        let Some(style) = attribute_after(paragraph, "<w:pStyle ", "w:val=\"") else {
+            continue;
+        };
+        let level = if style.eq_ignore_ascii_case("title") {
+            1
+        } else {
+            match style.strip_prefix("Heading").or_else(|| style.strip_prefix("heading")).map(str::parse::<usize>) {
+                Some(Ok(level)) => level,
+                _ => continue,
+            }
+        };
+        let title = paragraph_text(paragraph);
+        if !title.trim().is_empty() {
+            headings.push(OutlineEntry::new(title.trim().to_string(), level));
+        }
+    }
+    headings
+}
+
+/// Value of the first `attribute` inside the first `element` tag in `xml`
+fn attribute_after<'a>(xml: &'a str, element: &str, attribute: &str) -> Option<&'a str> {
+    let tag_start = xml.find(element)?;
+    let tag = &xml[tag_start..tag_start + xml[tag_start..].find('>')?];
+    let value_start = tag.find(attribute)? + attribute.len();
+    let value_len = tag[value_start..].find('"')?;
+    Some(&tag[value_start..value_start + value_len])
+}
+
+/// Concatenated `<w:t>` runs of a paragraph with XML entities decoded
+fn paragraph_text(paragraph: &str) -> String {
+    let mut text = String::new();
+    let mut rest = paragraph;
+    while let Some(start) = rest.find("<w:t") {
+        rest = &rest[start + 4..];
+        // Skip longer tags sharing the prefix, such as <w:tab/> or <w:tbl>
+        if !rest.starts_with('>') && !rest.starts_with(' ') {
+            continue;
+        }
+        let Some(open_end) = rest.find('>') else { break };
+        if rest[..open_end].ends_with('/') {
+            rest = &rest[open_end + 1..];
+            continue;
+        }
+        rest = &rest[open_end + 1..];
+        let Some(close) = rest.find("</w:t>") else { break };
+        text.push_str(&rest[..close]);
+        rest = &rest[close..];
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Turns headings in document order into a tree. A heading becomes a child of
+/// the nearest earlier heading with a lower level; skipped levels are allowed.
+fn nest_headings(headings: Vec<OutlineEntry>) -> Vec<OutlineEntry> {
+    let mut roots: Vec<OutlineEntry> = Vec::new();
+    // Open headings from the top level down; each is a child of the one before it
+    let mut stack: Vec<OutlineEntry> = Vec::new();
+    for heading in headings {
+        while stack.last().is_some_and(|open| open.level >= heading.level) {
+            close_heading(&mut stack, &mut roots);
+        }
+        stack.push(heading);
+    }
+    while !stack.is_empty() {
+        close_heading(&mut stack, &mut roots);
+    }
+    roots
+}
+
+fn close_heading(stack: &mut Vec<OutlineEntry>, roots: &mut Vec<OutlineEntry>) {
+    let Some(closed) = stack.pop() else { return };
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(closed),
+        None => roots.push(closed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_outline_nests_and_skips_code() {
+        let text = "# Guide\nIntro\n## Install ##\n```\n# not a heading\n```\n### Linux\n#hashtag\n## Usage\n# Appendix\n";
+        let outline = nest_headings(markdown_headings(text.as_bytes()));
+        assert_eq!(outline.len(), 2);
+        assert_eq!(outline[0].title, "Guide");
+        assert_eq!(outline[0].offset, Some(0));
+        let sections: Vec<&str> = outline[0].children.iter().map(|h| h.title.as_str()).collect();
+        assert_eq!(sections, vec!["Install", "Usage"]);
+        assert_eq!(outline[0].children[0].children[0].title, "Linux");
+        assert_eq!(outline[0].children[0].offset, Some(text.find("## Install").unwrap()));
+        assert_eq!(outline[1].title, "Appendix");
+    }
+
+    #[test]
+    fn test_markdown_offsets_count_original_bytes() {
+        // A stray Latin-1 byte widens to a 3-byte replacement character when decoded
+        let mut bytes = b"# Caf\xe9\nText\n".to_vec();
+        let second = bytes.len();
+        bytes.extend_from_slice(b"## Menu\n");
+        let outline = nest_headings(markdown_headings(&bytes));
+        assert_eq!(outline[0].title, "Caf\u{FFFD}");
+        assert_eq!(outline[0].children[0].offset, Some(second));
+    }
+
+    #[test]
+    fn test_docx_heading_styles() {
+        let xml = concat!(
+            r#"<w:body><w:p><w:pPr><w:pStyle w:val="Title"/></w:pPr><w:r><w:t>Annual </w:t></w:r><w:r><w:t xml:space="preserve">Report &amp; Plan</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:tab/><w:t>Budget</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>Body text</w:t></w:r></w:p></w:body>"#,
+        );
+        let outline = nest_headings(docx_xml_headings(xml));
+        assert_eq!(outline.len(), 1);
+        assert_eq!(outline[0].title, "Annual Report & Plan");
+        assert_eq!(outline[0].children[0].title, "Budget");
+        assert_eq!(outline[0].children[0].level, 2);
+    }
+}
@@ -0,0 +1,67 @@
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::path::Path;
+
+/// Bits in a perceptual hash
+pub const PHASH_BITS: u32 = 64;
+
+/// Images at most this many bits apart count as near-duplicates unless the caller says otherwise
+pub const DEFAULT_MAX_PHASH_DISTANCE: u32 = 10;
+
+/// Difference hash (dHash) of an image: it is shrunk to 9x8 grayscale pixels
+/// and each bit says whether a pixel is brighter than its right neighbour.
+/// Resizing or re-encoding barely changes it, unlike a hash of the file bytes.
+pub fn perceptual_hash_of_image(image: &DynamicImage) -> u64 {
+    let gray = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = gray.get_pixel(x, y).0[0] > gray.get_pixel(x + 1, y).0[0];
+            hash = (hash << 1) | brighter as u64;
+        }
+    }
+    hash
+}
+
+/// Decodes the image and returns its perceptual hash, or `None` if it can't be read
+pub fn perceptual_hash(path: &Path) -> Option<u64> {
+    image::open(path).ok().map(|image| perceptual_hash_of_image(&image))
+}
+
+/// Formats a hash as 16 lowercase hex digits, the way it is stored
+pub fn to_hex_hash(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+pub fn parse_hex_hash(hex: &str) -> Option<u64> {
+    u64::from_str_radix(hex.trim(), 16).ok()
+}
+
+/// Bits that differ between two hashes, from 0 (same picture) to 64
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            let shade = (x * 200 / width + y * 40 / height) as u8;
+            Rgb([shade, shade / 2, 255 - shade])
+        }))
+    }
+
+    #[test]
+    fn test_resized_copy_hashes_close_and_other_image_far() {
+        let original = perceptual_hash_of_image(&gradient(640, 480));
+        let resized = perceptual_hash_of_image(&gradient(160, 120));
+        let flipped = perceptual_hash_of_image(&gradient(640, 480).fliph());
+
+        assert!(hamming_distance(original, resized) <= 4);
+        assert!(hamming_distance(original, flipped) > DEFAULT_MAX_PHASH_DISTANCE);
+        assert_eq!(parse_hex_hash(&to_hex_hash(original)), Some(original));
+    }
+}
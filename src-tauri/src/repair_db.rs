@@ -1,6 +1,21 @@
 use log::{info, warn, error};
 use lancedb::Connection;
-use crate::db::{connect_db, TEXT_TABLE_NAME, IMAGE_TABLE_NAME, force_drop_table};
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use arrow_array::{Array, FixedSizeListArray, Float32Array, Int32Array, RecordBatch, StringArray};
+use arrow_schema::DataType;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use crate::archive::{composite_path, split_composite_path};
+use crate::core::term_frequencies::forget_document_terms;
+use crate::core::virtual_content::is_virtual_path;
+use crate::db::{
+    bump_index_version, connect_db, content_table_name, delete_document, force_drop_table, list_indexed_documents, sql_string_literal,
+    DocumentSortBy, AMHARIC_EMBEDDING_DIM,
+    AMHARIC_TEXT_TABLE_NAME,
+    CODE_EMBEDDING_DIM, CODE_TEXT_TABLE_NAME, IMAGE_EMBEDDING_DIM, IMAGE_TABLE_NAME, TEXT_EMBEDDING_DIM, TEXT_TABLE_NAME,
+};
 
 /// Drops the documents table and recreates it with the correct schema
 pub async fn repair_database() -> Result<(), String> {
@@ -62,4 +77,425 @@ async fn drop_table(conn: &Connection, table_name: &str) -> Result<(), String> {
     }
     
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// Integrity findings for a single table
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TableIntegrityReport {
+    pub table_name: String,
+    pub total_rows: usize,
+    /// Rows whose embedding is null, all zeros, or contains NaN values
+    pub empty_embedding_rows: usize,
+    /// Set to the stored dimension when it differs from what the embedder produces
+    pub wrong_dimension: Option<i32>,
+    /// Files whose chunk ids are duplicated or not contiguous from 0
+    pub files_with_chunk_problems: Vec<String>,
+    /// Indexed paths that no longer exist on disk
+    pub orphaned_paths: Vec<String>,
+    /// Rows removed when the check ran with `fix` enabled
+    pub rows_deleted: usize,
+}
+
+impl TableIntegrityReport {
+    fn is_healthy(&self) -> bool {
+        self.empty_embedding_rows == 0
+            && self.wrong_dimension.is_none()
+            && self.files_with_chunk_problems.is_empty()
+            && self.orphaned_paths.is_empty()
+    }
+}
+
+/// Result of [`check_index_integrity`]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub tables: Vec<TableIntegrityReport>,
+    pub healthy: bool,
+    /// True when every problem found can be fixed by deleting rows. A wrong
+    /// embedding dimension needs a full `repair_database` instead.
+    pub repairable: bool,
+    pub fixed: bool,
+}
+
+/// Scans the content tables for corrupt or stale rows.
+///
+/// With `fix` set, every row of a file with an empty embedding, chunk problems
+/// or a missing source file is removed so the file gets re-indexed cleanly. Tables with the wrong embedding dimension are only
+/// reported.
+pub async fn check_index_integrity(conn: &Connection, fix: bool) -> Result<IntegrityReport, String> {
+    info!("Checking index integrity (fix: {})", fix);
+
+    let table_names = conn.table_names().execute().await.map_err(|e| {
+        format!("Failed to list tables: {}", e)
+    })?;
+
+    let tables_to_check = [
+        (TEXT_TABLE_NAME, TEXT_EMBEDDING_DIM, true),
+        (AMHARIC_TEXT_TABLE_NAME, AMHARIC_EMBEDDING_DIM, true),
+        (CODE_TEXT_TABLE_NAME, CODE_EMBEDDING_DIM, true),
+        (IMAGE_TABLE_NAME, IMAGE_EMBEDDING_DIM, false),
+    ];
+
+    let mut report = IntegrityReport {
+        healthy: true,
+        repairable: true,
+        fixed: false,
+        tables: Vec::new(),
+    };
+
+    for (table_name, expected_dim, chunked) in tables_to_check {
+        if !table_names.iter().any(|name| name == table_name) {
+            continue;
+        }
+        let table_report = check_table_integrity(conn, table_name, expected_dim, chunked, fix).await?;
+        if !table_report.is_healthy() {
+            report.healthy = false;
+            if table_report.wrong_dimension.is_some() {
+                report.repairable = false;
+            }
+        }
+        if table_report.rows_deleted > 0 {
+            report.fixed = true;
+        }
+        report.tables.push(table_report);
+    }
+
+    info!(
+        "Index integrity check finished: healthy={}, repairable={}, fixed={}",
+        report.healthy, report.repairable, report.fixed
+    );
+    Ok(report)
+}
+
+async fn check_table_integrity(
+    conn: &Connection,
+    table_name: &str,
+    expected_dim: i32,
+    chunked: bool,
+    fix: bool,
+) -> Result<TableIntegrityReport, String> {
+    let table = conn.open_table(table_name).execute().await.map_err(|e| {
+        format!("Failed to open table '{}': {}", table_name, e)
+    })?;
+
+    let mut report = TableIntegrityReport {
+        table_name: table_name.to_string(),
+        ..Default::default()
+    };
+
+    let schema = table.schema().await.map_err(|e| {
+        format!("Failed to read schema of '{}': {}", table_name, e)
+    })?;
+    if let Ok(field) = schema.field_with_name("embedding") {
+        if let DataType::FixedSizeList(_, dim) = field.data_type() {
+            if *dim != expected_dim {
+                warn!("Table '{}' stores {}-dimensional embeddings, expected {}", table_name, dim, expected_dim);
+                report.wrong_dimension = Some(*dim);
+            }
+        }
+    }
+
+    let mut columns = vec!["file_path", "embedding"];
+    if chunked {
+        columns.push("chunk_id");
+    }
+    let batches: Vec<RecordBatch> = table
+        .query()
+        .select(Select::columns(&columns))
+        .execute()
+        .await
+        .map_err(|e| format!("Failed to scan table '{}': {}", table_name, e))?
+        .try_collect()
+        .await
+        .map_err(|e| format!("Failed to scan table '{}': {}", table_name, e))?;
+
+    let mut chunk_ids: BTreeMap<String, Vec<i32>> = BTreeMap::new();
+    let mut files_with_empty_embeddings: BTreeSet<String> = BTreeSet::new();
+    for batch in &batches {
+        report.total_rows += batch.num_rows();
+        let paths = batch.column_by_name("file_path").and_then(|c| c.as_any().downcast_ref::<StringArray>());
+        let embeddings = batch.column_by_name("embedding").and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>());
+        let (Some(paths), Some(embeddings)) = (paths, embeddings) else {
+            return Err(format!("Table '{}' is missing expected columns", table_name));
+        };
+        let chunks = batch.column_by_name("chunk_id").and_then(|c| c.as_any().downcast_ref::<Int32Array>());
+
+        for row in 0..batch.num_rows() {
+            if is_empty_embedding(embeddings, row) {
+                report.empty_embedding_rows += 1;
+                files_with_empty_embeddings.insert(paths.value(row).to_string());
+            }
+            let ids = chunk_ids.entry(paths.value(row).to_string()).or_default();
+            if let Some(chunks) = chunks {
+                ids.push(chunks.value(row));
+            }
+        }
+    }
+
+    for (path, ids) in chunk_ids.iter_mut() {
+        if chunked {
+            ids.sort_unstable();
+            let contiguous = ids.iter().enumerate().all(|(i, id)| *id == i as i32);
+            if !contiguous {
+                report.files_with_chunk_problems.push(path.clone());
+            }
+        }
+        if !indexed_path_exists(path) {
+            report.orphaned_paths.push(path.clone());
+        }
+    }
+
+    if fix && report.wrong_dimension.is_none() {
+        let rows_before = report.total_rows;
+        let files_to_drop: BTreeSet<&String> = files_with_empty_embeddings
+            .iter()
+            .chain(report.files_with_chunk_problems.iter())
+            .chain(report.orphaned_paths.iter())
+            .collect();
+        for path in files_to_drop {
+            delete_document(&table, path).await.map_err(|e| {
+                format!("Failed to delete '{}' from '{}': {}", path, table_name, e)
+            })?;
+            forget_document_terms(path);
+        }
+        let rows_after = table.count_rows(None).await.map_err(|e| {
+            format!("Failed to count rows in '{}': {}", table_name, e)
+        })?;
+        report.rows_deleted = rows_before.saturating_sub(rows_after);
+        info!("Removed {} corrupt rows from '{}'", report.rows_deleted, table_name);
+    }
+
+    Ok(report)
+}
+
+/// An embedding is unusable if it is null, all zeros, or contains NaN
+fn is_empty_embedding(embeddings: &FixedSizeListArray, row: usize) -> bool {
+    if embeddings.is_null(row) {
+        return true;
+    }
+    let values = embeddings.value(row);
+    let Some(values) = values.as_any().downcast_ref::<Float32Array>() else {
+        return true;
+    };
+    values.null_count() == values.len()
+        || values.iter().any(|v| v.is_some_and(f32::is_nan))
+        || values.iter().all(|v| v.map_or(true, |v| v == 0.0))
+}
+
+/// Index entries sharing one content hash, some of which pointed at files that are gone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciledGroup {
+    /// "text", "amharic", "code" or "image"
+    pub content_type: String,
+    pub content_hash: String,
+    /// Paths that still exist and stay in the index
+    pub kept: Vec<String>,
+    /// Paths that no longer exist and were removed
+    pub removed: Vec<String>,
+}
+
+/// Result of [`reconcile_moved_files`]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MovedFilesReport {
+    pub groups: Vec<ReconciledGroup>,
+    pub entries_removed: usize,
+}
+
+/// Whether the file behind an index path is still on disk. Virtual content
+/// has no file and lives until it is removed explicitly.
+pub(crate) fn indexed_path_exists(path: &str) -> bool {
+    if is_virtual_path(path) {
+        return true;
+    }
+    // Archive members are stored as `archive!member`; check the archive itself
+    let on_disk = split_composite_path(path).map_or(path, |(archive, _)| archive);
+    Path::new(on_disk).exists()
+}
+
+/// Where the indexed path `indexed` lives after `old` was renamed or moved to
+/// `new`, or `None` when it isn't under `old`. Archive members follow their archive.
+fn moved_path(indexed: &str, old: &Path, new: &Path) -> Option<String> {
+    if is_virtual_path(indexed) {
+        return None;
+    }
+    if let Some((archive, member)) = split_composite_path(indexed) {
+        let archive = moved_path(archive, old, new)?;
+        return Some(composite_path(Path::new(&archive), member));
+    }
+    let rest = Path::new(indexed).strip_prefix(old).ok()?;
+    // Joining an empty path would add a trailing separator
+    let moved = if rest.as_os_str().is_empty() { new.to_path_buf() } else { new.join(rest) };
+    Some(moved.to_string_lossy().into_owned())
+}
+
+/// Points the index entries of `old`, or of everything under it when it is a
+/// folder, at their new location after a rename or move. Embeddings are kept,
+/// so nothing is re-extracted. Returns the number of files updated.
+pub async fn update_moved_paths(conn: &Connection, old: &Path, new: &Path) -> Result<usize, String> {
+    let documents = list_indexed_documents(conn, None, DocumentSortBy::Path)
+        .await
+        .map_err(|e| format!("Failed to list indexed documents: {}", e))?;
+
+    let mut updated = 0;
+    for document in documents {
+        let Some(new_path) = moved_path(&document.file_path, old, new) else {
+            continue;
+        };
+        let Some(table_name) = content_table_name(&document.content_type) else {
+            continue;
+        };
+        let table = conn
+            .open_table(table_name)
+            .execute()
+            .await
+            .map_err(|e| format!("Failed to open table '{}': {}", table_name, e))?;
+        table
+            .update()
+            .only_if(format!("file_path = {}", sql_string_literal(&document.file_path)))
+            .column("file_path", sql_string_literal(&new_path))
+            .execute()
+            .await
+            .map_err(|e| format!("Failed to update '{}' in '{}': {}", document.file_path, table_name, e))?;
+        updated += 1;
+    }
+    if updated > 0 {
+        bump_index_version();
+        info!("Updated {} index entries moved from {} to {}", updated, old.display(), new.display());
+    }
+    Ok(updated)
+}
+
+/// Cleans up after files moved behind the watcher's back.
+///
+/// A moved file ends up indexed twice: under its old path and, once re-indexed,
+/// its new one, both with the same content hash. For every such group with at
+/// least one live path, the entries whose files are gone are removed. Entries
+/// with no live copy are left alone; they are orphans, not moves, and
+/// [`check_index_integrity`] handles those.
+pub async fn reconcile_moved_files(conn: &Connection) -> Result<MovedFilesReport, String> {
+    let documents = list_indexed_documents(conn, None, DocumentSortBy::Path)
+        .await
+        .map_err(|e| format!("Failed to list indexed documents: {}", e))?;
+
+    let mut by_hash: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+    for document in documents {
+        by_hash
+            .entry((document.content_type, document.content_hash))
+            .or_default()
+            .push(document.file_path);
+    }
+
+    let mut report = MovedFilesReport::default();
+    for ((content_type, content_hash), paths) in by_hash {
+        if paths.len() < 2 {
+            continue;
+        }
+        let (kept, removed): (Vec<String>, Vec<String>) = paths.into_iter().partition(|path| indexed_path_exists(path));
+        if kept.is_empty() || removed.is_empty() {
+            continue;
+        }
+
+        let Some(table_name) = content_table_name(&content_type) else {
+            warn!("Unknown content type '{}' while reconciling moves", content_type);
+            continue;
+        };
+        let table = conn.open_table(table_name).execute().await.map_err(|e| {
+            format!("Failed to open table '{}': {}", table_name, e)
+        })?;
+        for path in &removed {
+            delete_document(&table, path).await.map_err(|e| {
+                format!("Failed to delete '{}' from '{}': {}", path, table_name, e)
+            })?;
+            forget_document_terms(path);
+            info!("Removed moved file's old entry {} (now at {})", path, kept[0]);
+        }
+
+        report.entries_removed += removed.len();
+        report.groups.push(ReconciledGroup {
+            content_type,
+            content_hash,
+            kept,
+            removed,
+        });
+    }
+
+    info!(
+        "Reconciled {} moved files, removing {} stale entries",
+        report.groups.len(),
+        report.entries_removed
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{connect_db_with_path, open_or_create_text_table, upsert_document, TestDb};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_reconcile_removes_old_path_of_moved_file() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let table = open_or_create_text_table(&conn).await.unwrap();
+        let embedding = vec![vec![0.1f32; TEXT_EMBEDDING_DIM as usize]];
+
+        let dir = tempdir().unwrap();
+        let new_path = dir.path().join("report.txt");
+        std::fs::write(&new_path, "moved").unwrap();
+        let new_path = new_path.to_string_lossy().to_string();
+        let old_path = dir.path().join("old/report.txt").to_string_lossy().to_string();
+        upsert_document(&table, &old_path, "moved_hash", &embedding, None).await.unwrap();
+        upsert_document(&table, &new_path, "moved_hash", &embedding, None).await.unwrap();
+        // Gone with no live copy: an orphan, not a move
+        upsert_document(&table, "/nowhere/a.txt", "orphan_hash", &embedding, None).await.unwrap();
+        upsert_document(&table, "/nowhere/b.txt", "orphan_hash", &embedding, None).await.unwrap();
+
+        let report = reconcile_moved_files(&conn).await.unwrap();
+        assert_eq!(report.entries_removed, 1);
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(report.groups[0].kept, vec![new_path]);
+        assert_eq!(report.groups[0].removed, vec![old_path]);
+        assert_eq!(table.count_rows(None).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_update_moved_paths_repoints_search_results() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let table = open_or_create_text_table(&conn).await.unwrap();
+        let embedding = vec![0.3f32; TEXT_EMBEDDING_DIM as usize];
+        upsert_document(&table, "/docs/draft.txt", "draft_hash", &[embedding.clone()], None).await.unwrap();
+        upsert_document(&table, "/docs/drafts/old.txt", "old_hash", &[vec![0.9f32; TEXT_EMBEDDING_DIM as usize]], None)
+            .await
+            .unwrap();
+
+        let updated = update_moved_paths(&conn, Path::new("/docs/draft.txt"), Path::new("/docs/final.txt"))
+            .await
+            .unwrap();
+        assert_eq!(updated, 1, "A sibling sharing the name prefix must not move");
+
+        let batches: Vec<RecordBatch> = table
+            .query()
+            .nearest_to(embedding)
+            .unwrap()
+            .limit(1)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let paths = batches[0]
+            .column_by_name("file_path")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(paths.value(0), "/docs/final.txt");
+
+        assert_eq!(
+            moved_path("/docs/drafts/old.txt", Path::new("/docs/drafts"), Path::new("/archive/drafts")),
+            Some("/archive/drafts/old.txt".to_string())
+        );
+    }
+}
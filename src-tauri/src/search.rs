@@ -1,19 +1,33 @@
 use crate::db::{
-    connect_db, open_or_create_amharic_text_table, open_or_create_image_table, open_or_create_text_table, DbError
+    connect_db, file_path_in_predicate, file_path_under_predicate, index_version, open_or_create_amharic_text_table, open_or_create_code_table,
+    open_or_create_image_table, open_or_create_text_table, sql_string_literal, DbError, IMAGE_TABLE_NAME
 };
-use crate::embedder::{embed_text, EmbeddingError};
-use crate::extractor::{ContentType, DetectedLanguage}; // Added import
-use crate::image_embedder::{embed_text_for_image_search, ImageEmbeddingError};
-use arrow_array::{Array, Float32Array, StringArray, TimestampSecondArray};
+use crate::color::{color_similarity, parse_hex_color};
+use crate::phash::{hamming_distance, parse_hex_hash, perceptual_hash, PHASH_BITS};
+use crate::chunker::chunk_text;
+use crate::core::file_types::{categorize_file, file_type_info, FileCategory};
+use crate::core::favorites::{favorite_paths, favorites_version};
+use crate::core::settings::{current_settings, DistanceMetric, ScoreScale};
+use crate::embedder::{cosine_similarity, embed_code, embed_text, l2_norm, EmbeddingError};
+use crate::extractor::{extract_text, get_content_type, ContentType, DetectedLanguage}; // Added import
+use crate::image_embedder::{embed_image, embed_text_for_image_search, ImageEmbeddingError};
+use arrow_array::{Array, FixedSizeListArray, Float32Array, Int32Array, StringArray, TimestampSecondArray};
+use chrono::{DateTime, Datelike, Local, TimeZone};
 use futures_util::TryStreamExt;
 use lancedb::connection::Connection;
 use lancedb::query::{ExecutableQuery, QueryBase, Select};
 use lancedb::table::Table;
+use lancedb::DistanceType;
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 use whatlang::{detect, Lang};
+use once_cell::sync::Lazy;
 use std::cmp::Ordering;
-use std::sync::Arc;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 #[cfg(test)]
@@ -25,6 +39,22 @@ pub const DEFAULT_SEARCH_LIMIT: usize = 20;
 /// The minimum score (1.0 / distance) to include a result
 pub const DEFAULT_MIN_SCORE: f32 = 0.6;
 
+/// Lowest threshold the `min_results` fallback relaxes `min_score` to
+pub const MIN_SCORE_FLOOR: f32 = 0.2;
+
+/// How far the threshold drops on each `min_results` fallback step
+const MIN_SCORE_FALLBACK_STEP: f32 = 0.05;
+
+/// CLIP text-to-image similarities rarely go above this, so it is mapped to
+/// 1.0 to put image hits on the same scale as text hits
+const IMAGE_SCORE_CEILING: f32 = 0.4;
+
+/// How many distinct queries the result cache remembers
+const SEARCH_CACHE_CAPACITY: usize = 64;
+
+/// How long a cached result stays valid, even if the index hasn't changed
+const SEARCH_CACHE_TTL: Duration = Duration::from_secs(30);
+
 /// Error types that can occur during semantic search operations
 #[derive(Error, Debug)]
 pub enum SearchError {
@@ -52,6 +82,94 @@ pub enum SearchContentType {
     ImageOnly,
 }
 
+/// A table [`multimodal_search_outcome`] can search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SearchTable {
+    /// English and other non-Amharic text
+    Text,
+    Amharic,
+    Code,
+    Image,
+}
+
+/// Tables to search for `content_type`. Amharic text has its own table and
+/// model: `include_amharic` forces it in or out, and when unset it replaces
+/// the English table only for queries that are themselves Amharic.
+pub fn tables_to_search(
+    content_type: SearchContentType,
+    include_amharic: Option<bool>,
+    query_is_amharic: bool,
+) -> BTreeSet<SearchTable> {
+    let mut tables = BTreeSet::new();
+    if content_type != SearchContentType::ImageOnly {
+        match include_amharic {
+            Some(true) => tables.extend([SearchTable::Text, SearchTable::Amharic]),
+            Some(false) => {
+                tables.insert(SearchTable::Text);
+            }
+            None if query_is_amharic => {
+                tables.insert(SearchTable::Amharic);
+            }
+            None => {
+                tables.insert(SearchTable::Text);
+            }
+        }
+        tables.insert(SearchTable::Code);
+    }
+    if content_type != SearchContentType::TextOnly {
+        tables.insert(SearchTable::Image);
+    }
+    tables
+}
+
+fn detect_query_language(query: &str) -> DetectedLanguage {
+    match detect(query) {
+        Some(info) if info.lang() == Lang::Eng => DetectedLanguage::English,
+        Some(info) if info.lang() == Lang::Amh => DetectedLanguage::Amharic,
+        _ => DetectedLanguage::Other,
+    }
+}
+
+/// What makes two text hits the same result. Only the best-scoring chunk of
+/// each key is kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupKey {
+    /// One result per file
+    #[default]
+    FilePath,
+    /// One result per chunk, for paths that hold several logical documents
+    FilePathAndChunk,
+    /// One result per distinct content, so identical files merge as well
+    ContentHash,
+}
+
+impl DedupKey {
+    fn key(self, file_path: &str, chunk_id: i32, content_hash: &str) -> String {
+        match self {
+            DedupKey::FilePath => file_path.to_string(),
+            DedupKey::FilePathAndChunk => format!("{}#{}", file_path, chunk_id),
+            DedupKey::ContentHash => content_hash.to_string(),
+        }
+    }
+}
+
+/// LanceDB's name for `metric`
+fn distance_type(metric: DistanceMetric) -> DistanceType {
+    match metric {
+        DistanceMetric::L2 => DistanceType::L2,
+        DistanceMetric::Cosine => DistanceType::Cosine,
+        DistanceMetric::Dot => DistanceType::Dot,
+    }
+}
+
+/// Sets each result's `display_score` from its raw score
+pub fn apply_score_scale(results: &mut [SearchResult], scale: ScoreScale) {
+    for result in results {
+        result.display_score = Some(scale.display(result.score));
+    }
+}
+
 /// Represents a single search result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
@@ -72,6 +190,26 @@ pub struct SearchResult {
 
     /// Optional image-specific data
     pub image_data: Option<ImageData>,
+
+    /// Icon the frontend should show, from `get_file_type_info`
+    #[serde(default)]
+    pub icon_key: String,
+
+    /// Other paths with the same content, when duplicates were collapsed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicate_paths: Vec<String>,
+
+    /// `score` on the user's chosen [`ScoreScale`], set when building a search response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_score: Option<f32>,
+
+    /// Folder passed to `index_folder` when the file was indexed, if recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_root: Option<String>,
+
+    /// Chunk the score came from; `None` for images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_id: Option<i32>,
 }
 
 /// Additional data for image results
@@ -82,6 +220,405 @@ pub struct ImageData {
     pub thumbnail_path: Option<String>,
 }
 
+/// Search results that share a parent directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderGroup {
+    /// Parent directory of the grouped results
+    pub folder: String,
+
+    /// Score of the best result in this folder
+    pub best_score: f32,
+
+    /// Results in this folder, in ranked order
+    pub results: Vec<SearchResult>,
+}
+
+/// Buckets ranked results by their parent directory.
+///
+/// Results keep their relative order within each group, and groups are
+/// ordered by the score of their best result (highest first).
+pub fn group_results_by_folder(results: &[SearchResult]) -> Vec<FolderGroup> {
+    let mut groups: Vec<FolderGroup> = Vec::new();
+    let mut group_index: std::collections::HashMap<String, usize> =
+        std::collections::HashMap::new();
+
+    for result in results {
+        let folder = std::path::Path::new(&result.file_path)
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        match group_index.get(&folder) {
+            Some(&index) => {
+                let group = &mut groups[index];
+                if result.score > group.best_score {
+                    group.best_score = result.score;
+                }
+                group.results.push(result.clone());
+            }
+            None => {
+                group_index.insert(folder.clone(), groups.len());
+                groups.push(FolderGroup {
+                    folder,
+                    best_score: result.score,
+                    results: vec![result.clone()],
+                });
+            }
+        }
+    }
+
+    groups.sort_by(|a, b| b.best_score.partial_cmp(&a.best_score).unwrap_or(Ordering::Equal));
+    groups
+}
+
+/// Whether a search returns matching files or the folders that contain them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResultGranularity {
+    #[default]
+    File,
+    Folder,
+}
+
+/// A folder ranked by the search matches inside it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderMatch {
+    pub folder: String,
+    /// Combined score of the matches: `1 - Π(1 - score)`. One strong match
+    /// dominates, and every further match raises it a little.
+    pub score: f32,
+    pub matching_files: usize,
+    /// Matching files in ranked order
+    pub files: Vec<String>,
+}
+
+/// Rolls file results up to the folder `depth` levels above each file
+/// (1 is the parent) and ranks the folders by their combined score.
+pub fn aggregate_results_by_folder(results: &[SearchResult], depth: usize) -> Vec<FolderMatch> {
+    let mut folders: Vec<FolderMatch> = Vec::new();
+    let mut folder_index: HashMap<String, usize> = HashMap::new();
+
+    for result in results {
+        let folder = std::path::Path::new(&result.file_path)
+            .ancestors()
+            .nth(depth.max(1))
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let index = *folder_index.entry(folder.clone()).or_insert_with(|| {
+            folders.push(FolderMatch {
+                folder,
+                score: 0.0,
+                matching_files: 0,
+                files: Vec::new(),
+            });
+            folders.len() - 1
+        });
+        let entry = &mut folders[index];
+        entry.score = 1.0 - (1.0 - entry.score) * (1.0 - result.score.clamp(0.0, 1.0));
+        entry.matching_files += 1;
+        entry.files.push(result.file_path.clone());
+    }
+
+    folders.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    folders
+}
+
+/// Collapses results with the same content hash into the highest-scored one,
+/// listing the other copies in its `duplicate_paths`. Order is otherwise kept;
+/// results without a hash are never merged.
+pub fn collapse_duplicate_results(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut collapsed: Vec<SearchResult> = Vec::with_capacity(results.len());
+    let mut by_hash: HashMap<String, usize> = HashMap::new();
+
+    for result in results {
+        if result.content_hash.is_empty() {
+            collapsed.push(result);
+            continue;
+        }
+        match by_hash.get(&result.content_hash) {
+            Some(&index) => {
+                let kept = &mut collapsed[index];
+                if result.score > kept.score {
+                    // The better copy takes over the slot; the old one becomes a duplicate
+                    let previous_path = std::mem::replace(&mut kept.file_path, result.file_path);
+                    kept.score = result.score;
+                    kept.last_modified = result.last_modified;
+                    kept.duplicate_paths.push(previous_path);
+                } else {
+                    kept.duplicate_paths.push(result.file_path);
+                }
+            }
+            None => {
+                by_hash.insert(result.content_hash.clone(), collapsed.len());
+                collapsed.push(result);
+            }
+        }
+    }
+    collapsed
+}
+
+/// Coarse age of a result's file, by calendar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateBucket {
+    Today,
+    /// Since Monday, before today
+    ThisWeek,
+    /// Since the 1st, before this week
+    ThisMonth,
+    Older,
+}
+
+/// Which bucket `timestamp` (Unix seconds) falls in, seen from `now`'s calendar.
+/// Timestamps in the future count as today.
+pub fn date_bucket<Tz: TimeZone>(timestamp: i64, now: &DateTime<Tz>) -> DateBucket {
+    let Some(time) = DateTime::from_timestamp(timestamp, 0).map(|t| t.with_timezone(&now.timezone())) else {
+        return DateBucket::Older;
+    };
+    let (day, today) = (time.date_naive(), now.date_naive());
+    if day >= today {
+        DateBucket::Today
+    } else if day.iso_week() == today.iso_week() {
+        DateBucket::ThisWeek
+    } else if day.year() == today.year() && day.month() == today.month() {
+        DateBucket::ThisMonth
+    } else {
+        DateBucket::Older
+    }
+}
+
+/// Result counts for a faceted search UI, over every candidate that cleared
+/// the score threshold rather than just the returned page
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchFacets {
+    pub content_types: HashMap<ContentType, usize>,
+    pub categories: HashMap<FileCategory, usize>,
+    pub modified: HashMap<DateBucket, usize>,
+}
+
+/// When the file behind `result` was last modified, in Unix seconds. Falls
+/// back to `last_modified`, the time it was indexed, if the file can't be read.
+fn file_modified_time(result: &SearchResult) -> i64 {
+    std::fs::metadata(&result.file_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|since_epoch| since_epoch.as_secs() as i64)
+        .unwrap_or(result.last_modified)
+}
+
+pub fn compute_facets<Tz: TimeZone>(results: &[SearchResult], now: &DateTime<Tz>) -> SearchFacets {
+    let mut facets = SearchFacets::default();
+    for result in results {
+        *facets.content_types.entry(result.content_type.clone()).or_default() += 1;
+        *facets
+            .categories
+            .entry(categorize_file(&Path::new(&result.file_path).to_path_buf()))
+            .or_default() += 1;
+        *facets.modified.entry(date_bucket(file_modified_time(result), now)).or_default() += 1;
+    }
+    facets
+}
+
+/// Parameters for [`multimodal_search_with_options`]
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    /// Maximum number of results to return (default: DEFAULT_SEARCH_LIMIT)
+    pub limit: Option<usize>,
+
+    /// Minimum score threshold (0.0 to 1.0, default: DEFAULT_MIN_SCORE)
+    pub min_score: Option<f32>,
+
+    /// If fewer results clear `min_score`, lower it step by step, down to
+    /// [`MIN_SCORE_FLOOR`], until this many do (default: the `min_results` setting)
+    pub min_results: Option<usize>,
+
+    /// Filter to specific content type (default: SearchContentType::All)
+    pub content_type: Option<SearchContentType>,
+
+    /// Multiplier applied to text result scores before merging
+    pub text_weight: f32,
+
+    /// Multiplier applied to image result scores before merging
+    pub image_weight: f32,
+
+    /// How text and code hits are merged into results (default: one per file)
+    pub dedup_key: DedupKey,
+
+    /// Only search these files, to drill down into an earlier result set
+    pub within_paths: Option<Vec<String>>,
+
+    /// Also count the matches by content type, category and date
+    pub include_facets: bool,
+
+    /// Search the Amharic table too (`true`) or never (`false`). Unset, it is
+    /// searched instead of the English table when the query is Amharic.
+    pub include_amharic: Option<bool>,
+
+    /// Multiply the scores of favorite files by the `favorite_boost` setting
+    pub boost_favorites: bool,
+
+    /// Only return favorite files and files in favorite folders
+    pub favorites_only: bool,
+
+    /// Serve repeated searches from the result cache (default: true). Off for
+    /// callers that must run every search, like the latency benchmark.
+    pub use_cache: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            limit: None,
+            min_score: None,
+            min_results: None,
+            content_type: None,
+            text_weight: 1.0,
+            image_weight: 1.0,
+            dedup_key: DedupKey::default(),
+            within_paths: None,
+            include_facets: false,
+            include_amharic: None,
+            boost_favorites: false,
+            favorites_only: false,
+            use_cache: true,
+        }
+    }
+}
+
+/// Whether `file_path` is a favorite or lies in a favorite folder
+fn is_favorite(file_path: &str, favorites: &HashSet<String>) -> bool {
+    favorites.contains(file_path) || Path::new(file_path).ancestors().skip(1).any(|folder| {
+        folder.to_str().is_some_and(|folder| favorites.contains(folder))
+    })
+}
+
+/// Multiplies the scores of favorite results by `boost`, keeping them at most 1
+fn apply_favorite_boost(results: &mut [SearchResult], favorites: &HashSet<String>, boost: f32) {
+    for result in results.iter_mut().filter(|result| is_favorite(&result.file_path, favorites)) {
+        result.score = (result.score * boost).min(1.0);
+    }
+}
+
+/// Scales every result's score by `weight`, leaving them untouched for the default of 1.0
+fn apply_score_weight(results: &mut [SearchResult], weight: f32) {
+    if weight == 1.0 {
+        return;
+    }
+    for result in results.iter_mut() {
+        result.score *= weight;
+    }
+}
+
+/// The threshold to filter `scores` with. Starts at `min_score`; while fewer
+/// than `min_results` scores clear it, drops by [`MIN_SCORE_FALLBACK_STEP`]
+/// until it reaches [`MIN_SCORE_FLOOR`]. A `min_score` already below the floor
+/// is used as is, so the fallback never raises a threshold.
+fn relaxed_min_score(scores: &[f32], min_score: f32, min_results: usize) -> f32 {
+    let floor = MIN_SCORE_FLOOR.min(min_score);
+    let clearing = |threshold: f32| scores.iter().filter(|score| **score >= threshold).count();
+    let mut threshold = min_score;
+    while threshold > floor && clearing(threshold) < min_results {
+        threshold = (threshold - MIN_SCORE_FALLBACK_STEP).max(floor);
+    }
+    threshold
+}
+
+struct CachedSearch {
+    results: Vec<SearchResult>,
+    facets: Option<SearchFacets>,
+    inserted_at: Instant,
+    index_version: u64,
+}
+
+/// Small LRU cache of search results, so incremental typing doesn't re-embed
+/// and re-scan for queries that were just run
+struct SearchCache {
+    entries: HashMap<u64, CachedSearch>,
+    /// Keys from least to most recently used
+    order: VecDeque<u64>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl SearchCache {
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+            ttl,
+        }
+    }
+
+    fn touch(&mut self, key: u64) {
+        self.order.retain(|k| *k != key);
+        self.order.push_back(key);
+    }
+
+    /// Returns the cached results if they are younger than the TTL and the index hasn't changed since
+    fn get(&mut self, key: u64, current_version: u64) -> Option<(Vec<SearchResult>, Option<SearchFacets>)> {
+        let fresh = match self.entries.get(&key) {
+            Some(entry) => entry.index_version == current_version && entry.inserted_at.elapsed() < self.ttl,
+            None => return None,
+        };
+        if !fresh {
+            self.entries.remove(&key);
+            self.order.retain(|k| *k != key);
+            return None;
+        }
+        self.touch(key);
+        self.entries.get(&key).map(|entry| (entry.results.clone(), entry.facets.clone()))
+    }
+
+    fn insert(&mut self, key: u64, results: Vec<SearchResult>, facets: Option<SearchFacets>, index_version: u64) {
+        self.entries.insert(key, CachedSearch { results, facets, inserted_at: Instant::now(), index_version });
+        self.touch(key);
+        while self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.entries.remove(&evicted);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+static SEARCH_CACHE: Lazy<Mutex<SearchCache>> =
+    Lazy::new(|| Mutex::new(SearchCache::new(SEARCH_CACHE_CAPACITY, SEARCH_CACHE_TTL)));
+
+/// Drops every cached search result
+pub fn clear_search_cache() {
+    SEARCH_CACHE.lock().unwrap().clear();
+}
+
+/// Cache key covering everything that affects a search's results
+fn search_cache_key(conn: &Connection, query: &str, options: &SearchOptions) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    conn.uri().hash(&mut hasher);
+    query.trim().hash(&mut hasher);
+    format!("{:?}", options.content_type).hash(&mut hasher);
+    options.limit.hash(&mut hasher);
+    options.min_score.map(f32::to_bits).hash(&mut hasher);
+    options.min_results.unwrap_or_else(|| current_settings().min_results).hash(&mut hasher);
+    options.text_weight.to_bits().hash(&mut hasher);
+    options.image_weight.to_bits().hash(&mut hasher);
+    options.dedup_key.hash(&mut hasher);
+    options.within_paths.hash(&mut hasher);
+    options.include_facets.hash(&mut hasher);
+    options.include_amharic.hash(&mut hasher);
+    options.boost_favorites.hash(&mut hasher);
+    options.favorites_only.hash(&mut hasher);
+    if options.boost_favorites || options.favorites_only {
+        favorites_version().hash(&mut hasher);
+        current_settings().favorite_boost.to_bits().hash(&mut hasher);
+    }
+    current_settings().distance_metric.hash(&mut hasher);
+    hasher.finish()
+}
+
 /// Performs a semantic search using the given query across both text and image tables
 ///
 /// # Arguments
@@ -97,17 +634,99 @@ pub async fn multimodal_search(
     min_score: Option<f32>,
     content_type: Option<SearchContentType>,
 ) -> Result<Vec<SearchResult>, SearchError> {
+    let options = SearchOptions {
+        limit,
+        min_score,
+        content_type,
+        ..Default::default()
+    };
+    multimodal_search_with_options(conn, query, &options).await
+}
+
+/// Performs a multimodal search with the full set of [`SearchOptions`].
+///
+/// Image scores are normalized onto the text scale first, then one `min_score`
+/// is applied to every table, so the threshold never shifts the text/image mix.
+/// When fewer than `min_results` hits clear it, the threshold is lowered in
+/// steps down to [`MIN_SCORE_FLOOR`] rather than returning an empty page.
+/// The per-source weights are applied after thresholding, just before the
+/// merged results are sorted, so they only change how text and image hits
+/// rank against each other. Favorites are boosted or filtered at that point too.
+pub async fn multimodal_search_with_options(
+    conn: &Connection,
+    query: &str,
+    options: &SearchOptions,
+) -> Result<Vec<SearchResult>, SearchError> {
+    multimodal_search_outcome(conn, query, options)
+        .await
+        .map(|outcome| outcome.results)
+}
+
+/// Results of a multimodal search, noting sources that couldn't be searched
+#[derive(Debug, Clone, Default)]
+pub struct MultimodalSearchOutcome {
+    pub results: Vec<SearchResult>,
+    /// The text search errored; `results` then only holds image hits
+    pub text_search_failed: bool,
+    /// Counts over all matches, when `include_facets` was set
+    pub facets: Option<SearchFacets>,
+}
+
+/// Like [`multimodal_search_with_options`], but a failing text search is
+/// reported in the outcome instead of failing the whole search. Partial
+/// results are not cached, so the next search retries the text tables.
+pub async fn multimodal_search_outcome(
+    conn: &Connection,
+    query: &str,
+    options: &SearchOptions,
+) -> Result<MultimodalSearchOutcome, SearchError> {
     // Validate input
     if query.trim().is_empty() {
         return Err(SearchError::EmptyQuery);
     }
 
+    let cache_key = search_cache_key(conn, query, options);
+    let version = index_version();
+    let cached = if options.use_cache {
+        SEARCH_CACHE.lock().unwrap().get(cache_key, version)
+    } else {
+        None
+    };
+    if let Some((cached, facets)) = cached {
+        debug!("Returning {} cached results for query: {}", cached.len(), query);
+        return Ok(MultimodalSearchOutcome {
+            results: cached,
+            text_search_failed: false,
+            facets,
+        });
+    }
+
     info!("Performing multimodal search for query: {}", query);
 
     // Set search parameters
-    let result_limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
-    let score_threshold = min_score.unwrap_or(DEFAULT_MIN_SCORE);
-    let content_filter = content_type.unwrap_or(SearchContentType::All);
+    let result_limit = options.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let score_threshold = options.min_score.unwrap_or(DEFAULT_MIN_SCORE);
+    let min_results = options.min_results.unwrap_or_else(|| current_settings().min_results);
+    let content_filter = options.content_type.unwrap_or(SearchContentType::All);
+    let mut path_filters = Vec::new();
+    match &options.within_paths {
+        Some(paths) if paths.is_empty() => return Ok(MultimodalSearchOutcome::default()),
+        Some(paths) => path_filters.push(file_path_in_predicate(paths)),
+        None => {}
+    }
+    if options.favorites_only {
+        // Filtered in the tables, so favorites beyond the fetch limit still show up
+        let favorites: Vec<String> = favorite_paths().into_iter().collect();
+        if favorites.is_empty() {
+            return Ok(MultimodalSearchOutcome::default());
+        }
+        path_filters.push(file_path_under_predicate(&favorites));
+    }
+    let path_filter = match path_filters.len() {
+        0 => None,
+        1 => path_filters.pop(),
+        _ => Some(path_filters.iter().map(|filter| format!("({})", filter)).collect::<Vec<_>>().join(" AND ")),
+    };
 
     // For tests, add debug output
     #[cfg(test)]
@@ -116,37 +735,55 @@ pub async fn multimodal_search(
         result_limit, score_threshold, content_filter
     );
 
-    // Open tables and decide which ones to search
-    let search_text = true;
-    let search_images = true;
+    let query_is_amharic = detect_query_language(query) == DetectedLanguage::Amharic;
+    let tables = tables_to_search(content_filter, options.include_amharic, query_is_amharic);
+    debug!("Searching tables {:?}", tables);
 
-    // Store all results in a single vector
-    let mut combined_results = Vec::new();
+    // Every table is searched unfiltered; the shared threshold is applied below
+    let mut text_results = Vec::new();
+    let mut code_results = Vec::new();
+    let mut image_results = Vec::new();
+    let mut text_search_failed = false;
 
     // We need to fetch more results than the requested limit from each table
     // to account for deduplication and ensure we have enough for the total limit
     let fetch_limit = result_limit * 2;
 
     // Search for text content if requested
-    if search_text {
-        debug!("Searching text content for: {}", query);
-        #[cfg(test)]
-        println!("Searching text content for: {}", query);
-
-        
-        let query = format!("{}", query);
-        let text_results =
-            search_text_content(&query, fetch_limit, score_threshold).await?;
+    for table in [SearchTable::Text, SearchTable::Amharic] {
+        if !tables.contains(&table) {
+            continue;
+        }
+        debug!("Searching {:?} content for: {}", table, query);
 
-        debug!("Found {} text results", text_results.len());
-        #[cfg(test)]
-        println!("Found {} text results", text_results.len());
+        match search_language_table(conn, query, table, fetch_limit, f32::MIN, options.dedup_key, path_filter.as_deref()).await {
+            Ok(results) => {
+                debug!("Found {} {:?} results", results.len(), table);
+                text_results.extend(results);
+            }
+            Err(e) => {
+                // A broken text table shouldn't black out image results
+                error!("Text search failed, continuing with image results only: {}", e);
+                text_search_failed = true;
+            }
+        }
+    }
 
-        combined_results.extend(text_results);
+    // Source code lives in its own table with a code-specific model
+    if tables.contains(&SearchTable::Code) {
+        match search_code_content(conn, query, fetch_limit, f32::MIN, options.dedup_key, path_filter.as_deref()).await {
+            Ok(results) => {
+                debug!("Found {} code results", results.len());
+                code_results = results;
+            }
+            Err(e) => {
+                warn!("Code search failed, continuing without code results: {}", e);
+            }
+        }
     }
 
     // Search for images if requested
-    if search_images {
+    if tables.contains(&SearchTable::Image) {
         debug!("Searching image content for: {}", query);
         println!("Searching image content for: {}", query);
         #[cfg(test)]
@@ -155,13 +792,10 @@ pub async fn multimodal_search(
         let image_table = open_or_create_image_table(conn).await?;
 
         println!("the image table connected successfully");
-        match search_image_content(&image_table, query, fetch_limit, score_threshold).await {
-            Ok(image_results) => {
-                debug!("Found {} image results", image_results.len());
-
-                println!("Found {} image results", image_results.len());
-
-                combined_results.extend(image_results);
+        match search_image_content(&image_table, query, fetch_limit, path_filter.as_deref()).await {
+            Ok(results) => {
+                debug!("Found {} image results", results.len());
+                image_results = results;
             }
             Err(e) => {
                 println!("Failed to search image content: {}", e);
@@ -189,12 +823,44 @@ pub async fn multimodal_search(
         }
     }
 
+    let scores: Vec<f32> = text_results
+        .iter()
+        .chain(&code_results)
+        .chain(&image_results)
+        .map(|result| result.score)
+        .collect();
+    let threshold = relaxed_min_score(&scores, score_threshold, min_results);
+    if threshold < score_threshold {
+        debug!(
+            "Lowered min_score from {} to {} to reach {} results",
+            score_threshold, threshold, min_results
+        );
+    }
+
+    let mut combined_results = Vec::new();
+    for (mut results, weight) in [
+        (text_results, options.text_weight),
+        (code_results, options.text_weight),
+        (image_results, options.image_weight),
+    ] {
+        results.retain(|result| result.score >= threshold);
+        apply_score_weight(&mut results, weight);
+        combined_results.extend(results);
+    }
+
+    if options.boost_favorites {
+        apply_favorite_boost(&mut combined_results, &favorite_paths(), current_settings().favorite_boost);
+    }
+
     // Sort by score (highest first)
     combined_results.sort_by(|a, b| {
         // Compare scores in reverse (higher first)
         b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal)
     });
 
+    // Counted before truncating, so they cover every match
+    let facets = options.include_facets.then(|| compute_facets(&combined_results, &Local::now()));
+
     // Limit results to the requested number
     if combined_results.len() > result_limit {
         combined_results.truncate(result_limit);
@@ -210,26 +876,53 @@ pub async fn multimodal_search(
         combined_results.len()
     );
 
-    Ok(combined_results)
+    if options.use_cache && !text_search_failed {
+        SEARCH_CACHE.lock().unwrap().insert(cache_key, combined_results.clone(), facets.clone(), version);
+    }
+    Ok(MultimodalSearchOutcome {
+        results: combined_results,
+        text_search_failed,
+        facets,
+    })
 }
 
-/// Search for text content using the given query
+/// Search for text content using the given query, in the table matching its language
 async fn search_text_content(
+    conn: &Connection,
     query: &str,
     limit: usize,
     min_score: f32,
+    dedup_key: DedupKey,
 ) -> Result<Vec<SearchResult>, SearchError> {
-    let lang_info = detect(&query);
-    let detected_lang = match lang_info {
-        Some(info) if info.lang() == Lang::Eng => DetectedLanguage::English,
-        Some(info) if info.lang() == Lang::Amh => DetectedLanguage::Amharic,
-        _ => DetectedLanguage::Other,
+    let detected_lang = detect_query_language(query);
+    debug!("Detected language: {:?}", detected_lang);
+    let table = if detected_lang == DetectedLanguage::Amharic {
+        SearchTable::Amharic
+    } else {
+        SearchTable::Text
+    };
+    search_language_table(conn, query, table, limit, min_score, dedup_key, None).await
+}
+
+/// Searches the English (`SearchTable::Text`) or Amharic table, embedding the
+/// query with that table's model whatever language the query is in
+async fn search_language_table(
+    conn: &Connection,
+    query: &str,
+    table: SearchTable,
+    limit: usize,
+    min_score: f32,
+    dedup_key: DedupKey,
+    filter: Option<&str>,
+) -> Result<Vec<SearchResult>, SearchError> {
+    let language = if table == SearchTable::Amharic {
+        DetectedLanguage::Amharic
+    } else {
+        DetectedLanguage::English
     };
-    println!("Detected language: {:?}", detected_lang);
     // Generate embedding for the query
     let query_vec = vec![query.to_string()];
-    // For search queries, assume English for now. This could be enhanced later.
-    let embeddings = embed_text(&query_vec, &detected_lang, true)?;
+    let embeddings = embed_text(&query_vec, &language, true)?;
 
     if embeddings.is_empty() {
         return Err(SearchError::OperationFailed(
@@ -244,25 +937,64 @@ async fn search_text_content(
     let query_vec = query_embedding.clone();
 
     // Use the query() method with vector similarity
-    let conn = connect_db().await?;
-    let table = if detected_lang == DetectedLanguage::Amharic {
-        open_or_create_amharic_text_table(&conn).await?
+    let table = if language == DetectedLanguage::Amharic {
+        open_or_create_amharic_text_table(conn).await?
     } else {
-        open_or_create_text_table(&conn).await?
+        open_or_create_text_table(conn).await?
     };
     println!("table name: {}", table.name());
-    println!("table schema: {:?}", detected_lang);
+    search_chunked_table(&table, query_vec, limit, min_score, current_settings().distance_metric, dedup_key, filter).await
+}
+
+/// Search source code with the code embedding model
+async fn search_code_content(
+    conn: &Connection,
+    query: &str,
+    limit: usize,
+    min_score: f32,
+    dedup_key: DedupKey,
+    filter: Option<&str>,
+) -> Result<Vec<SearchResult>, SearchError> {
+    let table = open_or_create_code_table(conn).await?;
+    // Nothing to find, so don't load the code model just to embed the query
+    if table.count_rows(None).await.map_err(DbError::from)? == 0 {
+        return Ok(Vec::new());
+    }
+    let embeddings = embed_code(&[query.to_string()], true)?;
+    let query_vec = embeddings.into_iter().next().ok_or_else(|| {
+        SearchError::OperationFailed("Failed to generate code embedding for query".to_string())
+    })?;
+
+    search_chunked_table(&table, query_vec, limit, min_score, current_settings().distance_metric, dedup_key, filter).await
+}
+
+/// Nearest-neighbour search over a chunked text table, keeping the best chunk per `dedup_key`.
+/// `filter` is a SQL predicate rows must match before they are ranked.
+async fn search_chunked_table(
+    table: &Table,
+    query_vec: Vec<f32>,
+    limit: usize,
+    min_score: f32,
+    metric: DistanceMetric,
+    dedup_key: DedupKey,
+    filter: Option<&str>,
+) -> Result<Vec<SearchResult>, SearchError> {
     // Include all necessary columns
-    let vector_query = table
+    let mut vector_query = table
         .query()
         .nearest_to(query_vec)
         .map_err(|e| DbError::from(e))?
+        .distance_type(distance_type(metric))
         .select(Select::columns(&[
             "file_path",
             "content_hash",
             "chunk_id",
             "last_modified",
+            "index_root",
         ]));
+    if let Some(filter) = filter {
+        vector_query = vector_query.only_if(filter);
+    }
 
     let query_result = vector_query
         .limit(limit)
@@ -276,7 +1008,7 @@ async fn search_text_content(
         .await
         .map_err(|e| SearchError::OperationFailed(e.to_string()))?;
 
-    // A map to track the best result for each file path
+    // A map to track the best result for each dedup key
     let mut best_results: std::collections::HashMap<String, SearchResult> =
         std::collections::HashMap::new();
 
@@ -301,6 +1033,13 @@ async fn search_text_content(
             .ok_or_else(|| {
                 SearchError::OperationFailed("Missing last_modified column".to_string())
             })?;
+        let index_roots = batch
+            .column_by_name("index_root")
+            .and_then(|array| array.as_any().downcast_ref::<StringArray>());
+        let chunk_ids = batch
+            .column_by_name("chunk_id")
+            .and_then(|array| array.as_any().downcast_ref::<Int32Array>())
+            .ok_or_else(|| SearchError::OperationFailed("Missing chunk_id column".to_string()))?;
 
         // The distance column name might vary by LanceDB version, try both common names
         let distances = batch
@@ -313,7 +1052,7 @@ async fn search_text_content(
         for i in 0..batch.num_rows() {
             // Convert distance to score (0-1 scale, higher is better)
             let distance = distances.value(i);
-            let score = 1.0 - (distance / 2.0);
+            let score = metric.score(distance);
 
             // Skip results below threshold
             if score < min_score {
@@ -323,6 +1062,8 @@ async fn search_text_content(
             let file_path = files.value(i).to_string();
             let content_hash = content_hashes.value(i).to_string();
             let last_modified = last_modified.value(i);
+            let chunk_id = chunk_ids.value(i);
+            let key = dedup_key.key(&file_path, chunk_id, &content_hash);
 
             let result = SearchResult {
                 file_path: file_path.clone(),
@@ -331,15 +1072,20 @@ async fn search_text_content(
                 last_modified,
                 content_type: ContentType::Text,
                 image_data: None,
+                icon_key: file_type_info(Path::new(&file_path), false).icon_key,
+                duplicate_paths: Vec::new(),
+                display_score: None,
+                index_root: index_roots.filter(|a| a.is_valid(i)).map(|a| a.value(i).to_string()),
+                chunk_id: Some(chunk_id),
             };
 
-            // Keep only the highest scoring chunk for each file
-            if let Some(existing) = best_results.get(&file_path) {
+            // Keep only the highest scoring chunk for each key
+            if let Some(existing) = best_results.get(&key) {
                 if score > existing.score {
-                    best_results.insert(file_path, result);
+                    best_results.insert(key, result);
                 }
             } else {
-                best_results.insert(file_path, result);
+                best_results.insert(key, result);
             }
         }
     }
@@ -349,12 +1095,14 @@ async fn search_text_content(
     Ok(search_results)
 }
 
-/// Search for image content using the given query
+/// Search for image content using the given query. Scores are normalized
+/// against [`IMAGE_SCORE_CEILING`] so they compare with text scores, and
+/// capped at 1.0 for the rare match above it.
 async fn search_image_content(
     table: &Table,
     query: &str,
     limit: usize,
-    _min_score: f32,
+    filter: Option<&str>,
 ) -> Result<Vec<SearchResult>, SearchError> {
     // Generate embedding for the query text to search image embeddings
     // We use the special text-to-image embedding function to ensure compatibility
@@ -364,12 +1112,29 @@ async fn search_image_content(
         SearchError::ImageEmbeddingError(e)
     })?;
 
+    let mut results = search_image_table(table, embedding, limit, current_settings().distance_metric, filter).await?;
+    for result in results.iter_mut() {
+        result.score = (result.score / IMAGE_SCORE_CEILING).min(1.0);
+    }
+    Ok(results)
+}
+
+/// Nearest-neighbour search over the image table. Scores are the metric's
+/// raw similarity; only rows failing the optional `filter` predicate are left out.
+async fn search_image_table(
+    table: &Table,
+    embedding: Vec<f32>,
+    limit: usize,
+    metric: DistanceMetric,
+    filter: Option<&str>,
+) -> Result<Vec<SearchResult>, SearchError> {
     // Use the query() method with vector similarity
     // Include all necessary columns and use column configuration to specify the vector column
-    let vector_query = table
+    let mut vector_query = table
         .query()
         .nearest_to(embedding)
         .map_err(|e| DbError::from(e))?
+        .distance_type(distance_type(metric))
         .select(Select::columns(&[
             "file_path",
             "file_hash",
@@ -377,7 +1142,11 @@ async fn search_image_content(
             "width",
             "height",
             "thumbnail_path",
+            "index_root",
         ]));
+    if let Some(filter) = filter {
+        vector_query = vector_query.only_if(filter);
+    }
     let query_result = vector_query
         .limit(limit)
         .execute()
@@ -425,6 +1194,10 @@ async fn search_image_content(
             .column_by_name("thumbnail_path")
             .and_then(|array| array.as_any().downcast_ref::<StringArray>());
 
+        let index_roots = batch
+            .column_by_name("index_root")
+            .and_then(|array| array.as_any().downcast_ref::<StringArray>());
+
         // The distance column name might vary by LanceDB version, try both common names
         let distances = batch
             .column_by_name("distance")
@@ -436,12 +1209,7 @@ async fn search_image_content(
         for i in 0..batch.num_rows() {
             // Convert distance to score (0-1 scale, higher is better)
             let distance = distances.value(i);
-            println!("distances: {:?}", distances);
-            let score = 1.0 - (distance / 2.0);
-            let score = score * 10.0;
-            if score < 0.5 {
-                continue;
-            }
+            let score = metric.score(distance);
             let file_path = files.value(i).to_string();
             let file_hash = file_hashes.value(i).to_string();
             let last_modified = last_modified.value(i);
@@ -488,6 +1256,11 @@ async fn search_image_content(
                 last_modified,
                 content_type: ContentType::Image,
                 image_data,
+                icon_key: file_type_info(Path::new(&file_path), false).icon_key,
+                duplicate_paths: Vec::new(),
+                display_score: None,
+                index_root: index_roots.filter(|a| a.is_valid(i)).map(|a| a.value(i).to_string()),
+                chunk_id: None,
             };
 
             // Keep only the highest scoring result for each file
@@ -506,24 +1279,558 @@ async fn search_image_content(
     Ok(search_results)
 }
 
-// For backward compatibility
-pub async fn semantic_search(
-    query: &str,
-    limit: Option<usize>,
-    min_score: Option<f32>,
+/// Every image whose `column` is set, scored by `score` from the row's file
+/// path and `column` value; rows it returns `None` for are left out. These
+/// columns are tiny strings, so scanning them all is cheap next to a vector search.
+async fn scan_image_column(
+    table: &Table,
+    column: &str,
+    score: impl Fn(&str, &str) -> Option<f32>,
 ) -> Result<Vec<SearchResult>, SearchError> {
-    let result_limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
-    let score_threshold = min_score.unwrap_or(DEFAULT_MIN_SCORE);
-    let results = search_text_content(query, result_limit, score_threshold).await?;
-
-    Ok(results)
-}
-
-#[cfg(test)]
+    let batches = table
+        .query()
+        .only_if(format!("{} IS NOT NULL", column))
+        .select(Select::columns(&[
+            "file_path",
+            "file_hash",
+            "last_modified",
+            "width",
+            "height",
+            "thumbnail_path",
+            column,
+            "index_root",
+        ]))
+        .execute()
+        .await
+        .map_err(|e| DbError::from(e))?
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(|e| SearchError::OperationFailed(e.to_string()))?;
+
+    let mut results = Vec::new();
+    for batch in batches {
+        let string_column = |name: &str| batch.column_by_name(name).and_then(|a| a.as_any().downcast_ref::<StringArray>());
+        let int_column = |name: &str| batch.column_by_name(name).and_then(|a| a.as_any().downcast_ref::<arrow_array::Int32Array>());
+        let (Some(files), Some(file_hashes), Some(values), Some(last_modified)) = (
+            string_column("file_path"),
+            string_column("file_hash"),
+            string_column(column),
+            batch
+                .column_by_name("last_modified")
+                .and_then(|a| a.as_any().downcast_ref::<TimestampSecondArray>()),
+        ) else {
+            return Err(SearchError::OperationFailed("Missing image columns".to_string()));
+        };
+        let (widths, heights, thumbnail_paths) = (int_column("width"), int_column("height"), string_column("thumbnail_path"));
+        let index_roots = string_column("index_root");
+
+        for i in 0..batch.num_rows() {
+            if !values.is_valid(i) {
+                continue;
+            }
+            let Some(score) = score(files.value(i), values.value(i)) else {
+                continue;
+            };
+            let file_path = files.value(i).to_string();
+            results.push(SearchResult {
+                score,
+                content_hash: file_hashes.value(i).to_string(),
+                last_modified: last_modified.value(i),
+                content_type: ContentType::Image,
+                image_data: Some(ImageData {
+                    width: widths.filter(|a| a.is_valid(i)).map(|a| a.value(i)),
+                    height: heights.filter(|a| a.is_valid(i)).map(|a| a.value(i)),
+                    thumbnail_path: thumbnail_paths.filter(|a| a.is_valid(i)).map(|a| a.value(i).to_string()),
+                }),
+                icon_key: file_type_info(Path::new(&file_path), false).icon_key,
+                duplicate_paths: Vec::new(),
+                display_score: None,
+                index_root: index_roots.filter(|a| a.is_valid(i)).map(|a| a.value(i).to_string()),
+                chunk_id: None,
+                file_path,
+            });
+        }
+    }
+    Ok(results)
+}
+
+/// Finds images whose dominant color is closest to `rgb`, scored by color
+/// similarity from 0 to 1. With a reference image, only images visually similar
+/// to it are considered, ranked by color and visual similarity equally.
+/// Images indexed before colors were stored are never returned.
+pub async fn search_images_by_color(
+    conn: &Connection,
+    rgb: [u8; 3],
+    limit: usize,
+    reference_image: Option<&str>,
+) -> Result<Vec<SearchResult>, SearchError> {
+    let table = open_or_create_image_table(conn).await?;
+    let mut results = scan_image_column(&table, "dominant_color", |_, hex| {
+        parse_hex_color(hex).map(|color| color_similarity(rgb, color))
+    })
+    .await?;
+
+    if let Some(reference_image) = reference_image {
+        let embedding = embed_image(reference_image)?;
+        let visual = search_image_table(&table, embedding, limit * 4, current_settings().distance_metric, None).await?;
+        let visual_scores: HashMap<String, f32> = visual
+            .into_iter()
+            .map(|r| (r.file_path, r.score.clamp(0.0, 1.0)))
+            .collect();
+        results.retain_mut(|r| match visual_scores.get(&r.file_path) {
+            Some(visual_score) => {
+                r.score = 0.5 * r.score + 0.5 * visual_score;
+                true
+            }
+            None => false,
+        });
+    }
+
+    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    results.truncate(limit);
+    Ok(results)
+}
+
+/// The perceptual hash stored for `file_path`, if it is indexed with one
+async fn stored_phash(table: &Table, file_path: &str) -> Result<Option<u64>, SearchError> {
+    let predicate = format!("file_path = {} AND phash IS NOT NULL", sql_string_literal(file_path));
+    let batches = table
+        .query()
+        .only_if(predicate)
+        .select(Select::columns(&["phash"]))
+        .limit(1)
+        .execute()
+        .await
+        .map_err(|e| DbError::from(e))?
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(|e| SearchError::OperationFailed(e.to_string()))?;
+    Ok(batches.iter().find_map(|batch| {
+        let hashes = batch.column_by_name("phash")?.as_any().downcast_ref::<StringArray>()?;
+        (!hashes.is_empty()).then(|| parse_hex_hash(hashes.value(0))).flatten()
+    }))
+}
+
+/// Indexed images that look like the one at `path` even when resized or
+/// re-encoded: those whose perceptual hash is at most `max_distance` bits from
+/// its hash. Scored from 1 (same hash) down, by the share of bits that match.
+/// The image's stored hash is used when it is indexed; otherwise it is hashed
+/// from disk. Images indexed before hashes were stored are never returned.
+pub async fn find_similar_images_by_phash(
+    conn: &Connection,
+    path: &str,
+    max_distance: u32,
+) -> Result<Vec<SearchResult>, SearchError> {
+    let table = open_or_create_image_table(conn).await?;
+    let reference = stored_phash(&table, path)
+        .await?
+        .or_else(|| perceptual_hash(Path::new(path)))
+        .ok_or_else(|| SearchError::OperationFailed(format!("Cannot read image {}", path)))?;
+
+    let mut results = scan_image_column(&table, "phash", |file_path, hex| {
+        let distance = hamming_distance(reference, parse_hex_hash(hex)?);
+        (file_path != path && distance <= max_distance).then(|| 1.0 - distance as f32 / PHASH_BITS as f32)
+    })
+    .await?;
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+    Ok(results)
+}
+
+/// Averages the stored chunk embeddings of `file_path` into one unit-length
+/// vector, or returns `None` when the table doesn't hold the file.
+async fn stored_embedding(table: &Table, file_path: &str) -> Result<Option<Vec<f32>>, SearchError> {
+    let predicate = format!("file_path = {}", sql_string_literal(file_path));
+    let batches = table
+        .query()
+        .only_if(predicate)
+        .select(Select::columns(&["embedding"]))
+        .execute()
+        .await
+        .map_err(|e| DbError::from(e))?
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(|e| SearchError::OperationFailed(e.to_string()))?;
+
+    let mut sum: Vec<f32> = Vec::new();
+    let mut count = 0usize;
+    for batch in batches {
+        let Some(embeddings) = batch
+            .column_by_name("embedding")
+            .and_then(|array| array.as_any().downcast_ref::<FixedSizeListArray>())
+        else {
+            continue;
+        };
+        for i in 0..embeddings.len() {
+            if embeddings.is_null(i) {
+                continue;
+            }
+            let chunk = embeddings.value(i);
+            let Some(values) = chunk.as_any().downcast_ref::<Float32Array>() else {
+                continue;
+            };
+            if sum.is_empty() {
+                sum = vec![0.0; values.len()];
+            }
+            for (acc, value) in sum.iter_mut().zip(values.values().iter()) {
+                *acc += value;
+            }
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        return Ok(None);
+    }
+    let norm = sum.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        sum.iter_mut().for_each(|v| *v /= norm);
+    }
+    Ok(Some(sum))
+}
+
+/// Average of `vectors` scaled to unit length, or `None` when there are none
+fn mean_unit_vector(vectors: &[Vec<f32>]) -> Option<Vec<f32>> {
+    let first = vectors.first()?;
+    let mut sum = vec![0.0f32; first.len()];
+    for vector in vectors {
+        for (acc, value) in sum.iter_mut().zip(vector) {
+            *acc += value;
+        }
+    }
+    let norm = l2_norm(&sum);
+    if norm > 0.0 {
+        sum.iter_mut().for_each(|v| *v /= norm);
+    }
+    Some(sum)
+}
+
+/// One vector for a whole file and the table whose model produced it
+struct FileEmbedding {
+    table: Table,
+    vector: Vec<f32>,
+    /// Read from the index rather than embedded just now
+    stored: bool,
+}
+
+/// Finds the vector for `file_path`, averaging its chunks. Uses the vectors
+/// already stored for the file so indexed files are not re-embedded; files
+/// missing from the index are embedded on the fly with the same model the
+/// indexer would pick.
+async fn file_embedding(conn: &Connection, file_path: &str) -> Result<FileEmbedding, SearchError> {
+    let path = Path::new(file_path);
+    if get_content_type(path) == ContentType::Image {
+        let table = open_or_create_image_table(conn).await?;
+        return match stored_embedding(&table, file_path).await? {
+            Some(vector) => Ok(FileEmbedding { table, vector, stored: true }),
+            None => {
+                debug!("{} is not indexed, embedding image on the fly", file_path);
+                let vector = embed_image(file_path)?;
+                Ok(FileEmbedding { table, vector, stored: false })
+            }
+        };
+    }
+
+    let text_table = open_or_create_text_table(conn).await?;
+    let amharic_table = open_or_create_amharic_text_table(conn).await?;
+    let code_table = open_or_create_code_table(conn).await?;
+    for table in [&text_table, &amharic_table, &code_table] {
+        if let Some(vector) = stored_embedding(table, file_path).await? {
+            return Ok(FileEmbedding { table: table.clone(), vector, stored: true });
+        }
+    }
+
+    debug!("{} is not indexed, embedding content on the fly", file_path);
+    let extraction = extract_text(path).map_err(|e| SearchError::OperationFailed(e.to_string()))?;
+    let content = vec![extraction.text];
+    let (table, embeddings) = match categorize_file(&path.to_path_buf()) {
+        FileCategory::Code => (code_table, embed_code(&content, false)?),
+        _ if extraction.language == DetectedLanguage::Amharic => {
+            (amharic_table, embed_text(&content, &extraction.language, false)?)
+        }
+        _ => (text_table, embed_text(&content, &extraction.language, false)?),
+    };
+    let vector = mean_unit_vector(&embeddings)
+        .ok_or_else(|| SearchError::OperationFailed(format!("No embedding generated for {}", file_path)))?;
+    Ok(FileEmbedding { table, vector, stored: false })
+}
+
+/// Finds the files closest to `file_path` in embedding space.
+///
+/// Uses the vectors already stored for the file, so indexed files are not
+/// re-embedded. Files missing from the index are embedded on the fly with the
+/// same model the indexer would pick. The file itself is never returned.
+pub async fn related_files(
+    conn: &Connection,
+    file_path: &str,
+    limit: usize,
+) -> Result<Vec<SearchResult>, SearchError> {
+    // A file's own chunks come back first, so ask for extra rows
+    let fetch_limit = (limit + 1) * 4;
+    let metric = current_settings().distance_metric;
+
+    let FileEmbedding { table, vector, .. } = file_embedding(conn, file_path).await?;
+    let mut results = if get_content_type(Path::new(file_path)) == ContentType::Image {
+        search_image_table(&table, vector, fetch_limit, metric, None).await?
+    } else {
+        search_chunked_table(&table, vector, fetch_limit, f32::MIN, metric, DedupKey::FilePath, None).await?
+    };
+
+    results.retain(|result| result.file_path != file_path);
+    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    results.truncate(limit);
+    Ok(results)
+}
+
+/// Keywords returned with a file comparison
+const SHARED_KEYWORD_LIMIT: usize = 10;
+
+/// Words too common to say anything about what two documents share
+const KEYWORD_STOPWORDS: &[&str] = &[
+    "about", "after", "also", "and", "are", "been", "before", "but", "can", "could", "for", "from", "had", "has",
+    "have", "her", "his", "into", "its", "more", "not", "one", "only", "other", "our", "out", "over", "she",
+    "should", "some", "such", "than", "that", "the", "their", "them", "then", "there", "these", "they", "this",
+    "those", "was", "were", "what", "when", "which", "while", "who", "will", "with", "would", "you", "your",
+];
+
+/// How often each candidate keyword occurs in `text`: lowercased words of at
+/// least three letters, minus stopwords
+pub(crate) fn keyword_counts(text: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let word = word.to_lowercase();
+        if word.chars().count() < 3 || word.chars().all(|c| c.is_numeric()) || KEYWORD_STOPWORDS.contains(&word.as_str()) {
+            continue;
+        }
+        *counts.entry(word).or_default() += 1;
+    }
+    counts
+}
+
+/// Words both texts use, most shared first. A word's weight is how often the
+/// text using it less often uses it.
+pub fn shared_keywords(a: &str, b: &str, limit: usize) -> Vec<String> {
+    let counts_b = keyword_counts(b);
+    let mut shared: Vec<(String, usize)> = keyword_counts(a)
+        .into_iter()
+        .filter_map(|(word, count)| counts_b.get(&word).map(|other| (word, count.min(*other))))
+        .collect();
+    shared.sort_by(|x, y| y.1.cmp(&x.1).then_with(|| x.0.cmp(&y.0)));
+    shared.into_iter().take(limit).map(|(word, _)| word).collect()
+}
+
+/// How related two files are
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSimilarity {
+    /// Cosine similarity of the files' averaged embeddings, from -1 to 1
+    pub similarity: f32,
+    /// Both vectors came from the index; nothing had to be embedded
+    pub from_index: bool,
+    /// Words both files use, when requested and both files have text
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shared_keywords: Vec<String>,
+}
+
+/// Compares two files by the cosine similarity of their embeddings, averaged
+/// over chunks. Both must be embedded by the same model: two text files, two
+/// source files or two images.
+pub async fn file_similarity(
+    conn: &Connection,
+    path_a: &str,
+    path_b: &str,
+    include_keywords: bool,
+) -> Result<FileSimilarity, SearchError> {
+    let a = file_embedding(conn, path_a).await?;
+    let b = file_embedding(conn, path_b).await?;
+    if a.table.name() != b.table.name() {
+        return Err(SearchError::OperationFailed(format!(
+            "{} and {} are embedded by different models ({} and {}) and can't be compared",
+            path_a,
+            path_b,
+            a.table.name(),
+            b.table.name()
+        )));
+    }
+    let similarity = cosine_similarity(&a.vector, &b.vector).ok_or_else(|| {
+        SearchError::OperationFailed(format!("Embeddings of {} and {} can't be compared", path_a, path_b))
+    })?;
+
+    let mut shared = Vec::new();
+    if include_keywords && a.table.name() != IMAGE_TABLE_NAME {
+        match (extract_text(Path::new(path_a)), extract_text(Path::new(path_b))) {
+            (Ok(text_a), Ok(text_b)) => shared = shared_keywords(&text_a.text, &text_b.text, SHARED_KEYWORD_LIMIT),
+            (Err(e), _) | (_, Err(e)) => warn!("Skipping shared keywords: {}", e),
+        }
+    }
+    Ok(FileSimilarity {
+        similarity,
+        from_index: a.stored && b.stored,
+        shared_keywords: shared,
+    })
+}
+
+/// Rank offset from the original reciprocal rank fusion paper; dampens the
+/// advantage of the very top ranks so agreement between lists matters more
+const RRF_K: f32 = 60.0;
+
+/// Fuses several ranked result lists with weighted reciprocal rank fusion.
+///
+/// Each file scores `sum(weight / (RRF_K + rank))` over the lists it appears
+/// in, so files found by several phrasings rise to the top. The returned
+/// results are sorted best first and carry the fused score divided by its
+/// best possible value, so 1.0 means first in every list.
+pub fn reciprocal_rank_fusion(ranked_lists: Vec<Vec<SearchResult>>, weights: &[f32]) -> Vec<SearchResult> {
+    let best_possible: f32 = weights.iter().take(ranked_lists.len()).map(|weight| weight / (RRF_K + 1.0)).sum();
+    let mut fused: HashMap<String, SearchResult> = HashMap::new();
+    for (mut list, weight) in ranked_lists.into_iter().zip(weights.iter().copied()) {
+        list.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        for (rank, result) in list.into_iter().enumerate() {
+            let contribution = weight / (RRF_K + rank as f32 + 1.0);
+            fused
+                .entry(result.file_path.clone())
+                .and_modify(|existing| existing.score += contribution)
+                .or_insert(SearchResult { score: contribution, ..result });
+        }
+    }
+    let mut results: Vec<SearchResult> = fused.into_values().collect();
+    if best_possible > 0.0 {
+        for result in &mut results {
+            result.score /= best_possible;
+        }
+    }
+    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    results
+}
+
+/// Runs a text search per query and fuses the ranked lists.
+///
+/// `weights` defaults to 1.0 per query and must match `queries` in length.
+pub async fn multi_query_search(
+    conn: &Connection,
+    queries: &[String],
+    weights: Option<&[f32]>,
+    limit: usize,
+    min_score: f32,
+) -> Result<Vec<SearchResult>, SearchError> {
+    if queries.is_empty() || queries.iter().any(|q| q.trim().is_empty()) {
+        return Err(SearchError::EmptyQuery);
+    }
+    let weights = match weights {
+        Some(weights) if weights.len() != queries.len() => {
+            return Err(SearchError::OperationFailed(format!(
+                "Got {} weights for {} queries",
+                weights.len(),
+                queries.len()
+            )));
+        }
+        Some(weights) => weights.to_vec(),
+        None => vec![1.0; queries.len()],
+    };
+
+    let mut ranked_lists = Vec::with_capacity(queries.len());
+    for query in queries {
+        ranked_lists.push(search_text_content(conn, query, limit, min_score, DedupKey::FilePath).await?);
+    }
+
+    let mut results = reciprocal_rank_fusion(ranked_lists, &weights);
+    results.truncate(limit);
+    info!("Multi-query search over {} queries returned {} results", queries.len(), results.len());
+    Ok(results)
+}
+
+/// Most chunks of a pasted passage searched individually before fusing
+const MAX_PASSAGE_CHUNKS: usize = 8;
+
+/// `text` cut after its first `max_chunks` chunks, so nothing past them is embedded
+fn leading_chunks(text: &str, max_chunks: usize) -> Result<&str, SearchError> {
+    let chunks = chunk_text(text).map_err(|e| SearchError::OperationFailed(e.to_string()))?;
+    if chunks.len() <= max_chunks {
+        return Ok(text);
+    }
+    // Chunks are trimmed slices of the text, in order
+    let mut end = 0;
+    for chunk in &chunks[..max_chunks] {
+        match text[end..].find(chunk.as_str()) {
+            Some(offset) => end += offset + chunk.len(),
+            None => break,
+        }
+    }
+    Ok(&text[..end])
+}
+
+/// Finds documents similar to an arbitrary passage of text.
+///
+/// Unlike a query, the passage is embedded as a document (chunked, without the
+/// query prefix). Each chunk is searched on its own and the ranked lists are
+/// fused, so documents matching several parts of the passage rank highest.
+pub async fn search_with_text(
+    conn: &Connection,
+    text: &str,
+    limit: usize,
+    min_score: f32,
+) -> Result<Vec<SearchResult>, SearchError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(SearchError::EmptyQuery);
+    }
+
+    let language = match detect(text) {
+        Some(info) if info.lang() == Lang::Amh => DetectedLanguage::Amharic,
+        Some(info) if info.lang() == Lang::Eng => DetectedLanguage::English,
+        _ => DetectedLanguage::Other,
+    };
+    let passage = leading_chunks(text, MAX_PASSAGE_CHUNKS)?;
+    let mut chunk_embeddings = embed_text(&[passage.to_string()], &language, false)?;
+    if chunk_embeddings.is_empty() {
+        return Err(SearchError::OperationFailed(
+            "Failed to generate embeddings for the passage".to_string(),
+        ));
+    }
+    chunk_embeddings.truncate(MAX_PASSAGE_CHUNKS);
+
+    let table = if language == DetectedLanguage::Amharic {
+        open_or_create_amharic_text_table(conn).await?
+    } else {
+        open_or_create_text_table(conn).await?
+    };
+
+    let metric = current_settings().distance_metric;
+    let mut ranked_lists = Vec::with_capacity(chunk_embeddings.len());
+    for embedding in chunk_embeddings {
+        ranked_lists.push(search_chunked_table(&table, embedding, limit, min_score, metric, DedupKey::FilePath, None).await?);
+    }
+    let weights = vec![1.0; ranked_lists.len()];
+    let mut results = reciprocal_rank_fusion(ranked_lists, &weights);
+    results.truncate(limit);
+    info!("Passage search ({} chars) returned {} results", text.len(), results.len());
+    Ok(results)
+}
+
+// For backward compatibility
+pub async fn semantic_search(
+    query: &str,
+    limit: Option<usize>,
+    min_score: Option<f32>,
+) -> Result<Vec<SearchResult>, SearchError> {
+    let result_limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let score_threshold = min_score.unwrap_or(DEFAULT_MIN_SCORE);
+    let conn = connect_db().await?;
+    let results = search_text_content(&conn, query, result_limit, score_threshold, DedupKey::FilePath).await?;
+
+    Ok(results)
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
     use crate::db::TestDb;
-    use crate::db::{connect_db_with_path, upsert_document, upsert_image};
+    use crate::db::{connect_db_with_path, upsert_document, upsert_image, TEXT_TABLE_NAME};
+    use arrow_array::{RecordBatch, RecordBatchIterator};
+    use arrow_schema::{DataType, Field, Schema};
 
     // Setup test database with both text and image data
     async fn setup_test_multimodal_db() -> (Connection, TestDb) {
@@ -565,27 +1872,31 @@ mod tests {
             // Upsert the document - wrap the embedding in a Vec for chunking compatibility
             let file_path = format!("/test/{}", path);
             let content_hash = format!("hash_{}", path);
-            upsert_document(&text_table, &file_path, &content_hash, &[embedding])
+            upsert_document(&text_table, &file_path, &content_hash, &[embedding], Some("/test"))
                 .await
                 .unwrap();
         }
 
         // Add test image documents
         let images = [
-            ("photo1.jpg", "A photo of a cat playing with a toy", 1.0),
+            ("photo1.jpg", "A photo of a cat playing with a toy", 1.0, "#808080", "00000000000000ff"),
             (
                 "landscape.jpg",
                 "A beautiful mountain landscape at sunset",
                 2.0,
+                "#e07020",
+                "00000000000000fc",
             ),
             (
                 "chart.png",
                 "A data visualization chart about machine learning",
                 3.0,
+                "#2050c0",
+                "ffffffffffff0000",
             ),
         ];
 
-        for (path, _description, seed) in images.iter() {
+        for (path, _description, seed, color, phash) in images.iter() {
             // Create an embedding for image (use a simplified embedding for testing)
             let embedding: Vec<f32> = (0..IMAGE_EMBEDDING_DIM as usize)
                 .map(|i| (i as f32 / IMAGE_EMBEDDING_DIM as f32) * seed)
@@ -602,6 +1913,9 @@ mod tests {
                 Some(640),
                 Some(480),
                 Some("/thumbnails/thumb.jpg"),
+                Some(color),
+                Some(phash),
+                None,
             )
             .await
             .unwrap();
@@ -673,6 +1987,7 @@ mod tests {
                 ContentType::Text,
                 "Result should be text type"
             );
+            assert_eq!(result.index_root.as_deref(), Some("/test"));
         }
 
         // Since image embeddings work differently in tests, we'll just check that
@@ -688,4 +2003,498 @@ mod tests {
 
         assert!(image_result.is_ok(), "Image-only search should complete");
     }
+
+    /// Replaces `table_name` with a table of an unrelated layout, so searching it fails
+    async fn break_table(conn: &Connection, table_name: &str) {
+        conn.drop_table(table_name).await.unwrap();
+        let broken_schema = Arc::new(Schema::new(vec![Field::new("unrelated", DataType::Utf8, true)]));
+        let batch = RecordBatch::new_empty(broken_schema.clone());
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], broken_schema);
+        conn.create_table(table_name, Box::new(reader)).execute().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_file_similarity_uses_stored_embeddings() {
+        let (conn, _test_db) = setup_test_multimodal_db().await;
+
+        // The synthetic document vectors all point the same way
+        let result = file_similarity(&conn, "/test/test_doc1.txt", "/test/test_doc2.txt", false).await.unwrap();
+        assert!((result.similarity - 1.0).abs() < 1e-4, "Got {}", result.similarity);
+        assert!(result.from_index, "Indexed files should not be re-embedded");
+
+        let mixed = file_similarity(&conn, "/test/test_doc1.txt", "/test/photo1.jpg", false).await;
+        assert!(mixed.err().unwrap().to_string().contains("different models"));
+    }
+
+    #[test]
+    fn test_shared_keywords_ranks_common_words() {
+        let a = "Budget review: the budget covers hiring and the hiring plan for Q3. Budget approved.";
+        let b = "Hiring plan draft. The budget is tight, so hiring waits for the budget review in 2024.";
+        assert_eq!(shared_keywords(a, b, 3), vec!["budget", "hiring", "plan"]);
+        assert!(shared_keywords("alpha beta", "gamma delta", 5).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_text_table_error_keeps_image_results() {
+        let (conn, _test_db) = setup_test_multimodal_db().await;
+
+        break_table(&conn, TEXT_TABLE_NAME).await;
+
+        // An image whose vector is exactly the query's, so it scores as high as possible
+        let query_embedding = embed_text_for_image_search("mountain landscape").unwrap();
+        let image_table = open_or_create_image_table(&conn).await.unwrap();
+        upsert_image(&image_table, "/test/mountains.jpg", "hash_mountains", &query_embedding, None, None, None, None, None, None)
+            .await
+            .unwrap();
+
+        let options = SearchOptions {
+            min_score: Some(-1000.0),
+            ..Default::default()
+        };
+        let outcome = multimodal_search_outcome(&conn, "mountain landscape", &options)
+            .await
+            .expect("A text table error should not fail the search");
+        assert!(outcome.text_search_failed);
+        assert!(
+            outcome.results.iter().any(|r| r.file_path == "/test/mountains.jpg"),
+            "Image results should still be returned"
+        );
+        assert!(outcome.results.iter().all(|r| r.content_type == ContentType::Image));
+    }
+
+    #[test]
+    fn test_favorite_boost_only_touches_favorites() {
+        let mut results = vec![
+            make_result("/plain.txt", 0.8),
+            make_result("/starred.txt", 0.6),
+            make_result("/projects/notes.txt", 0.5),
+            make_result("/projects-old/notes.txt", 0.5),
+            make_result("/projects/deep/report.txt", 0.9),
+        ];
+        let favorites: HashSet<String> = ["/starred.txt", "/projects"].into_iter().map(String::from).collect();
+        apply_favorite_boost(&mut results, &favorites, 1.5);
+        assert_eq!(results[0].score, 0.8);
+        assert!((results[1].score - 0.9).abs() < 1e-6);
+        // Files in a favorite folder count, but not a folder that shares its prefix
+        assert!((results[2].score - 0.75).abs() < 1e-6);
+        assert_eq!(results[3].score, 0.5);
+        // A boost never pushes a score past 1
+        assert_eq!(results[4].score, 1.0);
+    }
+
+    #[test]
+    fn test_tables_to_search_follow_content_type() {
+        use SearchTable::*;
+        let tables = |content_type, include_amharic, amharic_query| {
+            tables_to_search(content_type, include_amharic, amharic_query).into_iter().collect::<Vec<_>>()
+        };
+        assert_eq!(tables(SearchContentType::All, None, false), vec![Text, Code, Image]);
+        assert_eq!(tables(SearchContentType::All, None, true), vec![Amharic, Code, Image]);
+        assert_eq!(tables(SearchContentType::TextOnly, Some(true), false), vec![Text, Amharic, Code]);
+        assert_eq!(tables(SearchContentType::TextOnly, Some(false), true), vec![Text, Code]);
+        assert_eq!(tables(SearchContentType::ImageOnly, Some(true), false), vec![Image]);
+    }
+
+    #[tokio::test]
+    async fn test_image_only_search_skips_text_tables() {
+        let (conn, _test_db) = setup_test_multimodal_db().await;
+        break_table(&conn, TEXT_TABLE_NAME).await;
+
+        let options = SearchOptions {
+            min_score: Some(-1000.0),
+            content_type: Some(SearchContentType::ImageOnly),
+            ..Default::default()
+        };
+        let outcome = multimodal_search_outcome(&conn, "mountain landscape", &options)
+            .await
+            .expect("Image-only search should not touch the text table");
+        assert!(!outcome.text_search_failed);
+        assert!(outcome.results.iter().all(|r| r.content_type == ContentType::Image));
+    }
+
+    #[tokio::test]
+    async fn test_text_only_search_skips_image_table() {
+        let (conn, _test_db) = setup_test_multimodal_db().await;
+        break_table(&conn, IMAGE_TABLE_NAME).await;
+
+        let options = SearchOptions {
+            min_score: Some(-1000.0),
+            content_type: Some(SearchContentType::TextOnly),
+            ..Default::default()
+        };
+        let outcome = multimodal_search_outcome(&conn, "test document", &options)
+            .await
+            .expect("Text-only search should not open the image table");
+        assert!(!outcome.text_search_failed);
+        assert!(outcome.results.iter().all(|r| r.content_type == ContentType::Text));
+    }
+
+    #[tokio::test]
+    async fn test_search_images_by_color_ranks_closest_color_first() {
+        let (conn, _test_db) = setup_test_multimodal_db().await;
+
+        let results = search_images_by_color(&conn, [230, 110, 30], 2, None)
+            .await
+            .expect("Color search failed");
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].file_path, "/test/landscape.jpg");
+        assert!(results[0].score > results[1].score);
+        assert_eq!(results[0].image_data.as_ref().and_then(|d| d.width), Some(640));
+    }
+
+    #[tokio::test]
+    async fn test_phash_search_finds_near_duplicates_only() {
+        let (conn, _test_db) = setup_test_multimodal_db().await;
+
+        let results = find_similar_images_by_phash(&conn, "/test/photo1.jpg", 10).await.expect("Hash search failed");
+        let paths: Vec<&str> = results.iter().map(|r| r.file_path.as_str()).collect();
+        assert_eq!(paths, vec!["/test/landscape.jpg"]);
+        assert_eq!(results[0].score, 1.0 - 2.0 / 64.0);
+
+        let results = find_similar_images_by_phash(&conn, "/test/photo1.jpg", 64).await.unwrap();
+        assert_eq!(results.len(), 2, "The image itself is never its own near-duplicate");
+    }
+
+    fn make_result(file_path: &str, score: f32) -> SearchResult {
+        SearchResult {
+            file_path: file_path.to_string(),
+            score,
+            content_hash: format!("hash_{}", file_path),
+            last_modified: 0,
+            content_type: ContentType::Text,
+            image_data: None,
+            icon_key: "text".to_string(),
+            duplicate_paths: Vec::new(),
+            display_score: None,
+            index_root: None,
+            chunk_id: None,
+        }
+    }
+
+    #[test]
+    fn test_collapse_duplicate_results_keeps_best_copy() {
+        let mut copy = make_result("/projects/report.pdf", 0.7);
+        copy.content_hash = "shared".to_string();
+        let mut original = make_result("/downloads/report.pdf", 0.9);
+        original.content_hash = "shared".to_string();
+        let other = make_result("/docs/notes.txt", 0.8);
+
+        let collapsed = collapse_duplicate_results(vec![copy, other, original]);
+        assert_eq!(collapsed.len(), 2);
+        assert_eq!(collapsed[0].file_path, "/downloads/report.pdf");
+        assert_eq!(collapsed[0].score, 0.9);
+        assert_eq!(collapsed[0].duplicate_paths, vec!["/projects/report.pdf".to_string()]);
+        assert!(collapsed[1].duplicate_paths.is_empty());
+    }
+
+    #[test]
+    fn test_score_scales_preserve_order() {
+        assert_eq!(ScoreScale::Unit.display(1.2), 1.0);
+        assert_eq!(ScoreScale::Percent.display(0.874), 87.0);
+        assert_eq!(ScoreScale::Stars.display(0.87), 4.5);
+        assert_eq!(ScoreScale::Stars.display(-0.3), 0.0);
+
+        for scale in [ScoreScale::Unit, ScoreScale::Percent, ScoreScale::Stars] {
+            let shown: Vec<f32> = (0..=100).map(|i| scale.display(i as f32 / 100.0)).collect();
+            assert!(shown.windows(2).all(|pair| pair[0] <= pair[1]), "{:?} is not monotonic", scale);
+        }
+    }
+
+    #[test]
+    fn test_aggregate_results_by_folder() {
+        let results = vec![
+            make_result("/work/alpha/src/a.rs", 0.6),
+            make_result("/work/beta/notes.txt", 0.7),
+            make_result("/work/alpha/docs/b.md", 0.5),
+        ];
+
+        let parents = aggregate_results_by_folder(&results, 1);
+        assert_eq!(parents.len(), 3);
+        assert_eq!(parents[0].folder, "/work/beta");
+
+        // Two moderate matches in one project outrank a single better one
+        let projects = aggregate_results_by_folder(&results, 2);
+        assert_eq!(projects.len(), 2);
+        assert_eq!(projects[0].folder, "/work/alpha");
+        assert_eq!(projects[0].matching_files, 2);
+        assert!((projects[0].score - 0.8).abs() < 1e-6);
+        assert_eq!(projects[0].files, vec!["/work/alpha/src/a.rs", "/work/alpha/docs/b.md"]);
+    }
+
+    #[test]
+    fn test_group_results_by_folder() {
+        let results = vec![
+            make_result("/docs/work/a.txt", 0.9),
+            make_result("/docs/personal/b.txt", 0.8),
+            make_result("/docs/work/c.txt", 0.7),
+            make_result("/docs/personal/d.txt", 0.95),
+        ];
+
+        let groups = group_results_by_folder(&results);
+        assert_eq!(groups.len(), 2);
+
+        // Ordered by best score: personal (0.95) before work (0.9)
+        assert_eq!(groups[0].folder, "/docs/personal");
+        assert_eq!(groups[0].best_score, 0.95);
+        assert_eq!(groups[0].results.len(), 2);
+        assert_eq!(groups[1].folder, "/docs/work");
+        assert_eq!(groups[1].results[0].file_path, "/docs/work/a.txt");
+        assert_eq!(groups[1].results[1].file_path, "/docs/work/c.txt");
+    }
+
+    #[test]
+    fn test_search_cache_hit_expiry_and_invalidation() {
+        let mut cache = SearchCache::new(2, Duration::from_secs(60));
+        cache.insert(1, vec![make_result("/a.txt", 0.9)], None, 7);
+        assert_eq!(cache.get(1, 7).map(|(r, _)| r.len()), Some(1));
+
+        // A write to the index (new version) invalidates the entry
+        assert!(cache.get(1, 8).is_none());
+        assert!(cache.get(1, 7).is_none(), "Stale entry should have been dropped");
+
+        // Least recently used entry is evicted past capacity
+        cache.insert(1, Vec::new(), None, 7);
+        cache.insert(2, Vec::new(), None, 7);
+        cache.get(1, 7);
+        cache.insert(3, Vec::new(), None, 7);
+        assert!(cache.get(2, 7).is_none());
+        assert!(cache.get(1, 7).is_some());
+
+        let mut expired = SearchCache::new(2, Duration::ZERO);
+        expired.insert(1, Vec::new(), None, 0);
+        assert!(expired.get(1, 0).is_none());
+    }
+
+    #[test]
+    fn test_compute_facets_buckets_dates() {
+        use chrono::Utc;
+
+        // Wednesday 2024-05-15 12:00 UTC
+        let now = Utc.with_ymd_and_hms(2024, 5, 15, 12, 0, 0).unwrap();
+        let at = |y, m, d| Utc.with_ymd_and_hms(y, m, d, 8, 0, 0).unwrap().timestamp();
+        assert_eq!(date_bucket(at(2024, 5, 15), &now), DateBucket::Today);
+        assert_eq!(date_bucket(at(2024, 5, 13), &now), DateBucket::ThisWeek);
+        assert_eq!(date_bucket(at(2024, 5, 12), &now), DateBucket::ThisMonth);
+        assert_eq!(date_bucket(at(2024, 4, 30), &now), DateBucket::Older);
+
+        // Buckets follow the file's own mtime, not when it was indexed
+        let dir = tempfile::tempdir().unwrap();
+        let on_disk = |name: &str, modified: i64| {
+            let path = dir.path().join(name);
+            let file = std::fs::File::create(&path).unwrap();
+            file.set_modified(std::time::UNIX_EPOCH + Duration::from_secs(modified as u64)).unwrap();
+            let mut result = make_result(&path.to_string_lossy(), 0.8);
+            result.last_modified = now.timestamp();
+            result
+        };
+        let notes = on_disk("notes.txt", at(2024, 5, 15));
+        let report = on_disk("report.pdf", at(2024, 5, 14));
+        // Gone from disk, so the indexed time is all there is
+        let mut photo = make_result("/photos/cat.jpg", 0.7);
+        photo.content_type = ContentType::Image;
+        photo.last_modified = at(2023, 1, 1);
+
+        let facets = compute_facets(&[notes, report, photo], &now);
+        assert_eq!(facets.content_types[&ContentType::Text], 2);
+        assert_eq!(facets.content_types[&ContentType::Image], 1);
+        assert_eq!(facets.categories[&FileCategory::Document], 2);
+        assert_eq!(facets.categories[&FileCategory::Image], 1);
+        assert_eq!(facets.modified[&DateBucket::Today], 1);
+        assert_eq!(facets.modified[&DateBucket::ThisWeek], 1);
+        assert_eq!(facets.modified[&DateBucket::Older], 1);
+    }
+
+    #[test]
+    fn test_apply_score_weight() {
+        let mut results = vec![make_result("/docs/a.txt", 0.8), make_result("/docs/b.txt", 0.5)];
+
+        apply_score_weight(&mut results, 1.0);
+        assert_eq!(results[0].score, 0.8);
+
+        apply_score_weight(&mut results, 0.5);
+        assert_eq!(results[0].score, 0.4);
+        assert_eq!(results[1].score, 0.25);
+    }
+
+    #[test]
+    fn test_relaxed_min_score_falls_back_to_floor() {
+        let scores = [0.9, 0.55, 0.42, 0.1];
+
+        // Enough results clear the threshold, so it stays put
+        assert_eq!(relaxed_min_score(&scores, 0.6, 1), 0.6);
+        assert_eq!(relaxed_min_score(&scores, 0.6, 0), 0.6);
+
+        let relaxed = relaxed_min_score(&scores, 0.6, 3);
+        assert!(relaxed <= 0.42 && relaxed > 0.35, "Got {}", relaxed);
+
+        // Never below the floor, even if that leaves fewer than min_results
+        assert_eq!(relaxed_min_score(&scores, 0.6, 4), MIN_SCORE_FLOOR);
+        // A threshold already under the floor is left alone
+        assert_eq!(relaxed_min_score(&scores, 0.01, 10), 0.01);
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion_rewards_agreement() {
+        let first = vec![make_result("/docs/a.txt", 0.9), make_result("/docs/b.txt", 0.8)];
+        let second = vec![make_result("/docs/c.txt", 0.95), make_result("/docs/b.txt", 0.7)];
+
+        let fused = reciprocal_rank_fusion(vec![first.clone(), second.clone()], &[1.0, 1.0]);
+        assert_eq!(fused.len(), 3, "Results should be deduplicated by path");
+        assert_eq!(fused[0].file_path, "/docs/b.txt", "A file found by both queries ranks first");
+        assert!(fused.iter().all(|r| r.score > 0.0 && r.score <= 1.0));
+
+        // First in every list is the best possible fused score
+        let fused = reciprocal_rank_fusion(vec![vec![make_result("/docs/a.txt", 0.2)]; 2], &[1.0, 3.0]);
+        assert!((fused[0].score - 1.0).abs() < 1e-6);
+
+        // Zero weight removes a query's influence on the order
+        let fused = reciprocal_rank_fusion(vec![first, second], &[1.0, 0.0]);
+        assert_eq!(fused[0].file_path, "/docs/a.txt");
+    }
+
+    #[test]
+    fn test_leading_chunks_cuts_long_passages_before_embedding() {
+        let short = "A short pasted passage about quarterly revenue.";
+        assert_eq!(leading_chunks(short, MAX_PASSAGE_CHUNKS).unwrap(), short);
+
+        let long = "The quarterly report shows revenue growth in every region except the north. ".repeat(400);
+        let leading = leading_chunks(&long, MAX_PASSAGE_CHUNKS).unwrap();
+        assert!(leading.len() < long.len());
+        assert!(long.starts_with(leading));
+        assert_eq!(chunk_text(leading).unwrap().len(), MAX_PASSAGE_CHUNKS);
+    }
+
+    #[tokio::test]
+    async fn test_related_files_uses_stored_embeddings() {
+        let (conn, _test_db) = setup_test_multimodal_db().await;
+
+        let related = related_files(&conn, "/test/test_doc1.txt", 5)
+            .await
+            .expect("Related files lookup failed");
+        assert_eq!(related.len(), 2);
+        assert!(related.iter().all(|r| r.file_path != "/test/test_doc1.txt"));
+        assert!(related[0].score >= related[1].score);
+    }
+
+    #[tokio::test]
+    async fn test_cosine_metric_ignores_vector_length() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let table = open_or_create_text_table(&conn).await.unwrap();
+
+        let dim = TEXT_EMBEDDING_DIM as usize;
+        let mut query = vec![0.0f32; dim];
+        query[0] = 1.0;
+        // Same direction as the query but five times as long
+        let long_parallel: Vec<f32> = query.iter().map(|v| v * 5.0).collect();
+        // Unit length, about 37 degrees away from the query
+        let mut near_unit = vec![0.0f32; dim];
+        near_unit[0] = 0.8;
+        near_unit[1] = 0.6;
+        upsert_document(&table, "/test/parallel.txt", "hash_parallel", &[long_parallel], None)
+            .await
+            .unwrap();
+        upsert_document(&table, "/test/near.txt", "hash_near", &[near_unit], None)
+            .await
+            .unwrap();
+
+        let by_l2 = search_chunked_table(&table, query.clone(), 2, f32::MIN, DistanceMetric::L2, DedupKey::FilePath, None)
+            .await
+            .unwrap();
+        assert_eq!(by_l2[0].file_path, "/test/near.txt");
+
+        let by_cosine = search_chunked_table(&table, query, 2, f32::MIN, DistanceMetric::Cosine, DedupKey::FilePath, None)
+            .await
+            .unwrap();
+        assert_eq!(by_cosine[0].file_path, "/test/parallel.txt");
+        assert!((by_cosine[0].score - 1.0).abs() < 1e-4);
+        assert!((by_cosine[1].score - 0.8).abs() < 1e-4);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_key_controls_chunk_merging() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let table = open_or_create_text_table(&conn).await.unwrap();
+
+        let dim = TEXT_EMBEDDING_DIM as usize;
+        let unit = |axis: usize| {
+            let mut v = vec![0.0f32; dim];
+            v[axis] = 1.0;
+            v
+        };
+        // Two passages under one path, plus a second file with the same content
+        upsert_document(&table, "virtual://clips/a", "hash_clips", &[unit(0), unit(1)], None)
+            .await
+            .unwrap();
+        upsert_document(&table, "/test/copy.txt", "hash_clips", &[unit(2)], None)
+            .await
+            .unwrap();
+
+        let search = |key| search_chunked_table(&table, unit(0), 10, f32::MIN, DistanceMetric::L2, key, None);
+        assert_eq!(search(DedupKey::FilePath).await.unwrap().len(), 2);
+
+        let by_chunk = search(DedupKey::FilePathAndChunk).await.unwrap();
+        assert_eq!(by_chunk.len(), 3);
+        let mut clip_chunks: Vec<_> = by_chunk
+            .iter()
+            .filter(|r| r.file_path == "virtual://clips/a")
+            .filter_map(|r| r.chunk_id)
+            .collect();
+        clip_chunks.sort();
+        assert_eq!(clip_chunks, vec![0, 1]);
+
+        let by_hash = search(DedupKey::ContentHash).await.unwrap();
+        assert_eq!(by_hash.len(), 1);
+        assert_eq!(by_hash[0].file_path, "virtual://clips/a");
+        assert_eq!(by_hash[0].chunk_id, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_empty_code_table_is_skipped_without_embedding() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+
+        // Returns before the code model is loaded, which would need a download
+        let results = search_code_content(&conn, "fn main", 10, f32::MIN, DedupKey::FilePath, None).await.unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_path_filter_restricts_search() {
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let table = open_or_create_text_table(&conn).await.unwrap();
+
+        let embedding = vec![0.5f32; TEXT_EMBEDDING_DIM as usize];
+        for path in ["/docs/a.txt", "/docs/b.txt", "/docs/bob's notes.txt", "/docs/sub/c.txt", "/docs/sub_dir/d.txt"] {
+            upsert_document(&table, path, &format!("hash_{}", path), &[embedding.clone()], None)
+                .await
+                .unwrap();
+        }
+
+        let within = vec!["/docs/bob's notes.txt".to_string(), "/docs/a.txt".to_string()];
+        let filter = file_path_in_predicate(&within);
+        let mut paths: Vec<String> =
+            search_chunked_table(&table, embedding, 10, f32::MIN, DistanceMetric::L2, DedupKey::FilePath, Some(&filter))
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| r.file_path)
+                .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["/docs/a.txt", "/docs/bob's notes.txt"]);
+
+        // Folders match the files below them, favorites-style
+        let under = file_path_under_predicate(&["/docs/sub".to_string(), "/docs/b.txt".to_string()]);
+        let mut paths: Vec<String> =
+            search_chunked_table(&table, vec![0.5f32; TEXT_EMBEDDING_DIM as usize], 10, f32::MIN, DistanceMetric::L2, DedupKey::FilePath, Some(&under))
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| r.file_path)
+                .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["/docs/b.txt", "/docs/sub/c.txt"]);
+    }
 }
@@ -6,15 +6,29 @@ use log::{error, info, warn};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver};
 use thiserror::Error;
-use crate::db::{delete_document, upsert_document, DbError, connect_db, open_or_create_text_table};
+use crate::db::{
+    delete_document, document_index_root, upsert_document, DbError, connect_db, db_location_generation,
+    open_or_create_text_table,
+};
 use crate::embedder::embed_text;
 use crate::extractor::{extract_text, calculate_hash};
 use crate::commands::search_commands::{add_file_to_index, remove_file_from_index};
+use crate::core::exclusions::is_path_excluded;
+use crate::core::file_system::is_entry_visible;
+use crate::core::indexer::{
+    check_content, index_folder_with_options, is_zero_byte_file, ContentCheck, IndexOptions,
+};
+use crate::core::metadata_index::sync_file_metadata;
+use crate::core::settings::current_settings;
+use crate::core::term_frequencies::{forget_document_terms, record_document_terms, save_term_frequencies};
+use crate::search::keyword_counts;
 use lancedb::Table;
-use std::sync::Arc;
-// Used in tests for timeouts
-#[cfg(test)]
-use std::time::Duration;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 #[cfg(test)]
 use crate::db::TestDb;
 #[cfg(test)]
@@ -24,6 +38,129 @@ use std::fs::metadata;
 // Define supported extensions
 const SUPPORTED_EXTENSIONS: &[&str] = &["txt", "md"];
 
+/// Most changed paths remembered while paused; later changes are dropped
+const MAX_PAUSED_PATHS: usize = 10_000;
+
+/// How often term frequencies changed by watched files are written to disk
+const TERM_FREQUENCY_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What watchers do with events that arrive while they are paused
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PausedEventPolicy {
+    /// Remember the changed paths and re-sync them on resume
+    #[default]
+    Queue,
+    /// Forget the events; re-index the affected folders by hand afterwards
+    Drop,
+}
+
+/// Whether watchers are paused and what they have held back meanwhile
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WatcherStatus {
+    pub paused: bool,
+    /// Paths waiting to be re-synced on resume
+    pub queued_paths: usize,
+    /// Events dropped since the last pause, by policy or because the queue was full
+    pub dropped_events: usize,
+}
+
+/// Paths changed while paused, bounded to `capacity`
+#[derive(Debug, Default)]
+struct PausedChanges {
+    paths: BTreeSet<PathBuf>,
+    /// Folders to rescan on resume because changes in them overflowed `paths`
+    rescan_folders: BTreeSet<PathBuf>,
+    dropped_events: usize,
+    capacity: usize,
+}
+
+impl PausedChanges {
+    /// Queues `paths`. Once the queue is full, the watch root a path lies in
+    /// (`roots`), or else its folder, is marked for a rescan instead.
+    fn record(&mut self, paths: Vec<PathBuf>, policy: PausedEventPolicy, roots: &[PathBuf]) {
+        if policy == PausedEventPolicy::Drop {
+            self.dropped_events += 1;
+            return;
+        }
+        for path in paths {
+            if self.paths.len() >= self.capacity && !self.paths.contains(&path) {
+                self.dropped_events += 1;
+                let folder = roots
+                    .iter()
+                    .find(|root| path.starts_with(root))
+                    .cloned()
+                    .or_else(|| path.parent().map(Path::to_path_buf));
+                self.rescan_folders.extend(folder);
+                continue;
+            }
+            self.paths.insert(path);
+        }
+    }
+
+    /// The queued paths and the folders to rescan, leaving both empty
+    fn take(&mut self) -> (BTreeSet<PathBuf>, BTreeSet<PathBuf>) {
+        self.dropped_events = 0;
+        (std::mem::take(&mut self.paths), std::mem::take(&mut self.rescan_folders))
+    }
+}
+
+static WATCHERS_PAUSED: AtomicBool = AtomicBool::new(false);
+/// Set while the database moves; like a pause, but changes are always queued
+static WATCHERS_HELD: AtomicBool = AtomicBool::new(false);
+/// Folders passed to `setup_watcher`, so relevance checks know where a
+/// watched path starts
+static WATCH_ROOTS: Lazy<Mutex<Vec<PathBuf>>> = Lazy::new(|| Mutex::new(Vec::new()));
+/// Set on resume until the event loop has re-synced the queued paths
+static RECONCILE_PENDING: AtomicBool = AtomicBool::new(false);
+static PAUSED_CHANGES: Lazy<Mutex<PausedChanges>> = Lazy::new(|| {
+    Mutex::new(PausedChanges {
+        capacity: MAX_PAUSED_PATHS,
+        ..Default::default()
+    })
+});
+
+/// Stops every watcher from acting on events, for example during a large move.
+/// Events are queued or dropped per the `paused_watcher_events` setting.
+pub fn pause_watchers() {
+    WATCHERS_PAUSED.store(true, Ordering::SeqCst);
+    info!("File watchers paused");
+}
+
+/// Lets watchers act on events again. The paths changed while paused are
+/// re-synced by the event loop on its next turn.
+pub fn resume_watchers() {
+    if WATCHERS_PAUSED.swap(false, Ordering::SeqCst) {
+        RECONCILE_PENDING.store(true, Ordering::SeqCst);
+        info!("File watchers resumed");
+    }
+}
+
+/// Keeps watchers off the database until dropped. Their changes are queued
+/// whatever `paused_watcher_events` says, and re-synced afterwards.
+pub(crate) struct HeldWatchers(());
+
+pub(crate) fn hold_watchers() -> HeldWatchers {
+    WATCHERS_HELD.store(true, Ordering::SeqCst);
+    HeldWatchers(())
+}
+
+impl Drop for HeldWatchers {
+    fn drop(&mut self) {
+        WATCHERS_HELD.store(false, Ordering::SeqCst);
+        RECONCILE_PENDING.store(true, Ordering::SeqCst);
+    }
+}
+
+pub fn watcher_status() -> WatcherStatus {
+    let changes = PAUSED_CHANGES.lock().unwrap();
+    WatcherStatus {
+        paused: WATCHERS_PAUSED.load(Ordering::SeqCst),
+        queued_paths: changes.paths.len(),
+        dropped_events: changes.dropped_events,
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum WatcherError {
     #[error("Failed to create file system watcher: {0}")]
@@ -64,6 +201,11 @@ pub async fn setup_watcher(
         .map_err(|e| WatcherError::PathWatchFailed(path_to_watch.to_string(), e))?;
 
     info!("Successfully watching path: {:?}", path);
+    let mut roots = WATCH_ROOTS.lock().unwrap();
+    if !roots.iter().any(|root| root == path) {
+        roots.push(path.to_path_buf());
+    }
+    drop(roots);
 
     Ok((watcher, rx))
 }
@@ -94,10 +236,45 @@ pub async fn setup_watcher_with_db(
 /// Loop exits when the sender is dropped (all senders dropped).
 pub async fn process_events(rx: Receiver<NotifyResult<Event>>, table: Arc<Table>) {
     info!("Starting event processing loop...");
+    let mut table = table;
+    let mut db_generation = db_location_generation();
+    let mut last_term_flush = Instant::now();
 
     // Use a loop with channel receiver's try_recv method to avoid indefinitely 
     // blocking in tests when the channel is closed
     loop {
+        if last_term_flush.elapsed() >= TERM_FREQUENCY_FLUSH_INTERVAL {
+            // A no-op unless an event changed the counts since the last flush
+            if let Err(e) = save_term_frequencies() {
+                warn!("Failed to save term frequencies: {}", e);
+            }
+            last_term_flush = Instant::now();
+        }
+        if db_generation != db_location_generation() {
+            // The database moved; the table still points at the old files
+            match reopen_text_table().await {
+                Ok(reopened) => {
+                    info!("Reopened the watched table at the new database location");
+                    table = Arc::new(reopened);
+                    db_generation = db_location_generation();
+                }
+                Err(e) => error!("Failed to reopen the watched table after a database move: {}", e),
+            }
+        }
+        let held = WATCHERS_HELD.load(Ordering::SeqCst);
+        if !held && !WATCHERS_PAUSED.load(Ordering::SeqCst) && RECONCILE_PENDING.swap(false, Ordering::SeqCst) {
+            let (changed, rescan_folders) = PAUSED_CHANGES.lock().unwrap().take();
+            info!("Re-syncing {} paths changed while watchers were paused", changed.len());
+            for path_buf in changed {
+                // Only the final state matters, so existence decides the action
+                let action = if path_buf.exists() { "Upsert" } else { "Delete" };
+                apply_path_change(action, path_buf, &table).await;
+            }
+            for folder in rescan_folders {
+                tokio::spawn(rescan_folder(folder));
+            }
+        }
+
         // Try to receive an event without blocking indefinitely
         match rx.try_recv() {
             Ok(result) => match result {
@@ -128,83 +305,18 @@ pub async fn process_events(rx: Receiver<NotifyResult<Event>>, table: Arc<Table>
                     }
                 };
 
+                if held || WATCHERS_PAUSED.load(Ordering::SeqCst) {
+                    let policy = if held { PausedEventPolicy::Queue } else { current_settings().paused_watcher_events };
+                    let roots = WATCH_ROOTS.lock().unwrap().clone();
+                    PAUSED_CHANGES.lock().unwrap().record(paths_to_check, policy, &roots);
+                    continue;
+                }
+
                 info!("Processing {} event with {} paths", action, paths_to_check.len());
                 
                 // Process each path from the event
                 for path_buf in paths_to_check {
-                    // Update the filename index for all files, regardless of content type
-                    if action == "Upsert" {
-                        // Update the filename index using the new async Tantivy command
-                        // We need to spawn a task because update_filename_index is now async
-                        let path_clone = path_buf.clone();
-                        tokio::spawn(async move {
-                            match metadata(&path_clone) {
-                                Ok(meta) => {
-                                    let last_modified = meta.modified()
-                                        .map(|time| time.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs())
-                                        .unwrap_or(0);
-                                    let size = meta.len();
-                                    if let Some(path_str) = path_clone.to_str() {
-                                        match add_file_to_index(path_str.to_string(), last_modified, size).await {
-                                            Ok(_) => info!("Updated Tantivy index (add/update) for {}", path_clone.display()),
-                                            Err(e) => error!("Failed to update Tantivy index (add/update) for {}: {}", path_clone.display(), e),
-                                        }
-                                    } else {
-                                         error!("Invalid path string for Tantivy add: {}", path_clone.display());
-                                    }
-                                }
-                                Err(e) => error!("Failed to get metadata for Tantivy add {}: {}", path_clone.display(), e),
-                            }
-                        });
-                    } else if action == "Delete" {
-                        // Remove from the filename index using the new async Tantivy command
-                        let path_clone = path_buf.clone();
-                        tokio::spawn(async move {
-                             if let Some(path_str) = path_clone.to_str() {
-                                match remove_file_from_index(path_str.to_string()).await {
-                                    Ok(_) => info!("Updated Tantivy index (remove) for {}", path_clone.display()),
-                                    Err(e) => error!("Failed to update Tantivy index (remove) for {}: {}", path_clone.display(), e),
-                                }
-                            } else {
-                                 error!("Invalid path string for Tantivy remove: {}", path_clone.display());
-                            }
-                        });
-                    }
-                    
-                    // Skip paths we don't care about for semantic indexing
-                    if !is_relevant_file(&path_buf) {
-                        info!("Skipping non-relevant file for semantic index: {}", path_buf.display());
-                        continue;
-                    }
-                    
-                    // Perform action based on event type for semantic search
-                    match action {
-                        "Upsert" => {
-                            info!("Action [Upsert] detected for: {}", path_buf.display());
-                            // Pass table reference
-                            match process_file_upsert(&path_buf, &table).await {
-                                Ok(_) => info!("Successfully processed upsert for {}", path_buf.display()),
-                                Err(e) => error!("Error processing upsert for {}: {}", path_buf.display(), e),
-                            }
-                        }
-                        "Delete" => {
-                            info!("Action [Delete] detected for: {}", path_buf.display());
-                            if let Some(path_str) = path_buf.to_str() {
-                                // Pass table reference
-                                match delete_document(&table, path_str).await {
-                                    Ok(_) => info!("Successfully deleted DB entry for {}", path_buf.display()),
-                                    Err(DbError::RecordNotFound(_)) => warn!("Attempted to delete non-existent DB entry for {}", path_buf.display()),
-                                    Err(e) => error!("Error deleting DB entry for {}: {}", path_buf.display(), e),
-                                }
-                            } else {
-                                error!("Invalid path string for deletion: {}", path_buf.display());
-                            }
-                        }
-                        _ => {
-                            // Should not get here due to the matching above
-                            warn!("Unhandled action type: {}", action); 
-                        }
-                    }
+                    apply_path_change(action, path_buf, &table).await;
                 }
             }
             Err(e) => {
@@ -225,19 +337,150 @@ pub async fn process_events(rx: Receiver<NotifyResult<Event>>, table: Arc<Table>
             }
         }
     }
+    if let Err(e) = save_term_frequencies() {
+        warn!("Failed to save term frequencies: {}", e);
+    }
     
     info!("Event processing loop exited");
 }
 
+/// Picks up the changes in `folder` that didn't fit in the paused queue.
+/// Files deleted meanwhile are left to the integrity check.
+async fn rescan_folder(folder: PathBuf) {
+    let Some(folder_str) = folder.to_str() else {
+        error!("Invalid path string for rescan: {}", folder.display());
+        return;
+    };
+    info!("Rescanning {} for changes missed while watchers were paused", folder.display());
+    let options = IndexOptions {
+        only_new: true,
+        queue_if_busy: true,
+        ..Default::default()
+    };
+    if let Err(e) = index_folder_with_options(folder_str, &options).await {
+        error!("Failed to rescan {}: {}", folder.display(), e);
+    }
+}
+
+async fn reopen_text_table() -> Result<Table, DbError> {
+    let conn = connect_db().await?;
+    open_or_create_text_table(&conn).await
+}
+
+/// Updates the filename index and the semantic index for one changed path.
+/// `action` is "Upsert" or "Delete".
+async fn apply_path_change(action: &str, path_buf: PathBuf, table: &Table) {
+    // Update the filename index for all files, regardless of content type
+    if action == "Upsert" {
+        // Update the filename index using the new async Tantivy command
+        // We need to spawn a task because update_filename_index is now async
+        let path_clone = path_buf.clone();
+        tokio::spawn(async move {
+            match metadata(&path_clone) {
+                Ok(meta) => {
+                    let last_modified = meta.modified()
+                        .map(|time| time.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis() as u64)
+                        .unwrap_or(0);
+                    let size = meta.len();
+                    if let Some(path_str) = path_clone.to_str() {
+                        match add_file_to_index(path_str.to_string(), last_modified, size).await {
+                            Ok(_) => info!("Updated filename index (add/update) for {}", path_clone.display()),
+                            Err(e) => error!("Failed to update filename index (add/update) for {}: {}", path_clone.display(), e),
+                        }
+                    } else {
+                         error!("Invalid path string for Tantivy add: {}", path_clone.display());
+                    }
+                }
+                Err(e) => error!("Failed to get metadata for Tantivy add {}: {}", path_clone.display(), e),
+            }
+        });
+    } else if action == "Delete" {
+        // Remove from the filename index using the new async Tantivy command
+        let path_clone = path_buf.clone();
+        tokio::spawn(async move {
+             if let Some(path_str) = path_clone.to_str() {
+                match remove_file_from_index(path_str.to_string()).await {
+                    Ok(_) => info!("Updated filename index (remove) for {}", path_clone.display()),
+                    Err(e) => error!("Failed to update filename index (remove) for {}: {}", path_clone.display(), e),
+                }
+            } else {
+                 error!("Invalid path string for Tantivy remove: {}", path_clone.display());
+            }
+        });
+    }
+    
+    // Keep folders indexed in metadata-only mode current
+    let metadata_path = path_buf.clone();
+    tokio::spawn(async move {
+        let synced = match connect_db().await {
+            Ok(conn) => sync_file_metadata(&conn, &metadata_path).await,
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = synced {
+            error!("Failed to update file metadata for {}: {}", metadata_path.display(), e);
+        }
+    });
+
+    // Skip paths we don't care about for semantic indexing
+    if !is_relevant_file(&path_buf) {
+        info!("Skipping non-relevant file for semantic index: {}", path_buf.display());
+        return;
+    }
+    
+    // Perform action based on event type for semantic search
+    match action {
+        "Upsert" => {
+            info!("Action [Upsert] detected for: {}", path_buf.display());
+            // Pass table reference
+            match process_file_upsert(&path_buf, table).await {
+                Ok(_) => info!("Successfully processed upsert for {}", path_buf.display()),
+                Err(e) => error!("Error processing upsert for {}: {}", path_buf.display(), e),
+            }
+        }
+        "Delete" => {
+            info!("Action [Delete] detected for: {}", path_buf.display());
+            if let Some(path_str) = path_buf.to_str() {
+                // Pass table reference
+                match delete_document(table, path_str).await {
+                    Ok(_) => {
+                        forget_document_terms(path_str);
+                        info!("Successfully deleted DB entry for {}", path_buf.display())
+                    }
+                    Err(DbError::RecordNotFound(_)) => warn!("Attempted to delete non-existent DB entry for {}", path_buf.display()),
+                    Err(e) => error!("Error deleting DB entry for {}: {}", path_buf.display(), e),
+                }
+            } else {
+                error!("Invalid path string for deletion: {}", path_buf.display());
+            }
+        }
+        _ => {
+            // Should not get here due to the matching above
+            warn!("Unhandled action type: {}", action); 
+        }
+    }
+}
+
 // Helper function to handle text extraction, embedding, and DB upsert for a file
 async fn process_file_upsert(path_buf: &Path, table: &Table) -> Result<(), DbError> {
+    if is_zero_byte_file(path_buf) {
+        info!("{} is empty, skipping upsert.", path_buf.display());
+        return remove_unindexable_document(path_buf, table).await;
+    }
     // Extract content returns TextExtractionResult { text: String, language: DetectedLanguage }
     let extraction_result = extract_text(path_buf)?;
     let trimmed_content = extraction_result.text.trim(); // Trim whitespace
 
-    if trimmed_content.is_empty() { // Check trimmed content
-        warn!("Extracted empty or whitespace-only content for {}, skipping upsert.", path_buf.display());
-        return Ok(()); // Nothing to embed or hash
+    let min_text_chars = current_settings().min_text_chars;
+    match check_content(trimmed_content, min_text_chars) {
+        ContentCheck::Meaningful => {}
+        ContentCheck::Empty => {
+            info!("Extracted whitespace-only content for {}, skipping upsert.", path_buf.display());
+            return remove_unindexable_document(path_buf, table).await; // Nothing to embed or hash
+        }
+        ContentCheck::TooShort => {
+            info!("Extracted text for {} is shorter than {} characters, skipping upsert.", path_buf.display(), min_text_chars);
+            return remove_unindexable_document(path_buf, table).await;
+        }
     }
     
     // Hash the content
@@ -265,8 +508,10 @@ async fn process_file_upsert(path_buf: &Path, table: &Table) -> Result<(), DbErr
     info!("  -> Successfully generated {} embeddings (chunks)", embedding_vec.len());
 
     if let Some(path_str) = path_buf.to_str() {
-        // Pass the entire vector of embeddings to upsert_document
-        upsert_document(table, path_str, &hash, &embedding_vec).await?;
+        // A re-indexed file stays attributed to the folder it was indexed under
+        let index_root = document_index_root(table, path_str).await?;
+        upsert_document(table, path_str, &hash, &embedding_vec, index_root.as_deref()).await?;
+        record_document_terms(path_str, keyword_counts(&extraction_result.text));
         Ok(())
     } else {
         // Keep this as an error because an invalid path is more serious
@@ -275,15 +520,23 @@ async fn process_file_upsert(path_buf: &Path, table: &Table) -> Result<(), DbErr
     }
 }
 
+/// Drops the rows a file left when it still had enough text to index
+async fn remove_unindexable_document(path_buf: &Path, table: &Table) -> Result<(), DbError> {
+    let path_str = path_buf.to_string_lossy();
+    delete_document(table, &path_str).await?;
+    forget_document_terms(&path_str);
+    Ok(())
+}
+
 /// Checks if a path points to a relevant file for indexing.
-/// Ignore hidden files/directories and check for supported extensions.
+/// Ignore hidden files/directories (unless the `include_hidden` setting is on)
+/// and check for supported extensions.
 fn is_relevant_file(path: &PathBuf) -> bool {
-    // Check if the file name itself starts with a dot.
-    let filename_is_hidden = path.file_name()
-        .and_then(|name| name.to_str())
-        .map_or(false, |name_str| name_str.starts_with('.'));
+    // Check the file and every folder between it and the watch root
+    let include_hidden = current_settings().include_hidden;
+    let is_hidden = is_hidden_below_root(path, &WATCH_ROOTS.lock().unwrap(), include_hidden);
 
-    if filename_is_hidden {
+    if is_hidden || is_path_excluded(path) {
         return false;
     }
 
@@ -304,6 +557,20 @@ fn is_relevant_file(path: &PathBuf) -> bool {
     result
 }
 
+/// Whether `path` or a folder on the way to it from the deepest of `roots`
+/// holding it is hidden. The watch root itself may be hidden; only the file's
+/// name is checked for paths outside every root.
+fn is_hidden_below_root(path: &Path, roots: &[PathBuf], include_hidden: bool) -> bool {
+    let root = roots.iter().filter(|root| path.starts_with(root)).max_by_key(|root| root.components().count());
+    let below_root = match root.and_then(|root| path.strip_prefix(root).ok()) {
+        Some(relative) => relative,
+        None => path.file_name().map(Path::new).unwrap_or(Path::new("")),
+    };
+    below_root
+        .components()
+        .any(|component| component.as_os_str().to_str().is_some_and(|name| !is_entry_visible(name, include_hidden)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -442,6 +709,35 @@ mod tests {
         // If we got here, the test passed (didn't hang)
     }
 
+    #[tokio::test]
+    async fn test_process_events_honours_the_pause() {
+        let (tx, rx) = create_mock_channel();
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let table = Arc::new(open_or_create_text_table(&conn).await.unwrap());
+        let dir = tempdir().unwrap();
+        let changed = dir.path().join("held.txt");
+        std::fs::write(&changed, "Written while the watchers were paused").unwrap();
+        let dropped_before = watcher_status().dropped_events;
+
+        pause_watchers();
+        tx.send(Ok(Event {
+            kind: EventKind::Create(CreateKind::File),
+            paths: vec![changed.clone()],
+            attrs: notify::event::EventAttributes::default(),
+        }))
+        .unwrap();
+        drop(tx);
+        process_events(rx, table.clone()).await;
+
+        // The event was queued or dropped per the policy instead of reaching the index
+        let queued = PAUSED_CHANGES.lock().unwrap().paths.remove(&changed);
+        let dropped = watcher_status().dropped_events > dropped_before;
+        resume_watchers();
+        assert!(queued || dropped);
+        assert_eq!(table.count_rows(None).await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn test_watcher_db_integration() {
         // Create a temporary directory for watching
@@ -487,4 +783,62 @@ mod tests {
         drop(watcher); // Stop the watcher
         drop(process_handle); // Stop the processing task
     }
+
+    #[tokio::test]
+    async fn test_upsert_skips_empty_and_whitespace_files() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let zero_byte = dir.path().join("zero.txt");
+        let spaces_only = dir.path().join("spaces.txt");
+        File::create(&zero_byte).unwrap();
+        fs::write(&spaces_only, "    \n\t   \n").unwrap();
+
+        let test_db = TestDb::new();
+        let conn = connect_db_with_path(&test_db.path).await.unwrap();
+        let table = open_or_create_text_table(&conn).await.unwrap();
+
+        process_file_upsert(&zero_byte, &table).await.expect("Empty file should be skipped, not fail");
+        process_file_upsert(&spaces_only, &table).await.expect("Whitespace file should be skipped, not fail");
+        assert_eq!(table.count_rows(None).await.unwrap(), 0, "Nothing should be embedded");
+    }
+
+    #[test]
+    fn test_paused_changes_are_bounded_and_deduplicated() {
+        let mut changes = PausedChanges {
+            capacity: 2,
+            ..Default::default()
+        };
+        let paths = |names: &[&str]| names.iter().map(PathBuf::from).collect::<Vec<_>>();
+
+        let roots = paths(&["/watched"]);
+
+        changes.record(paths(&["/a.txt", "/b.txt"]), PausedEventPolicy::Queue, &roots);
+        changes.record(paths(&["/a.txt", "/c.txt"]), PausedEventPolicy::Queue, &roots);
+        changes.record(paths(&["/d.txt"]), PausedEventPolicy::Drop, &roots);
+        changes.record(paths(&["/watched/deep/e.txt"]), PausedEventPolicy::Queue, &roots);
+        assert_eq!(changes.paths.len(), 2);
+        assert_eq!(changes.dropped_events, 3);
+
+        // Overflowing paths are made up for by rescanning where they lie
+        let (taken, rescan) = changes.take();
+        assert_eq!(taken.into_iter().collect::<Vec<_>>(), paths(&["/a.txt", "/b.txt"]));
+        assert_eq!(rescan.into_iter().collect::<Vec<_>>(), paths(&["/", "/watched"]));
+        assert!(changes.paths.is_empty());
+        assert_eq!(changes.dropped_events, 0);
+    }
+
+    #[test]
+    fn test_hidden_folders_below_the_watch_root_are_ignored() {
+        let roots = vec![PathBuf::from("/home/me/.config/notes"), PathBuf::from("/home/me")];
+        let hidden = |path: &str| is_hidden_below_root(Path::new(path), &roots, false);
+
+        // A hidden watch root is fine; what lies below it is checked
+        assert!(!hidden("/home/me/.config/notes/todo/list.txt"));
+        assert!(hidden("/home/me/.config/notes/.trash/list.txt"));
+        assert!(hidden("/home/me/project/.git/info/readme.md"));
+        assert!(hidden("/home/me/project/.draft.md"));
+        assert!(!hidden("/home/me/project/docs/readme.md"));
+        // Outside every root only the name counts
+        assert!(!hidden("/elsewhere/.cache/readme.md"));
+        assert!(!is_hidden_below_root(Path::new("/home/me/project/.git/readme.md"), &roots, true));
+    }
 }